@@ -1,6 +1,11 @@
 use crate::utils::{
-    ceil, get_attribute, AttributeNotFoundError, DataTypeError, MultiType, ScalarType, Shape,
+    ceil, get_attribute, normalize_axis, outputs_mask, AttributeNotFoundError, AxisOutOfRangeError,
+    DataTypeError, MultiType, ScalarType, Shape,
 };
+use crate::SessionConfig;
+use std::borrow::Cow;
+use std::collections::{hash_map::RandomState, HashMap};
+use std::hash::{BuildHasher, Hasher};
 use tera::{Context, Tera};
 use thiserror::Error;
 
@@ -13,6 +18,21 @@ pub const MAX_WORKGROUP_SIZE_X: u32 = 256;
 pub const MAX_WORKGROUP_SIZE_Y: u32 = 256;
 pub const MAX_WORKGROUP_SIZE_Z: u32 = 64;
 
+/// Matrix multiplications for which both the number of rows and the number of columns of the output exceed this number
+/// use a tiled shader that stages blocks of the inputs in workgroup memory.
+pub const GEMM_TILED_THRESHOLD: u64 = 32;
+
+/// Width and height of the blocks used by the tiled matrix multiplication shader (this is also its workgroup size in
+/// both dimensions)
+const GEMM_TILE_SIZE: u64 = 16;
+
+/// ReduceSum and ReduceMean operations that reduce at least this number of elements into each output (and that have at
+/// most this number of outputs) use a shader in which a workgroup cooperatively reduces the elements for one output.
+pub const REDUCE_WORKGROUP_THRESHOLD: u64 = 1024;
+
+/// Number of invocations in a workgroup that cooperatively reduces elements (must be a power of two)
+const REDUCE_WORKGROUP_SIZE: u64 = 256;
+
 lazy_static! {
     // Templates for shader source code that we generate for nodes
     pub static ref TEMPLATES: Tera = {
@@ -32,6 +52,21 @@ lazy_static! {
             include_str!("../templates/endomorphism/batchnormalization.wgsl"),
         )
         .unwrap();
+        tera.add_raw_template(
+            "endomorphism/gathernd.wgsl",
+            include_str!("../templates/endomorphism/gathernd.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "endomorphism/reversesequence.wgsl",
+            include_str!("../templates/endomorphism/reversesequence.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "endomorphism/scatternd.wgsl",
+            include_str!("../templates/endomorphism/scatternd.wgsl"),
+        )
+        .unwrap();
         tera.add_raw_template(
             "endomorphism/softmax.wgsl",
             include_str!("../templates/endomorphism/softmax.wgsl"),
@@ -47,6 +82,11 @@ lazy_static! {
             include_str!("../templates/endomorphism/cast.wgsl"),
         )
         .unwrap();
+        tera.add_raw_template(
+            "generate/random.wgsl",
+            include_str!("../templates/generate/random.wgsl"),
+        )
+        .unwrap();
         tera.add_raw_template(
             "matrix/concat.wgsl",
             include_str!("../templates/matrix/concat.wgsl"),
@@ -62,11 +102,41 @@ lazy_static! {
             include_str!("../templates/matrix/gemm.wgsl"),
         )
         .unwrap();
+        tera.add_raw_template(
+            "matrix/matmul_vector.wgsl",
+            include_str!("../templates/matrix/matmul_vector.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "matrix/gemm_tiled.wgsl",
+            include_str!("../templates/matrix/gemm_tiled.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "matrix/gru.wgsl",
+            include_str!("../templates/matrix/gru.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "matrix/lstm.wgsl",
+            include_str!("../templates/matrix/lstm.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "matrix/nonmaxsuppression.wgsl",
+            include_str!("../templates/matrix/nonmaxsuppression.wgsl"),
+        )
+        .unwrap();
         tera.add_raw_template(
             "matrix/resize.wgsl",
             include_str!("../templates/matrix/resize.wgsl"),
         )
         .unwrap();
+        tera.add_raw_template(
+            "matrix/roialign.wgsl",
+            include_str!("../templates/matrix/roialign.wgsl"),
+        )
+        .unwrap();
         tera.add_raw_template(
             "matrix/split.wgsl",
             include_str!("../templates/matrix/split.wgsl"),
@@ -97,11 +167,31 @@ lazy_static! {
             include_str!("../templates/pool/conv.wgsl"),
         )
         .unwrap();
+        tera.add_raw_template(
+            "pool/conv_nd.wgsl",
+            include_str!("../templates/pool/conv_nd.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "pool/conv_integer.wgsl",
+            include_str!("../templates/pool/conv_integer.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "pool/im2col.wgsl",
+            include_str!("../templates/pool/im2col.wgsl"),
+        )
+        .unwrap();
         tera.add_raw_template(
             "pool/reduce.wgsl",
             include_str!("../templates/pool/reduce.wgsl"),
         )
         .unwrap();
+        tera.add_raw_template(
+            "pool/reduce_workgroup.wgsl",
+            include_str!("../templates/pool/reduce_workgroup.wgsl"),
+        )
+        .unwrap();
         tera.add_raw_template("structs.wgsl", include_str!("../templates/structs.wgsl"))
             .unwrap();
         tera.add_raw_template(
@@ -114,11 +204,61 @@ lazy_static! {
             include_str!("../templates/snippets/activation_scalar.wgsl"),
         )
         .unwrap();
+        tera.add_raw_template(
+            "snippets/clamp.wgsl",
+            include_str!("../templates/snippets/clamp.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "snippets/mean_variance.wgsl",
+            include_str!("../templates/snippets/mean_variance.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "snippets/recurrence.wgsl",
+            include_str!("../templates/snippets/recurrence.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "endomorphism/dequantizelinear.wgsl",
+            include_str!("../templates/endomorphism/dequantizelinear.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "endomorphism/dropout.wgsl",
+            include_str!("../templates/endomorphism/dropout.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "endomorphism/quantizelinear.wgsl",
+            include_str!("../templates/endomorphism/quantizelinear.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "endomorphism/where.wgsl",
+            include_str!("../templates/endomorphism/where.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "endomorphism/qlinearactivation.wgsl",
+            include_str!("../templates/endomorphism/qlinearactivation.wgsl"),
+        )
+        .unwrap();
         tera.add_raw_template(
             "endomorphism/gather.wgsl",
             include_str!("../templates/endomorphism/gather.wgsl"),
         )
         .unwrap();
+        tera.add_raw_template(
+            "endomorphism/layernormalization.wgsl",
+            include_str!("../templates/endomorphism/layernormalization.wgsl"),
+        )
+        .unwrap();
+        tera.add_raw_template(
+            "endomorphism/groupnormalization.wgsl",
+            include_str!("../templates/endomorphism/groupnormalization.wgsl"),
+        )
+        .unwrap();
         tera
     };
 }
@@ -136,12 +276,18 @@ pub enum CompileError {
     #[error("attribute not found: {0}")]
     AttributeNotFound(#[from] AttributeNotFoundError),
 
+    #[error("invalid axis: {0}")]
+    AxisOutOfRange(#[from] AxisOutOfRangeError),
+
     #[error("operation not recognized: {0}")]
     InvalidOperation(String),
 
     #[error("op {0} is not implemented yet! Check the README if you want to implement it")]
     UnimplementedOp(String),
 
+    #[error("ops from the domain '{0}' are not supported")]
+    UnsupportedDomain(String),
+
     #[error("'{variant}' is not yet implemented for op {op}")]
     UnimplementedVariant { variant: String, op: String },
 
@@ -161,6 +307,15 @@ pub enum CompileError {
         input_shape: Shape,
     },
 
+    #[error("output {output_index} has invalid shape {output_shape}")]
+    InvalidOutputShape {
+        output_index: usize,
+        output_shape: Shape,
+    },
+
+    #[error("expected {expected} inputs, but there are {actual}")]
+    InvalidInputCount { expected: usize, actual: usize },
+
     #[error("the model exceeds the limit for {0}: {1} > {2}")]
     ComputeLimitExceeded(String, u32, u32),
 
@@ -214,12 +369,98 @@ fn agreed_type(
     data_type.ok_or(CompileError::TypeUnderspecified)
 }
 
+/// Whether a two-dimensional convolution is computed by the shader specialized for 3x3 kernels, which computes four output
+/// channels at a time and reads each 3x3 kernel as a mat3x3. The optimizer pads the weights of such convolutions so that
+/// they have the layout of a mat3x3 (of which the columns are 16 bytes apart). Dilations and padding of the input are
+/// handled by the shader itself.
+pub fn uses_conv_kernel_3(kernel_shape: &[i64], strides: &[i64], output_channels: u64) -> bool {
+    kernel_shape == [3, 3] && strides == [1, 1] && output_channels % 4 == 0
+}
+
+/// Activations that can be fused with the surrounding DequantizeLinear and QuantizeLinear into a QLinearActivation node
+pub const QLINEAR_ACTIVATIONS: &[&str] = &[
+    "Relu",
+    "LeakyRelu",
+    "Sigmoid",
+    "Softsign",
+    "Softplus",
+    "Clip",
+    "Celu",
+    "Elu",
+    "ThresholdedRelu",
+    "Affine",
+];
+
+/// The range of values that can be represented by a quantized type (either int8 or uint8)
+fn quantized_range(data_type: ScalarType) -> (i32, i32) {
+    match data_type {
+        ScalarType::I8 => (i8::MIN as i32, i8::MAX as i32),
+        _ => (u8::MIN as i32, u8::MAX as i32),
+    }
+}
+
+/// A seed for a random op that does not specify one, which differs each time it is called
+fn generated_seed() -> u32 {
+    RandomState::new().build_hasher().finish() as u32
+}
+
+/// Whether a shader can be generated for the op that writes its output to the buffer holding its (single) input. This
+/// is the case for element-wise operations that produce an output of the same type and shape as their input.
+///
+/// The in-place shader binds a single read-write buffer instead of separate input and output buffers (a buffer cannot be
+/// bound both for reading and for writing in the same dispatch), so its source differs from the out-of-place shader for
+/// the same node. An in-place and an out-of-place node therefore do not share a pipeline, even if they are otherwise
+/// identical.
+pub fn supports_in_place(node: &crate::onnx::NodeProto) -> bool {
+    node.get_domain().is_empty()
+        && node.get_input().len() == 1
+        && matches!(
+            node.get_op_type(),
+            "Abs"
+                | "Acos"
+                | "Asin"
+                | "Atan"
+                | "Ceil"
+                | "Cos"
+                | "Cosh"
+                | "Exp"
+                | "Floor"
+                | "Log"
+                | "Neg"
+                | "Round"
+                | "Sign"
+                | "Sin"
+                | "Sinh"
+                | "Sqrt"
+                | "Tan"
+                | "Tanh"
+                | "Reciprocal"
+                | "Relu"
+                | "Sigmoid"
+                | "Softsign"
+                | "Softplus"
+                | "Celu"
+                | "Elu"
+                | "LeakyRelu"
+                | "ThresholdedRelu"
+                | "Affine"
+                | "HardSigmoid"
+                | "HardSwish"
+        )
+}
+
+/// Generate the shader for a node. When `in_place` is set (only allowed if `supports_in_place` returns true for the
+/// node), the shader binds a single buffer that holds the input and to which the output is written.
 pub fn compile(
     node: &crate::onnx::NodeProto,
     input_shapes: &[&Shape],
     output_shapes: &[&Shape],
     opset_version: i64,
+    in_place: bool,
+    config: &SessionConfig,
 ) -> Result<CompiledNode, CompileError> {
+    debug_assert!(!in_place || supports_in_place(node));
+
     let input_lengths = input_shapes
         .iter()
         .map(|shape| shape.element_count())
@@ -244,24 +485,84 @@ pub fn compile(
     context.insert("o_chunks", &output_chunks);
     context.insert("op_type", &node.get_op_type());
     context.insert("opset_version", &opset_version);
+    context.insert("in_place", &in_place);
+    context.insert("approximate_activations", &config.approximate_activations);
+    context.insert("compensated_summation", &config.compensated_summation);
+
+    // Ops from domains other than the default ONNX domain are matched by their qualified name (e.g.
+    // "com.microsoft.SkipLayerNormalization")
+    let op_type: Cow<str> = match node.get_domain() {
+        "" | "ai.onnx" => Cow::Borrowed(node.get_op_type()),
+        domain @ "com.microsoft" => Cow::Owned(format!("{}.{}", domain, node.get_op_type())),
+        domain => return Err(CompileError::UnsupportedDomain(domain.to_string())),
+    };
 
-    let node_template: NodeTemplate = match node.get_op_type() {
-        op @ ("Reshape" | "Dropout" | "Identity" | "Flatten" | "Squeeze" | "Unsqueeze") => {
-            // These ops should all be optimized away earlier
+    let node_template: NodeTemplate = match op_type.as_ref() {
+        op @ ("Reshape" | "Identity" | "Flatten" | "Squeeze" | "Unsqueeze") => {
+            // These ops should all be optimized away earlier, or forward their input buffer (see `forwards_input` in gpu.rs)
             return Err(CompileError::InvalidOperation(op.to_string()));
         }
 
+        "Dropout" => {
+            // Dropout only needs a shader when its mask is requested (otherwise its input buffer is forwarded). At
+            // inference the output equals the input and the mask is all ones.
+            if config.deterministic && get_attribute("training_mode", Some(0), node)? == 1 {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: String::from("training_mode=1 (random mask) in deterministic mode"),
+                    op: String::from("Dropout"),
+                });
+            }
+            if !outputs_mask(node) || input_shapes.len() != 1 {
+                return Err(CompileError::InvalidOperation("Dropout".to_string()));
+            }
+
+            let element_count = ceil(output_lengths[0], 4);
+            let (x_threads, workgroup_size_x) = workgroup_size(
+                element_count,
+                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                MAX_WORKGROUP_SIZE_X,
+            )?;
+            context.insert("element_count", &element_count);
+            context.insert("workgroup_size_x", &workgroup_size_x);
+
+            NodeTemplate {
+                scalar_type: agreed_type(input_shapes, &output_shapes[0..1])?,
+                template: "endomorphism/dropout.wgsl",
+                threads: (x_threads, 1, 1),
+            }
+        }
+
         // Map simple function
-        "Abs" | "Acos" | "Asin" | "Atan" | "Ceil" | "Cos" | "Cosh" | "Exp" | "Floor" | "Log"
-        | "Round" | "Sign" | "Sin" | "Sinh" | "Sqrt" | "Tan" | "Tanh" | "Reciprocal" => {
+        op @ ("Abs" | "Acos" | "Asin" | "Atan" | "Ceil" | "Cos" | "Cosh" | "Exp" | "Floor"
+        | "Log" | "Neg" | "Round" | "Sign" | "Sin" | "Sinh" | "Sqrt" | "Tan" | "Tanh"
+        | "Reciprocal") => {
+            // Except for Abs, Neg and Sign, the functions used are only defined for floating point values in WGSL.
+            // Rounding an integer leaves it unchanged, so Ceil, Floor and Round copy integer inputs.
+            let scalar_type = agreed_type(input_shapes, output_shapes)?;
+            let integer = matches!(
+                scalar_type,
+                ScalarType::I32 | ScalarType::I8 | ScalarType::U8
+            );
+            if scalar_type != ScalarType::F32
+                && !matches!(op, "Abs" | "Neg" | "Sign")
+                && !(integer && matches!(op, "Ceil" | "Floor" | "Round"))
+            {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("{} input", scalar_type),
+                    op: op.to_string(),
+                });
+            }
+
+            let element_count = ceil(output_lengths[0], 4);
             let (x_threads, workgroup_size_x) = workgroup_size(
-                ceil(output_lengths[0], 4),
+                element_count,
                 MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
                 MAX_WORKGROUP_SIZE_X,
             )?;
             context.insert("workgroup_size_x", &workgroup_size_x);
+            context.insert("element_count", &element_count);
             NodeTemplate {
-                scalar_type: agreed_type(input_shapes, output_shapes)?,
+                scalar_type,
                 template: "endomorphism/map.wgsl",
                 threads: (x_threads, 1, 1),
             }
@@ -270,17 +571,16 @@ pub fn compile(
         op @ ("ReduceMean" | "ReduceSum" | "ReduceMax" | "ReduceMin" | "ReduceProd"
         | "ReduceL1" | "ReduceL2" | "ReduceLogSum" | "ReduceLogSumExp"
         | "ReduceSumSquare") => {
-            let all_axes: Vec<i64> = (0..(i_dims[0].len() as i64)).collect();
-            let axes: Vec<i64> = get_attribute("axes", Some(all_axes), node)?
-                .into_iter()
-                .map(|idx| {
-                    if idx < 0 {
-                        (i_dims[0].len() as i64) + idx
-                    } else {
-                        idx
-                    }
-                })
-                .collect();
+            let rank = i_dims[0].len();
+            let all_axes: Vec<i64> = (0..rank as i64).collect();
+            let mut axes = get_attribute("axes", Some(all_axes), node)?
+                .iter()
+                .map(|axis| Ok(normalize_axis(*axis, rank)? as i64))
+                .collect::<Result<Vec<i64>, CompileError>>()?;
+
+            // An axis may be listed more than once (e.g. as both 1 and -1 for a rank-2 input), but is reduced once
+            axes.sort_unstable();
+            axes.dedup();
             let scalar_type = agreed_type(&[input_shapes[0]], output_shapes)?;
 
             let dims_removed: Vec<i64> = input_shapes[0]
@@ -297,6 +597,18 @@ pub fn compile(
                 .collect();
             let chunks_with_dims_preserved = Shape::from(scalar_type, &dims_removed).chunks();
 
+            // WGSL has no 64-bit integer type; int64 values are read and written as two i32 values (low and high word)
+            // and reduced as i32 values, which suffices for e.g. calculating the element count from a shape
+            let int64 = scalar_type == ScalarType::I64;
+            if int64 && !matches!(op, "ReduceSum" | "ReduceProd" | "ReduceMin" | "ReduceMax") {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("{} input", scalar_type),
+                    op: op.to_string(),
+                });
+            }
+            context.insert("int64", &int64);
+            let scalar_type = if int64 { ScalarType::I32 } else { scalar_type };
+
             log::info!(
                 "reduce Op={} axes={:?} output_shape={:?} chunks_with_dims_preserved={:?} output_length={}",
                 op,
@@ -306,20 +618,80 @@ pub fn compile(
                 output_lengths[0]
             );
 
-            // The reduce shader will be invoked once for each scalar in the output (which represents one reduce operation)
-            let (x_threads, workgroup_size_x) = workgroup_size(
-                output_lengths[0],
-                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
-                MAX_WORKGROUP_SIZE_X,
-            )?;
-            context.insert("workgroup_size_x", &workgroup_size_x);
-            context.insert("chunks_with_dims_preserved", &chunks_with_dims_preserved);
-            context.insert("axes", &axes);
+            // When many elements are reduced into few outputs, each output is computed by a workgroup whose invocations
+            // each reduce part of the elements, after which the partial results are combined in workgroup memory.
+            let reduced_length: u64 = axes
+                .iter()
+                .map(|axis| input_shapes[0].dim(*axis as usize))
+                .product();
 
-            NodeTemplate {
-                scalar_type,
-                template: "pool/reduce.wgsl",
-                threads: (x_threads, 1, 1),
+            // The input index of the first element that is reduced into an output is found from the output index by
+            // taking (index / output chunk) % dim * input chunk for each kept axis.
+            let kept_axes: Vec<[u64; 3]> = input_shapes[0]
+                .dims
+                .iter()
+                .enumerate()
+                .filter(|(axis, _)| !axes.contains(&(*axis as i64)))
+                .map(|(axis, dim)| {
+                    [
+                        chunks_with_dims_preserved[axis],
+                        *dim,
+                        input_chunks[0][axis],
+                    ]
+                })
+                .collect();
+            if matches!(op, "ReduceSum" | "ReduceMean")
+                && !int64
+                && reduced_length >= REDUCE_WORKGROUP_THRESHOLD
+                && output_lengths[0] <= REDUCE_WORKGROUP_THRESHOLD
+            {
+                // The index of a reduced element is converted to an input offset in the same way as the output index
+                let mut reduced_axes = vec![];
+                let mut reduced_chunk = reduced_length;
+                for axis in &axes {
+                    let dim = input_shapes[0].dim(*axis as usize);
+                    reduced_chunk /= dim;
+                    reduced_axes.push([reduced_chunk, dim, input_chunks[0][*axis as usize]]);
+                }
+                context.insert("kept_axes", &kept_axes);
+                context.insert("reduced_axes", &reduced_axes);
+                context.insert("reduced_length", &reduced_length);
+                context.insert("workgroup_size_x", &REDUCE_WORKGROUP_SIZE);
+
+                NodeTemplate {
+                    scalar_type,
+                    template: "pool/reduce_workgroup.wgsl",
+                    threads: (output_lengths[0] as _, 1, 1),
+                }
+            } else {
+                // The reduce shader will be invoked once for each scalar in the output (which represents one reduce operation)
+                let (x_threads, workgroup_size_x) = workgroup_size(
+                    output_lengths[0],
+                    MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                    MAX_WORKGROUP_SIZE_X,
+                )?;
+                // Each output iterates over the elements reduced into it with a loop per reduced axis. Axes of size one
+                // are skipped, and reduced axes that are contiguous in the input (e.g. axes 1 and 2 of a rank-3 input, but
+                // also axes 0 and 2 if axis 1 has size one) are iterated as one. Each element is [length, input chunk].
+                let mut reduce_loops: Vec<[u64; 2]> = vec![];
+                for axis in &axes {
+                    let dim = input_shapes[0].dim(*axis as usize);
+                    let chunk = input_chunks[0][*axis as usize];
+                    match reduce_loops.last_mut() {
+                        _ if dim == 1 => {}
+                        Some(last) if last[1] == dim * chunk => *last = [last[0] * dim, chunk],
+                        _ => reduce_loops.push([dim, chunk]),
+                    }
+                }
+                context.insert("workgroup_size_x", &workgroup_size_x);
+                context.insert("kept_axes", &kept_axes);
+                context.insert("reduce_loops", &reduce_loops);
+
+                NodeTemplate {
+                    scalar_type,
+                    template: "pool/reduce.wgsl",
+                    threads: (x_threads, 1, 1),
+                }
             }
         }
 
@@ -328,7 +700,7 @@ pub fn compile(
             // Which axis to gather on. Negative value means counting dimensions from the back. Accepted range is [-r, r-1] where r = rank(data).
             // Default is 0. See https://github.com/onnx/onnx/blob/main/docs/Operators.md#attributes-25
             let axis = get_attribute("axis", Some(0), node)?;
-            if axis != 0 {
+            if normalize_axis(axis, input_shapes[0].rank())? != 0 {
                 return Err(CompileError::UnimplementedVariant {
                     variant: format!("axis={}", axis),
                     op: String::from("Gather"),
@@ -340,7 +712,8 @@ pub fn compile(
             let chunk_type = MultiType::for_size(elements_per_index as usize, scalar_type);
             let chunk_size = chunk_type.elements();
 
-            // The X dimension represents the indexes
+            // The X dimension represents the indexes. Scalar (rank 0) indices hold a single index, of which the output is
+            // the selected slice of data (which has one dimension less than data).
             let (x_threads, workgroup_size_x) = workgroup_size(
                 input_lengths[1],
                 MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
@@ -356,6 +729,10 @@ pub fn compile(
 
             context.insert("chunk_type", &chunk_type.wgsl_type_name());
             context.insert("chunk_size", &chunk_size);
+            context.insert(
+                "indices_int64",
+                &(input_shapes[1].data_type == ScalarType::I64),
+            );
             context.insert("workgroup_size_x", &workgroup_size_x);
             context.insert("workgroup_size_y", &workgroup_size_y);
 
@@ -366,137 +743,661 @@ pub fn compile(
             }
         }
 
-        "Cast" => {
-            let cast_to_type =
-                ScalarType::from_i32(get_attribute::<i64>("to", None, node)? as i32)?;
-            context.insert("cast_to_type", cast_to_type.wgsl_type_name());
+        "GatherND" => {
+            // Input 0 is data, input 1 is indices (of shape [..., k]). The first batch_dims dimensions of data and indices
+            // are batch dimensions, within which each index tuple of length k selects a slice of data (of shape
+            // data.shape[batch_dims + k..]). See https://github.com/onnx/onnx/blob/main/docs/Operators.md#GatherND
+            let (data_shape, indices_shape) = (input_shapes[0], input_shapes[1]);
+            let batch_dims = get_attribute("batch_dims", Some(0), node)?;
+            if batch_dims < 0 || batch_dims as usize >= indices_shape.rank() {
+                return Err(CompileError::InvalidAttributeValue {
+                    attribute: "batch_dims".to_string(),
+                    value: batch_dims.to_string(),
+                    opset_version,
+                });
+            }
+            let batch_dims = batch_dims as usize;
+            let k = indices_shape.dim(indices_shape.rank() - 1) as usize;
+            if batch_dims + k > data_shape.rank()
+                || data_shape.dims[0..batch_dims] != indices_shape.dims[0..batch_dims]
+            {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 1,
+                    input_shape: indices_shape.clone(),
+                });
+            }
+
+            let scalar_type = agreed_type(&input_shapes[0..1], output_shapes)?;
+            if scalar_type == ScalarType::I64 {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("{} data", scalar_type),
+                    op: "GatherND".to_string(),
+                });
+            }
+
+            // Each output element belongs to a slice selected by one index tuple. The tuples are grouped per batch.
+            let slice_size: u64 = data_shape.dims[(batch_dims + k)..].iter().product();
+            let tuples_per_batch: u64 = indices_shape.dims[batch_dims..(indices_shape.rank() - 1)]
+                .iter()
+                .product();
+            let batch_chunk: u64 = data_shape.dims[batch_dims..].iter().product();
 
             let (x_threads, workgroup_size_x) = workgroup_size(
-                ceil(output_lengths[0], 4),
+                output_lengths[0],
                 MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
                 MAX_WORKGROUP_SIZE_X,
             )?;
+            context.insert("batch_dims", &batch_dims);
+            context.insert("index_length", &k);
+            context.insert("slice_size", &slice_size);
+            context.insert("tuples_per_batch", &tuples_per_batch);
+            context.insert("batch_chunk", &batch_chunk);
+            context.insert(
+                "indices_int64",
+                &(indices_shape.data_type == ScalarType::I64),
+            );
             context.insert("workgroup_size_x", &workgroup_size_x);
+
             NodeTemplate {
-                scalar_type: agreed_type(input_shapes, &[])?,
-                template: "endomorphism/cast.wgsl",
+                scalar_type,
+                template: "endomorphism/gathernd.wgsl",
                 threads: (x_threads, 1, 1),
             }
         }
 
-        "Softmax" => {
-            let default_axis = match opset_version {
-                1..=10 => 1,   // https://github.com/onnx/onnx/blob/master/docs/Changelog.md#softmax-1
-                11..=12 => 1, // https://github.com/onnx/onnx/blob/master/docs/Changelog.md#softmax-11
-                13..=15 => -1, // https://github.com/onnx/onnx/blob/master/docs/Changelog.md#softmax-13
-                _ => return Err(CompileError::UnsupportedOpsetVersion(opset_version)),
-            };
+        "ReverseSequence" => {
+            // Input 0 is data (of rank >= 2) and input 1 holds the length of each sequence in the batch. The batch and time
+            // axes are the first two axes (in either order). See
+            // https://github.com/onnx/onnx/blob/main/docs/Operators.md#ReverseSequence
+            if input_shapes.len() != 2 {
+                return Err(CompileError::InvalidInputCount {
+                    expected: 2,
+                    actual: input_shapes.len(),
+                });
+            }
+            let (data_shape, lengths_shape) = (input_shapes[0], input_shapes[1]);
+            if data_shape.rank() < 2 {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 0,
+                    input_shape: data_shape.clone(),
+                });
+            }
 
-            /* Describes the axis of the inputs when coerced to 2D; defaults to one because the 0th axis most likely
-            describes the batch_size. From version 13 onwards, counting backwards is also allowed. We only support the
-            variant with [1,n] input tensors, where axis is 1 or -1 */
-            let mut axis = get_attribute("axis", Some(default_axis), node)?;
-            if axis < 0 {
-                if opset_version >= 13 {
-                    axis += input_shapes[0].rank() as i64;
-                } else {
+            let batch_axis = get_attribute("batch_axis", Some(1), node)?;
+            let time_axis = get_attribute("time_axis", Some(0), node)?;
+            for (attribute, axis) in [("batch_axis", batch_axis), ("time_axis", time_axis)] {
+                if !(0..=1).contains(&axis) || batch_axis == time_axis {
                     return Err(CompileError::InvalidAttributeValue {
-                        attribute: "axis".to_string(),
-                        value: format!("{}", axis),
+                        attribute: attribute.to_string(),
+                        value: axis.to_string(),
                         opset_version,
                     });
                 }
             }
-
-            if axis >= (input_shapes[0].rank() as i64) {
-                return Err(CompileError::InvalidAttributeValue {
-                    attribute: "axis".to_string(),
-                    value: format!("{}", axis),
-                    opset_version,
+            let (batch_axis, time_axis) = (batch_axis as usize, time_axis as usize);
+            if lengths_shape.dims != [data_shape.dim(batch_axis)] {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 1,
+                    input_shape: lengths_shape.clone(),
                 });
             }
 
-            if axis != 1 {
+            let scalar_type = agreed_type(&input_shapes[0..1], output_shapes)?;
+            if scalar_type == ScalarType::I64 {
                 return Err(CompileError::UnimplementedVariant {
-                    variant: format!(
-                        "softmax on an axis ({}) other than the second with [1,n] inputs",
-                        axis,
-                    ),
-                    op: "Softmax".to_string(),
+                    variant: format!("{} data", scalar_type),
+                    op: "ReverseSequence".to_string(),
                 });
             }
 
-            NodeTemplate {
-                scalar_type: agreed_type(input_shapes, output_shapes)?,
-                template: "endomorphism/softmax.wgsl",
-                threads: (1, 1, 1),
-            }
-        }
-
-        // Arithmetic operation
-        "Add" | "And" | "Div" | "Equal" | "Greater" | "GreaterOrEqual" | "Less" | "LessOrEqual"
-        | "Mod" | "Mul" | "Or" | "Sub" => {
-            let coefficient = get_attribute("coefficient", Some(1.0), node)?;
-            context.insert("coefficient", &coefficient);
-            context.insert(
-                "op_type",
-                match node.get_op_type() {
-                    "Add" => "+",
-                    "And" => "&",
-                    "Div" => "/",
-                    "Equal" => "==",
-                    "Greater" => ">",
-                    "GreaterOrEqual" => ">=",
-                    "Less" => "<",
-                    "LessOrEqual" => "<=",
-                    "Mod" => "%",
-                    "Mul" => "*",
-                    "Or" => "|",
-                    "Sub" => "-",
-                    _ => {
-                        return Err(CompileError::UnimplementedOp(
-                            node.get_op_type().to_string(),
-                        ))
-                    }
-                },
-            );
-
             let (x_threads, workgroup_size_x) = workgroup_size(
-                ceil(output_lengths[0], 4) as _,
+                output_lengths[0],
                 MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
                 MAX_WORKGROUP_SIZE_X,
             )?;
+            context.insert("batch_count", &data_shape.dim(batch_axis));
+            context.insert("batch_stride", &input_chunks[0][batch_axis]);
+            context.insert("time_count", &data_shape.dim(time_axis));
+            context.insert("time_stride", &input_chunks[0][time_axis]);
+            context.insert(
+                "lengths_int64",
+                &(lengths_shape.data_type == ScalarType::I64),
+            );
             context.insert("workgroup_size_x", &workgroup_size_x);
 
             NodeTemplate {
-                scalar_type: agreed_type(input_shapes, output_shapes)?,
-                template: "endomorphism/arithmetic.wgsl",
+                scalar_type,
+                template: "endomorphism/reversesequence.wgsl",
                 threads: (x_threads, 1, 1),
             }
         }
-        // Not taking into account attributes
-        "BatchNormalization" => {
-            /* Prior to version 9, BatchNormalization supported a 'spatial' mode where input mean/variance are of shape
-            [C,W,H] instead of just [C]. See https://github.com/onnx/onnx/blob/master/docs/Changelog.md#BatchNormalization-7.
-            This mode is not supported. */
-            if let Ok(spatial_value) = get_attribute::<i64>("spatial", None, node) {
-                if opset_version < 9 {
-                    return Err(CompileError::UnimplementedVariant {
-                        op: "BatchNormalization".to_string(),
-                        variant: "spatial".to_string(),
-                    });
-                } else {
-                    return Err(CompileError::InvalidAttributeValue {
-                        attribute: "spatial".to_string(),
-                        opset_version,
-                        value: spatial_value.to_string(),
-                    });
-                }
+
+        "ScatterND" => {
+            // Input 0 is data, input 1 is indices (of shape [..., k]) and input 2 holds the updates. Each index tuple of
+            // length k selects a slice of data (of shape data.shape[k..]) that is replaced by (or combined with) the
+            // corresponding slice of the updates. See https://github.com/onnx/onnx/blob/main/docs/Operators.md#ScatterND
+            let reduction = get_attribute("reduction", Some("none".to_string()), node)?;
+            if !matches!(reduction.as_str(), "none" | "add" | "mul" | "max" | "min") {
+                return Err(CompileError::InvalidAttributeValue {
+                    attribute: "reduction".to_string(),
+                    value: reduction,
+                    opset_version,
+                });
             }
 
-            // [N,C,w,h] => [N,C,w,h] where [w,h] is normalized using stats for each [N,C]
-            // N and C are optional and assumed to be one for lower-rank inputs
-            if input_shapes[0].rank() <= 2 || input_shapes[0].rank() > 4 {
+            let (data_shape, indices_shape, updates_shape) =
+                (input_shapes[0], input_shapes[1], input_shapes[2]);
+            let k = if indices_shape.is_empty() {
+                0
+            } else {
+                indices_shape.dim(indices_shape.rank() - 1) as usize
+            };
+            if indices_shape.is_empty() || k > data_shape.rank() {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 1,
+                    input_shape: indices_shape.clone(),
+                });
+            }
+
+            // The updates have shape indices.shape[..-1] + data.shape[k..]
+            let expected_updates_dims: Vec<u64> = indices_shape.dims[0..(indices_shape.rank() - 1)]
+                .iter()
+                .chain(data_shape.dims[k..].iter())
+                .cloned()
+                .collect();
+            if updates_shape.dims != expected_updates_dims {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 2,
+                    input_shape: updates_shape.clone(),
+                });
+            }
+
+            let scalar_type = agreed_type(&[data_shape, updates_shape], output_shapes)?;
+            if scalar_type == ScalarType::I64 {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("{} data", scalar_type),
+                    op: "ScatterND".to_string(),
+                });
+            }
+
+            // Each element of an index tuple is multiplied by the number of slices covered by a step along its axis
+            let slice_size: u64 = data_shape.dims[k..].iter().product();
+            let index_chunks: Vec<u64> = (0..k)
+                .map(|axis| data_shape.dims[(axis + 1)..k].iter().product())
+                .collect();
+
+            let (x_threads, workgroup_size_x) = workgroup_size(
+                output_lengths[0],
+                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                MAX_WORKGROUP_SIZE_X,
+            )?;
+            context.insert("reduction", &reduction);
+            context.insert("index_length", &k);
+            context.insert("index_chunks", &index_chunks);
+            context.insert("slice_size", &slice_size);
+            let update_count: u64 = indices_shape.dims[0..(indices_shape.rank() - 1)]
+                .iter()
+                .product();
+            context.insert("update_count", &update_count);
+            context.insert(
+                "indices_int64",
+                &(indices_shape.data_type == ScalarType::I64),
+            );
+            context.insert("workgroup_size_x", &workgroup_size_x);
+
+            NodeTemplate {
+                scalar_type,
+                template: "endomorphism/scatternd.wgsl",
+                threads: (x_threads, 1, 1),
+            }
+        }
+
+        op @ ("DequantizeLinear" | "QuantizeLinear") => {
+            // DequantizeLinear: y = (x - x_zero_point) * x_scale
+            // QuantizeLinear: y = saturate(round(x / y_scale) + y_zero_point)
+            // The scale and zero point are either scalars (per-tensor quantization) or 1-D tensors with an element for
+            // each index along `axis` (per-axis quantization)
+            let quantize = op == "QuantizeLinear";
+            let (x_shape, scale_shape) = (input_shapes[0], input_shapes[1]);
+            let quantized_type = if quantize {
+                output_shapes[0].data_type
+            } else {
+                x_shape.data_type
+            };
+            let supported = if quantize {
+                matches!(quantized_type, ScalarType::I8 | ScalarType::U8)
+                    && x_shape.data_type == ScalarType::F32
+            } else {
+                matches!(
+                    quantized_type,
+                    ScalarType::I8 | ScalarType::U8 | ScalarType::I32
+                )
+            };
+            if !supported {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("{} input", x_shape.data_type),
+                    op: op.to_string(),
+                });
+            }
+            if scale_shape.data_type != ScalarType::F32 {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("{} scale", scale_shape.data_type),
+                    op: op.to_string(),
+                });
+            }
+
+            let has_zero_point = input_shapes.len() > 2;
+            if has_zero_point && input_shapes[2].dims != scale_shape.dims {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 2,
+                    input_shape: input_shapes[2].clone(),
+                });
+            }
+
+            let per_axis = scale_shape.element_count() != 1;
+            if per_axis {
+                let axis = normalize_axis(get_attribute("axis", Some(1), node)?, x_shape.rank())?;
+                if scale_shape.dims != [x_shape.dim(axis)] {
+                    return Err(CompileError::InvalidInputShape {
+                        input_index: 1,
+                        input_shape: scale_shape.clone(),
+                    });
+                }
+                context.insert("axis_chunk", &input_chunks[0][axis]);
+                context.insert("axis_dim", &x_shape.dim(axis));
+            }
+
+            if quantize {
+                let (min, max) = quantized_range(quantized_type);
+                context.insert("quantized_min", &min);
+                context.insert("quantized_max", &max);
+            }
+
+            let (x_threads, workgroup_size_x) = workgroup_size(
+                output_lengths[0],
+                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                MAX_WORKGROUP_SIZE_X,
+            )?;
+            context.insert("per_axis", &per_axis);
+            context.insert("has_zero_point", &has_zero_point);
+            context.insert("workgroup_size_x", &workgroup_size_x);
+
+            NodeTemplate {
+                scalar_type: ScalarType::F32,
+                template: if quantize {
+                    "endomorphism/quantizelinear.wgsl"
+                } else {
+                    "endomorphism/dequantizelinear.wgsl"
+                },
+                threads: (x_threads, 1, 1),
+            }
+        }
+
+        "QLinearActivation" => {
+            // Fused DequantizeLinear -> activation -> QuantizeLinear with per-tensor scales and zero points (see
+            // `Optimizer::optimize_chain`), which are passed as attributes
+            let activation = get_attribute::<String>("activation", None, node)?;
+            if !QLINEAR_ACTIVATIONS.contains(&activation.as_str()) {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("activation={}", activation),
+                    op: "QLinearActivation".to_string(),
+                });
+            }
+            context.insert("activation", &activation);
+
+            let alpha = if activation == "LeakyRelu" {
+                get_attribute("alpha", Some(0.01), node)?
+            } else {
+                get_attribute("alpha", Some(1.0), node)?
+            };
+            context.insert("alpha", &alpha);
+            context.insert("beta", &get_attribute("beta", Some(0.0), node)?);
+            context.insert(
+                "clamp_min",
+                &format!("{:?}", get_attribute("min", Some(f32::MIN), node)?),
+            );
+            context.insert(
+                "clamp_max",
+                &format!("{:?}", get_attribute("max", Some(f32::MAX), node)?),
+            );
+
+            for name in ["x_scale", "y_scale"] {
+                let scale = get_attribute::<f32>(name, None, node)?;
+                context.insert(name, &format!("{:?}", scale));
+            }
+            for name in ["x_zero_point", "y_zero_point"] {
+                context.insert(name, &get_attribute::<i64>(name, None, node)?);
+            }
+
+            let output_type = output_shapes[0].data_type;
+            if !matches!(output_type, ScalarType::I8 | ScalarType::U8) {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("{} output", output_type),
+                    op: "QLinearActivation".to_string(),
+                });
+            }
+            let (min, max) = quantized_range(output_type);
+            context.insert("quantized_min", &min);
+            context.insert("quantized_max", &max);
+
+            let (x_threads, workgroup_size_x) = workgroup_size(
+                output_lengths[0],
+                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                MAX_WORKGROUP_SIZE_X,
+            )?;
+            context.insert("workgroup_size_x", &workgroup_size_x);
+
+            NodeTemplate {
+                scalar_type: ScalarType::F32,
+                template: "endomorphism/qlinearactivation.wgsl",
+                threads: (x_threads, 1, 1),
+            }
+        }
+
+        "Cast" => {
+            let cast_from_type = agreed_type(input_shapes, &[])?;
+            let cast_to_type =
+                ScalarType::from_i32(get_attribute::<i64>("to", None, node)? as i32)?;
+
+            // Elements are read using the input type and written using the output type (which may be of different size)
+            let cast_int64 = cast_from_type == ScalarType::I64 || cast_to_type == ScalarType::I64;
+            let element_type = |scalar_type: ScalarType| {
+                if cast_int64 {
+                    match scalar_type {
+                        ScalarType::I64 => "vec2<i32>".to_string(),
+                        _ => scalar_type.wgsl_type_name().to_string(),
+                    }
+                } else {
+                    format!("vec4<{}>", scalar_type.wgsl_type_name())
+                }
+            };
+            let element_stride = |scalar_type: ScalarType| {
+                if cast_int64 {
+                    scalar_type.stride()
+                } else {
+                    MultiType::Vec(scalar_type, 4).stride()
+                }
+            };
+
+            context.insert("cast_int64", &cast_int64);
+            context.insert("cast_from_type", cast_from_type.wgsl_type_name());
+            context.insert("cast_to_type", cast_to_type.wgsl_type_name());
+            context.insert("input_element_type", &element_type(cast_from_type));
+            context.insert("output_element_type", &element_type(cast_to_type));
+            context.insert("input_stride", &element_stride(cast_from_type));
+            context.insert("output_stride", &element_stride(cast_to_type));
+
+            let element_count = if cast_int64 {
+                output_lengths[0]
+            } else {
+                ceil(output_lengths[0], 4)
+            };
+            let (x_threads, workgroup_size_x) = workgroup_size(
+                element_count,
+                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                MAX_WORKGROUP_SIZE_X,
+            )?;
+            context.insert("workgroup_size_x", &workgroup_size_x);
+            context.insert("element_count", &element_count);
+            NodeTemplate {
+                scalar_type: cast_from_type,
+                template: "endomorphism/cast.wgsl",
+                threads: (x_threads, 1, 1),
+            }
+        }
+
+        "Softmax" => {
+            let default_axis = match opset_version {
+                1..=10 => 1,   // https://github.com/onnx/onnx/blob/master/docs/Changelog.md#softmax-1
+                11..=12 => 1, // https://github.com/onnx/onnx/blob/master/docs/Changelog.md#softmax-11
+                13..=15 => -1, // https://github.com/onnx/onnx/blob/master/docs/Changelog.md#softmax-13
+                _ => return Err(CompileError::UnsupportedOpsetVersion(opset_version)),
+            };
+
+            /* Describes the axis of the inputs when coerced to 2D; defaults to one because the 0th axis most likely
+            describes the batch_size. From version 13 onwards, counting backwards is also allowed. We only support the
+            variant with [1,n] input tensors, where axis is 1 or -1 */
+            let axis = get_attribute("axis", Some(default_axis), node)?;
+            if axis < 0 && opset_version < 13 {
+                return Err(CompileError::InvalidAttributeValue {
+                    attribute: "axis".to_string(),
+                    value: format!("{}", axis),
+                    opset_version,
+                });
+            }
+            let axis = normalize_axis(axis, input_shapes[0].rank())?;
+
+            if axis != 1 {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!(
+                        "softmax on an axis ({}) other than the second with [1,n] inputs",
+                        axis,
+                    ),
+                    op: "Softmax".to_string(),
+                });
+            }
+
+            NodeTemplate {
+                scalar_type: agreed_type(input_shapes, output_shapes)?,
+                template: "endomorphism/softmax.wgsl",
+                threads: (1, 1, 1),
+            }
+        }
+
+        // Arithmetic operation
+        "Add" | "And" | "BitShift" | "Div" | "Equal" | "Greater" | "GreaterOrEqual" | "Less"
+        | "LessOrEqual" | "Mod" | "Mul" | "Or" | "Sub" | "Sum" | "Xor" => {
+            let coefficient = get_attribute("coefficient", Some(1.0), node)?;
+            context.insert("coefficient", &coefficient);
+
+            // Comparisons produce booleans (stored as 0 or 1) regardless of the type of the values that are compared
+            let comparison = matches!(
+                node.get_op_type(),
+                "Equal" | "Greater" | "GreaterOrEqual" | "Less" | "LessOrEqual"
+            );
+            let scalar_type = if comparison {
+                let output_type = output_shapes[0].data_type;
+                if output_type != ScalarType::Bool {
+                    return Err(CompileError::TypesDisagree(ScalarType::Bool, output_type));
+                }
+                agreed_type(input_shapes, &[])?
+            } else {
+                agreed_type(input_shapes, output_shapes)?
+            };
+            context.insert("comparison", &comparison);
+            let op_symbol = match node.get_op_type() {
+                "Add" => "+",
+                "Sum" => {
+                    // Only the sum of two inputs is supported, which is the same as Add
+                    if input_shapes.len() != 2 {
+                        return Err(CompileError::UnimplementedVariant {
+                            op: "Sum".to_string(),
+                            variant: format!("with {} inputs", input_shapes.len()),
+                        });
+                    }
+                    "+"
+                }
+                op @ ("And" | "Or" | "Xor") => {
+                    // Logical operators take and produce booleans (stored as 0 or 1)
+                    if scalar_type != ScalarType::Bool {
+                        return Err(CompileError::UnimplementedVariant {
+                            op: op.to_string(),
+                            variant: format!("data type {}", scalar_type),
+                        });
+                    }
+                    context.insert("logical", &true);
+                    match op {
+                        "And" => "&",
+                        "Or" => "|",
+                        _ => "!=",
+                    }
+                }
+                "BitShift" => {
+                    // WGSL can only shift integers. Unsigned 8-bit integers are stored as i32, so the bits shifted out
+                    // of the lowest byte to the left need to be cleared.
+                    match scalar_type {
+                        ScalarType::I32 => {}
+                        ScalarType::U8 => context.insert("shift_mask", &0xffu32),
+                        _ => {
+                            return Err(CompileError::UnimplementedVariant {
+                                op: "BitShift".to_string(),
+                                variant: format!("data type {}", scalar_type),
+                            })
+                        }
+                    }
+
+                    let direction = get_attribute::<String>("direction", None, node)?;
+                    match direction.as_str() {
+                        "LEFT" => "<<",
+                        "RIGHT" => ">>",
+                        _ => {
+                            return Err(CompileError::InvalidAttributeValue {
+                                attribute: "direction".to_string(),
+                                value: direction,
+                                opset_version,
+                            })
+                        }
+                    }
+                }
+                "Div" => "/",
+                "Equal" => "==",
+                "Greater" => ">",
+                "GreaterOrEqual" => ">=",
+                "Less" => "<",
+                "LessOrEqual" => "<=",
+                "Mod" => {
+                    // Mod uses the C fmod semantics (where the sign of the remainder follows the dividend) when
+                    // fmod=1, and Python-style modulo (where it follows the divisor) otherwise. The latter is only
+                    // defined for integers.
+                    let fmod = get_attribute("fmod", Some(0), node)?;
+                    if fmod == 0 && scalar_type == ScalarType::F32 {
+                        return Err(CompileError::InvalidAttributeValue {
+                            attribute: "fmod".to_string(),
+                            value: fmod.to_string(),
+                            opset_version,
+                        });
+                    }
+                    context.insert("python_mod", &(fmod == 0));
+                    "%"
+                }
+                "Mul" => "*",
+                "Sub" => "-",
+                _ => {
+                    return Err(CompileError::UnimplementedOp(
+                        node.get_op_type().to_string(),
+                    ))
+                }
+            };
+            context.insert("op_type", op_symbol);
+
+            // When the inputs have different shapes, they are broadcast to the output shape (one element at a time)
+            let broadcast = input_shapes.len() == 2 && input_shapes[0].dims != input_shapes[1].dims;
+            if broadcast {
+                let strides = input_shapes
+                    .iter()
+                    .enumerate()
+                    .map(|(input_index, input_shape)| {
+                        broadcast_strides(input_shape, output_shapes[0]).ok_or_else(|| {
+                            CompileError::InvalidInputShape {
+                                input_index,
+                                input_shape: (*input_shape).clone(),
+                            }
+                        })
+                    })
+                    .collect::<Result<Vec<Vec<u64>>, CompileError>>()?;
+                context.insert("broadcast_strides", &strides);
+            }
+            context.insert("broadcast", &broadcast);
+
+            let element_count = if broadcast {
+                output_lengths[0]
+            } else {
+                ceil(output_lengths[0], 4)
+            };
+            let (x_threads, workgroup_size_x) = workgroup_size(
+                element_count,
+                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                MAX_WORKGROUP_SIZE_X,
+            )?;
+            context.insert("workgroup_size_x", &workgroup_size_x);
+            context.insert("element_count", &element_count);
+
+            NodeTemplate {
+                scalar_type,
+                template: "endomorphism/arithmetic.wgsl",
+                threads: (x_threads, 1, 1),
+            }
+        }
+
+        "Where" => {
+            if input_shapes.len() != 3 {
+                return Err(CompileError::InvalidInputCount {
+                    expected: 3,
+                    actual: input_shapes.len(),
+                });
+            }
+
+            // The condition is a boolean, the values that are selected (and the output) can be of any type
+            let condition_type = input_shapes[0].data_type;
+            if condition_type != ScalarType::Bool {
+                return Err(CompileError::TypesDisagree(
+                    ScalarType::Bool,
+                    condition_type,
+                ));
+            }
+            let scalar_type = agreed_type(&input_shapes[1..], output_shapes)?;
+
+            // All three inputs are broadcast to the output shape
+            let strides = input_shapes
+                .iter()
+                .enumerate()
+                .map(|(input_index, input_shape)| {
+                    broadcast_strides(input_shape, output_shapes[0]).ok_or_else(|| {
+                        CompileError::InvalidInputShape {
+                            input_index,
+                            input_shape: (*input_shape).clone(),
+                        }
+                    })
+                })
+                .collect::<Result<Vec<Vec<u64>>, CompileError>>()?;
+            context.insert("broadcast_strides", &strides);
+
+            let (x_threads, workgroup_size_x) = workgroup_size(
+                output_lengths[0],
+                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                MAX_WORKGROUP_SIZE_X,
+            )?;
+            context.insert("workgroup_size_x", &workgroup_size_x);
+
+            NodeTemplate {
+                scalar_type,
+                template: "endomorphism/where.wgsl",
+                threads: (x_threads, 1, 1),
+            }
+        }
+        // Not taking into account attributes
+        "BatchNormalization" => {
+            /* Prior to version 9, BatchNormalization supported a 'spatial' mode where input mean/variance are of shape
+            [C,W,H] instead of just [C]. See https://github.com/onnx/onnx/blob/master/docs/Changelog.md#BatchNormalization-7.
+            This mode is not supported. */
+            if let Ok(spatial_value) = get_attribute::<i64>("spatial", None, node) {
+                if opset_version < 9 {
+                    return Err(CompileError::UnimplementedVariant {
+                        op: "BatchNormalization".to_string(),
+                        variant: "spatial".to_string(),
+                    });
+                } else {
+                    return Err(CompileError::InvalidAttributeValue {
+                        attribute: "spatial".to_string(),
+                        opset_version,
+                        value: spatial_value.to_string(),
+                    });
+                }
+            }
+
+            // [N,C,w,h] => [N,C,w,h] where [w,h] is normalized using stats for each [N,C]
+            // N and C are optional and assumed to be one for lower-rank inputs
+            if input_shapes[0].rank() <= 2 || input_shapes[0].rank() > 4 {
                 return Err(CompileError::UnimplementedVariant {
                     op: "BatchNormalization".to_string(),
                     variant: format!("with input {}", input_shapes[0]),
@@ -559,21 +1460,60 @@ pub fn compile(
             }
         }
         op @ ("Relu" | "Sigmoid" | "Softsign" | "Softplus" | "Clip" | "Celu" | "Elu"
-        | "LeakyRelu") => {
-            let alpha = if op == "LeakyRelu" {
-                get_attribute("alpha", Some(0.01), node)?
-            } else {
-                get_attribute("alpha", Some(1.0), node)?
+        | "LeakyRelu" | "ThresholdedRelu" | "Affine" | "HardSigmoid" | "HardSwish") => {
+            let alpha = match op {
+                "LeakyRelu" => get_attribute("alpha", Some(0.01), node)?,
+                "HardSigmoid" => get_attribute("alpha", Some(0.2), node)?,
+                // HardSwish is HardSigmoid with fixed alpha and beta
+                "HardSwish" => 1.0 / 6.0,
+                _ => get_attribute("alpha", Some(1.0), node)?,
             };
             context.insert("alpha", &alpha);
 
+            let beta = match op {
+                "HardSigmoid" => get_attribute("beta", Some(0.5), node)?,
+                "HardSwish" => 0.5,
+                _ => get_attribute("beta", Some(0.0), node)?,
+            };
+            context.insert("beta", &beta);
+
+            // HardSigmoid and HardSwish clamp to [0, 1], like Clip does to [min, max] (see snippets/clamp.wgsl)
+            if op == "HardSigmoid" || op == "HardSwish" {
+                context.insert("clamp_min", "0.0");
+                context.insert("clamp_max", "1.0");
+            }
+
+            if op == "Clip" {
+                // Before opset 11, min and max are attributes. Since opset 11 these are (optional) inputs, which the
+                // optimizer moves to attributes when they are initializers or the outputs of Constant nodes.
+                if opset_version >= 11 && input_shapes.len() > 1 {
+                    return Err(CompileError::UnimplementedVariant {
+                        variant:
+                            "Clip with min or max input that is not an initializer or Constant node"
+                                .to_string(),
+                        op: op.to_string(),
+                    });
+                }
+                let min = get_attribute("min", Some(f32::MIN), node)?;
+                let max = get_attribute("max", Some(f32::MAX), node)?;
+
+                // Formatted such that extreme values are written in exponent notation
+                context.insert("clamp_min", &format!("{:?}", min));
+                context.insert("clamp_max", &format!("{:?}", max));
+            }
+
+            let element_count = ceil(output_lengths[0], 4);
             let (x_threads, workgroup_size_x) = workgroup_size(
-                ceil(output_lengths[0], 4),
+                element_count,
                 MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
                 MAX_WORKGROUP_SIZE_X,
             )?;
 
             context.insert("workgroup_size_x", &workgroup_size_x);
+            context.insert("element_count", &element_count);
+
+            // The number of elements in the last vector when the length is not a multiple of four (zero otherwise)
+            context.insert("tail_length", &(output_lengths[0] % 4));
 
             NodeTemplate {
                 scalar_type: agreed_type(input_shapes, output_shapes)?,
@@ -582,6 +1522,19 @@ pub fn compile(
             }
         }
         "Concat" => {
+            // The inputs are concatenated as flat buffers, which is only correct when all dimensions before the axis are 1
+            let axis = get_attribute::<i64>("axis", None, node)?;
+            let normalized_axis = normalize_axis(axis, output_shapes[0].rank())?;
+            if output_shapes[0].dims[..normalized_axis]
+                .iter()
+                .any(|dim| *dim != 1)
+            {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("axis={} with output shape {}", axis, output_shapes[0]),
+                    op: String::from("Concat"),
+                });
+            }
+
             let mut input_cumulative_len = vec![];
             let mut sum = 0;
             for len in input_lengths.iter() {
@@ -596,59 +1549,95 @@ pub fn compile(
                 threads: (ceil(output_lengths[0], 256) as u32, 1, 1),
             }
         }
-        op @ ("MaxPool" | "AveragePool" | "Conv" | "ConvRelu" | "ConvLeakyRelu" | "ConvMish"
-        | "GlobalAveragePool") => {
-            // TODO: Conv only support NxCxHxW for the moment.
-            debug_assert!(input_shapes[0].rank() == 4);
+        op @ ("MaxPool" | "AveragePool" | "LpPool" | "Conv" | "ConvRelu" | "ConvLeakyRelu"
+        | "ConvMish" | "ConvSwish" | "ConvInteger" | "GlobalAveragePool" | "GlobalLpPool") => {
+            // The spatial rank follows from the input, which is laid out as N x C x D1 x ... x Dn. Only Conv supports
+            // spatial ranks other than two for the moment.
+            let is_conv = op.starts_with("Conv");
+            let input_rank = input_shapes[0].rank();
+            if input_rank < 3 || (!is_conv && input_rank != 4) {
+                return Err(CompileError::UnimplementedVariant {
+                    op: op.to_string(),
+                    variant: format!("input with rank {}", input_rank),
+                });
+            }
+            let spatial_rank = input_rank - 2;
 
             // GlobalAveragePool is equivalent to AveragePool, with the kernel shape set to the size of the input tensor
             // See https://github.com/onnx/onnx/blob/main/docs/Operators.md#globalaveragepool
-            // Other attributes are not supported and also not relevant, and are simply ignored
-            let is_global_average_pool = op == "GlobalAveragePool";
-            if is_global_average_pool {
-                // Generate shader code as if this were a regular AveragePool
-                context.insert("op_type", "AveragePool");
+            // Other attributes are not supported and also not relevant, and are simply ignored. The same goes for
+            // GlobalLpPool and LpPool.
+            let is_global_pool = op == "GlobalAveragePool" || op == "GlobalLpPool";
+            if is_global_pool {
+                // Generate shader code as if this were a regular AveragePool or LpPool
+                context.insert("op_type", &op["Global".len()..]);
             }
 
             let auto_pad = get_attribute("auto_pad", Some("NOTSET".to_string()), node)?;
-            let dilations = get_attribute("dilations", Some(vec![1, 1]), node)?;
-            let kernel_shape = if is_global_average_pool {
-                vec![input_shapes[0].dim(2) as i64, input_shapes[0].dim(3) as i64]
+            let dilations = get_attribute("dilations", Some(vec![1; spatial_rank]), node)?;
+            let kernel_shape = if is_global_pool {
+                input_shapes[0].dims[2..]
+                    .iter()
+                    .map(|d| *d as i64)
+                    .collect()
+            } else if is_conv
+                && !node
+                    .get_attribute()
+                    .iter()
+                    .any(|a| a.get_name() == "kernel_shape")
+            {
+                // For Conv, the kernel shape may be omitted, in which case it is inferred from the weights
+                input_shapes[1].dims[2..]
+                    .iter()
+                    .map(|d| *d as i64)
+                    .collect()
             } else {
                 get_attribute::<Vec<i64>>("kernel_shape", None, node)?
             };
-            let strides = get_attribute("strides", Some(vec![1, 1]), node)?;
-            let pads = get_attribute("pads", Some(vec![0, 0, 0, 0]), node)?;
+            let strides = get_attribute("strides", Some(vec![1; spatial_rank]), node)?;
+            let pads = get_attribute("pads", Some(vec![0; spatial_rank * 2]), node)?;
+
+            // Each of the attributes must match the spatial rank (pads lists the begin and end padding for each axis)
+            for (attribute, value, expected_length) in [
+                ("kernel_shape", &kernel_shape, spatial_rank),
+                ("strides", &strides, spatial_rank),
+                ("dilations", &dilations, spatial_rank),
+                ("pads", &pads, spatial_rank * 2),
+            ] {
+                if value.len() != expected_length {
+                    return Err(CompileError::InvalidAttributeValue {
+                        attribute: attribute.to_string(),
+                        value: format!("{:?} (expected {} values)", value, expected_length),
+                        opset_version,
+                    });
+                }
+            }
 
             let pads = match auto_pad.as_str() {
                 "NOTSET" => pads.to_vec(),
-                "SAME_UPPER" => {
-                    let slack_0 = -strides[0] + ((kernel_shape[0] - 1) * dilations[0] + 1);
-                    let slack_0_div_2 = slack_0 / 2;
-                    let slack_rest_0 = slack_0 % 2;
-                    let slack_1 = -strides[1] + ((kernel_shape[1] - 1) * dilations[1] + 1);
-                    let slack_1_div_2 = slack_1 / 2;
-                    let slack_rest_1 = slack_1 % 2;
-                    vec![
-                        slack_0_div_2,
-                        slack_1_div_2,
-                        slack_0_div_2 + slack_rest_0,
-                        slack_1_div_2 + slack_rest_1,
-                    ]
-                }
-                "SAME_LOWER" => {
-                    let slack_0 = -strides[0] + ((kernel_shape[0] - 1) * dilations[0] + 1);
-                    let slack_0_div_2 = slack_0 / 2;
-                    let slack_rest_0 = slack_0 % 2;
-                    let slack_1 = -strides[1] + ((kernel_shape[1] - 1) * dilations[1] + 1);
-                    let slack_1_div_2 = slack_1 / 2;
-                    let slack_rest_1 = slack_1 % 2;
-                    vec![
-                        slack_0_div_2 + slack_rest_0,
-                        slack_1_div_2 + slack_rest_1,
-                        slack_0_div_2,
-                        slack_1_div_2,
-                    ]
+                // VALID means no padding at all, regardless of the pads attribute
+                "VALID" => vec![0; spatial_rank * 2],
+                "SAME_UPPER" | "SAME_LOWER" => {
+                    // Along each spatial axis the input is padded such that the output has ceil(input / stride)
+                    // elements. When the total padding is odd, the extra element of padding is added at the end
+                    // (SAME_UPPER) or at the beginning (SAME_LOWER).
+                    let mut same_pads = vec![0; spatial_rank * 2];
+                    for axis in 0..spatial_rank {
+                        let input_size = input_shapes[0].dim(axis + 2) as i64;
+                        let output_size = (input_size + strides[axis] - 1) / strides[axis];
+                        let kernel_extent = (kernel_shape[axis] - 1) * dilations[axis] + 1;
+                        let slack =
+                            ((output_size - 1) * strides[axis] + kernel_extent - input_size).max(0);
+                        let (half, rest) = (slack / 2, slack % 2);
+                        let (begin, end) = if auto_pad == "SAME_UPPER" {
+                            (half, half + rest)
+                        } else {
+                            (half + rest, half)
+                        };
+                        same_pads[axis] = begin;
+                        same_pads[spatial_rank + axis] = end;
+                    }
+                    same_pads
                 }
                 _ => {
                     return Err(CompileError::UnimplementedVariant {
@@ -660,37 +1649,161 @@ pub fn compile(
 
             let input_shape = &input_shapes[0];
             let output_shape = &output_shapes[0];
-            assert!(kernel_shape.len() >= 2);
-            assert!(kernel_shape[0] >= 0 && kernel_shape[1] >= 0);
+            assert!(kernel_shape.iter().all(|k| *k >= 0));
 
-            context.insert("original_width", &input_shape.dim(3));
-            context.insert("width", &output_shape.dim(3));
-            context.insert("original_height", &input_shape.dim(2));
+            let kernel_len: i64 = kernel_shape.iter().product();
+            if spatial_rank == 2 {
+                context.insert("original_width", &input_shape.dim(3));
+                context.insert("width", &output_shape.dim(3));
+                context.insert("original_height", &input_shape.dim(2));
+            }
+
+            // Number of kernel elements covered by a single step along each spatial axis
+            let kernel_chunks: Vec<i64> = (0..spatial_rank)
+                .map(|axis| kernel_shape[(axis + 1)..].iter().product())
+                .collect();
+            context.insert("kernel_chunks", &kernel_chunks);
             context.insert("channel", &input_shape.dim(1));
             context.insert("stride", &strides);
             context.insert("kernel_shape", &kernel_shape);
-            context.insert("kernel_len", &(kernel_shape[0] * kernel_shape[1]));
+            context.insert("kernel_len", &kernel_len);
             context.insert(
                 "kernel_channel_len",
-                &((kernel_shape[0] as u64) * (kernel_shape[1] as u64) * input_shape.dim(1)),
+                &((kernel_len as u64) * input_shape.dim(1)),
             );
             context.insert("pad", &pads);
             context.insert("dilation", &dilations);
 
             // GLSL shader for convolution computation
             match op {
-                "MaxPool" | "AveragePool" | "GlobalAveragePool" => NodeTemplate {
-                    scalar_type: agreed_type(input_shapes, &output_shapes[0..1])?,
-                    template: "pool/aggregate.wgsl",
-                    threads: (ceil(output_lengths[0], 1024) as _, 1, 1),
-                },
-                "Conv" | "ConvRelu" | "ConvLeakyRelu" | "ConvMish" => {
+                "MaxPool" | "AveragePool" | "LpPool" | "GlobalAveragePool" | "GlobalLpPool" => {
+                    // Each invocation aggregates four channels at a time. When the number of channels is not a multiple
+                    // of four, the last group of channels is only partially used.
+                    let channel_groups = ceil(input_shape.dim(1), 4);
+                    let group_count = output_shape.dim(0)
+                        * channel_groups
+                        * output_shape.dim(2)
+                        * output_shape.dim(3);
+                    context.insert("channel_groups", &channel_groups);
+                    context.insert("group_count", &group_count);
+
+                    // The order of the norm calculated by LpPool
+                    if op.ends_with("LpPool") {
+                        context.insert("p", &get_attribute("p", Some(2), node)?);
+                    }
+
+                    // MaxPool optionally outputs the indices of the maximum values in the input (flattened in row-major
+                    // order when storage_order is 0, or with the spatial dimensions in column-major order when it is 1).
+                    if op == "MaxPool" {
+                        let storage_order = get_attribute("storage_order", Some(0), node)?;
+                        if storage_order != 0 && storage_order != 1 {
+                            return Err(CompileError::InvalidAttributeValue {
+                                attribute: "storage_order".to_string(),
+                                value: storage_order.to_string(),
+                                opset_version,
+                            });
+                        }
+                        context.insert("storage_order", &storage_order);
+                        context.insert("output_indices", &(output_shapes.len() > 1));
+                    }
+
+                    NodeTemplate {
+                        scalar_type: agreed_type(input_shapes, &output_shapes[0..1])?,
+                        template: "pool/aggregate.wgsl",
+                        threads: (ceil(group_count, 256) as _, 1, 1),
+                    }
+                }
+                "ConvInteger" => {
+                    // Inputs: x, w, x_zero_point, w_zero_point. The zero points are optional and may be omitted by
+                    // leaving the input name empty. x_zero_point is a scalar, w_zero_point is either a scalar or has an
+                    // element for each output channel.
+                    if let Some(shape) = input_shapes
+                        .iter()
+                        .find(|shape| !matches!(shape.data_type, ScalarType::I8 | ScalarType::U8))
+                    {
+                        return Err(CompileError::UnimplementedVariant {
+                            variant: format!("{} input", shape.data_type),
+                            op: op.to_string(),
+                        });
+                    }
+
+                    // Grouped convolutions are not supported (each output channel is computed from all input channels)
+                    let group = get_attribute("group", Some(1), node)?;
+                    if group != 1 {
+                        return Err(CompileError::UnimplementedVariant {
+                            variant: format!("group={}", group),
+                            op: op.to_string(),
+                        });
+                    }
+
+                    let input_present = |index: usize| {
+                        node.get_input()
+                            .get(index)
+                            .map(|name| !name.is_empty())
+                            .unwrap_or(false)
+                    };
+                    let has_x_zero_point = input_present(2);
+                    let has_w_zero_point = input_present(3);
+                    if has_x_zero_point && input_shapes[2].element_count() != 1 {
+                        return Err(CompileError::InvalidInputShape {
+                            input_index: 2,
+                            input_shape: input_shapes[2].clone(),
+                        });
+                    }
+                    let w_zero_point_per_channel = if has_w_zero_point {
+                        let w_zero_point = input_shapes[input_shapes.len() - 1];
+                        if w_zero_point.element_count() != 1
+                            && w_zero_point.dims != [output_shape.dim(1)]
+                        {
+                            return Err(CompileError::InvalidInputShape {
+                                input_index: 3,
+                                input_shape: w_zero_point.clone(),
+                            });
+                        }
+                        w_zero_point.element_count() != 1
+                    } else {
+                        false
+                    };
+
+                    // Determine where each input and output is bound (bindings are numbered in the order of the inputs
+                    // that are present, followed by the output, four bindings per group)
+                    let mut bindings = HashMap::new();
+                    let mut binding_index = 0;
+                    for (index, name) in ["x", "w", "x_zero_point", "w_zero_point"]
+                        .iter()
+                        .enumerate()
+                    {
+                        if input_present(index) {
+                            bindings.insert(*name, [binding_index / 4, binding_index % 4]);
+                            binding_index += 1;
+                        }
+                    }
+                    bindings.insert("y", [binding_index / 4, binding_index % 4]);
+
+                    context.insert("bindings", &bindings);
+                    context.insert("has_x_zero_point", &has_x_zero_point);
+                    context.insert("has_w_zero_point", &has_w_zero_point);
+                    context.insert("w_zero_point_per_channel", &w_zero_point_per_channel);
+
+                    NodeTemplate {
+                        scalar_type: ScalarType::I32,
+                        template: "pool/conv_integer.wgsl",
+                        threads: (ceil(output_lengths[0], 256) as _, 1, 1),
+                    }
+                }
+                "Conv" | "ConvRelu" | "ConvLeakyRelu" | "ConvMish" | "ConvSwish" => {
                     // Alpha is the Leaky Relu attribute
                     let alpha = get_attribute("alpha", Some(0.01), node)?;
                     context.insert("alpha", &alpha);
 
                     // WGSL shader for convolution computation
-                    if (strides == [1, 1])
+                    if spatial_rank != 2 {
+                        NodeTemplate {
+                            scalar_type: agreed_type(input_shapes, output_shapes)?,
+                            template: "pool/conv_nd.wgsl",
+                            threads: (ceil(output_lengths[0], 256) as _, 1, 1),
+                        }
+                    } else if (strides == [1, 1])
                         && (kernel_shape == [1, 1])
                         && (dilations == [1, 1] && (pads == [0, 0, 0, 0]))
                         && (input_shape.dim(1) % 16 == 0)
@@ -701,11 +1814,7 @@ pub fn compile(
                             template: "pool/conv_kernel_1.wgsl",
                             threads: (ceil(output_lengths[0], 1024) as _, 1, 1),
                         }
-                    } else if (strides == [1, 1])
-                        && (kernel_shape == [3, 3])
-                        && (dilations == [1, 1])
-                        && (output_shape.dim(1) % 4 == 0)
-                    {
+                    } else if uses_conv_kernel_3(&kernel_shape, &strides, output_shape.dim(1)) {
                         NodeTemplate {
                             scalar_type: agreed_type(input_shapes, output_shapes)?,
                             template: "pool/conv_kernel_3.wgsl",
@@ -722,6 +1831,48 @@ pub fn compile(
                 _ => return Err(CompileError::InvalidOperation(op.to_string())),
             }
         }
+        "MatMul" if input_shapes.iter().any(|shape| shape.rank() == 1) => {
+            // A one-dimensional operand is promoted to a matrix ([K] to [1, K] for A, [K] to [K, 1] for B), and the added
+            // dimension is removed from the result again. The elements are laid out the same either way, so only the
+            // dimensions need to be promoted. Each output element is computed as a dot product.
+            let (a, b) = (input_shapes[0], input_shapes[1]);
+            if a.rank() > 2 || b.rank() > 2 {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: "batched operands combined with a one-dimensional operand".to_string(),
+                    op: "MatMul".to_string(),
+                });
+            }
+            let (m, k) = match a.dims.as_slice() {
+                [k] => (1, *k),
+                dims => (dims[0], dims[1]),
+            };
+            let (k_b, n) = match b.dims.as_slice() {
+                [k] => (*k, 1),
+                dims => (dims[0], dims[1]),
+            };
+            if k != k_b {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 1,
+                    input_shape: b.clone(),
+                });
+            }
+
+            let (x_threads, workgroup_size_x) = workgroup_size(
+                m * n,
+                MAX_COMPUTE_WORKGROUPS_PER_DIMENSION,
+                MAX_WORKGROUP_SIZE_X,
+            )?;
+            context.insert("m", &m);
+            context.insert("n", &n);
+            context.insert("k", &k);
+            context.insert("workgroup_size_x", &workgroup_size_x);
+
+            NodeTemplate {
+                scalar_type: agreed_type(input_shapes, output_shapes)?,
+                template: "matrix/matmul_vector.wgsl",
+                threads: (x_threads, 1, 1),
+            }
+        }
         op @ ("Gemm" | "MatMul") => {
             let alpha = get_attribute("alpha", Some(1.0), node)?;
             let beta = get_attribute("beta", Some(1.0), node)?;
@@ -743,21 +1894,55 @@ pub fn compile(
             context.insert("alpha", &alpha);
             context.insert("beta", &beta);
 
-            // Whether A resp. B should be transposed, or C should be broadcast (default: 0 = false)
-            if op == "Gemm" {
-                let transpose_a = get_attribute("transA", Some(0), node)?;
-                let transpose_b = get_attribute("transB", Some(0), node)?;
-                let broadcast = get_attribute("broadcast", Some(0), node)?;
-
-                if transpose_a != 0 || transpose_b != 0 || broadcast != 0 {
-                    return Err(CompileError::UnimplementedVariant {
-                        variant: "Gemm with transA/transB/broadcast not equal to zero".to_string(),
-                        op: op.to_string(),
-                    });
+            // C is optional for Gemm (since opset 11) and never present for MatMul. When C is omitted the beta * C term
+            // is left out entirely.
+            let has_bias = op == "Gemm" && input_shapes.len() > 2;
+            context.insert("has_bias", &has_bias);
+
+            let (m, n) = (input_shapes[0].dim(0), input_shapes[1].dim(1));
+            if input_shapes[0].rank() == 2
+                && input_shapes[1].rank() == 2
+                && m > GEMM_TILED_THRESHOLD
+                && n > GEMM_TILED_THRESHOLD
+            {
+                let k = input_shapes[0].dim(1);
+                context.insert("m", &m);
+                context.insert("n", &n);
+                context.insert("k", &k);
+                context.insert("tile_size", &GEMM_TILE_SIZE);
+                context.insert("tile_count", &ceil(k, GEMM_TILE_SIZE));
+
+                // C is broadcast to [M, N]; along dimensions of size 1 the same element is used for every row/column
+                if has_bias {
+                    let bias_shape = &input_shapes[2];
+                    let bias_dim = |index_from_end: usize| {
+                        if bias_shape.rank() > index_from_end {
+                            bias_shape.dim(bias_shape.rank() - 1 - index_from_end)
+                        } else {
+                            1
+                        }
+                    };
+                    let (bias_rows, bias_columns) = (bias_dim(1), bias_dim(0));
+                    context.insert(
+                        "bias_row_stride",
+                        &(if bias_rows == 1 { 0 } else { bias_columns }),
+                    );
+                    context.insert(
+                        "bias_column_stride",
+                        &(if bias_columns == 1 { 0 } else { 1 }),
+                    );
                 }
-            }
 
-            if input_shapes[0].dim(0) == 1 {
+                NodeTemplate {
+                    scalar_type: agreed_type(input_shapes, output_shapes)?,
+                    template: "matrix/gemm_tiled.wgsl",
+                    threads: (
+                        ceil(n, GEMM_TILE_SIZE) as _,
+                        ceil(m, GEMM_TILE_SIZE) as _,
+                        1,
+                    ),
+                }
+            } else if input_shapes[0].dim(0) == 1 {
                 NodeTemplate {
                     scalar_type: agreed_type(input_shapes, output_shapes)?,
                     template: "matrix/gemm_1.wgsl",
@@ -775,6 +1960,518 @@ pub fn compile(
                 }
             }
         }
+        op @ ("RandomNormal" | "RandomUniform") => {
+            let dtype = get_attribute("dtype", Some(1), node)?;
+            if dtype != 1 {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("dtype={}", dtype),
+                    op: op.to_string(),
+                });
+            }
+
+            // The seed determines the generated values. When no seed is set, one is generated when the node is compiled
+            // (as the ONNX specification prescribes), unless deterministic execution was requested.
+            let has_seed = node.get_attribute().iter().any(|a| a.get_name() == "seed");
+            let seed = if has_seed {
+                get_attribute::<f32>("seed", None, node)?.to_bits()
+            } else if config.deterministic {
+                0
+            } else {
+                generated_seed()
+            };
+            context.insert("seed", &seed);
+            context.insert("mean", &get_attribute("mean", Some(0.0f32), node)?);
+            context.insert("scale", &get_attribute("scale", Some(1.0f32), node)?);
+            context.insert("low", &get_attribute("low", Some(0.0f32), node)?);
+            context.insert("high", &get_attribute("high", Some(1.0f32), node)?);
+
+            NodeTemplate {
+                scalar_type: agreed_type(&[], output_shapes)?,
+                template: "generate/random.wgsl",
+                threads: (ceil(output_lengths[0], 256) as _, 1, 1),
+            }
+        }
+        "If" => {
+            // If nodes with a constant condition are replaced by the selected branch before compilation. Conditions
+            // that are computed by other nodes are not evaluated then, even when these only depend on constants or
+            // shapes (e.g. Shape -> Equal).
+            return Err(CompileError::UnimplementedVariant {
+                variant: "If with a condition that is not an initializer or the output of a Constant node"
+                    .to_string(),
+                op: "If".to_string(),
+            });
+        }
+        "Im2Col" => {
+            // Im2Col is not an ONNX operator; it is generated by the optimizer when a Conv is lowered to a Gemm. It writes
+            // each patch of the input that the kernel is applied to as a column of the output ([C*kH*kW, H_out*W_out]).
+            let kernel_shape = get_attribute::<Vec<i64>>("kernel_shape", None, node)?;
+            let dilations = get_attribute("dilations", Some(vec![1, 1]), node)?;
+            let strides = get_attribute("strides", Some(vec![1, 1]), node)?;
+            let pads = get_attribute("pads", Some(vec![0, 0, 0, 0]), node)?;
+
+            let input_shape = &input_shapes[0];
+            let output_width = (input_shape.dim(3) as i64 + pads[1] + pads[3]
+                - dilations[1] * (kernel_shape[1] - 1)
+                - 1)
+                / strides[1]
+                + 1;
+
+            context.insert("original_width", &input_shape.dim(3));
+            context.insert("original_height", &input_shape.dim(2));
+            context.insert("width", &output_width);
+            context.insert("stride", &strides);
+            context.insert("kernel_shape", &kernel_shape);
+            context.insert("kernel_len", &(kernel_shape[0] * kernel_shape[1]));
+            context.insert("pad", &pads);
+            context.insert("dilation", &dilations);
+
+            NodeTemplate {
+                scalar_type: agreed_type(input_shapes, output_shapes)?,
+                template: "pool/im2col.wgsl",
+                threads: (ceil(output_lengths[0], 256) as _, 1, 1),
+            }
+        }
+        "LSTM" | "GRU" => {
+            // Inputs: X, W, R, B, sequence_lens, initial_h (and for LSTM: initial_c, P). All but the first three are
+            // optional and may be omitted by leaving the input name empty. Outputs: Y, Y_h (and for LSTM: Y_c), which are
+            // all optional. Omitted inputs and outputs are not bound to the shader.
+            let input_present = |index: usize| {
+                node.get_input()
+                    .get(index)
+                    .map(|name| !name.is_empty())
+                    .unwrap_or(false)
+            };
+            let output_present = |index: usize| {
+                node.get_output()
+                    .get(index)
+                    .map(|name| !name.is_empty())
+                    .unwrap_or(false)
+            };
+
+            let is_lstm = node.get_op_type() == "LSTM";
+            let (num_gates, default_activations, input_names, output_names, template) = if is_lstm {
+                (
+                    4,
+                    vec!["Sigmoid", "Tanh", "Tanh"],
+                    vec![
+                        "x",
+                        "w",
+                        "r",
+                        "bias",
+                        "sequence_lens",
+                        "initial_h",
+                        "initial_c",
+                    ],
+                    vec!["y", "y_h", "y_c"],
+                    "matrix/lstm.wgsl",
+                )
+            } else {
+                (
+                    3,
+                    vec!["Sigmoid", "Tanh"],
+                    vec!["x", "w", "r", "bias", "sequence_lens", "initial_h"],
+                    vec!["y", "y_h"],
+                    "matrix/gru.wgsl",
+                )
+            };
+
+            let direction = get_attribute("direction", Some("forward".to_string()), node)?;
+            if direction != "forward" {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("direction={}", direction),
+                    op: node.get_op_type().to_string(),
+                });
+            }
+
+            let layout = get_attribute("layout", Some(0), node)?;
+            let input_forget = get_attribute("input_forget", Some(0), node)?;
+            if layout != 0 || input_forget != 0 {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("layout={}, input_forget={}", layout, input_forget),
+                    op: node.get_op_type().to_string(),
+                });
+            }
+
+            let activations = get_attribute::<Vec<String>>("activations", Some(vec![]), node)?;
+            if !activations.is_empty() && activations != default_activations {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: format!("activations={}", activations.join(",")),
+                    op: node.get_op_type().to_string(),
+                });
+            }
+
+            if input_present(4) || (is_lstm && input_present(7)) {
+                return Err(CompileError::UnimplementedVariant {
+                    variant: "sequence_lens or peephole (P) inputs".to_string(),
+                    op: node.get_op_type().to_string(),
+                });
+            }
+
+            // X is [seq_length, batch_size, input_size]
+            if input_shapes[0].rank() != 3 {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 0,
+                    input_shape: input_shapes[0].clone(),
+                });
+            }
+            let seq_length = input_shapes[0].dim(0);
+            let batch_size = input_shapes[0].dim(1);
+            let input_size = input_shapes[0].dim(2);
+            let hidden_size = get_attribute::<i64>("hidden_size", None, node)? as u64;
+
+            // Each invocation in the workgroup calculates one element of the hidden state
+            if hidden_size > MAX_WORKGROUP_SIZE_X as u64 {
+                return Err(CompileError::ComputeLimitExceeded(
+                    String::from("workgroup size"),
+                    hidden_size as _,
+                    MAX_WORKGROUP_SIZE_X,
+                ));
+            }
+
+            // Determine where each input and output is bound (bindings are numbered in the order of the inputs that
+            // are present, followed by the outputs, four bindings per group)
+            let mut bindings = HashMap::new();
+            let mut binding_index = 0;
+            for (index, name) in input_names.iter().enumerate() {
+                if input_present(index) {
+                    bindings.insert(*name, [binding_index / 4, binding_index % 4]);
+                    binding_index += 1;
+                }
+            }
+            for (index, name) in output_names.iter().enumerate() {
+                if output_present(index) {
+                    bindings.insert(*name, [binding_index / 4, binding_index % 4]);
+                    binding_index += 1;
+                }
+            }
+
+            if let Ok(clip) = get_attribute::<f32>("clip", None, node) {
+                context.insert("clip", &clip);
+            }
+            if !is_lstm {
+                let linear_before_reset = get_attribute("linear_before_reset", Some(0), node)?;
+                context.insert("linear_before_reset", &(linear_before_reset != 0));
+            }
+            context.insert("bindings", &bindings);
+            context.insert("has_bias", &input_present(3));
+            context.insert("has_initial_h", &input_present(5));
+            context.insert("has_initial_c", &(is_lstm && input_present(6)));
+            context.insert("num_gates", &num_gates);
+            context.insert("seq_length", &seq_length);
+            context.insert("batch_size", &batch_size);
+            context.insert("input_size", &input_size);
+            context.insert("hidden_size", &hidden_size);
+
+            let present_output_shapes: Vec<&Shape> = output_shapes
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| output_present(*index))
+                .map(|(_, shape)| *shape)
+                .collect();
+
+            // One workgroup per batch; the recurrence over the time steps happens inside the shader
+            NodeTemplate {
+                scalar_type: agreed_type(&input_shapes[0..1], &present_output_shapes)?,
+                template,
+                threads: (1, batch_size as _, 1),
+            }
+        }
+        op @ ("LayerNormalization"
+        | "com.microsoft.LayerNormalization"
+        | "com.microsoft.SkipLayerNormalization") => {
+            // LayerNormalization inputs: input, gamma, beta (optional). Outputs: output, mean (optional), inv_std_var
+            // (optional). SkipLayerNormalization additionally takes a skip input (after the input) and a bias input (at
+            // the end), which are added to the input before normalizing, and can output this sum as well.
+            let has_skip = op == "com.microsoft.SkipLayerNormalization";
+            let input_names: &[&str] = if has_skip {
+                &["input", "skip", "gamma", "beta", "bias"]
+            } else {
+                &["input", "gamma", "beta"]
+            };
+            let input_index = |name: &str| input_names.iter().position(|n| *n == name);
+            let input_present = |name: &str| {
+                input_index(name)
+                    .and_then(|index| node.get_input().get(index))
+                    .map_or(false, |input_name| !input_name.is_empty())
+            };
+            let required_inputs = if has_skip { 3 } else { 2 };
+            if !input_names[0..required_inputs]
+                .iter()
+                .all(|name| input_present(name))
+            {
+                return Err(CompileError::InvalidInputCount {
+                    expected: required_inputs,
+                    actual: input_shapes.len(),
+                });
+            }
+
+            // Each row of elements (from the axis onwards) is normalized separately. SkipLayerNormalization always
+            // normalizes over the last dimension.
+            let axis = normalize_axis(
+                get_attribute("axis", Some(-1), node)?,
+                input_shapes[0].rank().max(1),
+            )?;
+            let row_size: u64 = input_shapes[0].dims.iter().skip(axis).product();
+            if has_skip && input_shapes[1].dims != input_shapes[0].dims {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 1,
+                    input_shape: input_shapes[1].clone(),
+                });
+            }
+            let gamma_index = required_inputs - 1;
+            if input_shapes[gamma_index].element_count() != row_size {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: gamma_index,
+                    input_shape: input_shapes[gamma_index].clone(),
+                });
+            }
+            let row_count = input_lengths[0] / row_size;
+
+            // Determine where each input and output is bound (bindings are numbered in the order of the inputs that
+            // are present, followed by the outputs, four bindings per group)
+            let mut bindings = HashMap::new();
+            let mut binding_index = 0;
+            for name in input_names.iter() {
+                if input_present(name) {
+                    bindings.insert(*name, [binding_index / 4, binding_index % 4]);
+                    binding_index += 1;
+                }
+            }
+            for name in ["output", "mean", "inv_std_var", "sum"]
+                .iter()
+                .take(output_shapes.len())
+            {
+                bindings.insert(*name, [binding_index / 4, binding_index % 4]);
+                binding_index += 1;
+            }
+
+            let default_epsilon = if has_skip { 1e-12 } else { 1e-5 };
+            let epsilon = get_attribute("epsilon", Some(default_epsilon), node)?;
+            context.insert("epsilon", &epsilon);
+            context.insert("bindings", &bindings);
+            context.insert("has_skip", &has_skip);
+            context.insert("has_beta", &input_present("beta"));
+            context.insert("has_bias", &input_present("bias"));
+            context.insert("has_mean", &(output_shapes.len() > 1));
+            context.insert("has_inv_std_var", &(output_shapes.len() > 2));
+            context.insert("has_sum", &(output_shapes.len() > 3));
+            context.insert("row_count", &row_count);
+            context.insert("row_size", &row_size);
+
+            NodeTemplate {
+                scalar_type: agreed_type(&input_shapes[0..1], &output_shapes[0..1])?,
+                template: "endomorphism/layernormalization.wgsl",
+                threads: (ceil(row_count, 256) as _, 1, 1),
+            }
+        }
+        "GroupNormalization" => {
+            // Inputs: X [N, C, D1, ...], scale and bias. In opset 18 the scale and bias hold a value for each group; later
+            // opsets changed this to a value for each channel. Both are accepted.
+            if input_shapes.len() != 3 {
+                return Err(CompileError::InvalidInputCount {
+                    expected: 3,
+                    actual: input_shapes.len(),
+                });
+            }
+            if input_shapes[0].rank() < 2 {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 0,
+                    input_shape: input_shapes[0].clone(),
+                });
+            }
+
+            let num_groups = get_attribute::<i64>("num_groups", None, node)?;
+            let (batches, channels) = (input_shapes[0].dim(0), input_shapes[0].dim(1));
+            if num_groups <= 0 || channels % (num_groups as u64) != 0 {
+                return Err(CompileError::InvalidAttributeValue {
+                    attribute: "num_groups".to_string(),
+                    value: format!("{} (for {} channels)", num_groups, channels),
+                    opset_version,
+                });
+            }
+            let num_groups = num_groups as u64;
+
+            let per_channel = input_shapes[1].element_count() == channels;
+            let parameter_count = if per_channel { channels } else { num_groups };
+            for (input_index, input_shape) in input_shapes.iter().enumerate().skip(1) {
+                if input_shape.element_count() != parameter_count {
+                    return Err(CompileError::InvalidInputShape {
+                        input_index,
+                        input_shape: (*input_shape).clone(),
+                    });
+                }
+            }
+
+            let channels_per_group = channels / num_groups;
+            let channel_size = input_lengths[0] / (batches * channels).max(1);
+            let group_count = batches * num_groups;
+            let epsilon = get_attribute("epsilon", Some(1e-05), node)?;
+            context.insert("epsilon", &epsilon);
+            context.insert("num_groups", &num_groups);
+            context.insert("group_count", &group_count);
+            context.insert("group_size", &(channels_per_group * channel_size));
+            context.insert("channels_per_group", &channels_per_group);
+            context.insert("channel_size", &channel_size);
+            context.insert("per_channel", &per_channel);
+
+            NodeTemplate {
+                scalar_type: agreed_type(&input_shapes[0..1], &output_shapes[0..1])?,
+                template: "endomorphism/groupnormalization.wgsl",
+                threads: (ceil(group_count, 256) as _, 1, 1),
+            }
+        }
+        "NonMaxSuppression" => {
+            // Inputs: boxes [num_batches, spatial_dimension, 4] and scores [num_batches, num_classes, spatial_dimension].
+            // The optional max_output_boxes_per_class, iou_threshold and score_threshold inputs have been moved to
+            // attributes by the optimizer.
+            if input_shapes.len() != 2 {
+                return Err(CompileError::InvalidInputCount {
+                    expected: 2,
+                    actual: input_shapes.len(),
+                });
+            }
+
+            if input_shapes[0].rank() != 3 || input_shapes[0].dim(2) != 4 {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 0,
+                    input_shape: input_shapes[0].clone(),
+                });
+            }
+
+            let num_batches = input_shapes[0].dim(0);
+            let spatial_dimension = input_shapes[0].dim(1);
+            if input_shapes[1].rank() != 3
+                || input_shapes[1].dim(0) != num_batches
+                || input_shapes[1].dim(2) != spatial_dimension
+            {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 1,
+                    input_shape: input_shapes[1].clone(),
+                });
+            }
+            let num_classes = input_shapes[1].dim(1);
+
+            // The output is [num_selected_indices, 3]; as the number of selected boxes depends on the data, the first
+            // dimension is the maximum number of boxes that can be returned.
+            if output_shapes[0].rank() != 2
+                || output_shapes[0].dim(1) != 3
+                || output_shapes[0].data_type != ScalarType::I64
+            {
+                return Err(CompileError::InvalidOutputShape {
+                    output_index: 0,
+                    output_shape: output_shapes[0].clone(),
+                });
+            }
+
+            let center_point_box = get_attribute("center_point_box", Some(0), node)?;
+            let max_output_boxes_per_class =
+                get_attribute("max_output_boxes_per_class", Some(0), node)?;
+            let iou_threshold = get_attribute("iou_threshold", Some(0.0), node)?;
+            if let Ok(score_threshold) = get_attribute::<f32>("score_threshold", None, node) {
+                context.insert("score_threshold", &score_threshold);
+            }
+
+            context.insert("center_point_box", &center_point_box);
+            context.insert(
+                "max_output_boxes_per_class",
+                &max_output_boxes_per_class.max(0),
+            );
+            context.insert("iou_threshold", &iou_threshold);
+            context.insert("num_batches", &num_batches);
+            context.insert("num_classes", &num_classes);
+            context.insert("spatial_dimension", &spatial_dimension);
+            context.insert("max_selected", &output_shapes[0].dim(0));
+
+            NodeTemplate {
+                scalar_type: agreed_type(input_shapes, &[])?,
+                template: "matrix/nonmaxsuppression.wgsl",
+                threads: (1, 1, 1),
+            }
+        }
+        "RoiAlign" => {
+            // Inputs: X [N, C, H, W], rois [num_rois, 4] and batch_indices [num_rois]
+            if input_shapes.len() != 3 {
+                return Err(CompileError::InvalidInputCount {
+                    expected: 3,
+                    actual: input_shapes.len(),
+                });
+            }
+
+            if input_shapes[0].rank() != 4 {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 0,
+                    input_shape: input_shapes[0].clone(),
+                });
+            }
+
+            if input_shapes[1].rank() != 2 || input_shapes[1].dim(1) != 4 {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 1,
+                    input_shape: input_shapes[1].clone(),
+                });
+            }
+
+            if input_shapes[2].data_type != ScalarType::I64 {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 2,
+                    input_shape: input_shapes[2].clone(),
+                });
+            }
+
+            // The 'half_pixel' mode was added (and made the default) in opset 16
+            let default_coordinate_transformation_mode = if opset_version >= 16 {
+                "half_pixel"
+            } else {
+                "output_half_pixel"
+            };
+            let coordinate_transformation_mode = get_attribute(
+                "coordinate_transformation_mode",
+                Some(default_coordinate_transformation_mode.to_string()),
+                node,
+            )?;
+            let offset: f32 = match coordinate_transformation_mode.as_str() {
+                "half_pixel" => 0.5,
+                "output_half_pixel" => 0.0,
+                _ => {
+                    return Err(CompileError::InvalidAttributeValue {
+                        attribute: "coordinate_transformation_mode".to_string(),
+                        value: coordinate_transformation_mode,
+                        opset_version,
+                    })
+                }
+            };
+
+            let mode = get_attribute("mode", Some("avg".to_string()), node)?;
+            if mode != "avg" && mode != "max" {
+                return Err(CompileError::InvalidAttributeValue {
+                    attribute: "mode".to_string(),
+                    value: mode,
+                    opset_version,
+                });
+            }
+
+            let output_height = get_attribute("output_height", Some(1), node)?;
+            let output_width = get_attribute("output_width", Some(1), node)?;
+            let sampling_ratio = get_attribute("sampling_ratio", Some(0), node)?;
+            let spatial_scale = get_attribute("spatial_scale", Some(1.0), node)?;
+
+            context.insert(
+                "coordinate_transformation_mode",
+                &coordinate_transformation_mode,
+            );
+            context.insert("offset", &offset);
+            context.insert("mode", &mode);
+            context.insert("output_height", &output_height);
+            context.insert("output_width", &output_width);
+            context.insert("sampling_ratio", &sampling_ratio);
+            context.insert("spatial_scale", &spatial_scale);
+
+            NodeTemplate {
+                scalar_type: agreed_type(&input_shapes[0..2], output_shapes)?,
+                template: "matrix/roialign.wgsl",
+                threads: (ceil(output_lengths[0], 256) as u32, 1, 1),
+            }
+        }
         "Resize" => {
             let coordinate_transformation_mode = get_attribute(
                 "coordinate_transformation_mode",
@@ -792,11 +2489,38 @@ pub fn compile(
                 "align_corners" => {}
                 "asymmetric" => {}
                 "tf_crop_and_resize" => {
-                    let roi = get_attribute::<Vec<i64>>("roi", None, node)?;
-                    let extrapolation_value =
+                    // The roi lists the normalized start coordinates for each axis, followed by the end coordinates. An
+                    // output coordinate d along an axis maps to the input coordinate offset + d * step.
+                    let roi = get_attribute::<Vec<f32>>("roi", None, node)?;
+                    let rank = input_shapes[0].rank();
+                    if roi.len() != rank * 2 {
+                        return Err(CompileError::InvalidAttributeValue {
+                            attribute: "roi".to_string(),
+                            value: format!("{:?}", roi),
+                            opset_version,
+                        });
+                    }
+                    let (roi_offsets, roi_steps): (Vec<String>, Vec<String>) = (0..rank)
+                        .map(|axis| {
+                            let (start, end) = (roi[axis], roi[axis + rank]);
+                            let dim = input_shapes[0].dim(axis) as f32;
+                            let output_dim = output_shapes[0].dim(axis) as f32;
+                            let (offset, step) = if output_dim > 1.0 {
+                                (
+                                    start * (dim - 1.0),
+                                    (end - start) * (dim - 1.0) / (output_dim - 1.0),
+                                )
+                            } else {
+                                (0.5 * (start + end) * (dim - 1.0), 0.0)
+                            };
+                            (format!("{:?}", offset), format!("{:?}", step))
+                        })
+                        .unzip();
+                    let extrapolation_value: f32 =
                         get_attribute("extrapolation_value", Some(0.0), node)?;
-                    context.insert("roi", &roi);
-                    context.insert("extrapolation_value", &extrapolation_value);
+                    context.insert("roi_offsets", &roi_offsets);
+                    context.insert("roi_steps", &roi_steps);
+                    context.insert("extrapolation_value", &format!("{:?}", extrapolation_value));
                 }
                 _ => {
                     return Err(CompileError::UnimplementedVariant {
@@ -809,24 +2533,29 @@ pub fn compile(
                 }
             }
 
+            // When sizes are specified instead of scales, the coordinates are mapped using the exact (integer) input and
+            // output sizes, as the scales derived from these cannot always be represented exactly
             let scales = get_attribute::<Vec<f32>>("scales", Some(vec![]), node)?;
-            let scale_prints = if scales.is_empty() {
-                let sizes = get_attribute::<Vec<i64>>("sizes", Some(vec![]), node)?;
-                sizes
-                    .iter()
-                    .enumerate()
-                    .map(|(i, x)| {
-                        let tmp = *x as f32 / input_shapes[0].dim(i) as f32;
-                        format!("{:.2}", tmp)
-                    })
-                    .collect::<Vec<_>>()
+            let use_sizes = scales.is_empty();
+            let axis_count = if use_sizes {
+                get_attribute::<Vec<i64>>("sizes", Some(vec![]), node)?.len()
             } else {
-                scales.iter().map(|x| format!("{:.2}", x)).collect()
+                scales.len()
             };
+            let scale_prints: Vec<String> = scales.iter().map(|x| format!("{:?}", x)).collect();
+
+            // Each axis (including batch and channel axes) is scaled independently
+            if axis_count != input_shapes[0].rank() {
+                return Err(CompileError::InvalidInputShape {
+                    input_index: 0,
+                    input_shape: input_shapes[0].clone(),
+                });
+            }
 
             let mode = get_attribute("mode", Some("nearest".to_string()), node)?;
             context.insert("mode", &mode);
             context.insert("scales", &scale_prints);
+            context.insert("use_sizes", &use_sizes);
 
             match mode.as_str() {
                 "nearest" => {
@@ -871,15 +2600,14 @@ pub fn compile(
                 threads: (ceil(output_lengths[0], 256) as u32, 1, 1),
             }
         }
-        "Sum" => return Err(CompileError::UnimplementedOp(String::from("Sum"))),
         "Split" => {
-            let mut axis = get_attribute("axis", Some(0), node)?;
-            if axis < 0 {
-                axis += input_shapes[0].element_count() as i64
-            }
+            let axis = normalize_axis(
+                get_attribute("axis", Some(0), node)?,
+                input_shapes[0].rank(),
+            )?;
             context.insert("axis", &axis);
 
-            let split_chunk = input_shapes[0].dim(axis as usize) as usize / output_shapes.len();
+            let split_chunk = input_shapes[0].dim(axis) as usize / output_shapes.len();
             let default_split = (1..=output_shapes.len())
                 .map(|x| (x * split_chunk) as _)
                 .collect();
@@ -894,18 +2622,37 @@ pub fn compile(
             }
         }
         "Transpose" => {
-            let default = ((input_lengths[0] as i64)..0).collect::<Vec<_>>();
+            // By default the axes are reversed
+            let rank = input_shapes[0].rank();
+            let default = (0..rank as i64).rev().collect::<Vec<_>>();
             let perms: Vec<i64> = get_attribute("perm", Some(default), node)?;
-            let permuted_shapes = perms
+            let mut sorted_perms = perms.clone();
+            sorted_perms.sort_unstable();
+            if sorted_perms != (0..rank as i64).collect::<Vec<_>>() {
+                return Err(CompileError::InvalidAttributeValue {
+                    attribute: "perm".to_string(),
+                    value: format!("{:?} (not a permutation of the {} input axes)", perms, rank),
+                    opset_version,
+                });
+            }
+
+            let permuted_dims: Vec<u64> = perms
                 .iter()
-                .map(|p| output_shapes[0].dim(*p as usize))
-                .collect::<Vec<_>>();
+                .map(|p| input_shapes[0].dim(*p as usize))
+                .collect();
+            if output_shapes[0].dims != permuted_dims {
+                return Err(CompileError::InvalidOutputShape {
+                    output_index: 0,
+                    output_shape: output_shapes[0].clone(),
+                });
+            }
 
-            let mut chunks = vec![];
-            for i in 1..permuted_shapes.len() {
-                chunks.push(permuted_shapes[i..].iter().product::<u64>());
+            // Output axis j is input axis perms[j], so a step along input axis i is a step of the output chunk of the
+            // axis j for which perms[j] == i
+            let mut chunks = vec![0; rank];
+            for (output_axis, input_axis) in perms.iter().enumerate() {
+                chunks[*input_axis as usize] = output_chunks[0][output_axis];
             }
-            chunks.push(1);
 
             context.insert("permuted_chunks", &chunks);
 
@@ -965,7 +2712,37 @@ pub fn compile(
     })
 }
 
+/// Determine, for each dimension of the output shape, the stride of the corresponding dimension in the (broadcast) input
+/// shape following the multidirectional broadcasting rules of ONNX. Broadcast dimensions have a stride of zero. Returns
+/// None when the input cannot be broadcast to the output shape.
+fn broadcast_strides(input_shape: &Shape, output_shape: &Shape) -> Option<Vec<u64>> {
+    let output_rank = output_shape.rank();
+    let input_rank = input_shape.rank();
+    if input_rank > output_rank {
+        return None;
+    }
+
+    let input_chunks = input_shape.chunks();
+    (0..output_rank)
+        .map(|output_axis| {
+            if output_axis < output_rank - input_rank {
+                return Some(0);
+            }
+
+            let input_axis = output_axis - (output_rank - input_rank);
+            match input_shape.dim(input_axis) {
+                d if d == output_shape.dim(output_axis) => Some(input_chunks[input_axis]),
+                1 => Some(0),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
 /// Determines the appropriate number of threads and workgroup size given a number of times the entry point of the shader should be run
+///
+/// When possible, a workgroup size is chosen that divides the number of invocations evenly. Otherwise, the number of
+/// invocations (threads * workgroup size) will exceed `x`, and the shader must skip invocations with index >= x.
 fn workgroup_size(
     x: u64,
     max_threads: u32,
@@ -974,7 +2751,10 @@ fn workgroup_size(
     let max_x = max_threads as u64;
 
     Ok(if x > max_x {
-        let workgroup_size = ceil(x, max_x) as _;
+        let min_workgroup_size = ceil(x, max_x);
+        let workgroup_size = (min_workgroup_size..=(max_workgroup_size as u64))
+            .find(|size| (x / size) * size == x)
+            .unwrap_or(min_workgroup_size) as _;
         let threads = ceil(x, workgroup_size as u64) as _;
         log::info!(
             "WGS: {} > {}, so workgroup size={} x threads={}",
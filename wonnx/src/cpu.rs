@@ -0,0 +1,258 @@
+//! CPU implementations of ops that cannot be compiled to a shader. These are used when CPU fallback is enabled (see
+//! `SessionConfig::cpu_fallback`), in which case the inputs of the node are read back from the GPU, and the outputs are
+//! written to the GPU again. Only ops that move data around are implemented, so that the implementations can work on
+//! the elements as they are stored on the GPU regardless of their data type.
+use crate::onnx::NodeProto;
+use crate::utils::{get_attribute, AttributeNotFoundError, ScalarType, Shape};
+use std::convert::TryInto;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CpuError {
+    #[error("the op '{0}' has no CPU implementation")]
+    UnimplementedOp(String),
+
+    #[error("attribute not found: {0}")]
+    AttributeNotFound(#[from] AttributeNotFoundError),
+
+    #[error("input '{0}' of node '{1}' is required")]
+    InputMissing(String, String),
+
+    #[error("invalid value for attribute '{attribute}' of node '{node}': {value}")]
+    InvalidAttributeValue {
+        attribute: String,
+        value: String,
+        node: String,
+    },
+}
+
+/// The data of a tensor in the format it is stored in on the GPU (booleans take four bytes, 64-bit integers eight)
+pub struct CpuTensor {
+    pub data: Vec<u8>,
+    pub shape: Shape,
+}
+
+/// Whether the op can be executed on the CPU
+pub fn supports(op_type: &str) -> bool {
+    matches!(op_type, "Pad" | "Slice" | "Transpose")
+}
+
+/// Execute the node on the CPU. The inputs are the tensors for the inputs of the node that are not omitted (i.e. that
+/// have a non-empty name). Returns the data for each of the outputs, which have the specified shapes.
+pub fn execute(
+    node: &NodeProto,
+    inputs: &[CpuTensor],
+    output_shapes: &[Shape],
+    opset_version: i64,
+) -> Result<Vec<Vec<u8>>, CpuError> {
+    // Optional inputs may be omitted by leaving their name empty
+    let mut present_inputs = inputs.iter();
+    let inputs: Vec<Option<&CpuTensor>> = node
+        .get_input()
+        .iter()
+        .map(|name| match name.is_empty() {
+            true => None,
+            false => present_inputs.next(),
+        })
+        .collect();
+    let input =
+        |index: usize, name: &str| {
+            inputs.get(index).copied().flatten().ok_or_else(|| {
+                CpuError::InputMissing(name.to_string(), node.get_name().to_string())
+            })
+        };
+    let invalid = |attribute: &str, value: &[i64]| CpuError::InvalidAttributeValue {
+        attribute: attribute.to_string(),
+        value: format!("{:?}", value),
+        node: node.get_name().to_string(),
+    };
+
+    let x = input(0, "data")?;
+    let output_shape = &output_shapes[0];
+    let rank = x.shape.rank();
+    let input_chunks = x.shape.chunks();
+
+    let output = match node.get_op_type() {
+        "Transpose" => {
+            let perm: Vec<i64> =
+                get_attribute("perm", Some((0..rank as i64).rev().collect()), node)?;
+            if perm.len() != rank || perm.iter().any(|p| *p < 0 || *p as usize >= rank) {
+                return Err(invalid("perm", &perm));
+            }
+            map_elements(x, output_shape, &[], |index| {
+                Some(
+                    index
+                        .iter()
+                        .zip(perm.iter())
+                        .map(|(i, p)| i * input_chunks[*p as usize])
+                        .sum(),
+                )
+            })
+        }
+
+        "Slice" => {
+            let (starts, ends, axes, steps) = if opset_version >= 10 {
+                (
+                    integers(input(1, "starts")?),
+                    integers(input(2, "ends")?),
+                    input(3, "axes").ok().map(integers),
+                    input(4, "steps").ok().map(integers),
+                )
+            } else {
+                (
+                    get_attribute::<Vec<i64>>("starts", None, node)?,
+                    get_attribute::<Vec<i64>>("ends", None, node)?,
+                    get_attribute::<Vec<i64>>("axes", None, node).ok(),
+                    None,
+                )
+            };
+            let axes = axes.unwrap_or_else(|| (0..starts.len() as i64).collect());
+            let steps = steps.unwrap_or_else(|| vec![1; starts.len()]);
+            if ends.len() != starts.len() {
+                return Err(invalid("ends", &ends));
+            }
+
+            // The first element that is selected along each axis, and the step to the next
+            let mut first = vec![0; rank];
+            let mut step = vec![1; rank];
+            for (i, axis) in axes.iter().enumerate() {
+                let axis = if *axis < 0 {
+                    *axis + rank as i64
+                } else {
+                    *axis
+                };
+                if axis < 0 || axis as usize >= rank || steps.get(i).copied().unwrap_or(1) == 0 {
+                    return Err(invalid("axes", &axes));
+                }
+                let axis = axis as usize;
+                let dim = x.shape.dim(axis) as i64;
+                step[axis] = steps.get(i).copied().unwrap_or(1);
+                let start = if starts[i] < 0 {
+                    starts[i] + dim
+                } else {
+                    starts[i]
+                };
+                first[axis] = match step[axis] > 0 {
+                    true => start.clamp(0, dim),
+                    false => start.clamp(0, (dim - 1).max(0)),
+                };
+            }
+
+            map_elements(x, output_shape, &[], |index| {
+                Some(
+                    (0..rank)
+                        .map(|d| (first[d] + (index[d] as i64) * step[d]) as u64 * input_chunks[d])
+                        .sum(),
+                )
+            })
+        }
+
+        "Pad" => {
+            let mode = get_attribute("mode", Some("constant".to_string()), node)?;
+            let (pads, value) = if opset_version >= 11 {
+                (
+                    integers(input(1, "pads")?),
+                    input(2, "constant_value")
+                        .ok()
+                        .map(|value| value.data[0..x.shape.data_type.stride()].to_vec()),
+                )
+            } else {
+                let value = get_attribute("value", Some(0.0), node)?;
+                (
+                    get_attribute::<Vec<i64>>("pads", None, node)?,
+                    Some(element_bytes(value, x.shape.data_type)),
+                )
+            };
+            if pads.len() != rank * 2 {
+                return Err(invalid("pads", &pads));
+            }
+            let value = value.unwrap_or_else(|| vec![0; x.shape.data_type.stride()]);
+
+            map_elements(x, output_shape, &value, |index| {
+                let mut source = 0;
+                for (d, i) in index.iter().enumerate() {
+                    // A negative begin pad crops the input, which offsets the index into the interior of the input
+                    let dim = x.shape.dim(d) as i64;
+                    let mut i = *i as i64 - pads[d];
+                    if i < 0 || i >= dim {
+                        i = match mode.as_str() {
+                            "edge" if dim > 0 => i.clamp(0, dim - 1),
+                            "reflect" if dim > 1 => {
+                                // Mirror the index (without repeating the edge) until it falls within the input
+                                let period = 2 * (dim - 1);
+                                let i = i.rem_euclid(period);
+                                if i < dim {
+                                    i
+                                } else {
+                                    period - i
+                                }
+                            }
+                            "reflect" if dim > 0 => 0,
+                            "wrap" if dim > 0 => i.rem_euclid(dim),
+                            _ => return None,
+                        };
+                    }
+                    source += i as u64 * input_chunks[d];
+                }
+                Some(source)
+            })
+        }
+
+        op => return Err(CpuError::UnimplementedOp(op.to_string())),
+    };
+
+    Ok(vec![output])
+}
+
+/// Create the data for a tensor of the output shape, where each element is copied from the element of the input at the
+/// (flat) index returned by `source` for the index of the output element, or set to `fill` if it returns None.
+fn map_elements<F: Fn(&[u64]) -> Option<u64>>(
+    input: &CpuTensor,
+    output_shape: &Shape,
+    fill: &[u8],
+    source: F,
+) -> Vec<u8> {
+    let stride = input.shape.data_type.stride();
+    let output_chunks = output_shape.chunks();
+    let mut output = Vec::with_capacity(output_shape.buffer_bytes());
+    let mut index = vec![0; output_shape.rank()];
+    for flat_index in 0..output_shape.element_count() {
+        for (d, chunk) in output_chunks.iter().enumerate() {
+            index[d] = (flat_index / chunk) % output_shape.dim(d);
+        }
+        match source(&index) {
+            Some(source_index) => {
+                let offset = source_index as usize * stride;
+                output.extend_from_slice(&input.data[offset..(offset + stride)])
+            }
+            None => output.extend_from_slice(fill),
+        }
+    }
+    output
+}
+
+/// Read the elements of an integer tensor
+fn integers(tensor: &CpuTensor) -> Vec<i64> {
+    let stride = tensor.shape.data_type.stride();
+    tensor.data[0..tensor.shape.buffer_bytes()]
+        .chunks(stride)
+        .map(|bytes| match tensor.shape.data_type {
+            ScalarType::I64 => i64::from_le_bytes(bytes.try_into().unwrap()),
+            ScalarType::F32 => f32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+            ScalarType::I32 | ScalarType::Bool | ScalarType::I8 | ScalarType::U8 => {
+                i32::from_le_bytes(bytes.try_into().unwrap()) as i64
+            }
+        })
+        .collect()
+}
+
+/// The representation of a value as an element of the specified type on the GPU
+fn element_bytes(value: f32, data_type: ScalarType) -> Vec<u8> {
+    match data_type {
+        ScalarType::F32 => value.to_le_bytes().to_vec(),
+        ScalarType::I32 | ScalarType::Bool | ScalarType::I8 | ScalarType::U8 => {
+            (value as i32).to_le_bytes().to_vec()
+        }
+        ScalarType::I64 => (value as i64).to_le_bytes().to_vec(),
+    }
+}
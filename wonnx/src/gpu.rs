@@ -1,29 +1,89 @@
 use std::{
     borrow::Cow,
-    collections::{HashMap, HashSet},
+    collections::{hash_map::Entry, HashMap, HashSet},
+    convert::TryFrom,
     sync::Arc,
 };
 
 use thiserror::Error;
-use wgpu::{Buffer, BufferUsages, CommandEncoder};
+use wgpu::{Buffer, BufferUsages};
 
 use crate::{
-    compiler::{compile, CompileError, CompiledNode},
+    compiler::{compile, supports_in_place, CompileError, CompiledNode},
+    cpu::{self, CpuError, CpuTensor},
     ir::{Node, NodeDefinition, NodeIdentifier, OperatorDefinition},
-    onnx::TensorProto,
+    onnx::{NodeProto, TensorProto},
     resource::{self, resize},
-    utils::{ceil, DataTypeError, InputTensor, ScalarType, Shape, MINIMUM_BUFFER_SIZE_BYTES},
+    utils::{ceil, outputs_mask, DataTypeError, InputTensor, ScalarType, Shape},
+    SessionConfig,
 };
 
 /// The maximum number of bindings in a binding group (defined by wgpu)
 const MAX_BINDINGS_PER_GROUP: usize = 4;
 
 pub struct GpuModel {
-    device: wgpu::Device,
-    queue: wgpu::Queue,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
     onnx_opset_version: i64,
+    config: SessionConfig,
     steps: Vec<GpuStep>,
     inference_outputs: HashMap<String, InferenceOutput>,
+    intermediate_tensors: HashMap<String, GpuTensor>,
+
+    /// Names of the nodes producing intermediate tensors and the names of these tensors, in order of execution. Only
+    /// kept when `SessionConfig::check_finite` is set.
+    checked_outputs: Vec<(String, String)>,
+
+    /// The maximum size of a buffer used to read back a tensor (see `SessionConfig::max_readback_bytes`)
+    readback_chunk_bytes: u64,
+
+    /// Compiled pipelines by shader source code, so that nodes for which the same shader is generated share a pipeline
+    pipelines: HashMap<String, Arc<wgpu::ComputePipeline>>,
+
+    /// Description of the scheduled nodes and the buffers they use (see `Session::execution_plan`)
+    execution_plan: ExecutionPlan,
+
+    /// The buffers described in `execution_plan.buffers` (in the same order)
+    planned_buffers: Vec<Arc<Buffer>>,
+}
+
+/// The nodes that were scheduled for a model (after optimization) and the buffers these read from and write to
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionPlan {
+    /// The nodes in order of execution
+    pub nodes: Vec<PlannedNode>,
+
+    /// The buffers created for inputs, initializers and node outputs. Nodes refer to these by index.
+    pub buffers: Vec<PlannedBuffer>,
+}
+
+/// A node as it was scheduled for execution
+#[derive(Clone, Debug)]
+pub struct PlannedNode {
+    pub name: String,
+    pub op_type: String,
+
+    /// Indices (in `ExecutionPlan::buffers`) of the buffers holding the inputs of the node
+    pub input_buffers: Vec<usize>,
+
+    /// Indices (in `ExecutionPlan::buffers`) of the buffers the outputs of the node are written to. An element-wise node
+    /// computed in place writes to the buffer of its input, and a node that forwards its input (e.g. Reshape) does not
+    /// write at all.
+    pub output_buffers: Vec<usize>,
+
+    /// The number of workgroups the shader of the node is dispatched with, or None if the node is not executed by a
+    /// shader (because it is executed on the CPU, or forwards its input)
+    pub threads: Option<(u32, u32, u32)>,
+}
+
+/// A buffer in GPU memory holding tensor data
+#[derive(Clone, Debug)]
+pub struct PlannedBuffer {
+    /// The name of the input, initializer or node output for which the buffer was created
+    pub name: String,
+
+    /// The size of the tensor data in the buffer
+    pub size_bytes: u64,
 }
 
 /// An operation that is performed on the GPU as part of inference
@@ -32,16 +92,25 @@ enum GpuStep {
     Initializer(Arc<Buffer>),
 
     /// A buffer containing tensor data that is obtained from inference input
-    Input(String, Arc<Buffer>),
+    Input(String, Arc<Buffer>, Shape),
 
     /// A GPU program (shader) that reads from buffers created by other steps and writes to output buffers
     Operator {
-        pipeline: wgpu::ComputePipeline,
+        pipeline: Arc<wgpu::ComputePipeline>,
         bind_groups: Vec<wgpu::BindGroup>,
         threads: (u32, u32, u32),
         output_tensors: Vec<GpuTensor>,
     },
 
+    /// An operation that is performed on the CPU, because it cannot be compiled to a shader (see `cpu::supports`). The
+    /// input tensors are read back from the GPU, and the outputs are written to the output tensors.
+    Cpu {
+        node: Box<NodeProto>,
+        opset_version: i64,
+        input_tensors: Vec<GpuTensor>,
+        output_tensors: Vec<GpuTensor>,
+    },
+
     /// Operation that takes the output from a previous operation and assigns it to a second logical output
     Forward(GpuTensor),
 
@@ -49,9 +118,11 @@ enum GpuStep {
     None,
 }
 
-/// A tensor that resides in GPU memory
+/// A tensor that resides in GPU memory. Tensors returned by `Session::run_to_buffers` can be passed as input to another
+/// session that uses the same device (see `Session::from_model_sharing_device`), without reading them back to main
+/// memory.
 #[derive(Clone)]
-struct GpuTensor {
+pub struct GpuTensor {
     buffer: Arc<Buffer>,
     shape: Shape,
 }
@@ -69,10 +140,29 @@ pub enum GpuError {
 
     #[error("scalar type error: {0}")]
     ScalarType(#[from] DataTypeError),
+
+    #[error("intermediate tensor not found: '{0}'")]
+    IntermediateMissing(String),
+
+    #[error("the shape of the GPU tensor supplied for input '{0}' ({1}) does not match the input shape ({2})")]
+    InputShapeMismatch(String, Shape, Shape),
+
+    #[error("error executing op on the CPU: {0}")]
+    CpuError(#[from] CpuError),
+
+    #[error("the model has no output named '{0}'")]
+    UnknownOutput(String),
+
+    #[error("the buffer supplied for output '{0}' holds {1} values, but the output has {2}")]
+    OutputSizeMismatch(String, usize, usize),
+
+    #[error("the value {1} supplied for input '{0}' does not fit in a 32-bit integer")]
+    InputOutOfRange(String, i64),
 }
 
 enum InferenceOutput {
-    InferenceInput(String),
+    /// An inference input that is returned as output, with the shape declared for the output in the model
+    InferenceInput(String, Shape),
     Tensor(GpuTensor),
 }
 
@@ -80,45 +170,92 @@ impl GpuModel {
     /// Create a version of the specified model for which inference can be performed using the powers of the GPU
     pub fn from(
         root: Arc<Node>,
-        device: wgpu::Device,
-        queue: wgpu::Queue,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
         onnx_opset_version: i64,
+        config: &SessionConfig,
     ) -> Result<GpuModel, GpuError> {
+        // Buffers can only be copied in multiples of four bytes
+        let readback_chunk_bytes = config
+            .max_readback_bytes
+            .unwrap_or(device.limits().max_storage_buffer_binding_size as u64);
+        let readback_chunk_bytes = u64::max(
+            wgpu::COPY_BUFFER_ALIGNMENT,
+            readback_chunk_bytes - readback_chunk_bytes % wgpu::COPY_BUFFER_ALIGNMENT,
+        );
+
         let mut gpu_model = GpuModel {
             device,
             queue,
             onnx_opset_version,
+            config: config.clone(),
             steps: vec![],
             inference_outputs: HashMap::new(),
+            intermediate_tensors: HashMap::new(),
+            checked_outputs: vec![],
+            readback_chunk_bytes,
+            pipelines: HashMap::new(),
+            execution_plan: ExecutionPlan::default(),
+            planned_buffers: vec![],
         };
 
         // Walk the IR DAG and encode into GPU execution steps
         let mut readable_nodes: HashSet<NodeIdentifier> = HashSet::new();
         let mut node_outputs = HashMap::<NodeIdentifier, Vec<GpuTensor>>::new();
         let mut node_reg = HashSet::new();
+        #[allow(clippy::mutable_key_type)]
+        let mut consumer_counts = HashMap::new();
+        count_consumers(&root, &mut consumer_counts, &mut HashSet::new());
         gpu_model.sequence(
             root.clone(),
             &mut readable_nodes,
             &mut node_outputs,
             &mut node_reg,
+            &consumer_counts,
         )?;
 
         // Find out which outputs we should return as inference outputs
         if let NodeDefinition::Outputs { names } = &root.definition {
             for (usize, output_name) in names.iter().enumerate() {
-                let input = &root.inputs[usize];
+                let output = &root.inputs[usize];
+                let mut input = output;
+
+                // Ops that forward their input produce the same data, so read the output from the forwarded input (if
+                // this is an inference input, we cannot read back its buffer, but simply return its data)
+                while let NodeDefinition::Operator(op_def) = &input.source_node.definition {
+                    if !forwards_input(&op_def.proto) {
+                        break;
+                    }
+                    input = &input.source_node.inputs[0];
+                }
+
+                // The output has the shape declared for it, rather than that of the forwarded input (e.g. for Reshape)
+                let output_shape = match &output.source_node.definition {
+                    NodeDefinition::Operator(op_def) => {
+                        Some(op_def.output_shapes[output.output_index].clone())
+                    }
+                    _ => None,
+                };
+
                 gpu_model.inference_outputs.insert(
                     output_name.to_string(),
                     match &input.source_node.definition {
                         NodeDefinition::Operator(_) | NodeDefinition::Tensor(_) => {
                             let source_identifier = input.source_node.identifier();
                             let outputs = &node_outputs[&source_identifier];
-                            let tensor = outputs[input.output_index].clone();
+                            let mut tensor = outputs[input.output_index].clone();
+                            if let Some(output_shape) = output_shape {
+                                tensor.shape = output_shape;
+                            }
                             InferenceOutput::Tensor(tensor)
                         }
-                        NodeDefinition::Input(proto) => {
-                            InferenceOutput::InferenceInput(proto.get_name().to_string())
-                        }
+                        NodeDefinition::Input(proto) => InferenceOutput::InferenceInput(
+                            proto.get_name().to_string(),
+                            match output_shape {
+                                Some(output_shape) => output_shape,
+                                None => proto.get_shape()?,
+                            },
+                        ),
                         NodeDefinition::Outputs { .. } => {
                             unimplemented!("output after output node")
                         }
@@ -144,12 +281,14 @@ impl GpuModel {
 
     /// Write commands to the GPU to create the necessary resources to be able to perform inference (e.g. allocates buffers
     /// for intermediate results, compiles shader code, determines which outputs to return, etc.).
+    #[allow(clippy::mutable_key_type)] // See `count_consumers`
     fn sequence<'model>(
         &mut self,
         node: Arc<Node<'model>>,
         nodes_readable: &mut HashSet<NodeIdentifier<'model>>,
         node_outputs: &mut HashMap<NodeIdentifier<'model>, Vec<GpuTensor>>,
         node_reg: &mut HashSet<NodeIdentifier<'model>>,
+        consumer_counts: &HashMap<(NodeIdentifier<'model>, usize), usize>,
     ) -> Result<(), GpuError> {
         let node_identifier = node.identifier();
         let outputs_readable = nodes_readable.contains(&node_identifier);
@@ -165,7 +304,7 @@ impl GpuModel {
 
             if let NodeDefinition::Operator(op_def) = &node.definition {
                 // For these ops we just forward the buffer (so we should also forward readability)
-                if op_def.proto.get_op_type() == "Reshape" {
+                if outputs_readable && forwards_input(&op_def.proto) {
                     nodes_readable.insert(identifier.clone());
                 }
             }
@@ -178,9 +317,16 @@ impl GpuModel {
                     nodes_readable,
                     node_outputs,
                     node_reg,
+                    consumer_counts,
                 )?;
             }
 
+            // Optional inputs that were omitted are not bound to the shader; the compiler can find out which inputs are
+            // missing by looking at the (empty) input names of the node
+            if let NodeDefinition::Missing = &node_input.source_node.definition {
+                continue;
+            }
+
             // Select the tensor we want for our input
             let source_identifier = node_input.source_node.identifier();
             let input_tensor = {
@@ -204,10 +350,38 @@ impl GpuModel {
             let mut output_tensors = vec![];
             let gpu_op: GpuStep = match &node.definition {
                 NodeDefinition::Operator(op_def) => {
+                    // Element-wise ops can write their output to the buffer of their input when no other node uses that
+                    // buffer (a buffer that is forwarded by some other op may also be used elsewhere). When checking for
+                    // non-finite values, every output needs to remain readable so the offending node can be found.
+                    let in_place = !outputs_readable
+                        && !self.config.check_finite
+                        && supports_in_place(&op_def.proto)
+                        && match node.inputs.as_slice() {
+                            [input] => {
+                                matches!(&input.source_node.definition, NodeDefinition::Operator(source_def) if !forwards_input(&source_def.proto))
+                                    && consumer_counts
+                                        .get(&(input.source_node.identifier(), input.output_index))
+                                        == Some(&1)
+                                    && input_tensors[0].shape.buffer_bytes()
+                                        == op_def.output_shapes[0].buffer_bytes()
+                            }
+                            _ => false,
+                        };
+
+                    if in_place {
+                        // The value of the input will be overwritten, so it cannot be read back for debugging anymore
+                        let overwritten_buffer = &input_tensors[0].buffer;
+                        self.intermediate_tensors
+                            .retain(|_, tensor| !Arc::ptr_eq(&tensor.buffer, overwritten_buffer));
+                    }
+
                     let gpu_op = op_def.gpu_op(
                         &self.device,
+                        &mut self.pipelines,
                         outputs_readable,
+                        in_place,
                         self.onnx_opset_version,
+                        &self.config,
                         &input_tensors,
                     )?;
 
@@ -215,27 +389,95 @@ impl GpuModel {
                         GpuStep::Operator {
                             output_tensors: op_output_tensors,
                             ..
+                        }
+                        | GpuStep::Cpu {
+                            output_tensors: op_output_tensors,
+                            ..
                         } => {
                             output_tensors.extend(op_output_tensors.iter().cloned());
+                            for (output_name, output_tensor) in op_def
+                                .proto
+                                .get_output()
+                                .iter()
+                                .zip(op_output_tensors)
+                                .filter(|(output_name, _)| !output_name.is_empty())
+                            {
+                                self.intermediate_tensors
+                                    .insert(output_name.to_string(), output_tensor.clone());
+                                if self.config.check_finite {
+                                    self.checked_outputs.push((
+                                        op_def.proto.get_name().to_string(),
+                                        output_name.to_string(),
+                                    ));
+                                }
+                            }
                         }
                         GpuStep::Forward(output_tensor) => {
                             output_tensors.push(output_tensor.clone());
+
+                            // Forwarded op outputs can be read back under their new name as well (this is not possible
+                            // for forwarded inputs or initializers, which cannot be copied from)
+                            let forwards_op_output = self
+                                .intermediate_tensors
+                                .values()
+                                .any(|t| Arc::ptr_eq(&t.buffer, &output_tensor.buffer));
+                            if forwards_op_output {
+                                self.intermediate_tensors.insert(
+                                    op_def.proto.get_output()[0].to_string(),
+                                    output_tensor.clone(),
+                                );
+                            }
                         }
                         _ => unreachable!("gpu_op for operator produced something unexpected"),
                     }
 
+                    let input_buffers = input_tensors
+                        .iter()
+                        .map(|tensor| self.plan_buffer(tensor, "input"))
+                        .collect();
+                    // Forwarded outputs are not written, so these are not listed as outputs of the node
+                    let (op_output_tensors, threads) = match &gpu_op {
+                        GpuStep::Operator {
+                            output_tensors: op_output_tensors,
+                            threads,
+                            ..
+                        } => (op_output_tensors.as_slice(), Some(*threads)),
+                        GpuStep::Cpu {
+                            output_tensors: op_output_tensors,
+                            ..
+                        } => (op_output_tensors.as_slice(), None),
+                        _ => (&[] as &[GpuTensor], None),
+                    };
+                    let output_buffers = op_def
+                        .proto
+                        .get_output()
+                        .iter()
+                        .zip(op_output_tensors)
+                        .filter(|(output_name, _)| !output_name.is_empty())
+                        .map(|(output_name, tensor)| self.plan_buffer(tensor, output_name))
+                        .collect();
+                    self.execution_plan.nodes.push(PlannedNode {
+                        name: op_def.proto.get_name().to_string(),
+                        op_type: op_def.proto.get_op_type().to_string(),
+                        input_buffers,
+                        output_buffers,
+                        threads,
+                    });
+
                     gpu_op
                 }
                 NodeDefinition::Tensor(tensor_def) => {
                     let tensor_buffer =
                         Arc::new(tensor_def.buffer(&self.device, outputs_readable)?);
-                    output_tensors.push(GpuTensor {
+                    let tensor = GpuTensor {
                         shape: Shape::from(
                             ScalarType::from_i32(tensor_def.get_data_type())?,
                             tensor_def.get_dims(),
                         ),
                         buffer: tensor_buffer.clone(),
-                    });
+                    };
+                    self.plan_buffer(&tensor, tensor_def.get_name());
+                    output_tensors.push(tensor);
                     GpuStep::Initializer(tensor_buffer)
                 }
                 NodeDefinition::Input(input_def) => {
@@ -257,15 +499,17 @@ impl GpuModel {
                         &self.device,
                         input_shape.buffer_bytes(),
                         input_def.get_name(),
-                        BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                        BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
                     ));
 
-                    output_tensors.push(GpuTensor {
-                        shape: input_shape,
+                    let tensor = GpuTensor {
+                        shape: input_shape.clone(),
                         buffer: input_buffer.clone(),
-                    });
+                    };
+                    self.plan_buffer(&tensor, input_def.get_name());
+                    output_tensors.push(tensor);
 
-                    GpuStep::Input(input_def.get_name().to_string(), input_buffer)
+                    GpuStep::Input(input_def.get_name().to_string(), input_buffer, input_shape)
                 }
                 NodeDefinition::Missing | NodeDefinition::Outputs { .. } => {
                     // Nothing to sequence
@@ -283,22 +527,391 @@ impl GpuModel {
         }
     }
 
-    /// Perform inference using this model and the specified inference inputs.
+    /// Returns the index of the buffer of the tensor in the execution plan, adding the buffer (under the specified name)
+    /// if it is not yet part of the plan
+    fn plan_buffer(&mut self, tensor: &GpuTensor, name: &str) -> usize {
+        if let Some(index) = self
+            .planned_buffers
+            .iter()
+            .position(|buffer| Arc::ptr_eq(buffer, &tensor.buffer))
+        {
+            return index;
+        }
+
+        self.planned_buffers.push(tensor.buffer.clone());
+        self.execution_plan.buffers.push(PlannedBuffer {
+            name: name.to_string(),
+            size_bytes: tensor.buffer_bytes(),
+        });
+        self.planned_buffers.len() - 1
+    }
+
+    /// The nodes that are executed for this model and the buffers they use (see `Session::execution_plan`)
+    pub fn execution_plan(&self) -> &ExecutionPlan {
+        &self.execution_plan
+    }
+
+    /// The number of distinct compute pipelines created for this model (nodes with identical shaders share a pipeline)
+    pub fn pipeline_count(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    /// The number of distinct buffers created for the inputs, initializers and op outputs of this model
+    pub fn buffer_count(&self) -> usize {
+        let mut buffers: Vec<&Arc<Buffer>> = vec![];
+        for step in &self.steps {
+            let step_buffers: Vec<&Arc<Buffer>> = match step {
+                GpuStep::Initializer(buffer) | GpuStep::Input(_, buffer, _) => vec![buffer],
+                GpuStep::Operator { output_tensors, .. } | GpuStep::Cpu { output_tensors, .. } => {
+                    output_tensors.iter().map(|tensor| &tensor.buffer).collect()
+                }
+                GpuStep::Forward(_) | GpuStep::None => vec![],
+            };
+            for buffer in step_buffers {
+                if !buffers.iter().any(|b| Arc::ptr_eq(b, buffer)) {
+                    buffers.push(buffer);
+                }
+            }
+        }
+        buffers.len()
+    }
+
+    /// Perform inference using this model and the specified inference inputs. Next to the inference outputs, the values
+    /// of the intermediate tensors with the specified names are returned (these are copied from GPU memory, so they
+    /// do not need to be marked as readable when the model is created).
+    pub async fn infer_with_debug<'a>(
+        &self,
+        inference_inputs: &HashMap<String, InputTensor<'a>>,
+        intermediate_names: &[&str],
+    ) -> Result<HashMap<String, Vec<f32>>, GpuError> {
+        let intermediates = intermediate_names
+            .iter()
+            .map(|name| {
+                self.intermediate_tensors
+                    .get(*name)
+                    .map(|tensor| (name.to_string(), tensor))
+                    .ok_or_else(|| GpuError::IntermediateMissing(name.to_string()))
+            })
+            .collect::<Result<Vec<_>, GpuError>>()?;
+
+        let mut output_data = self.infer(inference_inputs).await?;
+        for (name, tensor) in intermediates {
+            if let Entry::Vacant(entry) = output_data.entry(name) {
+                entry.insert(
+                    tensor
+                        .copy_to_vec(&self.device, &self.queue, self.readback_chunk_bytes)
+                        .await,
+                );
+            }
+        }
+        Ok(output_data)
+    }
+
+    /// Perform inference once with all inputs set to zero and wait for it to complete. This way one-time costs (such as
+    /// the driver compiling a pipeline when it is first used) are not part of the first actual inference.
+    pub async fn warm_up(&self) -> Result<(), GpuError> {
+        let inference_inputs: HashMap<String, InputTensor> = self
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                GpuStep::Input(input_name, _, input_shape) => {
+                    let element_count = input_shape.element_count() as usize;
+                    let zeros = match input_shape.data_type {
+                        ScalarType::F32 => InputTensor::F32(Cow::Owned(vec![0.0; element_count])),
+                        _ => InputTensor::I32(Cow::Owned(vec![0; element_count])),
+                    };
+                    Some((input_name.to_string(), zeros))
+                }
+                _ => None,
+            })
+            .collect();
+        self.submit(&inference_inputs).await?;
+        self.device.poll(wgpu::Maintain::Wait);
+        Ok(())
+    }
+
+    /// The device and queue this model uses
+    pub fn device_queue(&self) -> (Arc<wgpu::Device>, Arc<wgpu::Queue>) {
+        (self.device.clone(), self.queue.clone())
+    }
+
+    /// Perform inference using this model and the specified inference inputs. All operations are encoded in a single
+    /// command buffer, which is submitted to the GPU at once.
     pub async fn infer<'a>(
         &self,
         inference_inputs: &HashMap<String, InputTensor<'a>>,
     ) -> Result<HashMap<String, Vec<f32>>, GpuError> {
+        self.submit(inference_inputs).await?;
+        if self.config.check_finite {
+            self.check_finite().await;
+        }
+        self.read_outputs(inference_inputs).await
+    }
+
+    /// Perform inference using this model and the specified inference inputs, and copy the outputs into the buffers
+    /// supplied for them. Outputs for which no buffer is supplied are not read back. The size of each buffer must match
+    /// the number of elements of the output.
+    pub async fn infer_into<'a>(
+        &self,
+        inference_inputs: &HashMap<String, InputTensor<'a>>,
+        outputs: &mut HashMap<String, &mut [f32]>,
+    ) -> Result<(), GpuError> {
+        for (output_name, target) in outputs.iter() {
+            let output_length = match self.inference_outputs.get(output_name) {
+                Some(InferenceOutput::Tensor(tensor)) => tensor.shape.element_count() as usize,
+                Some(InferenceOutput::InferenceInput(input_name, _)) => {
+                    match inference_inputs.get(input_name) {
+                        Some(InputTensor::F32(v)) => v.len(),
+                        Some(InputTensor::I32(v)) => v.len(),
+                        Some(InputTensor::I64(v)) => v.len(),
+                        Some(InputTensor::Gpu(tensor)) => tensor.shape.element_count() as usize,
+                        None => return Err(GpuError::InputMissing(input_name.to_string())),
+                    }
+                }
+                None => return Err(GpuError::UnknownOutput(output_name.to_string())),
+            };
+            if target.len() != output_length {
+                return Err(GpuError::OutputSizeMismatch(
+                    output_name.to_string(),
+                    target.len(),
+                    output_length,
+                ));
+            }
+        }
+
+        self.submit(inference_inputs).await?;
+        if self.config.check_finite {
+            self.check_finite().await;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let mapped_buffers = self
+            .map_output_buffers(outputs.keys().map(|name| &self.inference_outputs[name]))
+            .await;
+
+        for (output_name, target) in outputs.iter_mut() {
+            match &self.inference_outputs[output_name] {
+                InferenceOutput::InferenceInput(input_name, _) => {
+                    match &inference_inputs[input_name] {
+                        InputTensor::F32(v) => target.copy_from_slice(v),
+                        InputTensor::I32(v) => {
+                            for (t, i) in target.iter_mut().zip(v.iter()) {
+                                *t = *i as f32;
+                            }
+                        }
+                        InputTensor::I64(v) => {
+                            for (t, i) in target.iter_mut().zip(v.iter()) {
+                                *t = *i as f32;
+                            }
+                        }
+                        InputTensor::Gpu(tensor) => tensor.convert_into(
+                            &tensor
+                                .read_bytes(&self.device, &self.queue, self.readback_chunk_bytes)
+                                .await,
+                            target,
+                        ),
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                InferenceOutput::Tensor(tensor)
+                    if tensor.buffer_bytes() <= self.readback_chunk_bytes =>
+                {
+                    tensor.convert_into(&tensor.buffer.slice(..).get_mapped_range(), target)
+                }
+                InferenceOutput::Tensor(tensor) => tensor.convert_into(
+                    &tensor
+                        .read_bytes(&self.device, &self.queue, self.readback_chunk_bytes)
+                        .await,
+                    target,
+                ),
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        for buffer in mapped_buffers {
+            buffer.unmap();
+        }
+        Ok(())
+    }
+
+    /// Log a warning naming the first node (in order of execution) that produced NaN or infinite values
+    async fn check_finite(&self) {
+        for (node_name, output_name) in &self.checked_outputs {
+            // Outputs that were overwritten by an in-place op cannot be checked anymore
+            let tensor = match self.intermediate_tensors.get(output_name) {
+                Some(tensor) if tensor.shape.data_type == ScalarType::F32 => tensor,
+                _ => continue,
+            };
+            let data = tensor
+                .copy_to_vec(&self.device, &self.queue, self.readback_chunk_bytes)
+                .await;
+            if let Some(value) = data.iter().find(|value| !value.is_finite()) {
+                log::warn!(
+                    "node '{}' produced a non-finite value ({}) in output '{}'",
+                    node_name,
+                    value,
+                    output_name
+                );
+                return;
+            }
+        }
+    }
+
+    /// Perform inference using this model and the specified inference inputs, but do not read back the outputs. Instead
+    /// the tensors holding the outputs are returned. These remain in use by the model, so their contents are overwritten
+    /// when inference is performed again.
+    pub async fn infer_to_buffers<'a>(
+        &self,
+        inference_inputs: &HashMap<String, InputTensor<'a>>,
+    ) -> Result<HashMap<String, GpuTensor>, GpuError> {
+        self.submit(inference_inputs).await?;
+
+        let mut outputs = HashMap::new();
+        for (output_name, output_source) in &self.inference_outputs {
+            let tensor = match output_source {
+                InferenceOutput::Tensor(tensor) => tensor.clone(),
+                InferenceOutput::InferenceInput(input_name, shape) => {
+                    match &inference_inputs[input_name] {
+                        InputTensor::Gpu(tensor) => GpuTensor {
+                            buffer: tensor.buffer.clone(),
+                            shape: shape.clone(),
+                        },
+                        InputTensor::F32(v) => self.upload(input_name, v, shape, ScalarType::F32),
+                        InputTensor::I32(v) => self.upload(input_name, v, shape, ScalarType::I32),
+                        InputTensor::I64(v) => self.upload(input_name, v, shape, ScalarType::I64),
+                    }
+                }
+            };
+            outputs.insert(output_name.to_string(), tensor);
+        }
+        Ok(outputs)
+    }
+
+    /// Read a tensor that resides in GPU memory of the device used by this model (e.g. one returned by
+    /// `infer_to_buffers`) to main memory
+    pub async fn read_tensor(&self, tensor: &GpuTensor) -> Vec<f32> {
+        tensor
+            .copy_to_vec(&self.device, &self.queue, self.readback_chunk_bytes)
+            .await
+    }
+
+    /// Create a tensor holding the specified data (which is used to return an inference input as output). The tensor has
+    /// the dimensions declared for the input, unless the data has a different number of elements (e.g. when the batch is
+    /// run in a loop).
+    fn upload<T: Clone + bytemuck::Pod>(
+        &self,
+        name: &str,
+        data: &[T],
+        declared_shape: &Shape,
+        data_type: ScalarType,
+    ) -> GpuTensor {
+        let dims = if declared_shape.element_count() == data.len() as u64 {
+            declared_shape.dims.clone()
+        } else {
+            vec![data.len() as u64]
+        };
+        GpuTensor {
+            buffer: Arc::new(resource::create_buffer_init(
+                &self.device,
+                data,
+                name,
+                BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            )),
+            shape: Shape { dims, data_type },
+        }
+    }
+
+    /// Encode all inference steps in a single command buffer and submit it to the GPU. When some steps are performed on
+    /// the CPU, the steps before each of these are submitted first, so that their outputs can be read back.
+    async fn submit<'a>(
+        &self,
+        inference_inputs: &HashMap<String, InputTensor<'a>>,
+    ) -> Result<(), GpuError> {
         log::info!("encode inference steps");
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        // Inputs that already reside in GPU memory are copied to the input buffers before any shader is run
         for step in &self.steps {
-            step.encode(&self.queue, &mut encoder, inference_inputs)?;
+            if let GpuStep::Input(input_name, input_buffer, input_shape) = step {
+                if let Some(InputTensor::Gpu(tensor)) = inference_inputs.get(input_name) {
+                    if tensor.shape != *input_shape {
+                        return Err(GpuError::InputShapeMismatch(
+                            input_name.to_string(),
+                            tensor.shape.clone(),
+                            input_shape.clone(),
+                        ));
+                    }
+                    // An output that forwards an input holds the input buffer itself, which need not be copied
+                    if Arc::ptr_eq(&tensor.buffer, input_buffer) {
+                        continue;
+                    }
+                    encoder.copy_buffer_to_buffer(
+                        &tensor.buffer,
+                        0,
+                        input_buffer,
+                        0,
+                        input_shape.buffer_bytes() as _,
+                    );
+                }
+            }
+        }
+
+        let mut remaining_steps = &self.steps[..];
+        loop {
+            let gpu_step_count = remaining_steps
+                .iter()
+                .position(|step| matches!(step, GpuStep::Cpu { .. }))
+                .unwrap_or(remaining_steps.len());
+            {
+                // All dispatches are recorded in a single compute pass. Dispatches are executed in order, and wgpu
+                // inserts the necessary barriers between dispatches that write to and read from the same storage buffer.
+                let mut compute_pass =
+                    encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+                for step in &remaining_steps[0..gpu_step_count] {
+                    step.encode(&self.queue, &mut compute_pass, inference_inputs)?;
+                }
+            }
+            log::info!("submit inference steps");
+            self.queue.submit(Some(encoder.finish()));
+
+            match remaining_steps.get(gpu_step_count) {
+                Some(GpuStep::Cpu {
+                    node,
+                    opset_version,
+                    input_tensors,
+                    output_tensors,
+                }) => {
+                    log::info!("execute {} on the CPU", node.get_name());
+                    let mut inputs = vec![];
+                    for tensor in input_tensors {
+                        inputs.push(CpuTensor {
+                            data: tensor
+                                .read_bytes(&self.device, &self.queue, self.readback_chunk_bytes)
+                                .await,
+                            shape: tensor.shape.clone(),
+                        });
+                    }
+                    let output_shapes: Vec<Shape> =
+                        output_tensors.iter().map(|t| t.shape.clone()).collect();
+                    let outputs = cpu::execute(node, &inputs, &output_shapes, *opset_version)?;
+                    for (tensor, data) in output_tensors.iter().zip(outputs) {
+                        if !data.is_empty() {
+                            self.queue.write_buffer(&tensor.buffer, 0, &data);
+                        }
+                    }
+
+                    remaining_steps = &remaining_steps[(gpu_step_count + 1)..];
+                    encoder = self
+                        .device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+                }
+                _ => break,
+            }
         }
-        log::info!("submit inference steps");
-        self.queue.submit(Some(encoder.finish()));
         log::info!("inference completed");
-        self.read_outputs(inference_inputs).await
+        Ok(())
     }
 
     /// Reads the relevant buffers for the requested inference outputs
@@ -308,25 +921,131 @@ impl GpuModel {
     ) -> Result<HashMap<String, Vec<f32>>, GpuError> {
         let mut output_data = HashMap::new();
 
+        // Map all output buffers at once, so that we only need to wait for the GPU once. On WebGPU the output buffers
+        // cannot be mapped, so these are copied to a separate buffer when read instead. The same happens for buffers that
+        // are larger than the readback limit, which are copied in chunks.
+        #[cfg(not(target_arch = "wasm32"))]
+        let mapped_buffers = self
+            .map_output_buffers(self.inference_outputs.values())
+            .await;
+
         for (output_name, output_source) in &self.inference_outputs {
             output_data.insert(
                 output_name.to_string(),
                 match output_source {
-                    InferenceOutput::InferenceInput(input_name) => {
+                    InferenceOutput::InferenceInput(input_name, _) => {
                         match &inference_inputs[input_name] {
                             InputTensor::F32(v) => v.to_vec(),
                             InputTensor::I32(v) => v.iter().map(|f| (*f) as f32).collect(),
+                            InputTensor::I64(v) => v.iter().map(|f| (*f) as f32).collect(),
+                            InputTensor::Gpu(tensor) => {
+                                tensor
+                                    .copy_to_vec(
+                                        &self.device,
+                                        &self.queue,
+                                        self.readback_chunk_bytes,
+                                    )
+                                    .await
+                            }
                         }
                     }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    InferenceOutput::Tensor(tensor)
+                        if tensor.buffer_bytes() <= self.readback_chunk_bytes =>
+                    {
+                        tensor.convert_to_vec(&tensor.buffer.slice(..).get_mapped_range())
+                    }
+                    #[cfg(not(target_arch = "wasm32"))]
+                    InferenceOutput::Tensor(tensor) => {
+                        tensor
+                            .copy_to_vec(&self.device, &self.queue, self.readback_chunk_bytes)
+                            .await
+                    }
+                    #[cfg(target_arch = "wasm32")]
                     InferenceOutput::Tensor(tensor) => {
-                        tensor.read_to_vec(&self.device, &self.queue).await?
+                        tensor
+                            .copy_to_vec(&self.device, &self.queue, self.readback_chunk_bytes)
+                            .await
                     }
                 },
             );
         }
 
+        #[cfg(not(target_arch = "wasm32"))]
+        for buffer in mapped_buffers {
+            buffer.unmap();
+        }
+
         Ok(output_data)
     }
+
+    /// Maps the buffers of the specified outputs (that are not larger than the readback limit) for reading, so that we
+    /// only need to wait for the GPU once. The returned buffers need to be unmapped after reading.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn map_output_buffers<'b>(
+        &'b self,
+        output_sources: impl Iterator<Item = &'b InferenceOutput>,
+    ) -> Vec<&'b Arc<Buffer>> {
+        let mut mapped_buffers: Vec<&Arc<Buffer>> = vec![];
+        let mut map_futures = vec![];
+        for output_source in output_sources {
+            if let InferenceOutput::Tensor(tensor) = output_source {
+                if tensor.buffer_bytes() <= self.readback_chunk_bytes
+                    && !mapped_buffers
+                        .iter()
+                        .any(|buffer| Arc::ptr_eq(buffer, &tensor.buffer))
+                {
+                    map_futures.push(tensor.buffer.slice(..).map_async(wgpu::MapMode::Read));
+                    mapped_buffers.push(&tensor.buffer);
+                }
+            }
+        }
+        self.device.poll(wgpu::Maintain::Wait);
+        for map_future in map_futures {
+            map_future.await.expect("failed to run compute on gpu!");
+        }
+        mapped_buffers
+    }
+}
+
+/// Counts for each output of each node in the graph how many times it is used as input by other nodes
+// NodeIdentifier only hashes and compares the address of the node, so the interior mutability of the protobuf messages
+// held by the node does not affect its use as a key
+#[allow(clippy::mutable_key_type)]
+fn count_consumers<'model>(
+    node: &Arc<Node<'model>>,
+    consumer_counts: &mut HashMap<(NodeIdentifier<'model>, usize), usize>,
+    visited: &mut HashSet<NodeIdentifier<'model>>,
+) {
+    if !visited.insert(node.identifier()) {
+        return;
+    }
+
+    for input in &node.inputs {
+        *consumer_counts
+            .entry((input.source_node.identifier(), input.output_index))
+            .or_insert(0) += 1;
+        count_consumers(&input.source_node, consumer_counts, visited);
+    }
+}
+
+/// Whether the op only changes the shape of its (first) input, in which case its output can use the input buffer
+fn forwards_input(proto: &NodeProto) -> bool {
+    match proto.get_op_type() {
+        "Reshape" | "Identity" | "Flatten" | "Squeeze" | "Unsqueeze" => true,
+        // When the mask is requested, Dropout is executed by a shader that also writes the mask
+        "Dropout" => !outputs_mask(proto),
+        _ => false,
+    }
+}
+
+/// Lays out integer input values the way the input buffer stores them: int64 values are stored as two 32-bit words (of
+/// which the shaders only read the lower one), all other integers as a single word
+fn integer_input_words(values: impl Iterator<Item = i32>, input_shape: &Shape) -> Vec<i32> {
+    match input_shape.data_type {
+        ScalarType::I64 => values.flat_map(|value| [value, value >> 31]).collect(),
+        _ => values.collect(),
+    }
 }
 
 trait TensorProtoExtra {
@@ -343,13 +1062,6 @@ impl TensorProtoExtra for TensorProto {
             input_shape
         );
 
-        let data = self.get_float_data();
-        let raw_data = if !data.is_empty() {
-            bytemuck::cast_slice(data)
-        } else {
-            self.get_raw_data()
-        };
-
         let buffer_usage = match readable {
             true => {
                 // On wgpu we can MAP_READ a buffer that is also used as STORAGE, but WebGPU (on at least Chrome)
@@ -358,96 +1070,139 @@ impl TensorProtoExtra for TensorProto {
                 if cfg!(target_arch = "wasm32") {
                     BufferUsages::STORAGE | BufferUsages::COPY_SRC
                 } else {
-                    BufferUsages::STORAGE | BufferUsages::MAP_READ
+                    BufferUsages::STORAGE | BufferUsages::MAP_READ | BufferUsages::COPY_SRC
                 }
             }
-            false => BufferUsages::STORAGE,
+            // Initializers can always be copied from, so that they can be read back for ops executed on the CPU
+            false => BufferUsages::STORAGE | BufferUsages::COPY_SRC,
         };
 
-        // Do not create buffers that are too small
-        Ok(if raw_data.len() < MINIMUM_BUFFER_SIZE_BYTES as _ {
-            let mut larger_raw_data = raw_data.to_vec();
-            larger_raw_data.resize(MINIMUM_BUFFER_SIZE_BYTES as _, 0);
-            resource::create_buffer_init(device, &larger_raw_data, self.get_name(), buffer_usage)
-        } else {
-            resource::create_buffer_init(device, raw_data, self.get_name(), buffer_usage)
-        })
+        Ok(resource::tensor_buffer(device, self, buffer_usage)?)
     }
 }
 
 impl<'model> OperatorDefinition<'model> {
+    #[allow(clippy::too_many_arguments)]
     fn gpu_op(
         &self,
         device: &wgpu::Device,
+        pipelines: &mut HashMap<String, Arc<wgpu::ComputePipeline>>,
         outputs_readable: bool,
+        in_place: bool,
         opset_version: i64,
+        config: &SessionConfig,
         input_tensors: &[GpuTensor],
     ) -> Result<GpuStep, GpuError> {
         let proto = &self.proto;
 
-        // Some nodes have specific GPU implementations, match these here
-        match proto.get_op_type() {
-            // Some ops do nothing but forward their input
-            "Reshape" | "Identity" | "Flatten" | "Squeeze" | "Unsqueeze" | "Dropout" => {
-                let value_shape = &self.output_shapes[0];
-                let output_tensor = GpuTensor {
-                    buffer: input_tensors[0].buffer.clone(),
-                    shape: value_shape.clone(),
-                };
-                return Ok(GpuStep::Forward(output_tensor));
-            }
-            _ => {}
+        // Some ops do nothing but forward their input
+        if forwards_input(proto) {
+            let value_shape = &self.output_shapes[0];
+            let output_tensor = GpuTensor {
+                buffer: input_tensors[0].buffer.clone(),
+                shape: value_shape.clone(),
+            };
+            return Ok(GpuStep::Forward(output_tensor));
         }
 
-        let label = Some(proto.get_name());
+        let input_shapes: Vec<&Shape> = input_tensors.iter().map(|input| &input.shape).collect();
+        let output_shapes: Vec<&Shape> = self.output_shapes.iter().collect();
 
-        // Create output buffers for this op node
-        let output_tensors: Vec<GpuTensor> = proto
-            .get_output()
-            .iter()
-            .enumerate()
-            .map(|(output_index, output_name)| {
-                let value_shape = &self.output_shapes[output_index];
-                log::info!(
-                    "Creating op output buffer for output #{} ({}) of {} shaped {}",
-                    output_index,
-                    output_name,
+        // Compile shader for node. When that is not possible, the node may be executed on the CPU instead.
+        let compiled = match compile(
+            proto,
+            &input_shapes,
+            &output_shapes,
+            opset_version,
+            in_place,
+            config,
+        ) {
+            Ok(compiled) => Some(compiled),
+            Err(CompileError::UnimplementedOp(_) | CompileError::UnimplementedVariant { .. })
+                if config.cpu_fallback && cpu::supports(proto.get_op_type()) =>
+            {
+                log::warn!(
+                    "{} ({}) cannot be compiled to a shader and is executed on the CPU",
                     proto.get_name(),
-                    value_shape
+                    proto.get_op_type()
                 );
+                None
+            }
+            Err(e) => return Err(e.into()),
+        };
 
-                let buffer_usage = if outputs_readable {
-                    // On wgpu we can MAP_READ a buffer that is also used as STORAGE, but WebGPU (on at least Chrome)
-                    // disallows this. Therefore we need to do an additional copy into a MAP_READ buffer when reading back a
-                    // STORAGE buffer when on WebGPU.
-                    if cfg!(target_arch = "wasm32") {
-                        BufferUsages::STORAGE | BufferUsages::COPY_SRC
-                    } else {
-                        BufferUsages::STORAGE | BufferUsages::MAP_READ
-                    }
-                } else {
-                    BufferUsages::STORAGE
-                };
+        let label = Some(proto.get_name());
 
-                let buffer = Arc::new(resource::buffer(
-                    device,
-                    value_shape.buffer_bytes(),
-                    output_name.as_str(),
-                    buffer_usage,
-                ));
-                GpuTensor {
-                    buffer,
-                    shape: value_shape.clone(),
-                }
-            })
-            .collect();
+        // Create output buffers for this op node (or write the output to the input buffer, when computing in place)
+        let output_tensors: Vec<GpuTensor> = if in_place {
+            log::info!(
+                "Computing {} in place, writing output to the input buffer",
+                proto.get_name()
+            );
+            vec![GpuTensor {
+                buffer: input_tensors[0].buffer.clone(),
+                shape: self.output_shapes[0].clone(),
+            }]
+        } else {
+            proto
+                .get_output()
+                .iter()
+                .enumerate()
+                .map(|(output_index, output_name)| {
+                    let value_shape = &self.output_shapes[output_index];
+                    log::info!(
+                        "Creating op output buffer for output #{} ({}) of {} shaped {}",
+                        output_index,
+                        output_name,
+                        proto.get_name(),
+                        value_shape
+                    );
 
-        let input_shapes: Vec<&Shape> = input_tensors.iter().map(|input| &input.shape).collect();
-        let output_shapes: Vec<&Shape> = self.output_shapes.iter().collect();
+                    // All op outputs can be copied from, so that intermediate values can be read back for debugging
+                    let buffer_usage = if outputs_readable {
+                        // On wgpu we can MAP_READ a buffer that is also used as STORAGE, but WebGPU (on at least Chrome)
+                        // disallows this. Therefore we need to do an additional copy into a MAP_READ buffer when reading back a
+                        // STORAGE buffer when on WebGPU.
+                        if cfg!(target_arch = "wasm32") {
+                            BufferUsages::STORAGE | BufferUsages::COPY_SRC
+                        } else {
+                            BufferUsages::STORAGE | BufferUsages::MAP_READ | BufferUsages::COPY_SRC
+                        }
+                    } else {
+                        BufferUsages::STORAGE | BufferUsages::COPY_SRC
+                    };
+
+                    // The outputs of ops executed on the CPU are written to the buffer from main memory
+                    let buffer_usage = match compiled {
+                        Some(_) => buffer_usage,
+                        None => buffer_usage | BufferUsages::COPY_DST,
+                    };
+
+                    let buffer = Arc::new(resource::buffer(
+                        device,
+                        value_shape.buffer_bytes(),
+                        output_name.as_str(),
+                        buffer_usage,
+                    ));
+                    GpuTensor {
+                        buffer,
+                        shape: value_shape.clone(),
+                    }
+                })
+                .collect()
+        };
 
-        // Compile shader for node
-        let CompiledNode { shader, threads } =
-            compile(proto, &input_shapes, &output_shapes, opset_version)?;
+        let CompiledNode { shader, threads } = match compiled {
+            Some(compiled) => compiled,
+            None => {
+                return Ok(GpuStep::Cpu {
+                    node: Box::new(proto.clone().into_owned()),
+                    opset_version,
+                    input_tensors: input_tensors.to_vec(),
+                    output_tensors,
+                })
+            }
+        };
         log::debug!("shader: {}", shader);
 
         // Bind input and output buffers to the shader
@@ -466,8 +1221,13 @@ impl<'model> OperatorDefinition<'model> {
             binding_counter += 1;
         }
 
-        // Bind output buffers
-        for output_tensor in &output_tensors {
+        // Bind output buffers (when computing in place, the output buffer is the input buffer, which is already bound).
+        // Optional outputs that were omitted are not bound either.
+        for (output_tensor, _) in output_tensors
+            .iter()
+            .zip(proto.get_output())
+            .filter(|(_, output_name)| !in_place && !output_name.is_empty())
+        {
             // Bindings are numbered 0...3 (MAX_BINDINGS_PER_GROUP-1) in binding groups (starting at group 0)
             let binding_index = (binding_counter % MAX_BINDINGS_PER_GROUP) as u32;
 
@@ -479,16 +1239,26 @@ impl<'model> OperatorDefinition<'model> {
         }
 
         // Set up a pipeline (basically the shader source code with some metadata that determines how it will be executed)
+        // or reuse the pipeline that was created earlier for another node with the exact same shader
         let mut bind_groups = vec![];
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label,
-            layout: None,
-            module: &device.create_shader_module(&wgpu::ShaderModuleDescriptor {
-                label,
-                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&shader)),
-            }),
-            entry_point: "main",
-        });
+        let pipeline = match pipelines.entry(shader) {
+            Entry::Occupied(entry) => {
+                log::debug!("reusing pipeline for {}", proto.get_name());
+                entry.get().clone()
+            }
+            Entry::Vacant(entry) => {
+                let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label,
+                    layout: None,
+                    module: &device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+                        label,
+                        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(entry.key())),
+                    }),
+                    entry_point: "main",
+                });
+                entry.insert(Arc::new(pipeline)).clone()
+            }
+        };
 
         // Create 'bind groups' (groups of bound buffers)
         let number_of_groups = ceil(binding_counter as u64, MAX_BINDINGS_PER_GROUP as u64) as usize;
@@ -515,12 +1285,12 @@ impl<'model> OperatorDefinition<'model> {
 }
 
 impl GpuStep {
-    /// Writes the necessary commands for the GPU to execute this step into the command queue. Among other things this means
+    /// Writes the necessary commands for the GPU to execute this step into the compute pass. Among other things this means
     /// writing the inference input data to the appropriate (already created) buffers.
-    fn encode<'a>(
-        &self,
+    fn encode<'a, 'pass>(
+        &'pass self,
         queue: &wgpu::Queue,
-        encoder: &mut CommandEncoder,
+        compute_pass: &mut wgpu::ComputePass<'pass>,
         inputs: &HashMap<String, InputTensor<'a>>,
     ) -> Result<(), GpuError> {
         match self {
@@ -528,7 +1298,11 @@ impl GpuStep {
                 // Buffer already filled, no need to encode anything at this point.
                 Ok(())
             }
-            GpuStep::Input(input_name, input_buffer) => {
+            GpuStep::Cpu { .. } => {
+                // Executed in between submissions of the other steps (see `GpuModel::submit`)
+                Ok(())
+            }
+            GpuStep::Input(input_name, input_buffer, input_shape) => {
                 // Encode a command to write the input data to the corresponding input buffer (which was created empty
                 // by `GpuModel::from`
                 let input_data = inputs
@@ -545,11 +1319,23 @@ impl GpuStep {
                         );
                     }
                     InputTensor::I32(int_input) => {
-                        queue.write_buffer(
-                            input_buffer,
-                            0,
-                            bytemuck::cast_slice(&resize(int_input.to_vec())),
-                        );
+                        let words = integer_input_words(int_input.iter().copied(), input_shape);
+                        queue.write_buffer(input_buffer, 0, bytemuck::cast_slice(&resize(words)));
+                    }
+                    InputTensor::I64(long_input) => {
+                        let narrowed = long_input
+                            .iter()
+                            .map(|value| {
+                                i32::try_from(*value).map_err(|_| {
+                                    GpuError::InputOutOfRange(input_name.to_string(), *value)
+                                })
+                            })
+                            .collect::<Result<Vec<i32>, GpuError>>()?;
+                        let words = integer_input_words(narrowed.into_iter(), input_shape);
+                        queue.write_buffer(input_buffer, 0, bytemuck::cast_slice(&resize(words)));
+                    }
+                    InputTensor::Gpu(_) => {
+                        // Copied to the input buffer before the compute pass (see `GpuModel::submit`)
                     }
                 }
 
@@ -562,8 +1348,6 @@ impl GpuStep {
                 ..
             } => {
                 // Encode a command for invocation of a shader.
-                let mut compute_pass =
-                    encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
                 compute_pass.set_pipeline(pipeline);
                 for (index, bind_group) in bind_groups.iter().enumerate() {
                     compute_pass.set_bind_group(index as u32, bind_group, &[]);
@@ -577,52 +1361,101 @@ impl GpuStep {
 }
 
 impl GpuTensor {
-    /// Read the tensor from GPU memory to main memory (as Vec<f32>)
-    async fn read_to_vec(
+    /// The buffer holding the data of this tensor
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// The shape and data type of this tensor
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    /// Read the tensor from GPU memory to main memory (as Vec<f32>) by copying it to a separate buffer first. This works
+    /// for any buffer that can be copied from, including buffers that are not readable themselves.
+    async fn copy_to_vec(
         &self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-    ) -> Result<Vec<f32>, GpuError> {
-        let buffer_slice = self.buffer.slice(..);
-
-        // On wgpu we can MAP_READ a buffer that is also used as STORAGE, but WebGPU (on at least Chrome)
-        // disallows this. Therefore we need to do an additional copy into a MAP_READ buffer when reading back a
-        // STORAGE buffer when on WebGPU.
-        #[cfg(target_arch = "wasm32")]
-        let output_data = wgpu::util::DownloadBuffer::read_buffer(device, queue, &buffer_slice)
-            .await
-            .unwrap();
+        chunk_bytes: u64,
+    ) -> Vec<f32> {
+        self.convert_to_vec(&self.read_bytes(device, queue, chunk_bytes).await)
+    }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        let output_data = {
-            let _ = queue; // Need this because otherwise compiler complains we are not using the queue parameter
-            let buffer_future = buffer_slice.map_async(wgpu::MapMode::Read);
-            device.poll(wgpu::Maintain::Wait);
-            buffer_future.await.expect("failed to run compute on gpu!");
-            buffer_slice.get_mapped_range()
-        };
+    /// Read the contents of the buffer of this tensor (which must be possible to copy from) to main memory. The buffer is
+    /// copied to staging buffers of at most `chunk_bytes` bytes (a multiple of four), which are read back at once.
+    async fn read_bytes(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        chunk_bytes: u64,
+    ) -> Vec<u8> {
+        let size = self.buffer_bytes();
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let staging_buffers: Vec<Buffer> = (0..size)
+            .step_by(chunk_bytes as usize)
+            .map(|offset| {
+                let chunk_size = u64::min(chunk_bytes, size - offset);
+                let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("readback"),
+                    size: chunk_size,
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                encoder.copy_buffer_to_buffer(&self.buffer, offset, &staging_buffer, 0, chunk_size);
+                staging_buffer
+            })
+            .collect();
+        queue.submit(Some(encoder.finish()));
+
+        let map_futures: Vec<_> = staging_buffers
+            .iter()
+            .map(|buffer| buffer.slice(..).map_async(wgpu::MapMode::Read))
+            .collect();
+        device.poll(wgpu::Maintain::Wait);
+        let mut data = Vec::with_capacity(size as usize);
+        for (buffer, map_future) in staging_buffers.iter().zip(map_futures) {
+            map_future.await.expect("failed to read buffer from gpu!");
+            data.extend_from_slice(&buffer.slice(..).get_mapped_range());
+            buffer.unmap();
+        }
+        data
+    }
+
+    /// The number of bytes of the buffer of this tensor that hold its data
+    fn buffer_bytes(&self) -> u64 {
+        self.shape.buffer_bytes() as u64
+    }
 
+    fn convert_to_vec(&self, output_data: &[u8]) -> Vec<f32> {
+        let mut result = vec![0.0; self.shape.element_count() as usize];
+        self.convert_into(output_data, &mut result);
+        result
+    }
+
+    /// Convert the data read from the buffer of this tensor to f32 values, which are written to `target` (which must
+    /// hold as many values as the tensor has elements)
+    fn convert_into(&self, output_data: &[u8], target: &mut [f32]) {
         // The actual buffer may be bigger than what we should return, because buffers have a minimum size in wgpu
         // Fetch the size we should expect so we can chop the buffer to the correct size
         let output_buffer_size = self.shape.element_count() as usize;
-        let result = match self.shape.data_type {
-            ScalarType::F32 => bytemuck::cast_slice(&output_data)[..output_buffer_size].to_vec(),
-            ScalarType::I32 => {
-                let result_ints: Vec<i32> =
-                    bytemuck::cast_slice(&output_data)[..output_buffer_size].to_vec();
-                result_ints.iter().map(|i| *i as f32).collect()
+        match self.shape.data_type {
+            ScalarType::F32 => target.copy_from_slice(
+                &bytemuck::cast_slice::<u8, f32>(output_data)[..output_buffer_size],
+            ),
+            ScalarType::I32 | ScalarType::Bool | ScalarType::I8 | ScalarType::U8 => {
+                let ints: &[i32] = bytemuck::cast_slice(output_data);
+                for (t, i) in target.iter_mut().zip(&ints[..output_buffer_size]) {
+                    *t = *i as f32;
+                }
             }
             ScalarType::I64 => {
-                let result_ints: Vec<i64> =
-                    bytemuck::cast_slice(&output_data)[..output_buffer_size].to_vec();
-                result_ints.iter().map(|i| *i as f32).collect()
+                let ints: &[i64] = bytemuck::cast_slice(output_data);
+                for (t, i) in target.iter_mut().zip(&ints[..output_buffer_size]) {
+                    *t = *i as f32;
+                }
             }
-        };
-        drop(output_data);
-
-        // On WASM we are not mapping the buffer, so we don't need to unmap
-        #[cfg(not(target_arch = "wasm32"))]
-        self.buffer.unmap();
-        Ok(result)
+        }
     }
 }
@@ -1,6 +1,8 @@
 use crate::onnx::{ModelProto, NodeProto, TensorProto, ValueInfoProto};
 use crate::utils::{DataTypeError, ScalarType, Shape};
 use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::ptr;
@@ -20,6 +22,13 @@ impl<'model> OperatorDefinition<'model> {
     ) -> Result<OperatorDefinition<'model>, IrError> {
         let mut output_shapes: Vec<Shape> = Vec::with_capacity(node.get_output().len());
         for output_name in node.get_output() {
+            // Optional outputs can be omitted by leaving their name empty. These are not bound to the shader, so their
+            // shape does not matter.
+            if output_name.is_empty() {
+                output_shapes.push(Shape::from(ScalarType::F32, &[]));
+                continue;
+            }
+
             if !value_shapes.contains_key(output_name.as_str()) {
                 return Err(IrError::OutputNodeNotFound(output_name.to_string()));
             }
@@ -68,6 +77,113 @@ pub enum IrError {
 
     #[error("issue with data types: {0}")]
     Type(#[from] DataTypeError),
+
+    #[error("the graph contains a cycle that includes node '{0}'")]
+    CyclicGraph(String),
+
+    #[error("output {0} is not produced by any node")]
+    UnreachableOutput(String),
+}
+
+/// Sort the nodes of the model's graph topologically. ONNX requires nodes to be sorted already, but not all models
+/// adhere to this; nodes are kept in their original order where possible. Fails if the graph contains a cycle, or if
+/// one of its outputs is not produced by any node (and is not an input or initializer of the graph either).
+pub fn sort_nodes(model: &mut ModelProto) -> Result<(), IrError> {
+    let graph = model.get_graph();
+    for output in graph.get_output() {
+        let name = output.get_name();
+        if !name.is_empty()
+            && !graph
+                .get_node()
+                .iter()
+                .any(|node| node.get_output().iter().any(|output| output == name))
+            && !graph
+                .get_input()
+                .iter()
+                .any(|input| input.get_name() == name)
+            && !graph
+                .get_initializer()
+                .iter()
+                .any(|initializer| initializer.get_name() == name)
+        {
+            return Err(IrError::UnreachableOutput(name.to_string()));
+        }
+    }
+
+    // Find out which nodes each node depends on. Initializers take precedence over node outputs of the same name.
+    let nodes = graph.get_node();
+    let mut producers: HashMap<&str, usize> = HashMap::new();
+    for (index, node) in nodes.iter().enumerate() {
+        for output in node.get_output() {
+            if !output.is_empty() {
+                producers.insert(output, index);
+            }
+        }
+    }
+    for initializer in graph.get_initializer() {
+        producers.remove(initializer.get_name());
+    }
+    let dependencies: Vec<Vec<usize>> = nodes
+        .iter()
+        .map(|node| {
+            node.get_input()
+                .iter()
+                .filter_map(|input| producers.get(input.as_str()).copied())
+                .collect()
+        })
+        .collect();
+    let mut dependents = vec![vec![]; nodes.len()];
+    for (index, node_dependencies) in dependencies.iter().enumerate() {
+        for dependency in node_dependencies {
+            dependents[*dependency].push(index);
+        }
+    }
+
+    // Repeatedly take the first node (in the original order) of which all dependencies have been taken before
+    let mut dependency_counts: Vec<usize> = dependencies.iter().map(|d| d.len()).collect();
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..nodes.len())
+        .filter(|index| dependency_counts[*index] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(Reverse(index)) = ready.pop() {
+        order.push(index);
+        for dependent in &dependents[index] {
+            dependency_counts[*dependent] -= 1;
+            if dependency_counts[*dependent] == 0 {
+                ready.push(Reverse(*dependent));
+            }
+        }
+    }
+
+    if order.len() != nodes.len() {
+        // Each of the remaining nodes depends on another remaining node. Follow these dependencies until a node is
+        // visited twice, which means it is part of a cycle.
+        let mut index = (0..nodes.len())
+            .find(|index| dependency_counts[*index] > 0)
+            .unwrap();
+        let mut visited = HashSet::new();
+        while visited.insert(index) {
+            index = *dependencies[index]
+                .iter()
+                .find(|dependency| dependency_counts[**dependency] > 0)
+                .unwrap();
+        }
+        return Err(IrError::CyclicGraph(nodes[index].get_name().to_string()));
+    }
+
+    if order
+        .iter()
+        .enumerate()
+        .any(|(position, index)| position != *index)
+    {
+        let sorted_nodes: Vec<NodeProto> =
+            order.iter().map(|index| nodes[*index].clone()).collect();
+        model
+            .mut_graph()
+            .set_node(protobuf::RepeatedField::from(sorted_nodes));
+    }
+    Ok(())
 }
 
 impl<'m> NodeDefinition<'m> {
@@ -237,33 +353,37 @@ impl<'model> Node<'model> {
             .get_output()
             .iter()
             .map(|output_def| {
-                let output_name_string = output_def.get_name().to_string();
-                let output_node = model
-                    .get_graph()
-                    .get_node()
-                    .iter()
-                    .find(|x| -> bool { x.get_output().contains(&output_name_string) })
-                    .ok_or(IrError::OutputNodeNotFound(output_name_string))?;
-
-                let source_node = Node::<'model>::from_node(
-                    model,
-                    Cow::Borrowed(output_node),
-                    &value_shapes,
-                    &node_definitions_by_output,
-                    &mut nodes_by_name,
-                )?;
-
-                let output_index = output_node
-                    .get_output()
-                    .iter()
-                    .position(|s| s == output_def.get_name())
-                    .ok_or_else(|| {
-                        IrError::OutputNodeNotFound(output_def.get_name().to_string())
-                    })?;
-
-                Ok(Input {
-                    source_node,
-                    output_index,
+                let output_name = output_def.get_name();
+                Ok(match node_definitions_by_output.get(output_name) {
+                    // The output is produced by an op
+                    Some(NodeDefinition::Operator(op_def)) => Input {
+                        source_node: Node::<'model>::from_node(
+                            model,
+                            op_def.proto.clone(),
+                            &value_shapes,
+                            &node_definitions_by_output,
+                            &mut nodes_by_name,
+                        )?,
+                        output_index: op_def
+                            .proto
+                            .get_output()
+                            .iter()
+                            .position(|s| s == output_name)
+                            .ok_or_else(|| IrError::OutputNodeNotFound(output_name.to_string()))?,
+                    },
+                    // The output is an initializer or model input
+                    Some(source_node_definition) => Input {
+                        output_index: 0,
+                        source_node: match nodes_by_name.get(output_name) {
+                            Some(node) => node.clone(),
+                            None => {
+                                let node = Arc::new(Node::new(source_node_definition.clone()));
+                                nodes_by_name.insert(output_name.to_string(), node.clone());
+                                node
+                            }
+                        },
+                    },
+                    None => return Err(IrError::UnreachableOutput(output_name.to_string())),
                 })
             })
             .collect();
@@ -1,25 +1,34 @@
 pub mod compiler;
+mod cpu;
 mod gpu;
 pub mod ir;
 pub mod onnx;
 pub mod optimizer;
 pub mod resource;
+pub mod shape_inference;
+mod stateful;
 pub mod utils;
 
 #[macro_use]
 extern crate lazy_static;
 
-use compiler::CompileError;
-use gpu::GpuError;
+use compiler::{CompileError, CompiledNode};
 use ir::IrError;
 use optimizer::{Optimizer, OptimizerError};
-use protobuf::{self, Message, ProtobufError};
-use std::collections::HashMap;
-use std::path::Path;
+use protobuf::{self, Message, ProtobufEnum, ProtobufError};
+use shape_inference::ShapeInferenceError;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path};
 use std::result::Result;
+use std::sync::Arc;
 use utils::{DataTypeError, InputTensor};
 
 use crate::gpu::GpuModel;
+pub use crate::gpu::{ExecutionPlan, GpuError, GpuTensor, PlannedBuffer, PlannedNode};
+pub use crate::stateful::StatefulSession;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -50,6 +59,70 @@ pub enum WonnxError {
 /// ```
 pub struct Session {
     gpu_model: GpuModel,
+
+    /// The batch size of the model when it is run once for each batch element (see `SessionConfig::loop_batch`)
+    loop_batch_size: Option<u64>,
+
+    /// The outputs and intermediate values of the model that are not batched when the batch is run in a loop. These are
+    /// the same for each batch element, and are therefore only returned once.
+    loop_unbatched_values: HashSet<String>,
+}
+
+/// Options that influence how a model is compiled for the GPU.
+///
+/// # Examples
+///
+/// ```ignore
+/// let config = SessionConfig {
+///     approximate_activations: true,
+///     ..SessionConfig::default()
+/// };
+/// let mut session = Session::from_model_with_config(model, config).await.unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SessionConfig {
+    /// Compute Sigmoid and Tanh using a rational approximation instead of `exp` and `tanh`, which are slow on some
+    /// (mobile) GPUs. The absolute error of the approximation is below 1e-4.
+    pub approximate_activations: bool,
+
+    /// Use compensated (Kahan) summation in reductions (e.g. ReduceSum and ReduceMean), which keeps track of the
+    /// rounding error of the running sum. This is more accurate when many values are summed, but slower.
+    pub compensated_summation: bool,
+
+    /// Execute nodes that cannot be compiled to a shader on the CPU instead of failing to create the session. Only a few
+    /// ops (Pad, Slice and Transpose) can be executed on the CPU. This is slow, as the inputs of such a node need to be
+    /// read back from the GPU (and its outputs written to the GPU again).
+    pub cpu_fallback: bool,
+
+    /// After each inference, read back the outputs of all nodes and log a warning naming the first node that produced
+    /// NaN or infinite values (e.g. Sqrt of a negative number, or Reciprocal of zero). This is meant for debugging, as
+    /// reading back all intermediate values is slow.
+    pub check_finite: bool,
+
+    /// The maximum size (in bytes) of a single buffer used to read tensors back from the GPU. Larger tensors are copied
+    /// to several such buffers in chunks, which are reassembled in main memory. When not set, the maximum size of a
+    /// storage buffer binding supported by the device is used.
+    pub max_readback_bytes: Option<u64>,
+
+    /// When the inputs of the model have a batch size (first dimension) larger than one, compile the model for a batch
+    /// size of one and run it once for each batch element, after which the outputs are concatenated. This allows batched
+    /// inputs to be used with models containing ops that only support a batch size of one. Values with the same first
+    /// dimension as the inputs are assumed to be batched; shapes stored in the model itself (e.g. the shape input of
+    /// Reshape) are not adjusted.
+    pub loop_batch: bool,
+
+    /// Compile the nodes of the model as they are, without the optional steps of the graph optimizer (which removes e.g.
+    /// Identity and Dropout nodes, fuses operations and replaces some nodes by cheaper ones). Nodes that only change the
+    /// shape of their input then forward their input buffer instead. The steps the compiler depends on (folding Shape
+    /// nodes, moving constant inputs such as the bounds of Clip to attributes) and the checks for unsupported nodes are
+    /// still performed. This is meant for debugging, e.g. when an optimization is suspected to be incorrect.
+    pub disable_optimizer: bool,
+
+    /// Make running the same model twice with the same inputs produce bit-identical outputs, also across sessions. Random
+    /// ops (RandomNormal, RandomUniform) that do not specify a seed then use a fixed seed instead of one generated when
+    /// the model is compiled. Dropout is always computed as at inference, which is deterministic; a Dropout with
+    /// training_mode=1 fails to compile (the optimizer rejects it as well, also when `disable_optimizer` is set).
+    pub deterministic: bool,
 }
 
 #[derive(Error, Debug)]
@@ -87,12 +160,32 @@ pub enum SessionError {
 
     #[error("optimizer error: {0}")]
     OptimizerError(#[from] OptimizerError),
+
+    #[error("shape inference error: {0}")]
+    ShapeInferenceError(#[from] ShapeInferenceError),
+
+    #[error("invalid external data for tensor '{0}': {1}")]
+    InvalidExternalData(String, String),
+
+    #[error("not supported when the batch is run in a loop (see SessionConfig::loop_batch): {0}")]
+    UnsupportedInBatchLoop(String),
+
+    #[error("the length of input '{0}' ({1}) is not a multiple of the batch size ({2})")]
+    InvalidBatchLength(String, usize, u64),
 }
 
 impl Session {
-    // Read an ONNX model from a path and create a session.
+    // Read an ONNX model from a path and create a session. Tensors stored as external data are read from files relative
+    // to the directory containing the model.
     pub async fn from_path<P: AsRef<Path>>(path: P) -> Result<Session, SessionError> {
-        let model = onnx::ModelProto::parse_from_bytes(&std::fs::read(path)?)?;
+        let path = path.as_ref();
+        let mut model = onnx::ModelProto::parse_from_bytes(&std::fs::read(path)?)?;
+        let model_directory = path.parent().unwrap_or_else(|| Path::new(""));
+        for initializer in model.mut_graph().mut_initializer().iter_mut() {
+            if initializer.get_data_location() == onnx::TensorProto_DataLocation::EXTERNAL {
+                read_external_data(initializer, model_directory)?;
+            }
+        }
         Session::from_model(model).await
     }
 
@@ -103,10 +196,49 @@ impl Session {
 
     // Create a Session given an ONNX model.
     pub async fn from_model(model: onnx::ModelProto) -> Result<Session, SessionError> {
+        Session::from_model_with_config(model, SessionConfig::default()).await
+    }
+
+    // Create a Session given an ONNX model, compiled using the specified options.
+    pub async fn from_model_with_config(
+        model: onnx::ModelProto,
+        config: SessionConfig,
+    ) -> Result<Session, SessionError> {
         let (device, queue) = resource::request_device_queue().await;
+        Session::from_model_with_device_queue(model, Arc::new(device), Arc::new(queue), &config)
+    }
+
+    /// Create a Session given an ONNX model that uses the same device (GPU) as another session. The outputs of one
+    /// session (see `run_to_buffers`) can then be passed as inputs to the other without being read back to main memory.
+    pub async fn from_model_sharing_device(
+        model: onnx::ModelProto,
+        other: &Session,
+    ) -> Result<Session, SessionError> {
+        let (device, queue) = other.gpu_model.device_queue();
+        Session::from_model_with_device(model, device, queue, SessionConfig::default()).await
+    }
 
+    /// Create a Session given an ONNX model, compiled using the specified options, that runs on a device (GPU) and queue
+    /// created by the caller, e.g. to share them with a renderer. The device should allow as many storage buffers per
+    /// shader stage as the model requires (see `resource::request_device_queue` for the limits wonnx asks for itself).
+    pub async fn from_model_with_device(
+        model: onnx::ModelProto,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        config: SessionConfig,
+    ) -> Result<Session, SessionError> {
+        Session::from_model_with_device_queue(model, device, queue, &config)
+    }
+
+    fn from_model_with_device_queue(
+        mut model: onnx::ModelProto,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        config: &SessionConfig,
+    ) -> Result<Session, SessionError> {
         // Find the version of the ONNX operator set this model is using (this is useful because some operators' specifications change over time).
-        // Note, if any other op set than the ONNX operator set is referenced, we cannot run the model.
+        // Note, if any other op set than the ONNX operator set (or the com.microsoft op set) is referenced, we cannot run
+        // the model.
         // See https://github.com/onnx/onnx/blob/master/docs/Versioning.md#operator-sets
         let mut onnx_opset_version = None;
         for opset_import in model.get_opset_import() {
@@ -124,6 +256,9 @@ impl Session {
                         onnx_opset_version = Some(opset_import.get_version());
                     }
                 }
+                "com.microsoft" => {
+                    // Some operators from the ONNX Runtime contrib op set are supported (see the README)
+                }
                 some_other_opset => {
                     return Err(SessionError::UnknownOpset(some_other_opset.to_string()));
                 }
@@ -133,11 +268,83 @@ impl Session {
         // Optimize and compile the model graph to a set of buffers and 'builders' which can basically run GPU shader code referencing these buffers
         let onnx_opset_version = onnx_opset_version.ok_or(SessionError::UnknownOnnxOpsetVersion)?;
 
-        let mut optimizer = Optimizer::new();
-        let ir = optimizer.optimize(ir::Node::from_model(&model)?)?;
-        let gpu_model = GpuModel::from(ir, device, queue, onnx_opset_version)?;
+        // External data can only be resolved when the location of the model is known (see `from_path`)
+        for initializer in model.get_graph().get_initializer() {
+            if initializer.get_data_location() == onnx::TensorProto_DataLocation::EXTERNAL {
+                return Err(SessionError::InvalidExternalData(
+                    initializer.get_name().to_string(),
+                    "external data was not loaded".to_string(),
+                ));
+            }
+        }
+
+        if has_double_values(model.get_graph()) {
+            log::warn!("the model contains double precision (f64) values, which are computed with single precision (f32) on the GPU; results are less precise than the model specifies");
+        }
+
+        // Replace If nodes with a constant condition by the selected branch
+        optimizer::inline_constant_branches(&mut model)?;
+
+        // Replace Scan nodes by a copy of their body for each element of the scanned sequence
+        optimizer::unroll_scans(&mut model, onnx_opset_version)?;
+
+        // Make sure nodes are evaluated only after the nodes producing their inputs
+        ir::sort_nodes(&mut model)?;
+
+        // Compile the model for a single batch element when the batch is run in a loop
+        let (loop_batch_size, loop_unbatched_values) = match config.loop_batch {
+            true => match unbatch(&mut model) {
+                Some((batch_size, unbatched_values)) => (Some(batch_size), unbatched_values),
+                None => (None, HashSet::new()),
+            },
+            false => (None, HashSet::new()),
+        };
+
+        // Fill in the shapes of intermediate values that the model does not specify
+        shape_inference::infer_shapes(&mut model, onnx_opset_version)?;
+
+        let ir = ir::Node::from_model(&model)?;
+        let mut optimizer = if config.disable_optimizer {
+            Optimizer::without_optional_optimizations()
+        } else {
+            Optimizer::with_max_buffer_bytes(device.limits().max_storage_buffer_binding_size as u64)
+        };
+        let ir = optimizer.optimize(ir)?;
+        let gpu_model = GpuModel::from(ir, device, queue, onnx_opset_version, config)?;
+
+        Ok(Session {
+            gpu_model,
+            loop_batch_size,
+            loop_unbatched_values,
+        })
+    }
+
+    /// The number of distinct compute pipelines that were created for the model. Nodes for which the same shader code is
+    /// generated (e.g. identical operations on tensors of the same shape) share a single pipeline. A node that computes
+    /// its output in place uses a different shader than the same node computing into a separate buffer, so these do not
+    /// share a pipeline.
+    pub fn pipeline_count(&self) -> usize {
+        self.gpu_model.pipeline_count()
+    }
+
+    /// The number of distinct GPU buffers that were created for the model. Element-wise operations write their output to
+    /// the buffer of their input when that is not used elsewhere, so they do not need a buffer of their own.
+    pub fn buffer_count(&self) -> usize {
+        self.gpu_model.buffer_count()
+    }
 
-        Ok(Session { gpu_model })
+    /// Describes what was scheduled for the model: the nodes (after optimization) in the order in which they are executed,
+    /// the buffers each of them reads from and writes to, and the number of workgroups each shader is dispatched with.
+    /// This is intended for inspecting the performance and memory use of a model.
+    pub fn execution_plan(&self) -> &ExecutionPlan {
+        self.gpu_model.execution_plan()
+    }
+
+    /// Perform inference once (with all inputs set to zero) and wait for it to complete. Shaders are compiled when the
+    /// session is created, but some drivers only finish preparing a pipeline when it is first used. After calling this,
+    /// the first call to `run` is as fast as subsequent ones, which is useful when measuring latency.
+    pub async fn prepare(&self) -> Result<(), SessionError> {
+        Ok(self.gpu_model.warm_up().await?)
     }
 
     /// Perform inference given the inputs provided and return all the outputs the model was compiled to return.
@@ -145,6 +352,333 @@ impl Session {
         &self,
         inputs: &HashMap<String, InputTensor<'a>>,
     ) -> Result<HashMap<String, Vec<f32>>, SessionError> {
-        Ok(self.gpu_model.infer(inputs).await?)
+        match self.loop_batch_size {
+            Some(batch_size) => self.run_batch_loop(batch_size, inputs, &[]).await,
+            None => Ok(self.gpu_model.infer(inputs).await?),
+        }
+    }
+
+    /// Perform inference like `run`, but return the outputs as double (f64) values, e.g. for models that declare double
+    /// outputs. The model is still computed with single precision, so the values are only as precise as f32 values.
+    pub async fn run_f64<'a>(
+        &self,
+        inputs: &HashMap<String, InputTensor<'a>>,
+    ) -> Result<HashMap<String, Vec<f64>>, SessionError> {
+        Ok(self
+            .run(inputs)
+            .await?
+            .into_iter()
+            .map(|(name, data)| (name, data.into_iter().map(f64::from).collect()))
+            .collect())
     }
+
+    /// Perform inference given the inputs provided and copy the outputs into the buffers supplied for them (by output
+    /// name), so that no memory needs to be allocated for the outputs when inference is performed repeatedly. Outputs
+    /// for which no buffer is supplied are not read back. An error is returned when a buffer does not hold exactly as
+    /// many values as the output it is supplied for.
+    pub async fn run_into<'a>(
+        &self,
+        inputs: &HashMap<String, InputTensor<'a>>,
+        outputs: &mut HashMap<String, &mut [f32]>,
+    ) -> Result<(), SessionError> {
+        if self.loop_batch_size.is_some() {
+            return Err(SessionError::UnsupportedInBatchLoop(
+                "reading outputs into supplied buffers".to_string(),
+            ));
+        }
+        Ok(self.gpu_model.infer_into(inputs, outputs).await?)
+    }
+
+    /// Perform inference given the inputs provided, but instead of reading back the outputs, return the GPU tensors
+    /// that hold them. These can be passed as input (`InputTensor::Gpu`) to another session that uses the same device
+    /// (see `from_model_sharing_device`). The returned tensors are owned by this session, so their contents are
+    /// overwritten by the next run of this session.
+    pub async fn run_to_buffers<'a>(
+        &self,
+        inputs: &HashMap<String, InputTensor<'a>>,
+    ) -> Result<HashMap<String, GpuTensor>, SessionError> {
+        if self.loop_batch_size.is_some() {
+            return Err(SessionError::UnsupportedInBatchLoop(
+                "returning outputs as GPU tensors".to_string(),
+            ));
+        }
+        Ok(self.gpu_model.infer_to_buffers(inputs).await?)
+    }
+
+    /// Read a GPU tensor returned by `run_to_buffers` (of this session or another session that uses the same device) to
+    /// main memory.
+    pub async fn read_tensor(&self, tensor: &GpuTensor) -> Vec<f32> {
+        self.gpu_model.read_tensor(tensor).await
+    }
+
+    /// Perform inference given the inputs provided and return all the outputs the model was compiled to return, as well
+    /// as the values of the named intermediate (non-output) tensors. This is intended for debugging and does not alter
+    /// the outputs of the model. Intermediate values that are overwritten by an element-wise operation computed in place
+    /// cannot be read back.
+    pub async fn run_with_debug<'a>(
+        &self,
+        inputs: &HashMap<String, InputTensor<'a>>,
+        intermediate_names: &[&str],
+    ) -> Result<HashMap<String, Vec<f32>>, SessionError> {
+        match self.loop_batch_size {
+            Some(batch_size) => {
+                self.run_batch_loop(batch_size, inputs, intermediate_names)
+                    .await
+            }
+            None => Ok(self
+                .gpu_model
+                .infer_with_debug(inputs, intermediate_names)
+                .await?),
+        }
+    }
+
+    /// Runs the model (compiled for a single batch element) for each element of the batch in turn, and concatenates the
+    /// outputs (as well as the requested intermediate values) of all runs. Values that are not batched are only returned
+    /// once.
+    async fn run_batch_loop<'a>(
+        &self,
+        batch_size: u64,
+        inputs: &HashMap<String, InputTensor<'a>>,
+        intermediate_names: &[&str],
+    ) -> Result<HashMap<String, Vec<f32>>, SessionError> {
+        for (name, input) in inputs {
+            let length = match input {
+                InputTensor::F32(data) => data.len(),
+                InputTensor::I32(data) => data.len(),
+                InputTensor::I64(data) => data.len(),
+                InputTensor::Gpu(_) => continue,
+            };
+            if length % batch_size as usize != 0 {
+                return Err(SessionError::InvalidBatchLength(
+                    name.clone(),
+                    length,
+                    batch_size,
+                ));
+            }
+        }
+
+        let batch_size = batch_size as usize;
+        let mut outputs: HashMap<String, Vec<f32>> = HashMap::new();
+        for element in 0..batch_size {
+            let mut element_inputs = HashMap::new();
+            for (name, input) in inputs {
+                let element_input = match input {
+                    InputTensor::F32(data) => {
+                        let length = data.len() / batch_size;
+                        InputTensor::F32(Cow::Borrowed(
+                            &data[element * length..(element + 1) * length],
+                        ))
+                    }
+                    InputTensor::I32(data) => {
+                        let length = data.len() / batch_size;
+                        InputTensor::I32(Cow::Borrowed(
+                            &data[element * length..(element + 1) * length],
+                        ))
+                    }
+                    InputTensor::I64(data) => {
+                        let length = data.len() / batch_size;
+                        InputTensor::I64(Cow::Borrowed(
+                            &data[element * length..(element + 1) * length],
+                        ))
+                    }
+                    InputTensor::Gpu(_) => {
+                        return Err(SessionError::UnsupportedInBatchLoop(format!(
+                            "GPU tensor supplied for input '{}'",
+                            name
+                        )))
+                    }
+                };
+                element_inputs.insert(name.clone(), element_input);
+            }
+
+            let element_outputs = self
+                .gpu_model
+                .infer_with_debug(&element_inputs, intermediate_names)
+                .await?;
+            for (name, data) in element_outputs {
+                if element == 0 || !self.loop_unbatched_values.contains(&name) {
+                    outputs.entry(name).or_default().extend(data);
+                }
+            }
+        }
+        Ok(outputs)
+    }
+}
+
+/// Whether any of the inputs, outputs or initializers of the graph are of type double (f64), which is downcast to f32
+/// (see `ScalarType::from`)
+fn has_double_values(graph: &onnx::GraphProto) -> bool {
+    let double = onnx::TensorProto_DataType::DOUBLE.value();
+    graph
+        .get_input()
+        .iter()
+        .chain(graph.get_output())
+        .any(|value_info| {
+            value_info
+                .get_field_type()
+                .get_tensor_type()
+                .get_elem_type()
+                == double
+        })
+        || graph
+            .get_initializer()
+            .iter()
+            .any(|initializer| initializer.get_data_type() == double)
+}
+
+/// Returns the batch size of the model if all of its inputs (that are not initializers) have the same first dimension,
+/// which is larger than one. In that case, the first dimension of all inputs, outputs and intermediate values with that
+/// first dimension is set to one. The names of the outputs and intermediate values with a specified shape that do not
+/// have that first dimension are returned as well.
+fn unbatch(model: &mut onnx::ModelProto) -> Option<(u64, HashSet<String>)> {
+    let first_dim = |value_info: &onnx::ValueInfoProto| {
+        value_info
+            .get_field_type()
+            .get_tensor_type()
+            .get_shape()
+            .get_dim()
+            .first()
+            .map(|dim| dim.get_dim_value())
+    };
+
+    let graph = model.get_graph();
+    let initializer_names: HashSet<String> = graph
+        .get_initializer()
+        .iter()
+        .map(|initializer| initializer.get_name().to_string())
+        .collect();
+    let mut batch_size = None;
+    for input in graph.get_input() {
+        if initializer_names.contains(input.get_name()) {
+            continue;
+        }
+        match (batch_size, first_dim(input)) {
+            (_, None) => return None,
+            (None, Some(dim)) => batch_size = Some(dim),
+            (Some(size), Some(dim)) if size != dim => return None,
+            _ => {}
+        }
+    }
+    let batch_size = batch_size.filter(|size| *size > 1)?;
+
+    let mut unbatched_values = HashSet::new();
+    let mut unbatch_value = |value_info: &mut onnx::ValueInfoProto| {
+        let name = value_info.get_name().to_string();
+        let dims = value_info
+            .mut_field_type()
+            .mut_tensor_type()
+            .mut_shape()
+            .mut_dim();
+        match dims.first_mut() {
+            Some(dim) if dim.get_dim_value() == batch_size => dim.set_dim_value(1),
+            Some(_) => {
+                unbatched_values.insert(name);
+            }
+            None => {}
+        }
+    };
+    let graph = model.mut_graph();
+    graph
+        .mut_input()
+        .iter_mut()
+        .filter(|input| !initializer_names.contains(input.get_name()))
+        .for_each(&mut unbatch_value);
+    graph
+        .mut_value_info()
+        .iter_mut()
+        .for_each(&mut unbatch_value);
+    graph.mut_output().iter_mut().for_each(&mut unbatch_value);
+    Some((batch_size as u64, unbatched_values))
+}
+
+/// Generate the shader for a single node, without creating a session (and without requiring a GPU). This is the entry
+/// point for tools and tests that inspect the generated shader code or the number of threads it is dispatched with. The
+/// shapes of the inputs and outputs of the node need to be specified, as these are not inferred. The default
+/// `SessionConfig` is used.
+///
+/// # Examples
+///
+/// ```ignore
+/// let shape = Shape::from(ScalarType::F32, &[2, 3]);
+/// let compiled = wonnx::compile_node(&relu_node, &[&shape], &[&shape], 13)?;
+/// println!("{}", compiled.shader);
+/// ```
+pub fn compile_node(
+    node: &onnx::NodeProto,
+    inputs: &[&utils::Shape],
+    outputs: &[&utils::Shape],
+    opset_version: i64,
+) -> Result<CompiledNode, CompileError> {
+    compiler::compile(
+        node,
+        inputs,
+        outputs,
+        opset_version,
+        false,
+        &SessionConfig::default(),
+    )
+}
+
+/// Read the data of a tensor that is stored externally (see
+/// https://github.com/onnx/onnx/blob/main/docs/ExternalData.md) into its raw_data field.
+fn read_external_data(
+    tensor: &mut onnx::TensorProto,
+    model_directory: &Path,
+) -> Result<(), SessionError> {
+    let invalid =
+        |reason: String| SessionError::InvalidExternalData(tensor.get_name().to_string(), reason);
+
+    let mut location = None;
+    let mut offset = 0;
+    let mut length = None;
+    for entry in tensor.get_external_data() {
+        match entry.get_key() {
+            "location" => location = Some(entry.get_value()),
+            "offset" => {
+                offset = entry
+                    .get_value()
+                    .parse::<u64>()
+                    .map_err(|e| invalid(format!("invalid offset: {}", e)))?
+            }
+            "length" => {
+                length = Some(
+                    entry
+                        .get_value()
+                        .parse::<usize>()
+                        .map_err(|e| invalid(format!("invalid length: {}", e)))?,
+                )
+            }
+            _ => {}
+        }
+    }
+    let location = Path::new(location.ok_or_else(|| invalid("no location specified".to_string()))?);
+
+    // The data has to be stored next to the model (or in a subdirectory), so that a model cannot read arbitrary files
+    if !location
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(invalid(format!(
+            "location '{}' is not a path relative to the directory containing the model",
+            location.display()
+        )));
+    }
+
+    let mut file = File::open(model_directory.join(location))?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = vec![];
+    match length {
+        Some(length) => {
+            data.resize(length, 0);
+            file.read_exact(&mut data)?;
+        }
+        None => {
+            file.read_to_end(&mut data)?;
+        }
+    }
+
+    tensor.set_raw_data(data);
+    tensor.clear_external_data();
+    tensor.set_data_location(onnx::TensorProto_DataLocation::DEFAULT);
+    Ok(())
 }
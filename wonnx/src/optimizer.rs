@@ -1,11 +1,20 @@
-use protobuf::RepeatedField;
-use std::{borrow::Cow, collections::HashMap, sync::Arc};
+use protobuf::{ProtobufEnum, RepeatedField};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
 use thiserror::Error;
 
 use crate::{
-    ir::{Input, Node, NodeDefinition, NodeIdentifier, OperatorDefinition},
+    compiler::{uses_conv_kernel_3, GEMM_TILED_THRESHOLD, QLINEAR_ACTIVATIONS},
+    ir::{Input, IrError, Node, NodeDefinition, NodeIdentifier, OperatorDefinition},
+    onnx::{ModelProto, NodeProto, TensorProto, TensorProto_DataType},
     resource::padding,
-    utils::{attribute, get_attribute, AttributeNotFoundError, DataTypeError, ScalarType},
+    utils::{
+        attribute, get_attribute, initializer_of, node, outputs_mask, shape_dimension_range,
+        AttributeNotFoundError, DataTypeError, ScalarType, Shape,
+    },
 };
 
 #[derive(Debug, Error)]
@@ -28,6 +37,9 @@ pub enum OptimizerError {
 
     #[error("required attribute not found: {0}")]
     AttributeNotFound(#[from] AttributeNotFoundError),
+
+    #[error("error in intermediate representation: {0}")]
+    InvalidIr(#[from] IrError),
 }
 
 #[derive(Clone)]
@@ -39,13 +51,33 @@ struct Sequence<'model> {
 pub struct Optimizer<'model> {
     padded_tensors: HashMap<String, Arc<Node<'model>>>,
     optimized: HashMap<NodeIdentifier<'model>, Sequence<'model>>,
+    max_buffer_bytes: u64,
+    optional_optimizations: bool,
 }
 
 impl<'model> Optimizer<'model> {
     pub fn new() -> Self {
+        Self::with_max_buffer_bytes(wgpu::Limits::default().max_storage_buffer_binding_size as u64)
+    }
+
+    /// Create an optimizer that does not introduce intermediate tensors larger than the specified number of bytes (this
+    /// should be the largest storage buffer that can be bound on the device the model will run on)
+    pub fn with_max_buffer_bytes(max_buffer_bytes: u64) -> Self {
         Self {
             padded_tensors: HashMap::new(),
             optimized: HashMap::new(),
+            max_buffer_bytes,
+            optional_optimizations: true,
+        }
+    }
+
+    /// Create an optimizer that only performs the steps the compiler depends on: folding Shape nodes, moving constant
+    /// inputs (e.g. the bounds of Clip) to attributes, padding the weights of 3x3 convolutions and rejecting unsupported
+    /// nodes (e.g. Dropout with training_mode=1). Nodes are not removed, fused or replaced by other operations.
+    pub fn without_optional_optimizations() -> Self {
+        Self {
+            optional_optimizations: false,
+            ..Self::new()
         }
     }
 
@@ -111,7 +143,7 @@ impl<'model> Optimizer<'model> {
             match op_def.proto.get_op_type() {
                 // Identity: we just remove these nodes and connect the input of the destination node to our input's output
                 // A -> Identity -> B => A -> B
-                "Identity" => {
+                "Identity" if self.optional_optimizations => {
                     if node.inputs.len() != 1 {
                         return Err(OptimizerError::NoInputs);
                     }
@@ -131,7 +163,32 @@ impl<'model> Optimizer<'model> {
                         )));
                     }
 
-                    return self.optimize_branch_cached(node.inputs[0].source_node.clone(), chain);
+                    // When the mask is requested, the node is kept (it is all ones at inference). Its optional ratio
+                    // and training_mode inputs are removed, as these have no effect.
+                    if outputs_mask(&op_def.proto) {
+                        let mut new_proto = op_def.proto.clone().into_owned();
+                        new_proto.set_input(RepeatedField::from(vec![
+                            op_def.proto.get_input()[0].clone()
+                        ]));
+                        let mut new_def = op_def.clone();
+                        new_def.proto = Cow::Owned(new_proto);
+                        let source_node = self.optimize(node.inputs[0].source_node.clone())?;
+                        return Ok(Sequence {
+                            node: Arc::new(Node {
+                                definition: NodeDefinition::Operator(new_def),
+                                inputs: vec![Input {
+                                    source_node,
+                                    output_index: node.inputs[0].output_index,
+                                }],
+                            }),
+                            skip: 0,
+                        });
+                    }
+
+                    if self.optional_optimizations {
+                        return self
+                            .optimize_branch_cached(node.inputs[0].source_node.clone(), chain);
+                    }
                 }
                 _ => {}
             }
@@ -152,7 +209,12 @@ impl<'model> Optimizer<'model> {
             if dynamic_input_count == 1 {
                 chain.push((op_def.proto.get_op_type().to_string(), node.clone()));
 
-                if let Some(seq) = self.optimize_chain(chain)? {
+                let fused = if self.optional_optimizations {
+                    self.optimize_chain(chain)?
+                } else {
+                    None
+                };
+                if let Some(seq) = fused {
                     log::info!(
                         "chain optimization: fuse {} operators to {:?}",
                         seq.skip,
@@ -225,36 +287,40 @@ impl<'model> Optimizer<'model> {
         match &node.definition {
             NodeDefinition::Operator(op_def) => {
                 match op_def.proto.get_op_type() {
-                    "Conv" | "ConvRelu" | "ConvLeakyRelu" => {
+                    "Conv" | "ConvRelu" | "ConvLeakyRelu" | "ConvMish" | "ConvSwish" => {
+                        if op_def.proto.get_op_type() == "Conv" && self.optional_optimizations {
+                            if let Some(lowered) = self.lower_conv_to_gemm(op_def, &new_inputs)? {
+                                return Ok(lowered);
+                            }
+                        }
+
                         // This optimization inserts some padding to convolution between kernels with kernel 3x3, because of
                         // the stride of matrix3x3 is 16 in wgsl. It makes the computation matrixable and increases the performance.
-                        if new_inputs.len() > 2
-                            && get_attribute::<Vec<i64>>("kernel_shape", None, &op_def.proto)?
-                                == [3, 3]
-                            && (get_attribute("pads", Some(vec![0, 0, 0, 0]), &op_def.proto)?
-                                == [1, 1, 1, 1]
-                                || get_attribute(
-                                    "auto_pad",
-                                    Some("SAME_UPPER".to_string()),
-                                    &op_def.proto,
-                                )? == "SAME_UPPER")
-                            && get_attribute("strides", Some(vec![1, 1]), &op_def.proto)? == [1, 1]
+                        // The weights are padded for exactly those convolutions that the compiler computes using the 3x3
+                        // kernel shader (see `uses_conv_kernel_3`).
+                        if let NodeDefinition::Tensor(tensor) =
+                            &new_inputs[1].source_node.definition
                         {
-                            if let NodeDefinition::Tensor(tensor) =
-                                &new_inputs[1].source_node.definition
-                            {
+                            let dims = tensor.get_dims();
+                            let pad_weights = dims.len() == 4 && {
+                                let kernel_shape = get_attribute(
+                                    "kernel_shape",
+                                    Some(dims[2..].to_vec()),
+                                    &op_def.proto,
+                                )?;
+                                let strides =
+                                    get_attribute("strides", Some(vec![1, 1]), &op_def.proto)?;
+                                uses_conv_kernel_3(&kernel_shape, &strides, dims[0] as u64)
+                            };
+                            if pad_weights {
                                 new_inputs[1] = Input {
                                     output_index: 0,
                                     source_node: match self.padded_tensors.get(tensor.get_name()) {
                                         Some(padded_tensor_node) => padded_tensor_node.clone(),
                                         None => {
-                                            let data = tensor.get_float_data();
-                                            let raw_data = if !data.is_empty() {
-                                                bytemuck::cast_slice(data)
-                                            } else {
-                                                tensor.get_raw_data()
-                                            };
-
+                                            // Read the elements as f32 so that bfloat16 weights are upcast before padding
+                                            let float_data = tensor.float_values()?;
+                                            let raw_data: &[u8] = bytemuck::cast_slice(&float_data);
                                             let padded_raw_data = padding(raw_data, 12, 4);
 
                                             log::info!(
@@ -267,6 +333,8 @@ impl<'model> Optimizer<'model> {
                                             // Create a new tensor with the padded data
                                             let mut new_tensor = tensor.clone().into_owned();
                                             new_tensor.set_float_data(vec![]);
+                                            new_tensor
+                                                .set_data_type(TensorProto_DataType::FLOAT.value());
                                             new_tensor.set_raw_data(padded_raw_data);
                                             let new_node = Arc::new(Node {
                                                 definition: NodeDefinition::Tensor(Box::new(
@@ -293,13 +361,78 @@ impl<'model> Optimizer<'model> {
                         Ok(Arc::new(new_node))
                     }
 
-                    // The Clip, Split, Resize and Reshape operator each take optional inputs that influence the operation.
-                    // These are typically statically initialized tensors containing shapes. For more efficient execution we
-                    // move these static values to attributes.
-                    op @ ("Clip" | "Split" | "Resize" | "Reshape" | "ReduceSum") => {
+                    // Sub(0, x) and Mul(-1, x) negate x, which Neg does without reading the constant (the only difference
+                    // is that Sub(0, 0) is 0 while Neg(0) is -0, which compare equal)
+                    op @ ("Sub" | "Mul") if self.optional_optimizations => {
+                        match negated_input(op, op_def, &new_inputs)? {
+                            Some(index) => {
+                                let mut neg_proto = op_def.proto.clone().into_owned();
+                                neg_proto.set_op_type("Neg".to_string());
+                                neg_proto
+                                    .set_input(RepeatedField::from(vec![op_def.proto.get_input()
+                                        [index]
+                                        .clone()]));
+                                neg_proto.clear_attribute();
+                                log::info!(
+                                    "replacing {} node {} by Neg",
+                                    op,
+                                    op_def.proto.get_name()
+                                );
+                                Ok(Arc::new(Node {
+                                    inputs: vec![new_inputs.swap_remove(index)],
+                                    definition: NodeDefinition::Operator(Box::new(
+                                        OperatorDefinition {
+                                            proto: Cow::Owned(neg_proto),
+                                            output_shapes: op_def.output_shapes.clone(),
+                                        },
+                                    )),
+                                }))
+                            }
+                            None => Ok(Arc::new(Node {
+                                inputs: new_inputs,
+                                definition: NodeDefinition::Operator(op_def.clone()),
+                            })),
+                        }
+                    }
+
+                    // The shapes of all values are known when the model is compiled, so the output of Shape is a constant
+                    "Shape" => {
                         if new_inputs.is_empty() {
                             return Err(OptimizerError::NoInputs);
                         }
+                        let input_shape = new_inputs[0]
+                            .source_node
+                            .output_shape(new_inputs[0].output_index)?;
+                        let dims: Vec<i64> = input_shape.dims
+                            [shape_dimension_range(&op_def.proto, input_shape.rank())?]
+                        .iter()
+                        .map(|dim| *dim as i64)
+                        .collect();
+                        let tensor = initializer_of(
+                            &op_def.proto.get_output()[0],
+                            &[dims.len() as i64],
+                            dims,
+                        );
+                        Ok(Arc::new(Node {
+                            definition: NodeDefinition::Tensor(Box::new(Cow::Owned(tensor))),
+                            inputs: vec![],
+                        }))
+                    }
+
+                    // The Clip, Split, Resize, Reshape, Squeeze, Unsqueeze and NonMaxSuppression operator each take
+                    // optional inputs that influence the operation. These are typically statically initialized tensors
+                    // containing shapes. For more efficient execution we move these static values to attributes.
+                    op @ ("Clip" | "Split" | "Resize" | "Reshape" | "ReduceSum" | "Squeeze"
+                    | "Unsqueeze" | "NonMaxSuppression") => {
+                        // Number of leading inputs that contain the data to operate on (these are kept as inputs)
+                        let data_input_count = match op {
+                            "NonMaxSuppression" => 2,
+                            _ => 1,
+                        };
+
+                        if new_inputs.len() < data_input_count {
+                            return Err(OptimizerError::NoInputs);
+                        }
 
                         // Names of the inputs (see ONNX operator spec)
                         let attr_names = match op {
@@ -308,6 +441,8 @@ impl<'model> Optimizer<'model> {
                             "Reshape" => RESHAPE_INPUT_NAMES,
                             "Clip" => CLIP_INPUT_NAMES,
                             "ReduceSum" => REDUCESUM_INPUT_NAMES,
+                            "Squeeze" | "Unsqueeze" => SQUEEZE_INPUT_NAMES,
+                            "NonMaxSuppression" => NONMAXSUPPRESSION_INPUT_NAMES,
                             _ => unreachable!(),
                         };
 
@@ -315,87 +450,113 @@ impl<'model> Optimizer<'model> {
                         let mut new_proto = op_def.proto.clone().into_owned();
                         let mut attributes = op_def.proto.get_attribute().to_vec();
 
-                        // Loop over the inputs (skipping the first one - that's going to be the data input)
-                        for input_index in 1..(new_inputs.len().min(attr_names.len())) {
+                        // Loop over the inputs (skipping the data inputs)
+                        for input_index in
+                            data_input_count..(new_inputs.len().min(attr_names.len()))
+                        {
                             let source_node = &new_inputs[input_index].source_node;
-                            match &source_node.definition {
-                                // If the input is an initializer (Tensor) we can obtain the data from the definition and move it to an attribute
-                                NodeDefinition::Tensor(tensor_proto) => {
-                                    let attr_name = attr_names[input_index];
-                                    let data_type =
-                                        ScalarType::from_i32(tensor_proto.get_data_type())?;
-
-                                    match (op, attr_name) {
-                                        // Inputs that need to be converted to an i64 attribute
-                                        ("Split", "split")
-                                        | ("Resize", "roi")
-                                        | ("Resize", "sizes")
-                                        | ("Reshape", "shape")
-                                        | ("ReduceSum", "axes") => match data_type {
-                                            ScalarType::I64 => {
-                                                log::info!(
-                                                        "transferring input {} for op {} to i64 attribute (initializer data type: {:?})",
-                                                        attr_name,
-                                                        op,
-                                                        data_type
-                                                    );
-                                                let value = tensor_proto.get_int64_data().to_vec();
-                                                attributes.push(attribute(
-                                                    attr_names[input_index],
-                                                    value,
-                                                ));
-                                            }
-                                            _ => {
-                                                return Err(OptimizerError::InvalidInputDataType {
-                                                    data_type,
-                                                    input: attr_name.to_string(),
-                                                    op: op.to_string(),
-                                                })
-                                            }
-                                        },
-                                        // Inputs that need to be converted to an f32 attribute
-                                        ("Resize", "scales") => match data_type {
-                                            ScalarType::F32 => {
-                                                log::info!(
-                                                        "transferring input {} for op {} to f32 attribute (initializer data type: {:?})",
-                                                        attr_name,
-                                                        op,
-                                                        data_type
-                                                    );
-                                                let value: Vec<f32> =
-                                                    tensor_proto.get_float_data().to_vec();
-                                                attributes.push(attribute(
-                                                    attr_names[input_index],
-                                                    value,
-                                                ));
-                                            }
-                                            _ => {
-                                                return Err(OptimizerError::InvalidInputDataType {
-                                                    data_type,
-                                                    input: attr_name.to_string(),
-                                                    op: op.to_string(),
-                                                })
-                                            }
-                                        },
-                                        _ => {
-                                            // Some other unspecified input that we do not support yet
-                                            return Err(OptimizerError::Unsupported(format!(
-                                                "data_type {} for input {} to op {}",
-                                                tensor_proto.get_data_type(),
+                            if let NodeDefinition::Missing = source_node.definition {
+                                // Just remove it
+                                continue;
+                            }
+
+                            // If the input is an initializer or the output of a Constant node we can obtain the data
+                            // from the definition and move it to an attribute
+                            let tensor_proto = constant_tensor(&source_node.definition)
+                                .ok_or_else(|| {
+                                    // One of the inputs (except the first) is something other than a tensor (e.g. 'dynamic')
+                                    OptimizerError::Unsupported(format!(
+                                        "{} operation with dynamic input for {}",
+                                        op, attr_names[input_index]
+                                    ))
+                                })?;
+                            let attr_name = attr_names[input_index];
+                            let data_type = ScalarType::from_i32(tensor_proto.get_data_type())?;
+
+                            match (op, attr_name) {
+                                // Inputs that need to be converted to an i64 attribute
+                                ("Split", "split")
+                                | ("Resize", "sizes")
+                                | ("Reshape", "shape")
+                                | ("ReduceSum", "axes")
+                                | ("Squeeze", "axes")
+                                | ("Unsqueeze", "axes") => match data_type {
+                                    ScalarType::I64 => {
+                                        log::info!(
+                                                "transferring input {} for op {} to i64 attribute (initializer data type: {:?})",
                                                 attr_name,
-                                                op
-                                            )));
+                                                op,
+                                                data_type
+                                            );
+                                        let value = tensor_proto.int64_values()?;
+                                        attributes.push(attribute(attr_names[input_index], value));
+                                    }
+                                    _ => {
+                                        return Err(OptimizerError::InvalidInputDataType {
+                                            data_type,
+                                            input: attr_name.to_string(),
+                                            op: op.to_string(),
+                                        })
+                                    }
+                                },
+                                // Inputs that need to be converted to an f32 attribute
+                                ("Resize", "roi" | "scales") => match data_type {
+                                    ScalarType::F32 => {
+                                        log::info!(
+                                                "transferring input {} for op {} to f32 attribute (initializer data type: {:?})",
+                                                attr_name,
+                                                op,
+                                                data_type
+                                            );
+                                        let value = tensor_proto.float_values()?;
+                                        attributes.push(attribute(attr_names[input_index], value));
+                                    }
+                                    _ => {
+                                        return Err(OptimizerError::InvalidInputDataType {
+                                            data_type,
+                                            input: attr_name.to_string(),
+                                            op: op.to_string(),
+                                        })
+                                    }
+                                },
+                                // Scalar inputs that need to be converted to an i64 attribute
+                                ("NonMaxSuppression", "max_output_boxes_per_class") => {
+                                    match (data_type, tensor_proto.int64_values()?.as_slice()) {
+                                        (ScalarType::I64, [value]) => {
+                                            attributes.push(attribute(attr_name, *value));
+                                        }
+                                        _ => {
+                                            return Err(OptimizerError::InvalidInputDataType {
+                                                data_type,
+                                                input: attr_name.to_string(),
+                                                op: op.to_string(),
+                                            })
                                         }
                                     }
                                 }
-                                NodeDefinition::Missing => {
-                                    // Just remove it
+                                // Scalar inputs that need to be converted to an f32 attribute
+                                ("NonMaxSuppression", "iou_threshold" | "score_threshold")
+                                | ("Clip", "min" | "max") => {
+                                    match (data_type, tensor_proto.float_values()?.as_slice()) {
+                                        (ScalarType::F32, [value]) => {
+                                            attributes.push(attribute(attr_name, *value));
+                                        }
+                                        _ => {
+                                            return Err(OptimizerError::InvalidInputDataType {
+                                                data_type,
+                                                input: attr_name.to_string(),
+                                                op: op.to_string(),
+                                            })
+                                        }
+                                    }
                                 }
                                 _ => {
-                                    // One of the inputs (except the first) is something other than a tensor (e.g. 'dynamic')
+                                    // Some other unspecified input that we do not support yet
                                     return Err(OptimizerError::Unsupported(format!(
-                                        "{} operation with dynamic input for {}",
-                                        op, attr_names[input_index]
+                                        "data_type {} for input {} to op {}",
+                                        tensor_proto.get_data_type(),
+                                        attr_name,
+                                        op
                                     )));
                                 }
                             }
@@ -404,8 +565,9 @@ impl<'model> Optimizer<'model> {
                         // Create new node with extra attributes
                         new_proto.set_attribute(RepeatedField::from(attributes));
 
+                        new_inputs.truncate(data_input_count);
                         let new_node = Node {
-                            inputs: vec![new_inputs[0].clone()],
+                            inputs: new_inputs,
                             definition: NodeDefinition::Operator(Box::new(OperatorDefinition {
                                 proto: Cow::Owned(new_proto),
                                 output_shapes: op_def.output_shapes.clone(),
@@ -437,6 +599,157 @@ impl<'model> Optimizer<'model> {
         }
     }
 
+    /// Lower a Conv with a large kernel to an Im2Col operation, which writes the patches of the input that the kernel is
+    /// applied to as columns of a matrix, followed by a Gemm with the weights (as [M, C*kH*kW] matrix). The Gemm is then
+    /// performed by the tiled matrix multiplication shader. The result is reshaped to the original output shape:
+    ///
+    /// X -> Conv(W, B) -> Y => X -> Im2Col -> Gemm(W, B) -> Reshape -> Y
+    ///
+    /// Returns None when the Conv is not lowered (e.g. because the kernel is small, or the lowering is not supported).
+    fn lower_conv_to_gemm(
+        &mut self,
+        op_def: &OperatorDefinition<'model>,
+        inputs: &[Input<'model>],
+    ) -> Result<Option<Arc<Node<'model>>>, OptimizerError> {
+        let proto = &op_def.proto;
+        let kernel_shape = get_attribute::<Vec<i64>>("kernel_shape", Some(vec![]), proto)?;
+        let auto_pad = get_attribute("auto_pad", Some("NOTSET".to_string()), proto)?;
+        let group = get_attribute("group", Some(1), proto)?;
+        if inputs.len() < 2
+            || kernel_shape.len() != 2
+            || kernel_shape.iter().all(|k| *k <= 3)
+            || auto_pad != "NOTSET"
+            || group != 1
+        {
+            return Ok(None);
+        }
+
+        let input_shape = inputs[0].source_node.output_shape(inputs[0].output_index)?;
+        let output_shape = &op_def.output_shapes[0];
+        if input_shape.rank() != 4 || input_shape.dim(0) != 1 {
+            return Ok(None);
+        }
+
+        // The output of the Gemm has M rows and one column per output pixel
+        let rows = output_shape.dim(1);
+        let columns = output_shape.dim(2) * output_shape.dim(3);
+        let patch_length = input_shape.dim(1) * (kernel_shape[0] * kernel_shape[1]) as u64;
+        if rows <= GEMM_TILED_THRESHOLD || columns <= GEMM_TILED_THRESHOLD {
+            return Ok(None);
+        }
+
+        // The patch matrix holds every input value once for each kernel position it is part of, so for large inputs it
+        // may not fit in a single buffer
+        let patch_matrix_bytes = patch_length * columns * ScalarType::F32.stride() as u64;
+        if patch_matrix_bytes > self.max_buffer_bytes {
+            log::info!(
+                "not lowering Conv {} to Im2Col+Gemm as its patch matrix ({} bytes) exceeds the maximum buffer size",
+                proto.get_name(),
+                patch_matrix_bytes
+            );
+            return Ok(None);
+        }
+
+        // The weights and bias need to be initializers, as these are reshaped to [M, C*kH*kW] and [M, 1] respectively
+        let weights = match reshaped_tensor(&inputs[1], &[rows as i64, patch_length as i64]) {
+            Some(weights) => weights,
+            None => return Ok(None),
+        };
+        let bias = match inputs.get(2) {
+            Some(bias_input)
+                if !matches!(bias_input.source_node.definition, NodeDefinition::Missing) =>
+            {
+                match reshaped_tensor(bias_input, &[rows as i64, 1]) {
+                    Some(bias) => Some(bias),
+                    None => return Ok(None),
+                }
+            }
+            _ => None,
+        };
+
+        log::info!(
+            "lowering Conv {} with kernel {:?} to Im2Col+Gemm",
+            proto.get_name(),
+            kernel_shape
+        );
+
+        let name = proto.get_name();
+        let output_name = proto.get_output()[0].as_str();
+        let im2col_output_name = format!("{}/im2col", output_name);
+        let gemm_output_name = format!("{}/gemm", output_name);
+
+        let im2col = Arc::new(Node {
+            definition: NodeDefinition::Operator(Box::new(OperatorDefinition {
+                proto: Cow::Owned(node(
+                    vec![proto.get_input()[0].as_str()],
+                    vec![&im2col_output_name],
+                    &format!("{}/im2col", name),
+                    "Im2Col",
+                    proto.get_attribute().to_vec(),
+                )),
+                output_shapes: vec![Shape {
+                    dims: vec![patch_length, columns],
+                    data_type: output_shape.data_type,
+                }],
+            })),
+            inputs: vec![inputs[0].clone()],
+        });
+
+        let mut gemm_inputs = vec![
+            Input {
+                source_node: weights,
+                output_index: 0,
+            },
+            Input {
+                source_node: im2col,
+                output_index: 0,
+            },
+        ];
+        gemm_inputs.extend(bias.map(|source_node| Input {
+            source_node,
+            output_index: 0,
+        }));
+        let gemm_input_names: Vec<String> = gemm_inputs
+            .iter()
+            .map(|input| input.source_node.definition.output_name(0).to_string())
+            .collect();
+
+        let gemm = Arc::new(Node {
+            definition: NodeDefinition::Operator(Box::new(OperatorDefinition {
+                proto: Cow::Owned(node(
+                    gemm_input_names.iter().map(|x| x.as_str()).collect(),
+                    vec![&gemm_output_name],
+                    &format!("{}/gemm", name),
+                    "Gemm",
+                    vec![],
+                )),
+                output_shapes: vec![Shape {
+                    dims: vec![rows, columns],
+                    data_type: output_shape.data_type,
+                }],
+            })),
+            inputs: gemm_inputs,
+        });
+
+        let output_dims: Vec<i64> = output_shape.dims.iter().map(|d| *d as i64).collect();
+        Ok(Some(Arc::new(Node {
+            definition: NodeDefinition::Operator(Box::new(OperatorDefinition {
+                proto: Cow::Owned(node(
+                    vec![&gemm_output_name],
+                    vec![output_name],
+                    &format!("{}/reshape", name),
+                    "Reshape",
+                    vec![attribute("shape", output_dims)],
+                )),
+                output_shapes: op_def.output_shapes.clone(),
+            })),
+            inputs: vec![Input {
+                source_node: gemm,
+                output_index: 0,
+            }],
+        })))
+    }
+
     /// Attempt to fuse several operators in a chain of operators with no other dynamic inputs.
     fn optimize_chain(
         &mut self,
@@ -506,11 +819,521 @@ impl<'model> Optimizer<'model> {
                     unreachable!();
                 }
             }
+            // DequantizeLinear+activation+QuantizeLinear: combine into QLinearActivation when quantization is per-tensor
+            // and the scales and zero points are constant
+            ["DequantizeLinear", activation, "QuantizeLinear", ..]
+                if QLINEAR_ACTIVATIONS.contains(activation) =>
+            {
+                let dequantize = chain[chain.len() - 1].1.clone();
+                let activation = chain[chain.len() - 2].1.clone();
+                let quantize = chain[chain.len() - 3].1.clone();
+
+                let (dequantize_def, activation_def, quantize_def) = match (
+                    &dequantize.definition,
+                    &activation.definition,
+                    &quantize.definition,
+                ) {
+                    (
+                        NodeDefinition::Operator(dequantize_def),
+                        NodeDefinition::Operator(activation_def),
+                        NodeDefinition::Operator(quantize_def),
+                    ) => (dequantize_def, activation_def, quantize_def),
+                    _ => unreachable!(),
+                };
+
+                // Clip with min and max inputs is not fused (these are only moved to attributes later on)
+                if activation.inputs.len() != 1 {
+                    return Ok(None);
+                }
+
+                let (x_scale, x_zero_point, y_scale, y_zero_point) = match (
+                    constant_scale(&dequantize)?,
+                    constant_zero_point(&dequantize)?,
+                    constant_scale(&quantize)?,
+                    constant_zero_point(&quantize)?,
+                ) {
+                    (Some(x_scale), Some(x_zero_point), Some(y_scale), Some(y_zero_point)) => {
+                        (x_scale, x_zero_point, y_scale, y_zero_point)
+                    }
+                    _ => return Ok(None),
+                };
+
+                let mut attributes = activation_def.proto.get_attribute().to_vec();
+                attributes.extend([
+                    attribute("activation", activation_def.proto.get_op_type()),
+                    attribute("x_scale", x_scale),
+                    attribute("x_zero_point", x_zero_point),
+                    attribute("y_scale", y_scale),
+                    attribute("y_zero_point", y_zero_point),
+                ]);
+                let fused_proto = node(
+                    vec![dequantize_def.proto.get_input()[0].as_str()],
+                    quantize_def
+                        .proto
+                        .get_output()
+                        .iter()
+                        .map(|x| x.as_str())
+                        .collect(),
+                    &format!(
+                        "{}+{}+{}",
+                        dequantize.definition.get_name(),
+                        activation.definition.get_name(),
+                        quantize.definition.get_name()
+                    ),
+                    "QLinearActivation",
+                    attributes,
+                );
+
+                log::debug!(
+                    "can fuse chain of DequantizeLinear/{}/QuantizeLinear to QLinearActivation: {}",
+                    activation_def.proto.get_op_type(),
+                    fused_proto.get_name()
+                );
+
+                let node = Arc::new(Node {
+                    inputs: vec![dequantize.inputs[0].clone()],
+                    definition: NodeDefinition::Operator(Box::new(OperatorDefinition {
+                        proto: Cow::Owned(fused_proto),
+                        output_shapes: quantize_def.output_shapes.clone(),
+                    })),
+                });
+                let new_inputs = vec![Input {
+                    source_node: self.optimize(dequantize.inputs[0].source_node.clone())?,
+                    output_index: dequantize.inputs[0].output_index,
+                }];
+
+                Ok(Some(Sequence {
+                    node: self.optimized_with(&node, new_inputs)?,
+                    skip: 2,
+                }))
+            }
             _ => Ok(None),
         }
     }
 }
 
+/// The scale of a DequantizeLinear or QuantizeLinear node, if it is a constant scalar (per-tensor quantization)
+fn constant_scale(node: &Node) -> Result<Option<f32>, OptimizerError> {
+    match node
+        .inputs
+        .get(1)
+        .map(|input| &input.source_node.definition)
+    {
+        Some(NodeDefinition::Tensor(tensor)) => match tensor.float_values()?.as_slice() {
+            [scale] => Ok(Some(*scale)),
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// The zero point of a DequantizeLinear or QuantizeLinear node, if it is a constant scalar or not specified (in which
+/// case it is zero)
+fn constant_zero_point(node: &Node) -> Result<Option<i64>, OptimizerError> {
+    match node
+        .inputs
+        .get(2)
+        .map(|input| &input.source_node.definition)
+    {
+        None | Some(NodeDefinition::Missing) => Ok(Some(0)),
+        Some(NodeDefinition::Tensor(tensor)) => match tensor.int64_values()?.as_slice() {
+            [zero_point] => Ok(Some(*zero_point)),
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// The value of a node that is an initializer or a Constant node (with a tensor value)
+fn constant_tensor<'a>(definition: &'a NodeDefinition) -> Option<&'a TensorProto> {
+    match definition {
+        NodeDefinition::Tensor(tensor) => Some(tensor.as_ref()),
+        NodeDefinition::Operator(op_def) if op_def.proto.get_op_type() == "Constant" => op_def
+            .proto
+            .get_attribute()
+            .iter()
+            .find(|attribute| attribute.get_name() == "value")
+            .map(|attribute| attribute.get_t()),
+        _ => None,
+    }
+}
+
+/// The index of the input that is negated by a Sub(0, x), Mul(-1, x) or Mul(x, -1) node, where the constant is an
+/// initializer or Constant node that holds a single value (possibly repeated) and does not broadcast x to a larger shape
+fn negated_input(
+    op: &str,
+    op_def: &OperatorDefinition,
+    inputs: &[Input],
+) -> Result<Option<usize>, OptimizerError> {
+    if inputs.len() != 2 {
+        return Ok(None);
+    }
+
+    let holds_only = |input: &Input, value: f32| -> Result<bool, OptimizerError> {
+        let tensor = match constant_tensor(&input.source_node.definition) {
+            Some(tensor) => tensor,
+            None => return Ok(false),
+        };
+        let values = match ScalarType::from_i32(tensor.get_data_type())? {
+            ScalarType::F32 => tensor.float_values()?,
+            _ => tensor
+                .int64_values()?
+                .iter()
+                .map(|value| *value as f32)
+                .collect(),
+        };
+        Ok(!values.is_empty() && values.iter().all(|v| *v == value))
+    };
+
+    let candidates: &[(usize, usize)] = match op {
+        "Sub" => &[(1, 0)],
+        _ => &[(1, 0), (0, 1)],
+    };
+    let constant_value = if op == "Sub" { 0.0 } else { -1.0 };
+    for (index, constant_index) in candidates {
+        let negated = &inputs[*index];
+        if holds_only(&inputs[*constant_index], constant_value)?
+            && negated.source_node.output_shape(negated.output_index)?.dims
+                == op_def.output_shapes[0].dims
+        {
+            return Ok(Some(*index));
+        }
+    }
+    Ok(None)
+}
+
+/// Replace `If` nodes of which the condition is constant (an initializer or the output of a Constant node) with the nodes
+/// of the branch selected by the condition. The outputs of the branch are connected to the outputs of the If node with
+/// Identity nodes (which are removed by the optimizer). If nodes with any other condition are left in place.
+pub fn inline_constant_branches(model: &mut ModelProto) -> Result<(), OptimizerError> {
+    let graph = model.mut_graph();
+    let mut pending: VecDeque<NodeProto> = graph.take_node().into_iter().collect();
+    let mut nodes = Vec::with_capacity(pending.len());
+
+    while let Some(node) = pending.pop_front() {
+        if node.get_op_type() != "If" || !node.get_domain().is_empty() {
+            nodes.push(node);
+            continue;
+        }
+
+        let condition_name = node.get_input().first().ok_or(OptimizerError::NoInputs)?;
+        let condition = graph
+            .get_initializer()
+            .iter()
+            .find(|initializer| initializer.get_name() == condition_name)
+            .or_else(|| {
+                nodes
+                    .iter()
+                    .find(|n| {
+                        n.get_op_type() == "Constant"
+                            && n.get_output().first() == Some(condition_name)
+                    })
+                    .and_then(|n| n.get_attribute().iter().find(|a| a.get_name() == "value"))
+                    .map(|a| a.get_t())
+            })
+            .map(|tensor: &TensorProto| -> Result<bool, OptimizerError> {
+                Ok(tensor.data_bytes()?.iter().any(|b| *b != 0))
+            })
+            .transpose()?;
+
+        let condition = match condition {
+            Some(condition) => condition,
+            None => {
+                nodes.push(node);
+                continue;
+            }
+        };
+
+        let branch_name = if condition {
+            "then_branch"
+        } else {
+            "else_branch"
+        };
+        log::info!(
+            "inlining {} of If node {} (condition is constant)",
+            branch_name,
+            node.get_name()
+        );
+        let branch = node
+            .get_attribute()
+            .iter()
+            .find(|a| a.get_name() == branch_name)
+            .map(|a| a.get_g())
+            .ok_or_else(|| {
+                OptimizerError::Unsupported(format!("If node without {}", branch_name))
+            })?;
+
+        if branch.get_output().len() != node.get_output().len() {
+            return Err(OptimizerError::Unsupported(format!(
+                "{} of If node {} has {} outputs, expected {}",
+                branch_name,
+                node.get_name(),
+                branch.get_output().len(),
+                node.get_output().len()
+            )));
+        }
+
+        // Names in a subgraph are distinct from those in the outer graph, so these can be moved over as-is. The nodes
+        // of the branch are processed next, as these may contain If nodes themselves.
+        graph
+            .mut_initializer()
+            .extend(branch.get_initializer().iter().cloned());
+        graph
+            .mut_value_info()
+            .extend(branch.get_value_info().iter().cloned());
+        for (branch_output, output_name) in branch.get_output().iter().zip(node.get_output()) {
+            pending.push_front(crate::utils::node(
+                vec![branch_output.get_name()],
+                vec![output_name],
+                &format!("{}/{}", node.get_name(), output_name),
+                "Identity",
+                vec![],
+            ));
+        }
+        for branch_node in branch.get_node().iter().rev() {
+            pending.push_front(branch_node.clone());
+        }
+    }
+
+    graph.set_node(RepeatedField::from(nodes));
+    Ok(())
+}
+
+/// Replace `Scan` nodes with one state variable and one scan input by copies of their body, one for each element of the
+/// scanned sequence (which must have a known length). Each copy takes the element of the sequence it processes (taken
+/// with a Gather node) and the state produced by the previous copy. The scan outputs of all copies are stacked along the
+/// first axis using Unsqueeze and Concat nodes. Only scanning along the first axis is supported, in either direction.
+pub fn unroll_scans(model: &mut ModelProto, opset_version: i64) -> Result<(), OptimizerError> {
+    let graph = model.mut_graph();
+    let mut pending: VecDeque<NodeProto> = graph.take_node().into_iter().collect();
+    let mut nodes = Vec::with_capacity(pending.len());
+
+    while let Some(node) = pending.pop_front() {
+        if node.get_op_type() != "Scan" || !node.get_domain().is_empty() {
+            nodes.push(node);
+            continue;
+        }
+
+        let name = node.get_name();
+        let unsupported =
+            |reason: &str| OptimizerError::Unsupported(format!("Scan node {} {}", name, reason));
+        // Scan-8 has a different signature (with a batch axis and sequence lengths)
+        if opset_version < 9 {
+            return Err(unsupported("is only supported from opset 9 onwards"));
+        }
+        let num_scan_inputs = get_attribute::<i64>("num_scan_inputs", None, &node)?;
+        if num_scan_inputs != 1 || node.get_input().len() != 2 {
+            return Err(unsupported(
+                "must have one state variable and one scan input",
+            ));
+        }
+        let body = node
+            .get_attribute()
+            .iter()
+            .find(|a| a.get_name() == "body")
+            .map(|a| a.get_g())
+            .ok_or_else(|| unsupported("has no body"))?;
+        if body.get_input().len() != 2 || body.get_output().is_empty() {
+            return Err(unsupported(
+                "has a body with an unexpected number of inputs or outputs",
+            ));
+        }
+        let scan_output_count = body.get_output().len() - 1;
+        for axes_attribute in &["scan_input_axes", "scan_output_axes"] {
+            let axes = get_attribute::<Vec<i64>>(axes_attribute, Some(vec![]), &node)?;
+            if axes.iter().any(|axis| *axis != 0) {
+                return Err(unsupported(&format!("scans along axes {:?}", axes)));
+            }
+        }
+        let input_reversed =
+            get_attribute::<Vec<i64>>("scan_input_directions", Some(vec![0]), &node)?.first()
+                == Some(&1);
+        let output_directions = get_attribute::<Vec<i64>>(
+            "scan_output_directions",
+            Some(vec![0; scan_output_count]),
+            &node,
+        )?;
+
+        // The number of copies follows from the (declared) shape of the scan input
+        let (initial_state, scan_input) = (&node.get_input()[0], &node.get_input()[1]);
+        let sequence_length = graph
+            .get_input()
+            .iter()
+            .chain(graph.get_value_info())
+            .find(|info| info.get_name() == scan_input)
+            .and_then(|info| info.get_shape().ok())
+            .map(|shape| shape.dims)
+            .or_else(|| {
+                graph
+                    .get_initializer()
+                    .iter()
+                    .find(|initializer| initializer.get_name() == scan_input)
+                    .map(|initializer| initializer.get_dims().iter().map(|d| *d as u64).collect())
+            })
+            .and_then(|dims| dims.first().copied())
+            .filter(|length| *length > 0)
+            .ok_or_else(|| unsupported("has a scan input of which the length is unknown"))?;
+        log::info!(
+            "unrolling Scan node {} over a sequence of length {}",
+            name,
+            sequence_length
+        );
+
+        // Names in a subgraph are distinct from those in the outer graph, so initializers can be moved over as-is (these
+        // are shared by all copies of the body)
+        graph
+            .mut_initializer()
+            .extend(body.get_initializer().iter().cloned());
+        let (body_state, body_element) = (
+            body.get_input()[0].get_name(),
+            body.get_input()[1].get_name(),
+        );
+        let produced: Vec<&str> = body
+            .get_node()
+            .iter()
+            .flat_map(|n| n.get_output())
+            .map(|s| s.as_str())
+            .collect();
+
+        let mut state = initial_state.to_string();
+        let mut scan_outputs: Vec<Vec<String>> = vec![vec![]; scan_output_count];
+        let mut unrolled = vec![];
+        for iteration in 0..sequence_length {
+            let prefix = format!("{}/{}", name, iteration);
+            let index = if input_reversed {
+                sequence_length - 1 - iteration
+            } else {
+                iteration
+            };
+            let index_name = format!("{}/index", prefix);
+            let element_name = format!("{}/{}", prefix, body_element);
+            graph
+                .mut_initializer()
+                .push(initializer_of(&index_name, &[], vec![index as i64]));
+            unrolled.push(crate::utils::node(
+                vec![scan_input.as_str(), index_name.as_str()],
+                vec![element_name.as_str()],
+                &format!("{}/gather", prefix),
+                "Gather",
+                vec![],
+            ));
+
+            // Values produced inside the body are prefixed; the inputs of the body refer to the state and element
+            let rename = |value: &str| -> String {
+                if value == body_state {
+                    state.clone()
+                } else if value == body_element {
+                    element_name.clone()
+                } else if !value.is_empty() && produced.contains(&value) {
+                    format!("{}/{}", prefix, value)
+                } else {
+                    value.to_string()
+                }
+            };
+
+            for body_node in body.get_node() {
+                let mut copy = body_node.clone();
+                copy.set_name(format!("{}/{}", prefix, body_node.get_name()));
+                copy.set_input(body_node.get_input().iter().map(|i| rename(i)).collect());
+                copy.set_output(body_node.get_output().iter().map(|o| rename(o)).collect());
+                unrolled.push(copy);
+            }
+            for info in body.get_value_info() {
+                let mut copy = info.clone();
+                copy.set_name(rename(info.get_name()));
+                graph.mut_value_info().push(copy);
+            }
+
+            let outputs: Vec<String> = body
+                .get_output()
+                .iter()
+                .map(|o| rename(o.get_name()))
+                .collect();
+            for (scan_output, output) in scan_outputs.iter_mut().zip(&outputs[1..]) {
+                scan_output.push(output.clone());
+            }
+            state = outputs[0].clone();
+        }
+
+        // The final state is the state produced by the last copy of the body
+        unrolled.push(crate::utils::node(
+            vec![state.as_str()],
+            vec![node.get_output()[0].as_str()],
+            &format!("{}/state", name),
+            "Identity",
+            vec![],
+        ));
+
+        // Each scan output is the concatenation of the values produced by the copies of the body, with a leading axis
+        let axes_name = format!("{}/axes", name);
+        graph
+            .mut_initializer()
+            .push(initializer_of(&axes_name, &[1], vec![0i64]));
+        for (output_index, (elements, output_name)) in scan_outputs
+            .iter_mut()
+            .zip(&node.get_output()[1..])
+            .enumerate()
+        {
+            if output_directions.get(output_index) == Some(&1) {
+                elements.reverse();
+            }
+            let mut stacked = vec![];
+            for (iteration, element) in elements.iter().enumerate() {
+                let unsqueezed = format!("{}/{}/{}/unsqueezed", name, output_name, iteration);
+                unrolled.push(if opset_version >= 13 {
+                    crate::utils::node(
+                        vec![element.as_str(), axes_name.as_str()],
+                        vec![unsqueezed.as_str()],
+                        &format!("{}/{}/{}/unsqueeze", name, output_name, iteration),
+                        "Unsqueeze",
+                        vec![],
+                    )
+                } else {
+                    crate::utils::node(
+                        vec![element.as_str()],
+                        vec![unsqueezed.as_str()],
+                        &format!("{}/{}/{}/unsqueeze", name, output_name, iteration),
+                        "Unsqueeze",
+                        vec![attribute("axes", vec![0])],
+                    )
+                });
+                stacked.push(unsqueezed);
+            }
+            unrolled.push(crate::utils::node(
+                stacked.iter().map(|s| s.as_str()).collect(),
+                vec![output_name.as_str()],
+                &format!("{}/{}/concat", name, output_name),
+                "Concat",
+                vec![attribute("axis", 0)],
+            ));
+        }
+
+        // The copies of the body are processed next, as these may contain Scan nodes themselves
+        for unrolled_node in unrolled.into_iter().rev() {
+            pending.push_front(unrolled_node);
+        }
+    }
+
+    graph.set_node(RepeatedField::from(nodes));
+    Ok(())
+}
+
+/// Returns a copy of the initializer that provides the input with different dimensions (the data is not changed), or
+/// None when the input is not an initializer.
+fn reshaped_tensor<'model>(input: &Input<'model>, dims: &[i64]) -> Option<Arc<Node<'model>>> {
+    if let NodeDefinition::Tensor(tensor) = &input.source_node.definition {
+        let mut new_tensor = tensor.clone().into_owned();
+        new_tensor.set_name(format!("{}/reshaped", tensor.get_name()));
+        new_tensor.set_dims(dims.to_vec());
+        Some(Arc::new(Node {
+            definition: NodeDefinition::Tensor(Box::new(Cow::Owned(new_tensor))),
+            inputs: vec![],
+        }))
+    } else {
+        None
+    }
+}
+
 impl<'model> Default for Optimizer<'model> {
     fn default() -> Self {
         Self::new()
@@ -523,3 +1346,11 @@ static RESIZE_INPUT_NAMES: &[&str] = &["X", "roi", "scales", "sizes"];
 static RESHAPE_INPUT_NAMES: &[&str] = &["data", "shape"];
 static CLIP_INPUT_NAMES: &[&str] = &["input", "min", "max"];
 static REDUCESUM_INPUT_NAMES: &[&str] = &["input", "axes"];
+static SQUEEZE_INPUT_NAMES: &[&str] = &["data", "axes"];
+static NONMAXSUPPRESSION_INPUT_NAMES: &[&str] = &[
+    "boxes",
+    "scores",
+    "max_output_boxes_per_class",
+    "iou_threshold",
+    "score_threshold",
+];
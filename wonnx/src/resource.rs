@@ -1,3 +1,6 @@
+use crate::onnx::{TensorProto, TensorProto_DataType};
+use crate::utils::{ceil, DataTypeError, ScalarType, MINIMUM_BUFFER_SIZE_BYTES};
+use protobuf::ProtobufEnum;
 use wgpu::{util::DeviceExt, BufferUsages};
 
 // Get a device and a queue, honoring WGPU_ADAPTER_NAME and WGPU_BACKEND environment variables
@@ -8,37 +11,116 @@ pub async fn request_device_queue() -> (wgpu::Device, wgpu::Queue) {
         .await
         .expect("No GPU found given preference");
 
+    // Some ops (e.g. LSTM) bind more storage buffers than the WebGPU default of eight, so ask for whatever the adapter
+    // supports.
+    let limits = wgpu::Limits {
+        max_storage_buffers_per_shader_stage: adapter.limits().max_storage_buffers_per_shader_stage,
+        ..wgpu::Limits::default()
+    };
+
     // `request_device` instantiates the feature specific connection to the GPU, defining some parameters,
     //  `features` being the available features.
     adapter
-        .request_device(&wgpu::DeviceDescriptor::default(), None)
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                limits,
+                ..wgpu::DeviceDescriptor::default()
+            },
+            None,
+        )
         .await
         .expect("Could not create adapter for GPU device")
 }
 
+// Most shaders read and write vec4's, so buffers are always sized to hold a whole number of them. Otherwise the last
+// (partial) vec4 of a tensor would be out of bounds.
+const BUFFER_SIZE_ALIGNMENT_BYTES: usize = 16;
+
+fn aligned_size(size_bytes: usize) -> usize {
+    let alignment = BUFFER_SIZE_ALIGNMENT_BYTES as u64;
+    usize::max(
+        BUFFER_SIZE_ALIGNMENT_BYTES,
+        (ceil(size_bytes as u64, alignment) * alignment) as usize,
+    )
+}
+
 pub fn create_buffer_init<T: Clone + bytemuck::Pod>(
     device: &wgpu::Device,
     array: &[T],
     name: &str,
     usage: BufferUsages,
 ) -> wgpu::Buffer {
-    let array = resize(array.to_vec());
+    let mut contents = bytemuck::cast_slice::<T, u8>(array).to_vec();
+    contents.resize(aligned_size(contents.len()), 0);
 
     device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
         label: Some(name),
-        contents: bytemuck::cast_slice(&array),
+        contents: &contents,
         usage,
     })
 }
 
+/// Create a buffer holding the data of a tensor (e.g. an initializer) in the representation used on the GPU. Booleans and
+/// 8-bit integers are widened to 32-bit integers, bfloat16 values are upcast and double values downcast to f32; other
+/// values are copied as they are (int64 values thus take two 32-bit words each). The buffer is padded like those created
+/// by `create_buffer_init`, and is at least `MINIMUM_BUFFER_SIZE_BYTES` large so that it can always be bound.
+pub fn buffer_from_tensor_proto(
+    device: &wgpu::Device,
+    tensor: &TensorProto,
+) -> Result<wgpu::Buffer, DataTypeError> {
+    tensor_buffer(
+        device,
+        tensor,
+        BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+    )
+}
+
+pub(crate) fn tensor_buffer(
+    device: &wgpu::Device,
+    tensor: &TensorProto,
+    usage: BufferUsages,
+) -> Result<wgpu::Buffer, DataTypeError> {
+    let data_type = ScalarType::from_i32(tensor.get_data_type())?;
+    let data = tensor.data_bytes()?;
+    let widened_data: Vec<i32>;
+    let upcast_data: Vec<f32>;
+    let raw_data: &[u8] = match data_type {
+        ScalarType::F32 if tensor.get_data_type() != TensorProto_DataType::FLOAT.value() => {
+            upcast_data = tensor.float_values()?;
+            bytemuck::cast_slice(&upcast_data)
+        }
+        ScalarType::Bool => {
+            widened_data = data.iter().map(|b| (*b != 0) as i32).collect();
+            bytemuck::cast_slice(&widened_data)
+        }
+        ScalarType::I8 => {
+            widened_data = data.iter().map(|b| *b as i8 as i32).collect();
+            bytemuck::cast_slice(&widened_data)
+        }
+        ScalarType::U8 => {
+            widened_data = data.iter().map(|b| *b as i32).collect();
+            bytemuck::cast_slice(&widened_data)
+        }
+        _ => &data,
+    };
+
+    // Do not create buffers that are too small
+    Ok(if raw_data.len() < MINIMUM_BUFFER_SIZE_BYTES as _ {
+        let mut larger_raw_data = raw_data.to_vec();
+        larger_raw_data.resize(MINIMUM_BUFFER_SIZE_BYTES as _, 0);
+        create_buffer_init(device, &larger_raw_data, tensor.get_name(), usage)
+    } else {
+        create_buffer_init(device, raw_data, tensor.get_name(), usage)
+    })
+}
+
 pub fn buffer(
     device: &wgpu::Device,
     requested_size_bytes: usize,
     name: &str,
     usage: BufferUsages,
 ) -> wgpu::Buffer {
-    let slice_size = usize::max(16, requested_size_bytes);
-    let size = slice_size as wgpu::BufferAddress;
+    let size = aligned_size(requested_size_bytes) as wgpu::BufferAddress;
     device.create_buffer(&wgpu::BufferDescriptor {
         label: Some(name),
         size,
@@ -85,4 +167,60 @@ mod tests {
             wgpu::BufferUsages::STORAGE,
         );
     }
+
+    /// Read back the first `size` bytes of a buffer that can be copied from
+    fn read_buffer(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        buffer: &wgpu::Buffer,
+        size: u64,
+    ) -> Vec<u8> {
+        let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(buffer, 0, &staging_buffer, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        let slice = staging_buffer.slice(..);
+        let map_future = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(map_future).unwrap();
+        let data = slice.get_mapped_range().to_vec();
+        data
+    }
+
+    #[test]
+    fn test_buffer_from_tensor_proto() {
+        use crate::utils::{initializer_of, MINIMUM_BUFFER_SIZE_BYTES};
+
+        let (device, queue) = pollster::block_on(crate::resource::request_device_queue());
+
+        let floats = vec![1.5f32, -2.0, 3.25];
+        let tensor = initializer_of("F", &[3], floats.clone());
+        let buffer = crate::resource::buffer_from_tensor_proto(&device, &tensor).unwrap();
+        // The buffer is padded with zeroes to the minimum buffer size
+        let data = read_buffer(&device, &queue, &buffer, MINIMUM_BUFFER_SIZE_BYTES);
+        let values: Vec<f32> = data
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(&values[0..3], &floats[..]);
+        assert!(values[3..].iter().all(|v| *v == 0.0));
+
+        // Each int64 value takes two 32-bit words (low word first)
+        let ints = vec![-1i64, 1 << 33, 7];
+        let tensor = initializer_of("I", &[3], ints);
+        let buffer = crate::resource::buffer_from_tensor_proto(&device, &tensor).unwrap();
+        let data = read_buffer(&device, &queue, &buffer, 24);
+        let words: Vec<i32> = data
+            .chunks(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(words, vec![-1, -1, 0, 2, 7, 0]);
+    }
 }
@@ -0,0 +1,986 @@
+//! Shape inference for models that do not specify the shapes of (some) intermediate values.
+//!
+//! Exporters often only specify the shapes of the inputs and outputs of a model. Wonnx needs to know the shape of every
+//! value, so this module propagates shapes from the inputs and initializers through the nodes of the graph and adds the
+//! inferred shapes to the `value_info` of the graph.
+use crate::onnx::{ModelProto, NodeProto, TensorProto, ValueInfoProto};
+use crate::utils::{
+    get_attribute, shape_dimension_range, tensor_of_type, AttributeNotFoundError, DataTypeError,
+    ScalarType, Shape,
+};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ShapeInferenceError {
+    #[error("the shape of input '{0}' of node '{1}' is unknown")]
+    InputShapeMissing(String, String),
+
+    #[error("cannot infer the output shape of node '{node_name}' (op {op_type}); you may want to run onnx-simplifier on the model first.")]
+    Unsupported { node_name: String, op_type: String },
+
+    #[error("cannot infer the output shape of node '{node_name}' (op {op_type}): {reason}")]
+    Invalid {
+        node_name: String,
+        op_type: String,
+        reason: String,
+    },
+
+    #[error("input '{input}' of node '{node_name}' must be a constant (initializer or Constant node) to infer its output shape")]
+    ConstantRequired { input: String, node_name: String },
+
+    #[error("attribute not found: {0}")]
+    AttributeNotFound(#[from] AttributeNotFoundError),
+
+    #[error("issue with data types: {0}")]
+    Type(#[from] DataTypeError),
+}
+
+/// Infer the shapes of all values in the model's graph that are not specified in the graph's `value_info` (or outputs),
+/// and add them to the `value_info`.
+pub fn infer_shapes(model: &mut ModelProto, opset_version: i64) -> Result<(), ShapeInferenceError> {
+    let inferred = {
+        let graph = model.get_graph();
+        let mut shapes: HashMap<&str, Shape> = HashMap::new();
+        for value_info in graph
+            .get_input()
+            .iter()
+            .chain(graph.get_value_info())
+            .chain(graph.get_output())
+        {
+            if !value_info.get_name().is_empty() && value_info.has_field_type() {
+                shapes.insert(value_info.get_name(), value_info.get_shape()?);
+            }
+        }
+
+        let mut constants: HashMap<&str, &TensorProto> = HashMap::new();
+        for initializer in graph.get_initializer() {
+            shapes.insert(
+                initializer.get_name(),
+                Shape::from(
+                    ScalarType::from_i32(initializer.get_data_type())?,
+                    initializer.get_dims(),
+                ),
+            );
+            constants.insert(initializer.get_name(), initializer);
+        }
+
+        // ONNX requires the nodes in a graph to be sorted topologically
+        let mut inferred: Vec<ValueInfoProto> = vec![];
+        for node in graph.get_node() {
+            if node.get_op_type() == "Constant" {
+                if let Some(value) = node
+                    .get_attribute()
+                    .iter()
+                    .find(|a| a.get_name() == "value")
+                {
+                    constants.insert(&node.get_output()[0], value.get_t());
+                }
+            }
+
+            let missing_outputs = node
+                .get_output()
+                .iter()
+                .any(|output| !output.is_empty() && !shapes.contains_key(output.as_str()));
+            if !missing_outputs {
+                continue;
+            }
+
+            let input_shapes = node
+                .get_input()
+                .iter()
+                .map(|input| {
+                    if input.is_empty() {
+                        Ok(None)
+                    } else {
+                        shapes.get(input.as_str()).map(Some).ok_or_else(|| {
+                            ShapeInferenceError::InputShapeMissing(
+                                input.to_string(),
+                                node.get_name().to_string(),
+                            )
+                        })
+                    }
+                })
+                .collect::<Result<Vec<Option<&Shape>>, ShapeInferenceError>>()?;
+
+            let output_shapes = infer_node_shapes(node, &input_shapes, &constants, opset_version)?;
+            for (output_name, output_shape) in node.get_output().iter().zip(output_shapes) {
+                if output_name.is_empty() || shapes.contains_key(output_name.as_str()) {
+                    continue;
+                }
+                log::info!("inferred shape {} for value {}", output_shape, output_name);
+                let dims: Vec<i64> = output_shape.dims.iter().map(|d| *d as i64).collect();
+                inferred.push(tensor_of_type(
+                    output_name,
+                    &dims,
+                    output_shape.data_type.to_datatype(),
+                ));
+                shapes.insert(output_name, output_shape);
+            }
+        }
+        inferred
+    };
+
+    model.mut_graph().mut_value_info().extend(inferred);
+    Ok(())
+}
+
+/// Broadcast the specified shapes to a common shape (see
+/// https://github.com/onnx/onnx/blob/main/docs/Broadcasting.md#multidirectional-broadcasting)
+fn broadcast(shapes: &[&Shape]) -> Option<Vec<u64>> {
+    let rank = shapes.iter().map(|s| s.rank()).max().unwrap_or(0);
+    let mut dims = vec![1; rank];
+    for shape in shapes {
+        let offset = rank - shape.rank();
+        for (index, dim) in shape.dims.iter().enumerate() {
+            let target = &mut dims[offset + index];
+            if *target == 1 {
+                *target = *dim;
+            } else if *dim != 1 && *dim != *target {
+                return None;
+            }
+        }
+    }
+    Some(dims)
+}
+
+/// Resolve a (possibly negative) axis for a tensor of the specified rank (scalars are treated as having a single axis)
+fn normalize_axis(axis: i64, rank: usize) -> Option<usize> {
+    crate::utils::normalize_axis(axis, rank.max(1)).ok()
+}
+
+/// Calculate the size of the spatial output dimensions of a convolution or pooling operation
+#[allow(clippy::too_many_arguments)]
+fn spatial_output_dims(
+    input_dims: &[u64],
+    kernel_shape: &[i64],
+    dilations: &[i64],
+    strides: &[i64],
+    pads: &[i64],
+    auto_pad: &str,
+    ceil_mode: bool,
+) -> Vec<u64> {
+    let spatial_rank = input_dims.len();
+    (0..spatial_rank)
+        .map(|i| {
+            let input = input_dims[i] as i64;
+            let stride = strides[i].max(1);
+            let kernel = (kernel_shape[i] - 1) * dilations[i] + 1;
+            let output = match auto_pad {
+                "SAME_UPPER" | "SAME_LOWER" => (input + stride - 1) / stride,
+                "VALID" => (input - kernel) / stride + 1,
+                _ => {
+                    let padded =
+                        input + pads[i] + pads.get(i + spatial_rank).copied().unwrap_or(0) - kernel;
+                    if ceil_mode {
+                        (padded + stride - 1) / stride + 1
+                    } else {
+                        padded / stride + 1
+                    }
+                }
+            };
+            output.max(0) as u64
+        })
+        .collect()
+}
+
+fn infer_node_shapes(
+    node: &NodeProto,
+    input_shapes: &[Option<&Shape>],
+    constants: &HashMap<&str, &TensorProto>,
+    opset_version: i64,
+) -> Result<Vec<Shape>, ShapeInferenceError> {
+    let op_type = node.get_op_type();
+    let invalid = |reason: &str| ShapeInferenceError::Invalid {
+        node_name: node.get_name().to_string(),
+        op_type: op_type.to_string(),
+        reason: reason.to_string(),
+    };
+    let input = |index: usize| -> Result<&Shape, ShapeInferenceError> {
+        input_shapes
+            .get(index)
+            .copied()
+            .flatten()
+            .ok_or_else(|| invalid(&format!("input {} is required", index)))
+    };
+
+    // Obtain the (integer) contents of a constant input, if present
+    let constant_input = |index: usize| -> Result<Option<Vec<i64>>, ShapeInferenceError> {
+        match node.get_input().get(index) {
+            None => Ok(None),
+            Some(name) if name.is_empty() => Ok(None),
+            Some(name) => match constants.get(name.as_str()) {
+                Some(tensor) => Ok(Some(tensor.int64_values()?)),
+                None => Err(ShapeInferenceError::ConstantRequired {
+                    input: name.to_string(),
+                    node_name: node.get_name().to_string(),
+                }),
+            },
+        }
+    };
+
+    // Some operators moved (some of) their attributes to inputs in later opsets
+    let attribute_or_input = |name: &str,
+                              index: usize,
+                              input_since_opset: i64|
+     -> Result<Option<Vec<i64>>, ShapeInferenceError> {
+        if opset_version >= input_since_opset {
+            constant_input(index)
+        } else {
+            Ok(get_attribute::<Vec<i64>>(name, None, node).ok())
+        }
+    };
+
+    let same_as_input =
+        |index: usize| -> Result<Shape, ShapeInferenceError> { Ok(input(index)?.clone()) };
+
+    Ok(match op_type {
+        // Element-wise operators that do not change the shape
+        "Abs"
+        | "Affine"
+        | "Acos"
+        | "Acosh"
+        | "Asin"
+        | "Asinh"
+        | "Atan"
+        | "Atanh"
+        | "BatchNormalization"
+        | "Ceil"
+        | "Celu"
+        | "Clip"
+        | "Cos"
+        | "Cosh"
+        | "CumSum"
+        | "Dropout"
+        | "Elu"
+        | "Erf"
+        | "Exp"
+        | "Floor"
+        | "GroupNormalization"
+        | "HardSigmoid"
+        | "HardSwish"
+        | "Identity"
+        | "InstanceNormalization"
+        | "LeakyRelu"
+        | "Log"
+        | "LogSoftmax"
+        | "LRN"
+        | "Mish"
+        | "Neg"
+        | "Not"
+        | "Reciprocal"
+        | "Relu"
+        | "ReverseSequence"
+        | "Round"
+        | "ScatterND"
+        | "Selu"
+        | "Sigmoid"
+        | "Sign"
+        | "Sin"
+        | "Sinh"
+        | "Softmax"
+        | "Softplus"
+        | "Softsign"
+        | "Sqrt"
+        | "Tan"
+        | "Tanh"
+        | "ThresholdedRelu" => {
+            let output = same_as_input(0)?;
+            if op_type == "Dropout" && node.get_output().len() > 1 {
+                let mask = Shape {
+                    dims: output.dims.clone(),
+                    data_type: ScalarType::Bool,
+                };
+                vec![output, mask]
+            } else {
+                vec![output]
+            }
+        }
+
+        "LayerNormalization" | "SkipLayerNormalization" => {
+            // The mean and inverse standard deviation are computed for each row (the dimensions from the axis onwards)
+            let x = input(0)?;
+            let axis = normalize_axis(get_attribute("axis", Some(-1), node)?, x.rank())
+                .ok_or_else(|| invalid("axis out of range"))?;
+            let mut statistics = x.clone();
+            for dim in statistics.dims.iter_mut().skip(axis) {
+                *dim = 1;
+            }
+            let mut outputs = vec![x.clone(), statistics.clone(), statistics, x.clone()];
+            outputs.truncate(node.get_output().len());
+            outputs
+        }
+
+        "Cast" => {
+            let to = get_attribute::<i64>("to", None, node)?;
+            vec![Shape {
+                dims: input(0)?.dims.clone(),
+                data_type: ScalarType::from_i32(to as i32)?,
+            }]
+        }
+
+        // Element-wise operators that broadcast their inputs
+        "Add" | "And" | "BitShift" | "Div" | "Equal" | "Greater" | "GreaterOrEqual" | "Less"
+        | "LessOrEqual" | "Max" | "Mean" | "Min" | "Mod" | "Mul" | "Or" | "Pow" | "PRelu"
+        | "Sub" | "Sum" | "Where" | "Xor" => {
+            let shapes = input_shapes
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<&Shape>>();
+            let dims = broadcast(&shapes).ok_or_else(|| invalid("inputs cannot be broadcast"))?;
+            let data_type = match op_type {
+                "Equal" | "Greater" | "GreaterOrEqual" | "Less" | "LessOrEqual" => ScalarType::Bool,
+                "Where" => input(1)?.data_type,
+                _ => input(0)?.data_type,
+            };
+            vec![Shape { dims, data_type }]
+        }
+
+        "MatMul" => {
+            let (a, b) = (input(0)?, input(1)?);
+            if a.is_empty() || b.is_empty() {
+                return Err(invalid("inputs must have at least one dimension"));
+            }
+            // One-dimensional inputs are promoted to matrices, after which the added dimension is removed again
+            let a_dims = if a.rank() == 1 {
+                vec![1, a.dims[0]]
+            } else {
+                a.dims.clone()
+            };
+            let b_dims = if b.rank() == 1 {
+                vec![b.dims[0], 1]
+            } else {
+                b.dims.clone()
+            };
+            if a_dims[a_dims.len() - 1] != b_dims[b_dims.len() - 2] {
+                return Err(invalid("inner dimensions do not match"));
+            }
+            let a_batch = Shape {
+                dims: a_dims[..a_dims.len() - 2].to_vec(),
+                data_type: a.data_type,
+            };
+            let b_batch = Shape {
+                dims: b_dims[..b_dims.len() - 2].to_vec(),
+                data_type: b.data_type,
+            };
+            let mut dims = broadcast(&[&a_batch, &b_batch])
+                .ok_or_else(|| invalid("batch dimensions cannot be broadcast"))?;
+            if a.rank() > 1 {
+                dims.push(a_dims[a_dims.len() - 2]);
+            }
+            if b.rank() > 1 {
+                dims.push(b_dims[b_dims.len() - 1]);
+            }
+            vec![Shape {
+                dims,
+                data_type: a.data_type,
+            }]
+        }
+
+        "Gemm" => {
+            let (a, b) = (input(0)?, input(1)?);
+            if a.rank() != 2 || b.rank() != 2 {
+                return Err(invalid("inputs must be matrices"));
+            }
+            let trans_a = get_attribute("transA", Some(0), node)? != 0;
+            let trans_b = get_attribute("transB", Some(0), node)? != 0;
+            let m = if trans_a { a.dims[1] } else { a.dims[0] };
+            let n = if trans_b { b.dims[0] } else { b.dims[1] };
+            vec![Shape {
+                dims: vec![m, n],
+                data_type: a.data_type,
+            }]
+        }
+
+        "Conv" | "ConvInteger" | "MaxPool" | "AveragePool" | "LpPool" => {
+            let x = input(0)?;
+            if x.rank() < 3 {
+                return Err(invalid("input must have at least three dimensions"));
+            }
+            let spatial_rank = x.rank() - 2;
+            let (channels, default_kernel_shape) = if op_type.starts_with("Conv") {
+                let w = input(1)?;
+                if w.rank() != x.rank() {
+                    return Err(invalid("weights must have the same rank as the input"));
+                }
+                (
+                    w.dims[0],
+                    Some(w.dims[2..].iter().map(|d| *d as i64).collect()),
+                )
+            } else {
+                (x.dims[1], None)
+            };
+            let kernel_shape =
+                get_attribute::<Vec<i64>>("kernel_shape", default_kernel_shape, node)?;
+            if kernel_shape.len() != spatial_rank {
+                return Err(invalid("kernel shape does not match the input rank"));
+            }
+            let dilations = get_attribute("dilations", Some(vec![1; spatial_rank]), node)?;
+            let strides = get_attribute("strides", Some(vec![1; spatial_rank]), node)?;
+            let pads = get_attribute("pads", Some(vec![0; spatial_rank * 2]), node)?;
+            let auto_pad = get_attribute("auto_pad", Some("NOTSET".to_string()), node)?;
+            let ceil_mode = get_attribute("ceil_mode", Some(0), node)? != 0;
+
+            let mut dims = vec![x.dims[0], channels];
+            dims.extend(spatial_output_dims(
+                &x.dims[2..],
+                &kernel_shape,
+                &dilations,
+                &strides,
+                &pads,
+                &auto_pad,
+                ceil_mode,
+            ));
+            // ConvInteger accumulates its (8-bit) inputs into 32-bit integers
+            let data_type = match op_type {
+                "ConvInteger" => ScalarType::I32,
+                _ => x.data_type,
+            };
+            let output = Shape { dims, data_type };
+            if op_type == "MaxPool" && node.get_output().len() > 1 {
+                let indices = Shape {
+                    dims: output.dims.clone(),
+                    data_type: ScalarType::I64,
+                };
+                vec![output, indices]
+            } else {
+                vec![output]
+            }
+        }
+
+        "GlobalAveragePool" | "GlobalMaxPool" | "GlobalLpPool" => {
+            let x = input(0)?;
+            if x.rank() < 2 {
+                return Err(invalid("input must have at least two dimensions"));
+            }
+            let mut dims = x.dims[0..2].to_vec();
+            dims.resize(x.rank(), 1);
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "Flatten" => {
+            let x = input(0)?;
+            let axis = get_attribute("axis", Some(1), node)?;
+            let axis = if axis < 0 {
+                axis + x.rank() as i64
+            } else {
+                axis
+            };
+            if axis < 0 || axis as usize > x.rank() {
+                return Err(invalid("axis out of range"));
+            }
+            let outer: u64 = x.dims[..axis as usize].iter().product();
+            let inner: u64 = x.dims[axis as usize..].iter().product();
+            vec![Shape {
+                dims: vec![outer, inner],
+                data_type: x.data_type,
+            }]
+        }
+
+        "Reshape" => {
+            let x = input(0)?;
+            let shape =
+                attribute_or_input("shape", 1, 5)?.ok_or_else(|| invalid("shape is missing"))?;
+            let mut dims = shape
+                .iter()
+                .enumerate()
+                .map(|(i, d)| match *d {
+                    0 => x
+                        .dims
+                        .get(i)
+                        .copied()
+                        .ok_or_else(|| invalid("cannot copy dimension")),
+                    d if d > 0 => Ok(d as u64),
+                    _ => Ok(0),
+                })
+                .collect::<Result<Vec<u64>, ShapeInferenceError>>()?;
+            if let Some(inferred_index) = shape.iter().position(|d| *d == -1) {
+                let known: u64 = dims
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != inferred_index)
+                    .map(|(_, d)| *d)
+                    .product();
+                if known == 0 {
+                    return Err(invalid("cannot infer dimension"));
+                }
+                dims[inferred_index] = x.element_count() / known;
+            }
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "Transpose" => {
+            let x = input(0)?;
+            let perm = get_attribute::<Vec<i64>>(
+                "perm",
+                Some((0..x.rank() as i64).rev().collect()),
+                node,
+            )?;
+            let dims = perm
+                .iter()
+                .map(|p| {
+                    x.dims
+                        .get(*p as usize)
+                        .copied()
+                        .ok_or_else(|| invalid("invalid permutation"))
+                })
+                .collect::<Result<Vec<u64>, ShapeInferenceError>>()?;
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "Concat" => {
+            let first = input(0)?;
+            let axis = get_attribute::<i64>("axis", None, node)?;
+            let axis =
+                normalize_axis(axis, first.rank()).ok_or_else(|| invalid("axis out of range"))?;
+            let mut dims = first.dims.clone();
+            dims[axis] = input_shapes
+                .iter()
+                .flatten()
+                .map(|s| s.dims.get(axis).copied().unwrap_or(0))
+                .sum();
+            vec![Shape {
+                dims,
+                data_type: first.data_type,
+            }]
+        }
+
+        "Squeeze" => {
+            let x = input(0)?;
+            let axes = attribute_or_input("axes", 1, 13)?
+                .map(|axes| {
+                    axes.iter()
+                        .map(|a| {
+                            normalize_axis(*a, x.rank()).ok_or_else(|| invalid("axis out of range"))
+                        })
+                        .collect::<Result<Vec<usize>, ShapeInferenceError>>()
+                })
+                .transpose()?;
+            let dims = x
+                .dims
+                .iter()
+                .enumerate()
+                .filter(|(i, d)| match &axes {
+                    Some(axes) => !axes.contains(i),
+                    None => **d != 1,
+                })
+                .map(|(_, d)| *d)
+                .collect();
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "Unsqueeze" => {
+            let x = input(0)?;
+            let axes =
+                attribute_or_input("axes", 1, 13)?.ok_or_else(|| invalid("axes are missing"))?;
+            let output_rank = x.rank() + axes.len();
+            let mut axes = axes
+                .iter()
+                .map(|a| {
+                    normalize_axis(*a, output_rank).ok_or_else(|| invalid("axis out of range"))
+                })
+                .collect::<Result<Vec<usize>, ShapeInferenceError>>()?;
+            axes.sort_unstable();
+            let mut dims = x.dims.clone();
+            for axis in axes {
+                dims.insert(axis.min(dims.len()), 1);
+            }
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "ReduceMean" | "ReduceSum" | "ReduceMax" | "ReduceMin" | "ReduceProd" | "ReduceL1"
+        | "ReduceL2" | "ReduceLogSum" | "ReduceLogSumExp" | "ReduceSumSquare" | "ArgMax"
+        | "ArgMin" => {
+            let x = input(0)?;
+            let keep_dims = get_attribute("keepdims", Some(1), node)? != 0;
+            let axes = match op_type {
+                "ArgMax" | "ArgMin" => Some(vec![get_attribute("axis", Some(0), node)?]),
+                "ReduceSum" => attribute_or_input("axes", 1, 13)?,
+                _ => attribute_or_input("axes", 1, 18)?,
+            };
+            let axes = match axes {
+                Some(axes) if !axes.is_empty() => axes
+                    .iter()
+                    .map(|a| {
+                        normalize_axis(*a, x.rank()).ok_or_else(|| invalid("axis out of range"))
+                    })
+                    .collect::<Result<Vec<usize>, ShapeInferenceError>>()?,
+                _ => (0..x.rank()).collect(),
+            };
+            let dims = x
+                .dims
+                .iter()
+                .enumerate()
+                .filter_map(|(i, d)| match (axes.contains(&i), keep_dims) {
+                    (false, _) => Some(*d),
+                    (true, true) => Some(1),
+                    (true, false) => None,
+                })
+                .collect();
+            let data_type = match op_type {
+                "ArgMax" | "ArgMin" => ScalarType::I64,
+                _ => x.data_type,
+            };
+            vec![Shape { dims, data_type }]
+        }
+
+        "Shape" => vec![Shape {
+            dims: vec![shape_dimension_range(node, input(0)?.rank())?.len() as u64],
+            data_type: ScalarType::I64,
+        }],
+
+        "Size" => vec![Shape {
+            dims: vec![],
+            data_type: ScalarType::I64,
+        }],
+
+        "Constant" => {
+            let value = node
+                .get_attribute()
+                .iter()
+                .find(|a| a.get_name() == "value")
+                .ok_or_else(|| invalid("only constants with a 'value' attribute are supported"))?;
+            let tensor = value.get_t();
+            vec![Shape::from(
+                ScalarType::from_i32(tensor.get_data_type())?,
+                tensor.get_dims(),
+            )]
+        }
+
+        "If" => {
+            // Both branches produce outputs of the same type and shape (which can be taken from the branch outputs)
+            let branch = node
+                .get_attribute()
+                .iter()
+                .find(|a| a.get_name() == "then_branch")
+                .ok_or_else(|| invalid("If node without then_branch"))?
+                .get_g();
+            branch
+                .get_output()
+                .iter()
+                .map(|output| output.get_shape())
+                .collect::<Result<Vec<Shape>, _>>()?
+        }
+
+        "RandomNormal" | "RandomUniform" => {
+            let dims = get_attribute::<Vec<i64>>("shape", None, node)?;
+            let dtype = get_attribute("dtype", Some(1), node)?;
+            vec![Shape::from(ScalarType::from_i32(dtype as i32)?, &dims)]
+        }
+
+        "Gather" => {
+            let (x, indices) = (input(0)?, input(1)?);
+            let axis = get_attribute("axis", Some(0), node)?;
+            let axis =
+                normalize_axis(axis, x.rank()).ok_or_else(|| invalid("axis out of range"))?;
+            let mut dims = x.dims[..axis].to_vec();
+            dims.extend(&indices.dims);
+            dims.extend(&x.dims[axis + 1..]);
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "DequantizeLinear" => {
+            let x = input(0)?;
+            vec![Shape {
+                dims: x.dims.clone(),
+                data_type: ScalarType::F32,
+            }]
+        }
+
+        "QuantizeLinear" => {
+            // The output type is the type of the zero point, which defaults to uint8
+            let x = input(0)?;
+            vec![Shape {
+                dims: x.dims.clone(),
+                data_type: input(2).map_or(ScalarType::U8, |zero_point| zero_point.data_type),
+            }]
+        }
+
+        "GatherND" => {
+            // The output has shape indices.shape[..-1] + data.shape[batch_dims + k..], where k is the length of an index
+            // tuple (the last dimension of indices)
+            let (x, indices) = (input(0)?, input(1)?);
+            let batch_dims = get_attribute("batch_dims", Some(0), node)? as usize;
+            let k = indices
+                .dims
+                .last()
+                .map(|k| *k as usize)
+                .ok_or_else(|| invalid("indices must have at least one dimension"))?;
+            if batch_dims + k > x.rank() || batch_dims >= indices.rank() {
+                return Err(invalid(
+                    "batch_dims and index length exceed the rank of data",
+                ));
+            }
+            let mut dims = indices.dims[..indices.rank() - 1].to_vec();
+            dims.extend(&x.dims[batch_dims + k..]);
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "Split" => {
+            let x = input(0)?;
+            let axis = get_attribute("axis", Some(0), node)?;
+            let axis =
+                normalize_axis(axis, x.rank()).ok_or_else(|| invalid("axis out of range"))?;
+            let output_count = node.get_output().len() as u64;
+            let split = match attribute_or_input("split", 1, 13)? {
+                Some(split) if !split.is_empty() => split.iter().map(|s| *s as u64).collect(),
+                _ => vec![x.dims[axis] / output_count; output_count as usize],
+            };
+            split
+                .iter()
+                .map(|s| {
+                    let mut dims = x.dims.clone();
+                    dims[axis] = *s;
+                    Shape {
+                        dims,
+                        data_type: x.data_type,
+                    }
+                })
+                .collect()
+        }
+
+        "Slice" => {
+            let x = input(0)?;
+            let (starts, ends, axes, steps) = if opset_version >= 10 {
+                (
+                    constant_input(1)?,
+                    constant_input(2)?,
+                    constant_input(3)?,
+                    constant_input(4)?,
+                )
+            } else {
+                (
+                    get_attribute::<Vec<i64>>("starts", None, node).ok(),
+                    get_attribute::<Vec<i64>>("ends", None, node).ok(),
+                    get_attribute::<Vec<i64>>("axes", None, node).ok(),
+                    None,
+                )
+            };
+            let starts = starts.ok_or_else(|| invalid("starts are missing"))?;
+            let ends = ends.ok_or_else(|| invalid("ends are missing"))?;
+            let axes = axes.unwrap_or_else(|| (0..starts.len() as i64).collect());
+            let steps = steps.unwrap_or_else(|| vec![1; starts.len()]);
+
+            let mut dims = x.dims.clone();
+            for (i, axis) in axes.iter().enumerate() {
+                let axis =
+                    normalize_axis(*axis, x.rank()).ok_or_else(|| invalid("axis out of range"))?;
+                let dim = x.dims[axis] as i64;
+                let step = steps.get(i).copied().unwrap_or(1);
+                let resolve = |v: i64| if v < 0 { v + dim } else { v };
+                let (start, end) = (resolve(starts[i]), resolve(ends[i]));
+                let length = if step > 0 {
+                    let (start, end) = (start.clamp(0, dim), end.clamp(0, dim));
+                    (end - start + step - 1) / step
+                } else if step < 0 {
+                    let (start, end) = (start.clamp(0, dim - 1), end.clamp(-1, dim - 1));
+                    (start - end - step - 1) / -step
+                } else {
+                    return Err(invalid("step cannot be zero"));
+                };
+                dims[axis] = length.max(0) as u64;
+            }
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "Pad" => {
+            let x = input(0)?;
+            let pads =
+                attribute_or_input("pads", 1, 11)?.ok_or_else(|| invalid("pads are missing"))?;
+            if pads.len() != x.rank() * 2 {
+                return Err(invalid("pads do not match the input rank"));
+            }
+            let dims = x
+                .dims
+                .iter()
+                .enumerate()
+                .map(|(i, d)| (*d as i64 + pads[i] + pads[i + x.rank()]).max(0) as u64)
+                .collect();
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "Resize" | "Upsample" => {
+            let x = input(0)?;
+            let scales_index = if op_type == "Upsample" || opset_version < 11 {
+                1
+            } else {
+                2
+            };
+            let sizes = if op_type == "Resize" && opset_version >= 11 {
+                constant_input(3)?
+            } else {
+                None
+            };
+            let dims = match sizes {
+                Some(sizes) if !sizes.is_empty() => sizes.iter().map(|s| *s as u64).collect(),
+                _ => {
+                    let scales = match node.get_input().get(scales_index) {
+                        Some(name) if !name.is_empty() => match constants.get(name.as_str()) {
+                            Some(tensor) => tensor.float_values()?,
+                            None => {
+                                return Err(ShapeInferenceError::ConstantRequired {
+                                    input: name.to_string(),
+                                    node_name: node.get_name().to_string(),
+                                })
+                            }
+                        },
+                        _ => get_attribute::<Vec<f32>>("scales", None, node)?,
+                    };
+                    if scales.len() != x.rank() {
+                        return Err(invalid("scales do not match the input rank"));
+                    }
+
+                    // With tf_crop_and_resize, the scales apply to the size of the region of interest
+                    let mode = get_attribute(
+                        "coordinate_transformation_mode",
+                        Some("half_pixel".to_string()),
+                        node,
+                    )?;
+                    let roi_sizes = match (mode.as_str(), node.get_input().get(1)) {
+                        ("tf_crop_and_resize", Some(name)) if scales_index == 2 => {
+                            let roi = match constants.get(name.as_str()) {
+                                Some(tensor) => tensor.float_values()?,
+                                None => get_attribute::<Vec<f32>>("roi", None, node)?,
+                            };
+                            if roi.len() != x.rank() * 2 {
+                                return Err(invalid("roi does not match the input rank"));
+                            }
+                            (0..x.rank()).map(|i| roi[i + x.rank()] - roi[i]).collect()
+                        }
+                        _ => vec![1.0; x.rank()],
+                    };
+                    x.dims
+                        .iter()
+                        .zip(scales.iter())
+                        .zip(roi_sizes.iter())
+                        .map(|((d, s), r)| (*d as f32 * *r * *s).floor() as u64)
+                        .collect()
+                }
+            };
+            vec![Shape {
+                dims,
+                data_type: x.data_type,
+            }]
+        }
+
+        "LSTM" | "GRU" => {
+            let x = input(0)?;
+            if x.rank() != 3 {
+                return Err(invalid("input must have three dimensions"));
+            }
+            let hidden_size = get_attribute::<i64>("hidden_size", None, node)? as u64;
+            let direction = get_attribute("direction", Some("forward".to_string()), node)?;
+            let num_directions = if direction == "bidirectional" { 2 } else { 1 };
+            let (seq_length, batch_size) = (x.dims[0], x.dims[1]);
+            let mut outputs = vec![
+                Shape {
+                    dims: vec![seq_length, num_directions, batch_size, hidden_size],
+                    data_type: x.data_type,
+                },
+                Shape {
+                    dims: vec![num_directions, batch_size, hidden_size],
+                    data_type: x.data_type,
+                },
+            ];
+            if op_type == "LSTM" {
+                outputs.push(outputs[1].clone());
+            }
+            outputs
+        }
+
+        _ => {
+            return Err(ShapeInferenceError::Unsupported {
+                node_name: node.get_name().to_string(),
+                op_type: op_type.to_string(),
+            })
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::infer_shapes;
+    use crate::onnx::TensorProto_DataType;
+    use crate::utils::{attribute, graph, initializer, model, node, tensor, tensor_of_type};
+
+    #[test]
+    fn test_infer_shapes() {
+        let mut w = initializer("W", vec![1.0; 2 * 3 * 3 * 3]);
+        w.set_dims(vec![2, 3, 3, 3]);
+
+        // Model: X -> Conv -> A -> Relu -> B -> MaxPool -> C -> Flatten -> D -> Shape -> Y
+        let mut m = model(graph(
+            vec![tensor("X", &[1, 3, 8, 8])],
+            vec![tensor_of_type("Y", &[2], TensorProto_DataType::INT64)],
+            vec![],
+            vec![w],
+            vec![
+                node(
+                    vec!["X", "W"],
+                    vec!["A"],
+                    "conv",
+                    "Conv",
+                    vec![attribute("pads", vec![1, 1, 1, 1])],
+                ),
+                node(vec!["A"], vec!["B"], "relu", "Relu", vec![]),
+                node(
+                    vec!["B"],
+                    vec!["C"],
+                    "pool",
+                    "MaxPool",
+                    vec![
+                        attribute("kernel_shape", vec![2, 2]),
+                        attribute("strides", vec![2, 2]),
+                    ],
+                ),
+                node(vec!["C"], vec!["D"], "flatten", "Flatten", vec![]),
+                node(vec!["D"], vec!["Y"], "shape", "Shape", vec![]),
+            ],
+        ));
+
+        infer_shapes(&mut m, 13).unwrap();
+        let shapes: Vec<(String, Vec<u64>)> = m
+            .get_graph()
+            .get_value_info()
+            .iter()
+            .map(|vi| (vi.get_name().to_string(), vi.get_shape().unwrap().dims))
+            .collect();
+        assert_eq!(
+            shapes,
+            vec![
+                ("A".to_string(), vec![1, 2, 8, 8]),
+                ("B".to_string(), vec![1, 2, 8, 8]),
+                ("C".to_string(), vec![1, 2, 4, 4]),
+                ("D".to_string(), vec![1, 32]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! A session for models that are run repeatedly while carrying state from one run to the next, such as the key/value
+//! cache of an autoregressive transformer. The state tensors remain in GPU memory between runs: the state outputs of a
+//! step are copied to the state inputs of the next step on the GPU, without reading them back to main memory.
+use std::collections::HashMap;
+
+use crate::{utils::InputTensor, GpuTensor, Session, SessionError};
+
+/// Wraps a session and feeds designated outputs of each step back as inputs of the next step.
+///
+/// # Examples
+///
+/// ```ignore
+/// let session = Session::from_path("path/to/decoder.onnx").await.unwrap();
+/// let mut decoder = StatefulSession::new(session, &[("past", "present")]);
+/// let first = decoder.step(inputs_with_initial_past).await.unwrap();
+/// let second = decoder.step(inputs_without_past).await.unwrap();
+/// ```
+pub struct StatefulSession {
+    session: Session,
+
+    /// Pairs of the name of a state input and the name of the output that holds its value for the next step
+    state_names: Vec<(String, String)>,
+
+    /// The current value of each state input (by input name), as produced by the previous step
+    state: HashMap<String, GpuTensor>,
+}
+
+impl StatefulSession {
+    /// Create a stateful session from a session and pairs of (state input name, state output name). The outputs must
+    /// have the same shape and data type as the inputs they are fed back to.
+    pub fn new(session: Session, state_names: &[(&str, &str)]) -> StatefulSession {
+        StatefulSession {
+            session,
+            state_names: state_names
+                .iter()
+                .map(|(input, output)| (input.to_string(), output.to_string()))
+                .collect(),
+            state: HashMap::new(),
+        }
+    }
+
+    /// Perform inference once. State inputs that are not supplied are taken from the state outputs of the previous
+    /// step, so the initial state needs to be supplied in the first step (or after `reset`). The outputs that are not
+    /// state outputs are read back and returned.
+    pub async fn step<'a>(
+        &mut self,
+        mut inputs: HashMap<String, InputTensor<'a>>,
+    ) -> Result<HashMap<String, Vec<f32>>, SessionError> {
+        for (input_name, tensor) in &self.state {
+            if !inputs.contains_key(input_name) {
+                inputs.insert(input_name.clone(), InputTensor::Gpu(tensor.clone()));
+            }
+        }
+
+        let mut outputs = self.session.run_to_buffers(&inputs).await?;
+        for (input_name, output_name) in &self.state_names {
+            let tensor = outputs
+                .remove(output_name)
+                .ok_or_else(|| SessionError::InvalidOutput(output_name.clone()))?;
+            self.state.insert(input_name.clone(), tensor);
+        }
+
+        let mut output_data = HashMap::new();
+        for (output_name, tensor) in outputs {
+            output_data.insert(output_name, self.session.read_tensor(&tensor).await);
+        }
+        Ok(output_data)
+    }
+
+    /// The current value of the named state input, which is held in GPU memory (see `Session::read_tensor`)
+    pub fn state(&self, input_name: &str) -> Option<&GpuTensor> {
+        self.state.get(input_name)
+    }
+
+    /// Forget the state, so that the initial state needs to be supplied again in the next step
+    pub fn reset(&mut self) {
+        self.state.clear();
+    }
+
+    /// The session that performs the steps
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+}
@@ -5,9 +5,11 @@ use crate::onnx;
 use crate::onnx::OperatorSetIdProto;
 use crate::onnx::TensorProto_DataType;
 use crate::onnx::ValueInfoProto;
+use crate::GpuTensor;
 use std::borrow::Cow;
 use std::convert::From;
 use std::convert::Into;
+use std::convert::TryInto;
 use std::fmt::Display;
 use std::str::from_utf8;
 use thiserror::Error;
@@ -16,7 +18,7 @@ use thiserror::Error;
 * error: buffer binding size X is less than minimum 64" in Device::create_bind_group */
 pub const MINIMUM_BUFFER_SIZE_BYTES: u64 = 64;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Shape {
     pub dims: Vec<u64>,
     pub data_type: ScalarType,
@@ -51,8 +53,13 @@ impl Shape {
         self.dims[idx]
     }
 
+    /// Returns the number of elements spanned by a step along each axis. A scalar (rank 0) shape has no axes, and thus
+    /// no chunks.
     pub fn chunks(&self) -> Vec<u64> {
         let mut chunk = vec![];
+        if self.is_empty() {
+            return chunk;
+        }
         let ds = &self.dims;
         for i in 1..self.dims.len() {
             chunk.push(ds[i..].iter().product::<u64>());
@@ -65,6 +72,12 @@ impl Shape {
 pub enum InputTensor<'a> {
     F32(Cow<'a, [f32]>),
     I32(Cow<'a, [i32]>),
+
+    /// 64-bit integers (e.g. indices). The GPU only uses the lower 32 bits, so each value must fit in an i32.
+    I64(Cow<'a, [i64]>),
+
+    /// A tensor that resides in GPU memory of the device used by the session (e.g. an output of another session)
+    Gpu(GpuTensor),
 }
 
 impl<'a> From<&'a [f32]> for InputTensor<'a> {
@@ -73,12 +86,25 @@ impl<'a> From<&'a [f32]> for InputTensor<'a> {
     }
 }
 
+/// Double values are downcast to f32, as the model is computed with single precision (see `ScalarType::from`)
+impl<'a> From<&'a [f64]> for InputTensor<'a> {
+    fn from(a: &'a [f64]) -> Self {
+        InputTensor::F32(Cow::Owned(a.iter().map(|v| *v as f32).collect()))
+    }
+}
+
 impl<'a> From<&'a [i32]> for InputTensor<'a> {
     fn from(a: &'a [i32]) -> Self {
         InputTensor::I32(Cow::Borrowed(a))
     }
 }
 
+impl<'a> From<&'a [i64]> for InputTensor<'a> {
+    fn from(a: &'a [i64]) -> Self {
+        InputTensor::I64(Cow::Borrowed(a))
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum DataTypeError {
     #[error("the ONNX scalar data type '{0:?}' is not supported")]
@@ -97,6 +123,11 @@ pub enum ScalarType {
     F32,
     I64,
     I32,
+    /// Booleans are stored as i32 values that are either 0 (false) or 1 (true)
+    Bool,
+    /// 8-bit integers (used for quantized data) are stored as i32 values
+    I8,
+    U8,
 }
 
 impl ScalarType {
@@ -111,14 +142,34 @@ impl ScalarType {
             TensorProto_DataType::FLOAT => ScalarType::F32,
             TensorProto_DataType::INT64 => ScalarType::I64,
             TensorProto_DataType::INT32 => ScalarType::I32,
+            TensorProto_DataType::BOOL => ScalarType::Bool,
+            TensorProto_DataType::INT8 => ScalarType::I8,
+            TensorProto_DataType::UINT8 => ScalarType::U8,
+            // There is no bfloat16 arithmetic on the GPU, so bfloat16 values are upcast to f32 when loaded
+            TensorProto_DataType::BFLOAT16 => ScalarType::F32,
+            // Most GPUs cannot compute with f64, so double values are downcast to f32 (losing precision)
+            TensorProto_DataType::DOUBLE => ScalarType::F32,
             _ => return Err(DataTypeError::NotSupported(onnx)),
         })
     }
 
+    pub fn to_datatype(&self) -> TensorProto_DataType {
+        match self {
+            ScalarType::F32 => TensorProto_DataType::FLOAT,
+            ScalarType::I64 => TensorProto_DataType::INT64,
+            ScalarType::I32 => TensorProto_DataType::INT32,
+            ScalarType::Bool => TensorProto_DataType::BOOL,
+            ScalarType::I8 => TensorProto_DataType::INT8,
+            ScalarType::U8 => TensorProto_DataType::UINT8,
+        }
+    }
+
     pub fn stride(&self) -> usize {
         match self {
             ScalarType::F32 => 4,
             ScalarType::I32 => 4,
+            ScalarType::Bool => 4,
+            ScalarType::I8 | ScalarType::U8 => 4,
             ScalarType::I64 => 8,
         }
     }
@@ -127,6 +178,8 @@ impl ScalarType {
         match self {
             ScalarType::F32 => "f32",
             ScalarType::I32 => "i32",
+            ScalarType::Bool => "i32",
+            ScalarType::I8 | ScalarType::U8 => "i32",
             ScalarType::I64 => "i64",
         }
     }
@@ -134,7 +187,12 @@ impl ScalarType {
 
 impl Display for ScalarType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.wgsl_type_name())
+        match self {
+            ScalarType::Bool => write!(f, "bool"),
+            ScalarType::I8 => write!(f, "i8"),
+            ScalarType::U8 => write!(f, "u8"),
+            _ => write!(f, "{}", self.wgsl_type_name()),
+        }
     }
 }
 
@@ -239,6 +297,50 @@ pub fn get_attribute<T: std::convert::From<onnx::AttributeProto>>(
     }
 }
 
+#[derive(Error, Debug)]
+#[error("axis {axis} is out of range for a tensor of rank {rank}")]
+pub struct AxisOutOfRangeError {
+    pub axis: i64,
+    pub rank: usize,
+}
+
+/// Resolve a (possibly negative) axis for a tensor of the specified rank. Negative values count from the back, so the
+/// accepted range is [-rank, rank-1].
+pub fn normalize_axis(axis: i64, rank: usize) -> Result<usize, AxisOutOfRangeError> {
+    let normalized = if axis < 0 { axis + rank as i64 } else { axis };
+    if normalized < 0 || normalized >= rank as i64 {
+        Err(AxisOutOfRangeError { axis, rank })
+    } else {
+        Ok(normalized as usize)
+    }
+}
+
+/// The dimensions that a Shape node outputs for an input of the specified rank, as selected by its `start` and `end`
+/// attributes (opset 15). Negative values count from the back, and values out of range are clamped.
+pub fn shape_dimension_range(
+    node: &onnx::NodeProto,
+    rank: usize,
+) -> Result<std::ops::Range<usize>, AttributeNotFoundError> {
+    let clamp = |index: i64| {
+        let index = if index < 0 {
+            index + rank as i64
+        } else {
+            index
+        };
+        index.max(0).min(rank as i64) as usize
+    };
+    let start = clamp(get_attribute("start", Some(0), node)?);
+    let end = clamp(get_attribute("end", Some(rank as i64), node)?);
+    Ok(start..end.max(start))
+}
+
+/// Whether a Dropout node has its (optional) mask output requested
+pub fn outputs_mask(node: &onnx::NodeProto) -> bool {
+    node.get_output()
+        .get(1)
+        .map_or(false, |mask| !mask.is_empty())
+}
+
 /// Divide a number by the indicated dividend, then round up to the next multiple of the dividend if there is a rest.
 pub fn ceil(num: u64, div: u64) -> u64 {
     num / div + (num % div != 0) as u64
@@ -269,11 +371,119 @@ impl ValueInfoProto {
     }
 }
 
+impl onnx::TensorProto {
+    /// Returns the data of this tensor in the (little-endian) binary representation used for the `raw_data` field,
+    /// regardless of the field the data is actually stored in. Exporters are free to choose either the typed fields
+    /// (e.g. `float_data`, `int64_data`) or `raw_data`, so this should be used instead of reading a field directly.
+    pub fn data_bytes(&self) -> Result<Cow<'_, [u8]>, DataTypeError> {
+        if self.has_raw_data() {
+            return Ok(Cow::Borrowed(self.get_raw_data()));
+        }
+
+        let data_type = data_type_of(self.get_data_type())?;
+        Ok(match data_type {
+            TensorProto_DataType::FLOAT | TensorProto_DataType::COMPLEX64 => {
+                Cow::Borrowed(bytemuck::cast_slice(self.get_float_data()))
+            }
+            TensorProto_DataType::DOUBLE | TensorProto_DataType::COMPLEX128 => {
+                Cow::Borrowed(bytemuck::cast_slice(self.get_double_data()))
+            }
+            TensorProto_DataType::INT64 => {
+                Cow::Borrowed(bytemuck::cast_slice(self.get_int64_data()))
+            }
+            TensorProto_DataType::UINT64 => {
+                Cow::Borrowed(bytemuck::cast_slice(self.get_uint64_data()))
+            }
+            TensorProto_DataType::INT32 => {
+                Cow::Borrowed(bytemuck::cast_slice(self.get_int32_data()))
+            }
+            // UINT32 is stored in uint64_data, all smaller types are stored in int32_data (one value per element)
+            TensorProto_DataType::UINT32 => Cow::Owned(
+                self.get_uint64_data()
+                    .iter()
+                    .flat_map(|v| (*v as u32).to_le_bytes())
+                    .collect(),
+            ),
+            TensorProto_DataType::INT16
+            | TensorProto_DataType::UINT16
+            | TensorProto_DataType::FLOAT16
+            | TensorProto_DataType::BFLOAT16 => Cow::Owned(
+                self.get_int32_data()
+                    .iter()
+                    .flat_map(|v| (*v as u16).to_le_bytes())
+                    .collect(),
+            ),
+            TensorProto_DataType::INT8
+            | TensorProto_DataType::UINT8
+            | TensorProto_DataType::BOOL => {
+                Cow::Owned(self.get_int32_data().iter().map(|v| *v as u8).collect())
+            }
+            TensorProto_DataType::STRING | TensorProto_DataType::UNDEFINED => {
+                return Err(DataTypeError::NotSupported(data_type))
+            }
+        })
+    }
+
+    /// Returns the data of this tensor as f32 values (double values are narrowed). Returns an error when the tensor
+    /// does not contain floating point data.
+    pub fn float_values(&self) -> Result<Vec<f32>, DataTypeError> {
+        let bytes = self.data_bytes()?;
+        Ok(match data_type_of(self.get_data_type())? {
+            TensorProto_DataType::FLOAT => bytes
+                .chunks_exact(4)
+                .map(|c| f32::from_le_bytes(c.try_into().unwrap()))
+                .collect(),
+            TensorProto_DataType::DOUBLE => bytes
+                .chunks_exact(8)
+                .map(|c| f64::from_le_bytes(c.try_into().unwrap()) as f32)
+                .collect(),
+            // A bfloat16 value consists of the upper 16 bits of an f32
+            TensorProto_DataType::BFLOAT16 => bytes
+                .chunks_exact(2)
+                .map(|c| f32::from_bits((u16::from_le_bytes(c.try_into().unwrap()) as u32) << 16))
+                .collect(),
+            other => return Err(DataTypeError::NotSupported(other)),
+        })
+    }
+
+    /// Returns the data of this tensor as i64 values (values of other integer types are widened). Returns an error when
+    /// the tensor does not contain integer data.
+    pub fn int64_values(&self) -> Result<Vec<i64>, DataTypeError> {
+        let bytes = self.data_bytes()?;
+        Ok(match data_type_of(self.get_data_type())? {
+            TensorProto_DataType::INT64 | TensorProto_DataType::UINT64 => bytes
+                .chunks_exact(8)
+                .map(|c| i64::from_le_bytes(c.try_into().unwrap()))
+                .collect(),
+            TensorProto_DataType::INT32 => bytes
+                .chunks_exact(4)
+                .map(|c| i32::from_le_bytes(c.try_into().unwrap()) as i64)
+                .collect(),
+            TensorProto_DataType::UINT32 => bytes
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes(c.try_into().unwrap()) as i64)
+                .collect(),
+            TensorProto_DataType::INT8 => bytes.iter().map(|b| *b as i8 as i64).collect(),
+            TensorProto_DataType::UINT8 => bytes.iter().map(|b| *b as i64).collect(),
+            other => return Err(DataTypeError::NotSupported(other)),
+        })
+    }
+}
+
+fn data_type_of(onnx: i32) -> Result<TensorProto_DataType, DataTypeError> {
+    TensorProto_DataType::from_i32(onnx).ok_or(DataTypeError::NotRecognized(onnx))
+}
+
 /// Shorthand method to define an ONNX tensor with the specified name and shape (data type is f32)
 pub fn tensor(name: &str, dimensions: &[i64]) -> onnx::ValueInfoProto {
     tensor_of_type(name, dimensions, TensorProto_DataType::FLOAT)
 }
 
+/// Shorthand method to define an ONNX tensor with the specified name, shape and scalar type
+pub fn tensor_of(name: &str, dimensions: &[i64], scalar_type: ScalarType) -> onnx::ValueInfoProto {
+    tensor_of_type(name, dimensions, scalar_type.to_datatype())
+}
+
 /// Shorthand method to define an ONNX tensor with the specified name, shape and data type
 pub fn tensor_of_type(
     name: &str,
@@ -313,6 +523,19 @@ pub fn initializer(name: &str, data: Vec<f32>) -> onnx::TensorProto {
     initializer
 }
 
+/// Shorthand method to define an initializer with the specified name, shape and data. The data type of the initializer
+/// follows from the type of the values (e.g. `Vec<i64>` for INT64, `Vec<bool>` for BOOL).
+pub fn initializer_of(
+    name: &str,
+    dimensions: &[i64],
+    data: impl Into<onnx::TensorProto>,
+) -> onnx::TensorProto {
+    let mut initializer: onnx::TensorProto = data.into();
+    initializer.set_name(name.to_string());
+    initializer.set_dims(dimensions.to_vec());
+    initializer
+}
+
 pub fn attribute(name: &str, inputs: impl Into<onnx::AttributeProto>) -> onnx::AttributeProto {
     let mut attributes: onnx::AttributeProto = inputs.into();
     attributes.set_name(name.to_string());
@@ -372,6 +595,61 @@ pub fn model(graph: onnx::GraphProto) -> onnx::ModelProto {
     model
 }
 
+impl From<Vec<f32>> for onnx::TensorProto {
+    fn from(value: Vec<f32>) -> Self {
+        let mut tensor = onnx::TensorProto::new();
+        tensor.set_data_type(TensorProto_DataType::FLOAT.value());
+        tensor.set_float_data(value);
+        tensor
+    }
+}
+
+impl From<Vec<i64>> for onnx::TensorProto {
+    fn from(value: Vec<i64>) -> Self {
+        let mut tensor = onnx::TensorProto::new();
+        tensor.set_data_type(TensorProto_DataType::INT64.value());
+        tensor.set_int64_data(value);
+        tensor
+    }
+}
+
+impl From<Vec<i32>> for onnx::TensorProto {
+    fn from(value: Vec<i32>) -> Self {
+        let mut tensor = onnx::TensorProto::new();
+        tensor.set_data_type(TensorProto_DataType::INT32.value());
+        tensor.set_int32_data(value);
+        tensor
+    }
+}
+
+// Booleans and 8-bit integers are stored in the int32_data field
+impl From<Vec<bool>> for onnx::TensorProto {
+    fn from(value: Vec<bool>) -> Self {
+        let mut tensor = onnx::TensorProto::new();
+        tensor.set_data_type(TensorProto_DataType::BOOL.value());
+        tensor.set_int32_data(value.into_iter().map(|v| v as i32).collect());
+        tensor
+    }
+}
+
+impl From<Vec<i8>> for onnx::TensorProto {
+    fn from(value: Vec<i8>) -> Self {
+        let mut tensor = onnx::TensorProto::new();
+        tensor.set_data_type(TensorProto_DataType::INT8.value());
+        tensor.set_int32_data(value.into_iter().map(|v| v as i32).collect());
+        tensor
+    }
+}
+
+impl From<Vec<u8>> for onnx::TensorProto {
+    fn from(value: Vec<u8>) -> Self {
+        let mut tensor = onnx::TensorProto::new();
+        tensor.set_data_type(TensorProto_DataType::UINT8.value());
+        tensor.set_int32_data(value.into_iter().map(|v| v as i32).collect());
+        tensor
+    }
+}
+
 impl From<Vec<i64>> for onnx::AttributeProto {
     fn from(value: Vec<i64>) -> Self {
         let mut attributes = crate::onnx::AttributeProto::new();
@@ -420,6 +698,32 @@ impl From<&str> for onnx::AttributeProto {
     }
 }
 
+impl From<Vec<String>> for onnx::AttributeProto {
+    fn from(value: Vec<String>) -> Self {
+        let mut attributes = crate::onnx::AttributeProto::new();
+        attributes.set_strings(RepeatedField::from_vec(
+            value.into_iter().map(|s| s.into_bytes()).collect(),
+        ));
+        attributes
+    }
+}
+
+impl From<onnx::TensorProto> for onnx::AttributeProto {
+    fn from(value: onnx::TensorProto) -> Self {
+        let mut attributes = crate::onnx::AttributeProto::new();
+        attributes.set_t(value);
+        attributes
+    }
+}
+
+impl From<onnx::GraphProto> for onnx::AttributeProto {
+    fn from(value: onnx::GraphProto) -> Self {
+        let mut attributes = crate::onnx::AttributeProto::new();
+        attributes.set_g(value);
+        attributes
+    }
+}
+
 impl From<onnx::AttributeProto> for Vec<i64> {
     fn from(value: onnx::AttributeProto) -> Self {
         value.get_ints().to_vec()
@@ -444,6 +748,16 @@ impl From<onnx::AttributeProto> for i64 {
     }
 }
 
+impl From<onnx::AttributeProto> for Vec<String> {
+    fn from(value: onnx::AttributeProto) -> Self {
+        value
+            .get_strings()
+            .iter()
+            .map(|s| from_utf8(s).unwrap().to_string())
+            .collect()
+    }
+}
+
 impl From<onnx::AttributeProto> for String {
     fn from(value: onnx::AttributeProto) -> Self {
         from_utf8(value.get_s()).unwrap().to_string()
@@ -452,7 +766,94 @@ impl From<onnx::AttributeProto> for String {
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::{attribute, graph, initializer, model, node, tensor};
+    use crate::onnx::{TensorProto, TensorProto_DataType};
+    use crate::utils::{
+        attribute, graph, initializer, initializer_of, model, node, normalize_axis, tensor,
+        tensor_of, ScalarType, Shape,
+    };
+    use protobuf::ProtobufEnum;
+
+    fn tensor_proto(data_type: TensorProto_DataType) -> TensorProto {
+        let mut tensor = TensorProto::new();
+        tensor.set_data_type(data_type.value());
+        tensor
+    }
+
+    #[test]
+    fn test_tensor_data_encodings() {
+        let values = vec![1.5f32, -2.0, 0.0, 1e-3];
+        let float_data = initializer("C", values.clone());
+        let mut raw_data = tensor_proto(TensorProto_DataType::FLOAT);
+        raw_data.set_raw_data(values.iter().flat_map(|v| v.to_le_bytes()).collect());
+        assert_eq!(
+            float_data.data_bytes().unwrap(),
+            raw_data.data_bytes().unwrap()
+        );
+        assert_eq!(raw_data.float_values().unwrap(), values);
+
+        let mut double_data = tensor_proto(TensorProto_DataType::DOUBLE);
+        double_data.set_double_data(values.iter().map(|v| *v as f64).collect());
+        assert_eq!(double_data.float_values().unwrap(), values);
+
+        let ints = vec![1i64, -1, i64::MAX];
+        let mut int64_data = tensor_proto(TensorProto_DataType::INT64);
+        int64_data.set_int64_data(ints.clone());
+        let mut raw_data = tensor_proto(TensorProto_DataType::INT64);
+        raw_data.set_raw_data(ints.iter().flat_map(|v| v.to_le_bytes()).collect());
+        assert_eq!(
+            int64_data.data_bytes().unwrap(),
+            raw_data.data_bytes().unwrap()
+        );
+        assert_eq!(raw_data.int64_values().unwrap(), ints);
+
+        let mut uint64_data = tensor_proto(TensorProto_DataType::UINT64);
+        uint64_data.set_uint64_data(vec![1, 2, 3]);
+        assert_eq!(uint64_data.int64_values().unwrap(), vec![1, 2, 3]);
+
+        let mut bool_data = tensor_proto(TensorProto_DataType::BOOL);
+        bool_data.set_int32_data(vec![1, 0, 1]);
+        assert_eq!(bool_data.data_bytes().unwrap().as_ref(), &[1u8, 0, 1]);
+
+        assert!(tensor_proto(TensorProto_DataType::STRING)
+            .data_bytes()
+            .is_err());
+    }
+
+    #[test]
+    fn test_typed_tensors() {
+        let graph = graph(
+            vec![tensor_of("I", &[2], ScalarType::I64)],
+            vec![tensor_of("M", &[3], ScalarType::Bool)],
+            vec![],
+            vec![
+                initializer_of("A", &[2], vec![1i64, -2]),
+                initializer_of("B", &[3], vec![true, false, true]),
+            ],
+            vec![],
+        );
+
+        let input_shape = graph.get_input()[0].get_shape().unwrap();
+        assert_eq!(input_shape, Shape::from(ScalarType::I64, &[2]));
+        let output_shape = graph.get_output()[0].get_shape().unwrap();
+        assert_eq!(output_shape, Shape::from(ScalarType::Bool, &[3]));
+
+        let (a, b) = (&graph.get_initializer()[0], &graph.get_initializer()[1]);
+        assert_eq!(a.get_dims(), &[2]);
+        assert_eq!(a.int64_values().unwrap(), vec![1, -2]);
+        assert_eq!(b.get_data_type(), TensorProto_DataType::BOOL.value());
+        assert_eq!(b.data_bytes().unwrap().as_ref(), &[1u8, 0, 1]);
+    }
+
+    #[test]
+    fn test_normalize_axis() {
+        assert_eq!(normalize_axis(0, 3).unwrap(), 0);
+        assert_eq!(normalize_axis(2, 3).unwrap(), 2);
+        assert_eq!(normalize_axis(-1, 3).unwrap(), 2);
+        assert_eq!(normalize_axis(-3, 3).unwrap(), 0);
+        assert!(normalize_axis(3, 3).is_err());
+        assert!(normalize_axis(-4, 3).is_err());
+        assert!(normalize_axis(0, 0).is_err());
+    }
 
     #[test]
     fn test_model() {
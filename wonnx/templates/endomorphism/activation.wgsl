@@ -1,17 +1,55 @@
 {%- include "structs.wgsl" -%}
-
+{% if in_place %}
+// The output is written to the buffer that holds the input
+[[group(0), binding(0)]]
+var<storage, read_write> output_0: ArrayVector;
+{% set input_array = "output_0" %}
+{% else %}
 [[group(0), binding(0)]]
 var<storage, read> input_0: ArrayVector;
 
 [[group(0), binding(1)]]
 var<storage, write> output_0: ArrayVector;
+{% set input_array = "input_0" %}
+{% endif %}
 
 [[stage(compute), workgroup_size({{ workgroup_size_x }})]]
 fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	let gidx = global_id.x;
 
-	{% set activation_input = "input_0.data[gidx]" %}
-	{% set activation_output = "output_0.data[gidx]" %}
-	{% set activation_type = op_type %}
-	{%- include "snippets/activation_vec.wgsl" -%}
+	if (gidx < {{ element_count }}u) {
+		// The input is read once, before the output is written (which may be to the same buffer)
+		{%- if tail_length > 0 %}
+		// The last vector extends past the end of the tensor. Only its lanes that hold elements are read and written, so
+		// that whatever follows the tensor does not influence (or get overwritten by) the result.
+		let is_tail = gidx == {{ element_count - 1 }}u;
+		var activation_input_value = Vec4(Scalar(0));
+		if (is_tail) {
+			{%- for lane in range(end=tail_length) %}
+			activation_input_value[{{ lane }}] = {{ input_array }}.data[gidx][{{ lane }}];
+			{%- endfor %}
+		} else {
+			activation_input_value = {{ input_array }}.data[gidx];
+		}
+		{%- else %}
+		let activation_input_value = {{ input_array }}.data[gidx];
+		{%- endif %}
+		var activation_output_value: Vec4;
+		{% set activation_input = "activation_input_value" %}
+		{% set activation_output = "activation_output_value" %}
+		{% set activation_type = op_type %}
+		{%- include "snippets/activation_vec.wgsl" -%}
+
+		{%- if tail_length > 0 %}
+		if (is_tail) {
+			{%- for lane in range(end=tail_length) %}
+			output_0.data[gidx][{{ lane }}] = activation_output_value[{{ lane }}];
+			{%- endfor %}
+		} else {
+			output_0.data[gidx] = activation_output_value;
+		}
+		{%- else %}
+		output_0.data[gidx] = activation_output_value;
+		{%- endif %}
+	}
 }
\ No newline at end of file
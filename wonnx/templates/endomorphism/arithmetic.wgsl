@@ -1,37 +1,105 @@
 {%- include "structs.wgsl" -%}
 
+{% if comparison %}
+	// Comparisons produce booleans, which are stored as i32 values that are either 0 or 1
+	type OutputScalar = i32;
+{% else %}
+	type OutputScalar = Scalar;
+{% endif %}
+
+{% if broadcast %}
+	// Inputs of different shapes are broadcast to the output shape; each invocation calculates a single element
+	type Operand = Scalar;
+	type Unsigned = u32;
+	type Input = Array;
+	type OutputOperand = OutputScalar;
+{% else %}
+	type Operand = Vec4;
+	type Unsigned = vec4<u32>;
+	type Input = ArrayVector;
+	type OutputOperand = {% if comparison %}vec4<i32>{% else %}Vec4{% endif %};
+{% endif %}
+
+struct Output {
+	data: [[stride({% if broadcast %}{{ scalar_stride }}{% else %}{{ vec4_stride }}{% endif %})]] array<OutputOperand>;
+};
+
 [[group(0), binding(0)]]
-var<storage, read> input_0: ArrayVector;
+var<storage, read> input_0: Input;
 
 {% if i_lens | length == 2 %}
 
 [[group(0), binding(1)]]
-var<storage, read> input_1: ArrayVector;
+var<storage, read> input_1: Input;
 
 [[group(0), binding(2)]]
-var<storage, write> output_0: ArrayVector;
+var<storage, write> output_0: Output;
 
 {% else %}
 
 [[group(0), binding(1)]]
-var<storage, write> output_0: ArrayVector;
+var<storage, write> output_0: Output;
 
 {% endif %}
 
+fn apply(lhs: Operand, rhs: Operand) -> OutputOperand {
+	{% if op_type == "<<" or op_type == ">>" %}
+		// The shift amount has to be unsigned. Shifts are performed on the unsigned representation so that a right
+		// shift is a logical (rather than arithmetic) shift.
+		{% if shift_mask %}
+			return Operand((Unsigned(lhs) {{ op_type }} Unsigned(rhs)) & Unsigned({{ shift_mask }}u));
+		{% else %}
+			return Operand(Unsigned(lhs) {{ op_type }} Unsigned(rhs));
+		{% endif %}
+	{% elif logical %}
+		// Any non-zero value is considered to be true; the result is always either 0 or 1
+		let zero = Operand(Scalar(0));
+		return select(zero, Operand(Scalar(1)), (lhs != zero) {{ op_type }} (rhs != zero));
+	{% elif comparison %}
+		return select(OutputOperand(0), OutputOperand(1), lhs {{ op_type }} rhs);
+	{% elif python_mod %}
+		// The remainder takes the sign of the divisor (unlike the WGSL '%' which takes the sign of the dividend)
+		let zero = Operand(Scalar(0));
+		let remainder = lhs % rhs;
+		return select(remainder, remainder + rhs, (remainder != zero) & ((remainder < zero) != (rhs < zero)));
+	{% else %}
+		return lhs {{ op_type }} rhs;
+	{% endif %}
+}
+
 [[stage(compute), workgroup_size({{ workgroup_size_x }}, 1, 1)]]
 fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	let gidx = global_id.x;
 
-	{% if i_lens | length == 2 %}
-		output_0.data[gidx] = input_0.data[gidx] {{ op_type }} input_1.data[gidx];
+	{% if broadcast %}
+		if (gidx < {{ element_count }}u) {
+			var rest = gidx;
+			var index_0 = 0u;
+			var index_1 = 0u;
+			{% for chunk in o_chunks[0] %}
+				let d_{{ loop.index0 }} = rest / {{ chunk }}u;
+				rest = rest % {{ chunk }}u;
+				index_0 = index_0 + d_{{ loop.index0 }} * {{ broadcast_strides[0][loop.index0] }}u;
+				index_1 = index_1 + d_{{ loop.index0 }} * {{ broadcast_strides[1][loop.index0] }}u;
+			{% endfor %}
+
+			output_0.data[gidx] = apply(input_0.data[index_0], input_1.data[index_1]);
+		}
+
+	{% elif i_lens | length == 2 %}
+		if (gidx < {{ element_count }}u) {
+			output_0.data[gidx] = apply(input_0.data[gidx], input_1.data[gidx]);
+		}
 
 	{% else %}
-		output_0.data[gidx] = input_0.data[gidx] {{ op_type }} Vec4(
-			Scalar({{ coefficient }}), 
-			Scalar({{ coefficient }}),
-			Scalar({{ coefficient }}),
-			Scalar({{ coefficient }})
-		);
+		if (gidx < {{ element_count }}u) {
+			output_0.data[gidx] = apply(input_0.data[gidx], Vec4(
+				Scalar({{ coefficient }}), 
+				Scalar({{ coefficient }}),
+				Scalar({{ coefficient }}),
+				Scalar({{ coefficient }})
+			));
+		}
 		
 	{% endif %}
-}
\ No newline at end of file
+}
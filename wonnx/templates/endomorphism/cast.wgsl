@@ -1,16 +1,46 @@
-{%- include "structs.wgsl" -%}
-[[group(0), binding(0)]]
-var<storage, read> input_0: ArrayVector;
+{# 
+// The input and output of a cast have different data types, so this shader does not use the 'Scalar' type (which is set
+// to the input type). WGSL has no 64-bit integer type; int64 values are read and written as two i32 values (low and high
+// word). When either side is int64, each invocation casts a single element, otherwise four elements at a time are cast.
+#}
+
+struct InputArray {
+	data: [[stride({{ input_stride }})]] array<{{ input_element_type }}>;
+};
+
+struct OutputArray {
+	data: [[stride({{ output_stride }})]] array<{{ output_element_type }}>;
+};
 
-struct OutputArrayVector {
-	data: [[stride({{ vec4_stride }})]] array<vec4<{{ cast_to_type }}>>;
-}; 
+[[group(0), binding(0)]]
+var<storage, read> input_0: InputArray;
 
 [[group(0), binding(1)]]
-var<storage, write> output_0: OutputArrayVector;
+var<storage, write> output_0: OutputArray;
 
 [[stage(compute), workgroup_size({{ workgroup_size_x }})]]
 fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
-    let gidx = global_id.x;
-    output_0.data[gidx] = vec4<{{ cast_to_type }}>(input_0.data[gidx]);
-}
\ No newline at end of file
+	let gidx = global_id.x;
+
+	{% if cast_int64 %}
+		if (gidx < {{ element_count }}u) {
+			{% if cast_from_type == "i64" %}
+				// Only the low word is used; values outside of the i32 range are not supported
+				let value = input_0.data[gidx].x;
+			{% else %}
+				let value = input_0.data[gidx];
+			{% endif %}
+
+			{% if cast_to_type == "i64" %}
+				let converted = i32(value);
+				output_0.data[gidx] = vec2<i32>(converted, select(0, -1, converted < 0));
+			{% else %}
+				output_0.data[gidx] = {{ cast_to_type }}(value);
+			{% endif %}
+		}
+	{% else %}
+		if (gidx < {{ element_count }}u) {
+			output_0.data[gidx] = vec4<{{ cast_to_type }}>(input_0.data[gidx]);
+		}
+	{% endif %}
+}
@@ -0,0 +1,26 @@
+{%- include "structs.wgsl" -%}
+
+// The mask is a boolean tensor (booleans are stored as 32-bit integers)
+struct Mask {
+	data: [[stride(16)]] array<vec4<i32>>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: ArrayVector;
+
+[[group(0), binding(1)]]
+var<storage, write> output_0: ArrayVector;
+
+[[group(0), binding(2)]]
+var<storage, write> output_1: Mask;
+
+[[stage(compute), workgroup_size({{ workgroup_size_x }})]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ element_count }}u) {
+		// At inference nothing is dropped
+		output_0.data[gidx] = input_0.data[gidx];
+		output_1.data[gidx] = vec4<i32>(1, 1, 1, 1);
+	}
+}
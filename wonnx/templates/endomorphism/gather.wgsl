@@ -1,8 +1,16 @@
 {%- include "structs.wgsl" -%}
 
+{%- if indices_int64 %}
+// 64-bit indices are read as pairs of 32-bit integers, of which the first holds the lower 32 bits (indices are assumed
+// to fit in 32 bits)
+struct Indices {
+	data: [[stride(8)]] array<vec2<i32>>;
+};
+{%- else %}
 struct Indices {
 	data: [[stride(4)]] array<i32>;
 };
+{%- endif %}
 
 struct Chunk {
 	data: [[stride({{ scalar_stride * chunk_size }})]] array<{{ chunk_type }}>;
@@ -23,11 +31,13 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	let chunk_index = global_id.y; // Chunk of elements that we are copying for this index (chunk size determined dynamically)
 	let index_stride = {{ i_chunks[0][0] / chunk_size }}u;
 
-	// Negative indexing is apparently allowed; see https://github.com/onnx/onnx/blob/main/docs/Operators.md#inputs-38
-	var index = input_1.data[index_index];
-	if(index < 0) {
-		index = {{ i_shape[0][0] }} + index;
-	}
+	if (index_index < {{ i_lens[1] }}u && chunk_index < index_stride) {
+		// Negative indexing is apparently allowed; see https://github.com/onnx/onnx/blob/main/docs/Operators.md#inputs-38
+		var index = input_1.data[index_index]{% if indices_int64 %}.x{% endif %};
+		if(index < 0) {
+			index = {{ i_shape[0][0] }} + index;
+		}
 
-	output_0.data[(index_index * index_stride) + chunk_index] = input_0.data[(index * i32(index_stride)) + i32(chunk_index)];
+		output_0.data[(index_index * index_stride) + chunk_index] = input_0.data[(index * i32(index_stride)) + i32(chunk_index)];
+	}
 }
\ No newline at end of file
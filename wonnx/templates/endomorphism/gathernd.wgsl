@@ -0,0 +1,45 @@
+{%- include "structs.wgsl" -%}
+
+{%- if indices_int64 %}
+// 64-bit indices are read as pairs of 32-bit integers, of which the first holds the lower 32 bits (indices are assumed
+// to fit in 32 bits)
+struct Indices {
+	data: [[stride(8)]] array<vec2<i32>>;
+};
+{%- else %}
+struct Indices {
+	data: [[stride(4)]] array<i32>;
+};
+{%- endif %}
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array; // data
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Indices; // indices
+
+[[group(0), binding(2)]]
+var<storage, write> output_0: Array;
+
+[[stage(compute), workgroup_size({{ workgroup_size_x }})]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ o_lens[0] }}u) {
+		// The index tuple that selects the slice this output element is part of
+		let tuple_index = gidx / {{ slice_size }}u;
+		let batch = tuple_index / {{ tuples_per_batch }}u;
+
+		var source = batch * {{ batch_chunk }}u + gidx % {{ slice_size }}u;
+		{% for j in range(end=index_length) -%}
+			// Negative indexing is allowed, see https://github.com/onnx/onnx/blob/main/docs/Operators.md#GatherND
+			var index_{{ j }} = input_1.data[tuple_index * {{ index_length }}u + {{ j }}u]{% if indices_int64 %}.x{% endif %};
+			if (index_{{ j }} < 0) {
+				index_{{ j }} = index_{{ j }} + {{ i_shape[0] | nth(n=batch_dims + j) }};
+			}
+			source = source + u32(index_{{ j }}) * {{ i_chunks[0] | nth(n=batch_dims + j) }}u;
+		{% endfor %}
+
+		output_0.data[gidx] = input_0.data[source];
+	}
+}
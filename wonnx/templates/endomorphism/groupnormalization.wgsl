@@ -0,0 +1,41 @@
+{%- include "structs.wgsl" -%}
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array; // X
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Array; // scale
+
+[[group(0), binding(2)]]
+var<storage, read> input_2: Array; // bias
+
+[[group(0), binding(3)]]
+var<storage, write> output_0: Array;
+
+// Each invocation normalizes one group of channels in one batch (the channels of a group are contiguous in memory)
+[[stage(compute), workgroup_size(256, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let group = global_id.x;
+
+	if (group < {{ group_count }}u) {
+		let base = group * {{ group_size }}u;
+
+		{% set stats_count = group_size %}
+		{% set stats_stride = 1 %}
+		{% set stats_value = "input_0.data[base + stats_position]" %}
+		{%- include "snippets/mean_variance.wgsl" %}
+		let inv_std_dev = Scalar(1) / sqrt(variance + Scalar({{ epsilon }}));
+
+		let group_index = group % {{ num_groups }}u;
+		for(var i: u32 = 0u; i < {{ group_size }}u; i = i + 1u) {
+			{% if per_channel %}
+				// Scale and bias hold a value for each channel
+				let param = group_index * {{ channels_per_group }}u + i / {{ channel_size }}u;
+			{% else %}
+				// Scale and bias hold a value for each group
+				let param = group_index;
+			{% endif %}
+			output_0.data[base + i] = (input_0.data[base + i] - mean) * inv_std_dev * input_1.data[param] + input_2.data[param];
+		}
+	}
+}
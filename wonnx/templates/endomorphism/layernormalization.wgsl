@@ -0,0 +1,90 @@
+{%- include "structs.wgsl" -%}
+
+// Input
+[[group({{ bindings.input[0] }}), binding({{ bindings.input[1] }})]]
+var<storage, read> input_0: Array;
+
+{% if has_skip %}
+// Skip connection (added to the input before normalizing)
+[[group({{ bindings.skip[0] }}), binding({{ bindings.skip[1] }})]]
+var<storage, read> input_1: Array;
+{% endif %}
+
+// Scale (gamma)
+[[group({{ bindings.gamma[0] }}), binding({{ bindings.gamma[1] }})]]
+var<storage, read> input_2: Array;
+
+{% if has_beta %}
+// Shift (beta)
+[[group({{ bindings.beta[0] }}), binding({{ bindings.beta[1] }})]]
+var<storage, read> input_3: Array;
+{% endif %}
+
+{% if has_bias %}
+// Bias (added to the input before normalizing)
+[[group({{ bindings.bias[0] }}), binding({{ bindings.bias[1] }})]]
+var<storage, read> input_4: Array;
+{% endif %}
+
+[[group({{ bindings.output[0] }}), binding({{ bindings.output[1] }})]]
+var<storage, write> output_0: Array;
+
+{% if has_mean %}
+[[group({{ bindings.mean[0] }}), binding({{ bindings.mean[1] }})]]
+var<storage, write> output_1: Array;
+{% endif %}
+
+{% if has_inv_std_var %}
+[[group({{ bindings.inv_std_var[0] }}), binding({{ bindings.inv_std_var[1] }})]]
+var<storage, write> output_2: Array;
+{% endif %}
+
+{% if has_sum %}
+// Sum of the input, skip connection and bias
+[[group({{ bindings.sum[0] }}), binding({{ bindings.sum[1] }})]]
+var<storage, write> output_3: Array;
+{% endif %}
+
+// Returns the value to normalize at the specified index in the row that starts at the base index
+fn element(base: u32, index: u32) -> Scalar {
+	var value = input_0.data[base + index];
+	{% if has_skip %}
+		value = value + input_1.data[base + index];
+	{% endif %}
+	{% if has_bias %}
+		value = value + input_4.data[index];
+	{% endif %}
+	return value;
+}
+
+// Each invocation normalizes one row (i.e. all elements in the last dimension)
+[[stage(compute), workgroup_size(256, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let row = global_id.x;
+
+	if (row < {{ row_count }}u) {
+		let base = row * {{ row_size }}u;
+
+		{% set stats_count = row_size %}
+		{% set stats_stride = 1 %}
+		{% set stats_value = "element(base, stats_position)" %}
+		{%- include "snippets/mean_variance.wgsl" %}
+		let inv_std_var = Scalar(1) / sqrt(variance + Scalar({{ epsilon }}));
+
+		for(var i: u32 = 0u; i < {{ row_size }}u; i = i + 1u) {
+			let value = element(base, i);
+			output_0.data[base + i] = (value - mean) * inv_std_var * input_2.data[i]{% if has_beta %} + input_3.data[i]{% endif %};
+
+			{% if has_sum %}
+				output_3.data[base + i] = value;
+			{% endif %}
+		}
+
+		{% if has_mean %}
+			output_1.data[row] = mean;
+		{% endif %}
+		{% if has_inv_std_var %}
+			output_2.data[row] = inv_std_var;
+		{% endif %}
+	}
+}
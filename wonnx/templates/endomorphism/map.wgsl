@@ -1,20 +1,74 @@
 {%- include "structs.wgsl" -%}
+{% if in_place %}
+// The output is written to the buffer that holds the input
+[[group(0), binding(0)]]
+var<storage, read_write> output_0: ArrayVector;
+{% set input_array = "output_0" %}
+{% else %}
 [[group(0), binding(0)]]
 var<storage, read> input_0: ArrayVector;
 
 [[group(0), binding(1)]]
 var<storage, write> output_0: ArrayVector;
+{% set input_array = "input_0" %}
+{% endif %}
 
 [[stage(compute), workgroup_size({{ workgroup_size_x }})]]
 fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	let gidx = global_id.x;
-	
-	{% if op_type == "Reciprocal" %}
-		let one = Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1));
-		output_0.data[gidx] = one / (input_0.data[gidx]);
 
-	{% else %}
-		output_0.data[gidx] = {{ op_type | lower }}(input_0.data[gidx]);
+	if (gidx < {{ element_count }}u) {
+		let input = {{ input_array }}.data[gidx];
+
+		{% if op_type == "Reciprocal" %}
+			let one = Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1));
+			output_0.data[gidx] = one / input;
+
+		{% elif op_type == "Tanh" and approximate_activations %}
+			// Rational (Padé) approximation of tanh, which is accurate to within 1e-4. Beyond |x| = 9 the approximation
+			// diverges from tanh, while tanh(x) rounds to +/-1.
+			let t = clamp(input, Vec4(Scalar(-9)), Vec4(Scalar(9)));
+			let t2 = t * t;
+			let p = t * (Vec4(Scalar(135135)) + t2 * (Vec4(Scalar(17325)) + t2 * (Vec4(Scalar(378)) + t2)));
+			let q = Vec4(Scalar(135135)) + t2 * (Vec4(Scalar(62370)) + t2 * (Vec4(Scalar(3150)) + t2 * Vec4(Scalar(28))));
+			output_0.data[gidx] = clamp(p / q, Vec4(Scalar(-1)), Vec4(Scalar(1)));
+
+		{% elif op_type in ["Neg", "Abs"] and scalar_type == "i32" %}
+			// Integer negation wraps around as in ONNX, so that -i32::MIN is i32::MIN (and so is abs(i32::MIN)). This is
+			// spelled out instead of using unary minus and abs(), which are translated to ops that are undefined for
+			// i32::MIN on some backends.
+			let zero = Vec4(Scalar(0));
+			let negated = vec4<i32>(vec4<u32>(zero) - vec4<u32>(input));
+			{%- if op_type == "Neg" %}
+				output_0.data[gidx] = negated;
+			{%- else %}
+				output_0.data[gidx] = select(input, negated, input < zero);
+			{%- endif %}
+
+		{% elif op_type in ["Ceil", "Floor", "Round"] and scalar_type == "i32" %}
+			// Integers are already rounded (and WGSL only defines these functions for floating point types)
+			output_0.data[gidx] = input;
+
+		{% elif op_type == "Round" %}
+			// ONNX rounds halfway cases to the nearest even integer, which WGSL's round() does not do on every backend
+			let half = Vec4(Scalar(0.5));
+			let floored = floor(input);
+			let fraction = input - floored;
+			let floored_is_odd = floored - Vec4(Scalar(2)) * floor(floored * half) != Vec4(Scalar(0));
+			let round_up = (fraction > half) | ((fraction == half) & floored_is_odd);
+			output_0.data[gidx] = select(floored, floored + Vec4(Scalar(1)), round_up);
+
+		{% elif op_type == "Neg" %}
+			output_0.data[gidx] = -input;
+
+		{% elif op_type == "Sign" and scalar_type == "i32" %}
+			// WGSL only defines sign() for floating point types
+			let zero = Vec4(Scalar(0));
+			output_0.data[gidx] = select(zero, select(Vec4(Scalar(-1)), Vec4(Scalar(1)), input > zero), input != zero);
+
+		{% else %}
+			output_0.data[gidx] = {{ op_type | lower }}(input);
 
-	{% endif %}
+		{% endif %}
+	}
 }
\ No newline at end of file
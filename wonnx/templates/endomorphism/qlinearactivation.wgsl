@@ -0,0 +1,30 @@
+{%- include "structs.wgsl" -%}
+
+// Quantized values (8-bit integers are stored as 32-bit integers)
+struct Quantized {
+	data: [[stride(4)]] array<i32>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Quantized;
+
+[[group(0), binding(1)]]
+var<storage, write> output_0: Quantized;
+
+[[stage(compute), workgroup_size({{ workgroup_size_x }})]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ o_lens[0] }}u) {
+		// Dequantize, apply the activation and quantize again, without writing the intermediate values to memory
+		let x = Scalar(input_0.data[gidx] - {{ x_zero_point }}) * Scalar({{ x_scale }});
+		var y: Scalar;
+		{% set activation_input = "x" %}
+		{% set activation_output = "y" %}
+		{% set activation_type = activation %}
+		{%- include "snippets/activation_scalar.wgsl" -%}
+
+		let quantized = i32(round(y / Scalar({{ y_scale }}))) + {{ y_zero_point }};
+		output_0.data[gidx] = clamp(quantized, {{ quantized_min }}, {{ quantized_max }});
+	}
+}
@@ -0,0 +1,44 @@
+{%- include "structs.wgsl" -%}
+
+// Quantized values (8-bit integers are stored as 32-bit integers)
+struct Quantized {
+	data: [[stride(4)]] array<i32>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array; // x
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Array; // y_scale
+
+{%- if has_zero_point %}
+[[group(0), binding(2)]]
+var<storage, read> input_2: Quantized; // y_zero_point
+
+[[group(0), binding(3)]]
+var<storage, write> output_0: Quantized;
+{%- else %}
+[[group(0), binding(2)]]
+var<storage, write> output_0: Quantized;
+{%- endif %}
+
+[[stage(compute), workgroup_size({{ workgroup_size_x }})]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ o_lens[0] }}u) {
+		// Index of the scale and zero point to use for this element
+		{%- if per_axis %}
+			let channel = (gidx / {{ axis_chunk }}u) % {{ axis_dim }}u;
+		{%- else %}
+			let channel = 0u;
+		{%- endif %}
+
+		// Rounding is half to even, as required by ONNX
+		var y = i32(round(input_0.data[gidx] / input_1.data[channel]));
+		{%- if has_zero_point %}
+			y = y + input_2.data[channel];
+		{%- endif %}
+		output_0.data[gidx] = clamp(y, {{ quantized_min }}, {{ quantized_max }});
+	}
+}
@@ -0,0 +1,39 @@
+{%- include "structs.wgsl" -%}
+
+{%- if lengths_int64 %}
+// 64-bit lengths are read as pairs of 32-bit integers, of which the first holds the lower 32 bits
+struct Lengths {
+	data: [[stride(8)]] array<vec2<i32>>;
+};
+{%- else %}
+struct Lengths {
+	data: [[stride(4)]] array<i32>;
+};
+{%- endif %}
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array; // data
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Lengths; // sequence_lens
+
+[[group(0), binding(2)]]
+var<storage, write> output_0: Array;
+
+[[stage(compute), workgroup_size({{ workgroup_size_x }})]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ o_lens[0] }}u) {
+		let batch = (gidx / {{ batch_stride }}u) % {{ batch_count }}u;
+		let time = (gidx / {{ time_stride }}u) % {{ time_count }}u;
+		let length = u32(clamp(input_1.data[batch]{% if lengths_int64 %}.x{% endif %}, 0, {{ time_count }}));
+
+		// Elements beyond the length of their sequence (padding) are copied as they are
+		var source = gidx;
+		if (time < length) {
+			source = gidx - time * {{ time_stride }}u + (length - 1u - time) * {{ time_stride }}u;
+		}
+		output_0.data[gidx] = input_0.data[source];
+	}
+}
@@ -0,0 +1,68 @@
+{%- include "structs.wgsl" -%}
+
+{%- if indices_int64 %}
+// 64-bit indices are read as pairs of 32-bit integers, of which the first holds the lower 32 bits (indices are assumed
+// to fit in 32 bits)
+struct Indices {
+	data: [[stride(8)]] array<vec2<i32>>;
+};
+{%- else %}
+struct Indices {
+	data: [[stride(4)]] array<i32>;
+};
+{%- endif %}
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array; // data
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Indices; // indices
+
+[[group(0), binding(2)]]
+var<storage, read> input_2: Array; // updates
+
+[[group(0), binding(3)]]
+var<storage, write> output_0: Array;
+
+// Each invocation calculates a single output element. It starts out with the corresponding element of data, and then
+// applies all updates whose index tuple points at the slice the element is in, in order. This way no synchronization
+// between invocations is required when several updates target the same slice.
+[[stage(compute), workgroup_size({{ workgroup_size_x }})]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ o_lens[0] }}u) {
+		let slice_index = gidx / {{ slice_size }}u;
+		let slice_offset = gidx % {{ slice_size }}u;
+		var value = input_0.data[gidx];
+
+		for(var u: u32 = 0u; u < {{ update_count }}u; u = u + 1u) {
+			// Calculate the (flattened) index of the slice the update is written to
+			var destination = 0u;
+			{% for chunk in index_chunks -%}
+				var index_{{ loop.index0 }} = input_1.data[u * {{ index_length }}u + {{ loop.index0 }}u]{% if indices_int64 %}.x{% endif %};
+				if (index_{{ loop.index0 }} < 0) {
+					index_{{ loop.index0 }} = index_{{ loop.index0 }} + {{ i_shape[0] | nth(n=loop.index0) }};
+				}
+				destination = destination + u32(index_{{ loop.index0 }}) * {{ chunk }}u;
+			{% endfor %}
+
+			if (destination == slice_index) {
+				let update = input_2.data[u * {{ slice_size }}u + slice_offset];
+				{%- if reduction == "add" %}
+					value = value + update;
+				{%- elif reduction == "mul" %}
+					value = value * update;
+				{%- elif reduction == "max" %}
+					value = max(value, update);
+				{%- elif reduction == "min" %}
+					value = min(value, update);
+				{%- else %}
+					value = update;
+				{%- endif %}
+			}
+		}
+
+		output_0.data[gidx] = value;
+	}
+}
@@ -13,11 +13,10 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	{% if op_type == "Softmax" %}
 		// Softmax = exp(input - max(input)) / sum(exp(input - max(input)))
 		
-		// First, determine max(input)
-		// WGSL doesn't have a way to write -Infinity (https://github.com/gpuweb/gpuweb/issues/1769)
-		// Therefore we use log(0) instead which returns -Infinity
-		var max_element: Scalar = log(Scalar(0));
-		for(var k: u32 = 0u; k < {{ i_lens[0] }}u; k = k + 1u) {
+		// First, determine max(input). Subtracting it keeps exp() from overflowing for large inputs. WGSL doesn't have a
+		// way to write -Infinity (https://github.com/gpuweb/gpuweb/issues/1769), so we start from the first element.
+		var max_element: Scalar = input_0.data[gidx];
+		for(var k: u32 = 1u; k < {{ i_lens[0] }}u; k = k + 1u) {
 			let element = input_0.data[gidx + k];
 			max_element = max(max_element, element);
 		}
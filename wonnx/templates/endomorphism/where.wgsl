@@ -0,0 +1,40 @@
+{%- include "structs.wgsl" -%}
+
+// The condition is a boolean, stored as i32 values that are either 0 (false) or 1 (true)
+struct Condition {
+	data: [[stride(4)]] array<i32>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Condition;
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Array; // x
+
+[[group(0), binding(2)]]
+var<storage, read> input_2: Array; // y
+
+[[group(0), binding(3)]]
+var<storage, write> output_0: Array;
+
+[[stage(compute), workgroup_size({{ workgroup_size_x }}, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	// The inputs are broadcast to the output shape; each invocation calculates a single element
+	if (gidx < {{ o_lens[0] }}u) {
+		var rest = gidx;
+		var index_0 = 0u;
+		var index_1 = 0u;
+		var index_2 = 0u;
+		{% for chunk in o_chunks[0] %}
+			let d_{{ loop.index0 }} = rest / {{ chunk }}u;
+			rest = rest % {{ chunk }}u;
+			index_0 = index_0 + d_{{ loop.index0 }} * {{ broadcast_strides[0][loop.index0] }}u;
+			index_1 = index_1 + d_{{ loop.index0 }} * {{ broadcast_strides[1][loop.index0] }}u;
+			index_2 = index_2 + d_{{ loop.index0 }} * {{ broadcast_strides[2][loop.index0] }}u;
+		{% endfor %}
+
+		output_0.data[gidx] = select(input_2.data[index_2], input_1.data[index_1], input_0.data[index_0] != 0);
+	}
+}
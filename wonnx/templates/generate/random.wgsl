@@ -0,0 +1,34 @@
+{%- include "structs.wgsl" -%}
+
+[[group(0), binding(0)]]
+var<storage, write> output_0: Array;
+
+// PCG hash (see https://www.pcg-random.org and https://jcgt.org/published/0009/03/02/)
+fn pcg(value: u32) -> u32 {
+	let state = value * 747796405u + 2891336453u;
+	let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+	return (word >> 22u) ^ word;
+}
+
+// Uniformly distributed random number in [0, 1) for the given counter value (the output only depends on the counter and
+// the seed, so that the generated values are the same for each run)
+fn random(counter: u32) -> Scalar {
+	return Scalar(pcg(pcg(counter) ^ {{ seed }}u) >> 8u) / Scalar(16777216);
+}
+
+[[stage(compute), workgroup_size(256, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ o_lens[0] }}u) {
+		{% if op_type == "RandomNormal" %}
+			// Box-Muller transform of two uniformly distributed numbers (u1 is taken from (0, 1], as log(0) is infinite)
+			let u1 = Scalar(1) - random(gidx * 2u);
+			let u2 = random(gidx * 2u + 1u);
+			let normal = sqrt(Scalar(-2) * log(u1)) * cos(Scalar(6.283185307179586) * u2);
+			output_0.data[gidx] = Scalar({{ mean }}) + Scalar({{ scale }}) * normal;
+		{% else %}
+			output_0.data[gidx] = Scalar({{ low }}) + (Scalar({{ high }}) - Scalar({{ low }})) * random(gidx);
+		{% endif %}
+	}
+}
\ No newline at end of file
@@ -6,7 +6,7 @@ var<storage, read> input_0: ArrayVector;
 [[group(0), binding(1)]]
 var<storage, read> input_1: ArrayVector;
 
-{%- if i_lens | length == 3 -%} // Bias
+{%- if has_bias -%} // Bias
 [[group(0), binding(2)]]
 var<storage, read> input_2: ArrayVector;
 
@@ -52,20 +52,20 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 		}
 	}
 	
-	{%- if i_lens | length == 3 -%}
+	{%- if has_bias -%}
 		let bias_row = input_2.data[x]; 
 		var bias = transpose(Mat4x4(bias_row, bias_row, bias_row, bias_row));
 		for(var index_mat: u32 = 0u; index_mat < 4u; index_mat = index_mat + 1u) {
 			output_0.data[index + index_mat * {{ i_shape[1][1] / 4 | int }}u] = 
-				{%- if alpha != 1 -%} {{ alpha | float }} * {%- endif -%} 
+				{%- if alpha != 1 -%} Scalar({{ alpha | float }}) * {%- endif -%} 
 				tmpsum[index_mat] + 
-				{%- if beta != 1 -%} {{ beta | float }} * {%- endif -%} 
+				{%- if beta != 1 -%} Scalar({{ beta | float }}) * {%- endif -%} 
 				bias[index_mat]
 			;
 		}
 	{%- else -%}
 		for(var index_mat: u32 = 0u; index_mat < 4u; index_mat = index_mat + 1u) {
-			output_0.data[index + index_mat * {{ i_shape[1][1] / 4 | int }}u] = {%- if alpha != 1 -%} {{ alpha | float }} * {%- endif -%}tmpsum[index_mat];
+			output_0.data[index + index_mat * {{ i_shape[1][1] / 4 | int }}u] = {%- if alpha != 1 -%} Scalar({{ alpha | float }}) * {%- endif -%}tmpsum[index_mat];
 		}
 	{%- endif -%}
 }
\ No newline at end of file
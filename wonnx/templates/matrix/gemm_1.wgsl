@@ -6,7 +6,7 @@ var<storage, read> input_0: ArrayVector;
 [[group(0), binding(1)]]
 var<storage, read> input_1: Array;
 
-{%- if i_lens | length == 3 -%} // Bias
+{%- if has_bias -%} // Bias
 [[group(0), binding(2)]]
 var<storage, read> input_2: Array;
 
@@ -44,11 +44,11 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	}
 	
 	output_0.data[gidx] = 
-		{%- if alpha != 1 -%} {{ alpha | float }} * {%- endif -%} 
+		{%- if alpha != 1 -%} Scalar({{ alpha | float }}) * {%- endif -%} 
 		tmpsum
-		{%- if i_lens | length == 3 -%}
-			+ {%- if beta != 1 -%} {{ beta | float }} * {%- endif -%}
-			input_2.data[gidx];
+		{%- if has_bias -%}
+			+ {%- if beta != 1 -%} Scalar({{ beta | float }}) * {%- endif -%}
+			input_2.data[gidx]
 		{%- endif -%}
 	;
 }
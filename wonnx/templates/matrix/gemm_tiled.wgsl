@@ -0,0 +1,69 @@
+{%- include "structs.wgsl" -%}
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array;
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Array;
+
+{%- if has_bias -%} // Bias
+[[group(0), binding(2)]]
+var<storage, read> input_2: Array;
+
+[[group(0), binding(3)]]
+var<storage, write> output_0: Array;
+{%- else -%}
+[[group(0), binding(2)]]
+var<storage, write> output_0: Array;
+{%- endif -%}
+
+// Tiles of A and B that are shared between all invocations in a workgroup. Each invocation loads one element of each
+// tile, after which every invocation can read all elements of the tiles it needs for its output element.
+var<workgroup> tile_a: array<array<Scalar, {{ tile_size }}>, {{ tile_size }}>;
+var<workgroup> tile_b: array<array<Scalar, {{ tile_size }}>, {{ tile_size }}>;
+
+[[stage(compute), workgroup_size({{ tile_size }}, {{ tile_size }}, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>, [[builtin(local_invocation_id)]] local_id: vec3<u32>) {
+	let row = global_id.y;
+	let column = global_id.x;
+	let tile_row = local_id.y;
+	let tile_column = local_id.x;
+
+	var sum = Scalar(0);
+
+	for(var tile: u32 = 0u; tile < {{ tile_count }}u; tile = tile + 1u) {
+		// Elements outside A or B are loaded as zero so they do not contribute to the sum
+		let a_column = tile * {{ tile_size }}u + tile_column;
+		if (row < {{ m }}u && a_column < {{ k }}u) {
+			tile_a[tile_row][tile_column] = input_0.data[row * {{ k }}u + a_column];
+		} else {
+			tile_a[tile_row][tile_column] = Scalar(0);
+		}
+
+		let b_row = tile * {{ tile_size }}u + tile_row;
+		if (b_row < {{ k }}u && column < {{ n }}u) {
+			tile_b[tile_row][tile_column] = input_1.data[b_row * {{ n }}u + column];
+		} else {
+			tile_b[tile_row][tile_column] = Scalar(0);
+		}
+
+		workgroupBarrier();
+
+		for(var i: u32 = 0u; i < {{ tile_size }}u; i = i + 1u) {
+			sum = sum + tile_a[tile_row][i] * tile_b[i][tile_column];
+		}
+
+		workgroupBarrier();
+	}
+
+	if (row < {{ m }}u && column < {{ n }}u) {
+		output_0.data[row * {{ n }}u + column] = 
+			{%- if alpha != 1 -%} Scalar({{ alpha | float }}) * {%- endif -%}
+			sum
+			{%- if has_bias -%}
+				+ {%- if beta != 1 -%} Scalar({{ beta | float }}) * {%- endif -%}
+				input_2.data[row * {{ bias_row_stride }}u + column * {{ bias_column_stride }}u]
+			{%- endif -%}
+		;
+	}
+}
@@ -0,0 +1,103 @@
+{%- include "structs.wgsl" -%}
+
+// X (input sequence)
+[[group({{ bindings.x[0] }}), binding({{ bindings.x[1] }})]]
+var<storage, read> input_0: Array;
+
+// W (input weights for the z, r, h gates)
+[[group({{ bindings.w[0] }}), binding({{ bindings.w[1] }})]]
+var<storage, read> input_1: Array;
+
+// R (recurrence weights for the z, r, h gates)
+[[group({{ bindings.r[0] }}), binding({{ bindings.r[1] }})]]
+var<storage, read> input_2: Array;
+
+{% if has_bias %}
+// B (input bias followed by recurrence bias)
+[[group({{ bindings.bias[0] }}), binding({{ bindings.bias[1] }})]]
+var<storage, read> input_3: Array;
+{% endif %}
+
+{% if has_initial_h %}
+// Initial hidden state
+[[group({{ bindings.initial_h[0] }}), binding({{ bindings.initial_h[1] }})]]
+var<storage, read> input_5: Array;
+{% endif %}
+
+{% if bindings.y %}
+// Y (all intermediate hidden states)
+[[group({{ bindings.y[0] }}), binding({{ bindings.y[1] }})]]
+var<storage, write> output_0: Array;
+{% endif %}
+
+{% if bindings.y_h %}
+// Y_h (last hidden state)
+[[group({{ bindings.y_h[0] }}), binding({{ bindings.y_h[1] }})]]
+var<storage, write> output_1: Array;
+{% endif %}
+
+// Hidden state of the previous time step, shared by all invocations for the same batch
+var<workgroup> hidden: array<Scalar, {{ hidden_size }}>;
+
+{% if not linear_before_reset %}
+// Output of the reset gate for the current time step, which is applied to the hidden state before the recurrence
+var<workgroup> reset: array<Scalar, {{ hidden_size }}>;
+{% endif %}
+
+{%- include "snippets/recurrence.wgsl" -%}
+
+[[stage(compute), workgroup_size({{ hidden_size }}, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let unit = global_id.x;
+	let batch = global_id.y;
+	let state_index = batch * {{ hidden_size }}u + unit;
+	let z_row = unit;
+	let r_row = {{ hidden_size }}u + unit;
+	let h_row = {{ 2 * hidden_size }}u + unit;
+
+	{% if has_initial_h %}
+		hidden[unit] = input_5.data[state_index];
+	{% else %}
+		hidden[unit] = Scalar(0);
+	{% endif %}
+
+	workgroupBarrier();
+
+	for(var step: u32 = 0u; step < {{ seq_length }}u; step = step + 1u) {
+		let z = sigmoid(clip(input_projection(z_row, step, batch) + recurrent_projection(z_row)));
+		let r = sigmoid(clip(input_projection(r_row, step, batch) + recurrent_projection(r_row)));
+
+		{% if linear_before_reset %}
+			// ht = g(Xt*(Wh^T) + (rt (.) (Ht-1*(Rh^T) + Rbh)) + Wbh)
+			let h_candidate = tanh(clip(input_projection(h_row, step, batch) + r * recurrent_projection(h_row)));
+		{% else %}
+			// ht = g(Xt*(Wh^T) + (rt (.) Ht-1)*(Rh^T) + Rbh + Wbh)
+			reset[unit] = r;
+			workgroupBarrier();
+
+			var recurrent = Scalar(0);
+			for(var k: u32 = 0u; k < {{ hidden_size }}u; k = k + 1u) {
+				recurrent = recurrent + reset[k] * hidden[k] * input_2.data[h_row * {{ hidden_size }}u + k];
+			}
+
+			{% if has_bias %}
+				recurrent = recurrent + input_3.data[{{ 3 * hidden_size }}u + h_row];
+			{% endif %}
+			let h_candidate = tanh(clip(input_projection(h_row, step, batch) + recurrent));
+		{% endif %}
+
+		let h = (Scalar(1) - z) * h_candidate + z * hidden[unit];
+
+		// All invocations need to have read the previous hidden state before it can be overwritten
+		workgroupBarrier();
+		hidden[unit] = h;
+		{% if bindings.y %}
+			output_0.data[step * {{ batch_size * hidden_size }}u + state_index] = h;
+		{% endif %}
+		workgroupBarrier();
+	}
+
+	{% if bindings.y_h %}
+		output_1.data[state_index] = hidden[unit];
+	{% endif %}
+}
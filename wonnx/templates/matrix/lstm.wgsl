@@ -0,0 +1,105 @@
+{%- include "structs.wgsl" -%}
+
+// X (input sequence)
+[[group({{ bindings.x[0] }}), binding({{ bindings.x[1] }})]]
+var<storage, read> input_0: Array;
+
+// W (input weights for the i, o, f, c gates)
+[[group({{ bindings.w[0] }}), binding({{ bindings.w[1] }})]]
+var<storage, read> input_1: Array;
+
+// R (recurrence weights for the i, o, f, c gates)
+[[group({{ bindings.r[0] }}), binding({{ bindings.r[1] }})]]
+var<storage, read> input_2: Array;
+
+{% if has_bias %}
+// B (input bias followed by recurrence bias)
+[[group({{ bindings.bias[0] }}), binding({{ bindings.bias[1] }})]]
+var<storage, read> input_3: Array;
+{% endif %}
+
+{% if has_initial_h %}
+// Initial hidden state
+[[group({{ bindings.initial_h[0] }}), binding({{ bindings.initial_h[1] }})]]
+var<storage, read> input_5: Array;
+{% endif %}
+
+{% if has_initial_c %}
+// Initial cell state
+[[group({{ bindings.initial_c[0] }}), binding({{ bindings.initial_c[1] }})]]
+var<storage, read> input_6: Array;
+{% endif %}
+
+{% if bindings.y %}
+// Y (all intermediate hidden states)
+[[group({{ bindings.y[0] }}), binding({{ bindings.y[1] }})]]
+var<storage, write> output_0: Array;
+{% endif %}
+
+{% if bindings.y_h %}
+// Y_h (last hidden state)
+[[group({{ bindings.y_h[0] }}), binding({{ bindings.y_h[1] }})]]
+var<storage, write> output_1: Array;
+{% endif %}
+
+{% if bindings.y_c %}
+// Y_c (last cell state)
+[[group({{ bindings.y_c[0] }}), binding({{ bindings.y_c[1] }})]]
+var<storage, write> output_2: Array;
+{% endif %}
+
+// Hidden state of the previous time step, shared by all invocations for the same batch
+var<workgroup> hidden: array<Scalar, {{ hidden_size }}>;
+
+{%- include "snippets/recurrence.wgsl" -%}
+
+// Calculates Xt*(W^T) + Ht-1*(R^T) + Wb + Rb for a single element of the indicated gate
+fn gate(gate_index: u32, step: u32, batch: u32, unit: u32) -> Scalar {
+	let row = gate_index * {{ hidden_size }}u + unit;
+	return clip(input_projection(row, step, batch) + recurrent_projection(row));
+}
+
+[[stage(compute), workgroup_size({{ hidden_size }}, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let unit = global_id.x;
+	let batch = global_id.y;
+	let state_index = batch * {{ hidden_size }}u + unit;
+
+	{% if has_initial_h %}
+		hidden[unit] = input_5.data[state_index];
+	{% else %}
+		hidden[unit] = Scalar(0);
+	{% endif %}
+
+	{% if has_initial_c %}
+		var cell = input_6.data[state_index];
+	{% else %}
+		var cell = Scalar(0);
+	{% endif %}
+
+	workgroupBarrier();
+
+	for(var step: u32 = 0u; step < {{ seq_length }}u; step = step + 1u) {
+		let i = sigmoid(gate(0u, step, batch, unit));
+		let o = sigmoid(gate(1u, step, batch, unit));
+		let f = sigmoid(gate(2u, step, batch, unit));
+		let c = tanh(gate(3u, step, batch, unit));
+		cell = f * cell + i * c;
+		let h = o * tanh(cell);
+
+		// All invocations need to have read the previous hidden state before it can be overwritten
+		workgroupBarrier();
+		hidden[unit] = h;
+		{% if bindings.y %}
+			output_0.data[step * {{ batch_size * hidden_size }}u + state_index] = h;
+		{% endif %}
+		workgroupBarrier();
+	}
+
+	{% if bindings.y_h %}
+		output_1.data[state_index] = hidden[unit];
+	{% endif %}
+	{% if bindings.y_c %}
+		output_2.data[state_index] = cell;
+	{% endif %}
+}
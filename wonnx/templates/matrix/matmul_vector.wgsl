@@ -0,0 +1,27 @@
+{%- include "structs.wgsl" -%}
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array;
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Array;
+
+[[group(0), binding(2)]]
+var<storage, write> output_0: Array;
+
+[[stage(compute), workgroup_size({{ workgroup_size_x }})]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ m * n }}u) {
+		// The operands are [M, K] and [K, N] matrices, either of which may be stored as a vector of K elements
+		let row = gidx / {{ n }}u;
+		let column = gidx % {{ n }}u;
+
+		var sum = Scalar(0);
+		for(var i: u32 = 0u; i < {{ k }}u; i = i + 1u) {
+			sum = sum + input_0.data[row * {{ k }}u + i] * input_1.data[i * {{ n }}u + column];
+		}
+		output_0.data[gidx] = sum;
+	}
+}
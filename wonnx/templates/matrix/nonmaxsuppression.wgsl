@@ -0,0 +1,128 @@
+{%- include "structs.wgsl" -%}
+
+// Selected indices are int64, which WGSL does not support; each index is written as two i32 values (low and high word)
+struct Indices {
+	data: [[stride(4)]] array<i32>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array; // boxes [num_batches, spatial_dimension, 4]
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Array; // scores [num_batches, num_classes, spatial_dimension]
+
+[[group(0), binding(2)]]
+var<storage, read_write> output_0: Indices; // selected_indices [num_selected_indices, 3]
+
+// Returns the corners (x1, y1, x2, y2) of the indicated box with x1 <= x2 and y1 <= y2
+fn corners(batch: u32, box_index: u32) -> Vec4 {
+	let base = (batch * {{ spatial_dimension }}u + box_index) * 4u;
+	let a = input_0.data[base];
+	let b = input_0.data[base + 1u];
+	let c = input_0.data[base + 2u];
+	let d = input_0.data[base + 3u];
+
+	{% if center_point_box == 1 %}
+		// [x_center, y_center, width, height]
+		return Vec4(a - c / Scalar(2), b - d / Scalar(2), a + c / Scalar(2), b + d / Scalar(2));
+	{% else %}
+		// [y1, x1, y2, x2] where the corners may be any diagonal pair
+		return Vec4(min(b, d), min(a, c), max(b, d), max(a, c));
+	{% endif %}
+}
+
+fn intersection_over_union(batch: u32, box_a: u32, box_b: u32) -> Scalar {
+	let a = corners(batch, box_a);
+	let b = corners(batch, box_b);
+	let area_a = (a.z - a.x) * (a.w - a.y);
+	let area_b = (b.z - b.x) * (b.w - b.y);
+	let width = max(min(a.z, b.z) - max(a.x, b.x), Scalar(0));
+	let height = max(min(a.w, b.w) - max(a.y, b.y), Scalar(0));
+	let intersection = width * height;
+	let union_area = area_a + area_b - intersection;
+	if(intersection <= Scalar(0) || union_area <= Scalar(0)) {
+		return Scalar(0);
+	}
+	return intersection / union_area;
+}
+
+fn write_index(row: u32, column: u32, value: i32) {
+	let index = (row * 3u + column) * 2u;
+	output_0.data[index] = value;
+	output_0.data[index + 1u] = select(0, -1, value < 0);
+}
+
+// The number of selected boxes is data-dependent, but the output buffer is sized by the output shape declared in the
+// model. The selected [batch, class, box] triples are written to the start of the output, and any remaining rows are
+// filled with -1. The selection is performed greedily (by a single invocation) so the output is ordered by batch and
+// class, and by descending score within each class.
+[[stage(compute), workgroup_size(1)]]
+fn main() {
+	var selected_count = 0u;
+
+	for(var batch = 0u; batch < {{ num_batches }}u; batch = batch + 1u) {
+		for(var class_index = 0u; class_index < {{ num_classes }}u; class_index = class_index + 1u) {
+			let scores_base = (batch * {{ num_classes }}u + class_index) * {{ spatial_dimension }}u;
+			let class_start = selected_count;
+
+			// Boxes are visited in order of descending score (ties broken by box index). The last box that was visited is
+			// kept so that the next one can be found without keeping track of which boxes were visited before.
+			var last_score = Scalar(0);
+			var last_box = -1;
+
+			loop {
+				if(selected_count >= {{ max_selected }}u || selected_count - class_start >= {{ max_output_boxes_per_class }}u) {
+					break;
+				}
+
+				// Find the next box in order of descending score
+				var candidate = -1;
+				var candidate_score = Scalar(0);
+				for(var box_index = 0u; box_index < {{ spatial_dimension }}u; box_index = box_index + 1u) {
+					let score = input_1.data[scores_base + box_index];
+					{% if score_threshold is defined %}
+						if(score <= Scalar({{ score_threshold }})) {
+							continue;
+						}
+					{% endif %}
+
+					let after_last = last_box < 0 || score < last_score || (score == last_score && i32(box_index) > last_box);
+					let before_candidate = candidate < 0 || score > candidate_score;
+					if(after_last && before_candidate) {
+						candidate = i32(box_index);
+						candidate_score = score;
+					}
+				}
+
+				if(candidate < 0) {
+					break;
+				}
+				last_box = candidate;
+				last_score = candidate_score;
+
+				// Suppress the candidate if it overlaps too much with any box selected before for this class
+				var suppressed = false;
+				for(var selected = class_start; selected < selected_count; selected = selected + 1u) {
+					let selected_box = u32(output_0.data[(selected * 3u + 2u) * 2u]);
+					if(intersection_over_union(batch, u32(candidate), selected_box) > Scalar({{ iou_threshold }})) {
+						suppressed = true;
+						break;
+					}
+				}
+
+				if(!suppressed) {
+					write_index(selected_count, 0u, i32(batch));
+					write_index(selected_count, 1u, i32(class_index));
+					write_index(selected_count, 2u, candidate);
+					selected_count = selected_count + 1u;
+				}
+			}
+		}
+	}
+
+	for(var row = selected_count; row < {{ max_selected }}u; row = row + 1u) {
+		write_index(row, 0u, -1);
+		write_index(row, 1u, -1);
+		write_index(row, 2u, -1);
+	}
+}
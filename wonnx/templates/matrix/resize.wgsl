@@ -22,17 +22,52 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 			{% endif %}
 		{%- endfor %}
 
+		{%- if coordinate_transformation_mode == "tf_crop_and_resize" %}
+		{#- Each output coordinate is mapped into the region of interest; coordinates that fall outside of the input are
+		set to the extrapolation value #}
+		var outside = false;
+		var index = 0u;
+		{%- for chunks in i_chunks[0] %}
+			{% set dim = i_shape[0] | nth(n=loop.index0) %}
+			let x_{{ loop.index0 }} = {{ roi_offsets[loop.index0] }} + f32(d_{{ loop.index0 }}) * {{ roi_steps[loop.index0] }};
+			if (x_{{ loop.index0 }} < 0.0 || x_{{ loop.index0 }} > f32({{ dim - 1 }})) {
+				outside = true;
+			}
+			index = index + u32(clamp(i32(floor(x_{{ loop.index0 }})), 0, {{ dim - 1 }})) * {{ chunks }}u;
+		{%- endfor %}
+
+		if (outside) {
+			output_0.data[gidx] = Scalar({{ extrapolation_value }});
+			return;
+		}
+		{%- else %}
 		let index = 
 			{%- for chunks in i_chunks[0] -%}
-				{% set scale = scales | nth(n=loop.index0) %}
+				{%- if not use_sizes -%}
+					{% set scale = scales | nth(n=loop.index0) %}
+				{%- endif -%}
+				{% set dim = i_shape[0] | nth(n=loop.index0) %}
+				{% set output_dim = o_shape[0] | nth(n=loop.index0) %}
 				{%- if not loop.first %}
 					+ 
 				{%- endif -%}
-				u32(floor(
-					(Scalar(d_{{ loop.index0 }}) + Scalar(0.5)) / {{ scale }} - Scalar(0.5) 
-				)) * {{ chunks  }}u 
+				{#- pytorch_half_pixel is the same as half_pixel, except that the source coordinate is zero for axes that
+				are resized to a single element -#}
+				{%- if coordinate_transformation_mode == "pytorch_half_pixel" and output_dim == 1 -%}
+					0u
+				{%- elif use_sizes -%}
+				{#- The scale is output_dim / dim, which is applied using the exact sizes instead -#}
+				u32(clamp(i32(floor(
+					(f32(d_{{ loop.index0 }}) + 0.5) * f32({{ dim }}) / f32({{ output_dim }}) - 0.5 
+				)), 0, {{ dim - 1 }})) * {{ chunks  }}u 
+				{%- else -%}
+				u32(clamp(i32(floor(
+					(f32(d_{{ loop.index0 }}) + 0.5) / {{ scale }} - 0.5 
+				)), 0, {{ dim - 1 }})) * {{ chunks  }}u 
+				{%- endif -%}
 			{%- endfor -%}
 		;
+		{%- endif %}
 
 		output_0.data[gidx] = input_0.data[index];
 	}
@@ -0,0 +1,135 @@
+{%- include "structs.wgsl" -%}
+
+// Batch indices are int64, which WGSL does not support; the low word of each index is read as i32
+struct Indices {
+	data: [[stride(4)]] array<i32>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array; // X [N, C, H, W]
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Array; // rois [num_rois, 4]
+
+[[group(0), binding(2)]]
+var<storage, read> input_2: Indices; // batch_indices [num_rois]
+
+[[group(0), binding(3)]]
+var<storage, write> output_0: Array; // Y [num_rois, C, output_height, output_width]
+
+// Samples the indicated channel of the feature map at (y, x) with bilinear interpolation. Returns the four weighted
+// corner values (which are summed for 'avg' mode, and of which the maximum is taken for 'max' mode).
+fn sample(channel_base: u32, y_in: Scalar, x_in: Scalar) -> Vec4 {
+	let height = {{ i_shape[0][2] }};
+	let width = {{ i_shape[0][3] }};
+
+	// Samples that are outside of the feature map do not contribute
+	if(y_in < Scalar(-1) || y_in > Scalar(height) || x_in < Scalar(-1) || x_in > Scalar(width)) {
+		return Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0));
+	}
+
+	var y = max(y_in, Scalar(0));
+	var x = max(x_in, Scalar(0));
+	var y_low = i32(y);
+	var x_low = i32(x);
+	var y_high = y_low + 1;
+	var x_high = x_low + 1;
+
+	if(y_low >= height - 1) {
+		y_low = height - 1;
+		y_high = height - 1;
+		y = Scalar(y_low);
+	}
+
+	if(x_low >= width - 1) {
+		x_low = width - 1;
+		x_high = width - 1;
+		x = Scalar(x_low);
+	}
+
+	let ly = y - Scalar(y_low);
+	let lx = x - Scalar(x_low);
+	let hy = Scalar(1) - ly;
+	let hx = Scalar(1) - lx;
+
+	let v1 = input_0.data[channel_base + u32(y_low * width + x_low)];
+	let v2 = input_0.data[channel_base + u32(y_low * width + x_high)];
+	let v3 = input_0.data[channel_base + u32(y_high * width + x_low)];
+	let v4 = input_0.data[channel_base + u32(y_high * width + x_high)];
+	return Vec4(hy * hx * v1, hy * lx * v2, ly * hx * v3, ly * lx * v4);
+}
+
+[[stage(compute), workgroup_size(256, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+
+	if (gidx < {{ o_lens[0] }}u) {
+		let roi = gidx / {{ o_chunks[0][0] }}u;
+		let channel = (gidx % {{ o_chunks[0][0] }}u) / {{ o_chunks[0][1] }}u;
+		let bin_y = (gidx % {{ o_chunks[0][1] }}u) / {{ o_chunks[0][2] }}u;
+		let bin_x = gidx % {{ o_chunks[0][2] }}u;
+
+		let batch = u32(input_2.data[roi * 2u]);
+		let channel_base = batch * {{ i_chunks[0][0] }}u + channel * {{ i_chunks[0][1] }}u;
+
+		// ROI coordinates (x1, y1, x2, y2) in the feature map
+		let roi_start_x = input_1.data[roi * 4u] * Scalar({{ spatial_scale }}) - Scalar({{ offset }});
+		let roi_start_y = input_1.data[roi * 4u + 1u] * Scalar({{ spatial_scale }}) - Scalar({{ offset }});
+		let roi_end_x = input_1.data[roi * 4u + 2u] * Scalar({{ spatial_scale }}) - Scalar({{ offset }});
+		let roi_end_y = input_1.data[roi * 4u + 3u] * Scalar({{ spatial_scale }}) - Scalar({{ offset }});
+
+		{% if coordinate_transformation_mode == "output_half_pixel" %}
+			// Malformed ROIs are forced to be of size 1x1
+			let roi_width = max(roi_end_x - roi_start_x, Scalar(1));
+			let roi_height = max(roi_end_y - roi_start_y, Scalar(1));
+		{% else %}
+			let roi_width = roi_end_x - roi_start_x;
+			let roi_height = roi_end_y - roi_start_y;
+		{% endif %}
+
+		let bin_height = roi_height / Scalar({{ output_height }});
+		let bin_width = roi_width / Scalar({{ output_width }});
+
+		// Number of sampling points in each bin
+		{% if sampling_ratio > 0 %}
+			let grid_height = {{ sampling_ratio }}u;
+			let grid_width = {{ sampling_ratio }}u;
+		{% else %}
+			let grid_height = u32(ceil(bin_height));
+			let grid_width = u32(ceil(bin_width));
+		{% endif %}
+		let count = max(Scalar(grid_height * grid_width), Scalar(1));
+
+		var result = Scalar(0);
+		{% if mode == "max" %}
+			var first = true;
+		{% endif %}
+
+		for(var iy = 0u; iy < grid_height; iy = iy + 1u) {
+			let y = roi_start_y + Scalar(bin_y) * bin_height + (Scalar(iy) + Scalar(0.5)) * bin_height / Scalar(grid_height);
+
+			for(var ix = 0u; ix < grid_width; ix = ix + 1u) {
+				let x = roi_start_x + Scalar(bin_x) * bin_width + (Scalar(ix) + Scalar(0.5)) * bin_width / Scalar(grid_width);
+				let values = sample(channel_base, y, x);
+
+				{% if mode == "max" %}
+					let value = max(max(values.x, values.y), max(values.z, values.w));
+					if(first) {
+						result = value;
+						first = false;
+					} else {
+						result = max(result, value);
+					}
+				{% else %}
+					result = result + values.x + values.y + values.z + values.w;
+				{% endif %}
+			}
+		}
+
+		{% if mode == "max" %}
+			output_0.data[gidx] = result;
+		{% else %}
+			output_0.data[gidx] = result / count;
+		{% endif %}
+	}
+}
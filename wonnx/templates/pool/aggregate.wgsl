@@ -6,13 +6,23 @@ var<storage, read> input_0: Array;
 [[group(0), binding(1)]]
 var<storage, write> output_0: Array;
 
+{%- if output_indices %}
+// The indices of the maximum values are 64-bit integers, written as pairs of 32-bit integers (lower bits first)
+struct Indices {
+	data: [[stride(8)]] array<vec2<i32>>;
+};
+
+[[group(0), binding(2)]]
+var<storage, write> output_1: Indices;
+{%- endif %}
+
 [[stage(compute), workgroup_size(256, 1, 1)]]
 fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	let gidx = global_id.x;
 
-	if (gidx < {{ o_lens[0] / 4 }}u) {
-		let batch = gidx / {{ o_chunks[0][0] / 4 }}u; 
-		var rest = gidx % {{ o_chunks[0][0] / 4 }}u; 
+	if (gidx < {{ group_count }}u) {
+		let batch = gidx / {{ channel_groups * o_chunks[0][1] }}u; 
+		var rest = gidx % {{ channel_groups * o_chunks[0][1] }}u; 
 
 		let m = rest / {{ o_chunks[0][1] }}u;
 		rest = rest % {{ o_chunks[0][1] }}u;
@@ -21,6 +31,9 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 		let x = rest % {{ o_chunks[0][2] }}u;
 		
 		var result = Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0));
+		{%- if output_indices %}
+		var indices = vec4<u32>(0u, 0u, 0u, 0u);
+		{%- endif %}
 		
 		let base_index = batch * {{ i_chunks[0][0] }}u + m * {{ i_chunks[0][1] * 4 }}u + y * {{ stride[0] }}u * {{ original_width }}u+ x * {{ stride[1] }}u;
 
@@ -31,30 +44,84 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 				let tmp_x = j * {{ dilation[1] }}u;
 
 				let tmp_index  = base_index + tmp_y * {{ original_width }}u + tmp_x;
+				{%- if channel % 4 == 0 %}
 				let vector = Vec4(
 					input_0.data[tmp_index],
 					input_0.data[tmp_index + {{ i_chunks[0][1] }}u],
 					input_0.data[tmp_index + {{ 2 * i_chunks[0][1] }}u],
 					input_0.data[tmp_index + {{ 3 * i_chunks[0][1] }}u],
 				);
+				{%- else %}
+				// Channels beyond the last channel of the input are read as zero
+				var vector = Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0));
+				for(var index_vec: u32 = 0u; index_vec < 4u; index_vec = index_vec + 1u) {
+					if (m * 4u + index_vec < {{ channel }}u) {
+						vector[index_vec] = input_0.data[tmp_index + index_vec * {{ i_chunks[0][1] }}u];
+					}
+				}
+				{%- endif %}
 				
 				{%- if op_type == "MaxPool" -%}
-					result = max(result, vector);
+					{%- if output_indices %}
+					// Index of the element within its channel
+					{%- if storage_order == 0 %}
+					let spatial_index = (y * {{ stride[0] }}u + tmp_y) * {{ original_width }}u + x * {{ stride[1] }}u + tmp_x;
+					{%- else %}
+					let spatial_index = (y * {{ stride[0] }}u + tmp_y) + (x * {{ stride[1] }}u + tmp_x) * {{ original_height }}u;
+					{%- endif %}
+					{%- endif %}
+					if (i == 0u && j == 0u) {
+						result = vector;
+						{%- if output_indices %}
+						indices = vec4<u32>(spatial_index, spatial_index, spatial_index, spatial_index);
+						{%- endif %}
+					} else {
+						{%- if output_indices %}
+						let is_larger = vector > result;
+						result = select(result, vector, is_larger);
+						indices = select(indices, vec4<u32>(spatial_index, spatial_index, spatial_index, spatial_index), is_larger);
+						{%- else %}
+						result = max(result, vector);
+						{%- endif %}
+					}
 				{%- elif op_type == "AveragePool" -%}
 					result = result + vector;
+				{%- elif op_type == "LpPool" -%}
+					{%- if p == 1 -%}
+						result = result + abs(vector);
+					{%- elif p == 2 -%}
+						result = result + vector * vector;
+					{%- else -%}
+						result = result + pow(abs(vector), Vec4(Scalar({{ p }})));
+					{%- endif -%}
 				{%- endif -%}
 			}
 		}
 
 		{% if op_type == "AveragePool" -%}
 			result = result / {{ kernel_len }}.;
+		{%- elif op_type == "LpPool" -%}
+			{%- if p == 2 -%}
+				result = sqrt(result);
+			{%- elif p != 1 -%}
+				result = pow(result, Vec4(Scalar(1) / Scalar({{ p }})));
+			{%- endif -%}
 		{%- endif %}
 
 		let base_index_2 = batch * {{ o_chunks[0][0] }}u + m * {{ o_chunks[0][1] * 4 }}u + y * {{ width }}u + x;
 
 		for(var index_vec: u32 = 0u; index_vec < 4u; index_vec = index_vec + 1u) {
+			{%- if channel % 4 != 0 %}
+			if (m * 4u + index_vec >= {{ channel }}u) {
+				break;
+			}
+			{%- endif %}
 			let index = base_index_2 + index_vec * {{ o_chunks[0][1] }}u;
 			output_0.data[index] = result[index_vec];
+			{%- if output_indices %}
+			let channel_offset = (batch * {{ channel }}u + m * 4u + index_vec) * {{ i_chunks[0][1] }}u;
+			output_1.data[index] = vec2<i32>(i32(channel_offset + indices[index_vec]), 0);
+			{%- endif %}
 		}
 	}
 }
@@ -0,0 +1,79 @@
+{%- include "structs.wgsl" -%}
+
+// Integer convolution for inputs with any number of spatial dimensions (N x C x D1 x ... x Dn). The (8-bit) inputs are
+// stored as 32-bit integers, from which the zero points are subtracted before they are multiplied and accumulated.
+// Each invocation calculates a single output element.
+[[group({{ bindings.x[0] }}), binding({{ bindings.x[1] }})]]
+var<storage, read> input_0: Array;
+
+[[group({{ bindings.w[0] }}), binding({{ bindings.w[1] }})]]
+var<storage, read> input_1: Array;
+
+{% if has_x_zero_point %}
+[[group({{ bindings.x_zero_point[0] }}), binding({{ bindings.x_zero_point[1] }})]]
+var<storage, read> x_zero_point: Array;
+{% endif %}
+
+{% if has_w_zero_point %}
+[[group({{ bindings.w_zero_point[0] }}), binding({{ bindings.w_zero_point[1] }})]]
+var<storage, read> w_zero_point: Array;
+{% endif %}
+
+[[group({{ bindings.y[0] }}), binding({{ bindings.y[1] }})]]
+var<storage, write> output_0: Array;
+
+[[stage(compute), workgroup_size(256, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+	if (gidx < {{ o_lens[0] }}u) {
+		let batch = gidx / {{ o_chunks[0][0] }}u;
+		let m = (gidx % {{ o_chunks[0][0] }}u) / {{ o_chunks[0][1] }}u;
+
+		// Position of this output element along each of the spatial axes
+		{% for k in kernel_shape -%}
+			let o_{{ loop.index0 }} = (gidx / {{ o_chunks[0] | nth(n=loop.index0 + 2) }}u) % {{ o_shape[0] | nth(n=loop.index0 + 2) }}u;
+		{% endfor %}
+
+		{% if has_x_zero_point -%}
+			let x_zero = x_zero_point.data[0];
+		{%- else -%}
+			let x_zero = 0;
+		{%- endif %}
+		{% if w_zero_point_per_channel -%}
+			let w_zero = w_zero_point.data[m];
+		{%- elif has_w_zero_point -%}
+			let w_zero = w_zero_point.data[0];
+		{%- else -%}
+			let w_zero = 0;
+		{%- endif %}
+
+		var result: i32 = 0;
+
+		let root_index = batch * {{ i_chunks[0][0] }}u;
+		let root_kernel_index = m * {{ kernel_channel_len }}u;
+
+		for(var c: u32 = 0u; c < {{ channel }}u; c = c + 1u) {
+			let base_index = root_index + c * {{ i_chunks[0][1] }}u;
+			let base_kernel_index = root_kernel_index + c * {{ kernel_len }}u;
+
+			for(var k: u32 = 0u; k < {{ kernel_len }}u; k = k + 1u) {
+				var in_bounds = true;
+				var tmp_index = base_index;
+
+				{% for kernel_dim in kernel_shape -%}
+					let k_{{ loop.index0 }} = (k / {{ kernel_chunks | nth(n=loop.index0) }}u) % {{ kernel_dim }}u;
+					let tmp_{{ loop.index0 }} = i32(o_{{ loop.index0 }} * {{ stride | nth(n=loop.index0) }}u + k_{{ loop.index0 }} * {{ dilation | nth(n=loop.index0) }}u) - {{ pad | nth(n=loop.index0) }};
+					in_bounds = in_bounds && tmp_{{ loop.index0 }} >= 0 && tmp_{{ loop.index0 }} < {{ i_shape[0] | nth(n=loop.index0 + 2) }};
+					tmp_index = tmp_index + u32(tmp_{{ loop.index0 }}) * {{ i_chunks[0] | nth(n=loop.index0 + 2) }}u;
+				{% endfor %}
+
+				// Padding is filled with the zero point of x, and therefore does not contribute to the result
+				if (in_bounds) {
+					result = result + (input_0.data[tmp_index] - x_zero) * (input_1.data[base_kernel_index + k] - w_zero);
+				}
+			}
+		}
+
+		output_0.data[gidx] = result;
+	}
+}
@@ -0,0 +1,71 @@
+{%- include "structs.wgsl" -%}
+
+// Convolution for inputs with any number of spatial dimensions (N x C x D1 x ... x Dn). Each invocation calculates a
+// single output element.
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array;
+
+[[group(0), binding(1)]]
+var<storage, read> input_1: Array;
+
+{%- if i_lens | length == 3 -%} // Bias
+	[[group(0), binding(2)]]
+	var<storage, read> input_2: Array;
+
+	[[group(0), binding(3)]]
+	var<storage, write> output_0: Array;
+
+{%- else -%}
+	[[group(0), binding(2)]]
+	var<storage, write> output_0: Array;
+
+{%- endif %}
+
+[[stage(compute), workgroup_size(256, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+	if (gidx < {{ o_lens[0] }}u) {
+		let batch = gidx / {{ o_chunks[0][0] }}u;
+		let m = (gidx % {{ o_chunks[0][0] }}u) / {{ o_chunks[0][1] }}u;
+
+		// Position of this output element along each of the spatial axes
+		{% for k in kernel_shape -%}
+			let o_{{ loop.index0 }} = (gidx / {{ o_chunks[0] | nth(n=loop.index0 + 2) }}u) % {{ o_shape[0] | nth(n=loop.index0 + 2) }}u;
+		{% endfor %}
+
+		var result: Scalar = Scalar(0);
+
+		let root_index = batch * {{ i_chunks[0][0] }}u;
+		let root_kernel_index = m * {{ kernel_channel_len }}u;
+
+		for(var c: u32 = 0u; c < {{ channel }}u; c = c + 1u) {
+			let base_index = root_index + c * {{ i_chunks[0][1] }}u;
+			let base_kernel_index = root_kernel_index + c * {{ kernel_len }}u;
+
+			for(var k: u32 = 0u; k < {{ kernel_len }}u; k = k + 1u) {
+				var in_bounds = true;
+				var tmp_index = base_index;
+
+				{% for kernel_dim in kernel_shape -%}
+					let k_{{ loop.index0 }} = (k / {{ kernel_chunks | nth(n=loop.index0) }}u) % {{ kernel_dim }}u;
+					let tmp_{{ loop.index0 }} = i32(o_{{ loop.index0 }} * {{ stride | nth(n=loop.index0) }}u + k_{{ loop.index0 }} * {{ dilation | nth(n=loop.index0) }}u) - {{ pad | nth(n=loop.index0) }};
+					in_bounds = in_bounds && tmp_{{ loop.index0 }} >= 0 && tmp_{{ loop.index0 }} < {{ i_shape[0] | nth(n=loop.index0 + 2) }};
+					tmp_index = tmp_index + u32(tmp_{{ loop.index0 }}) * {{ i_chunks[0] | nth(n=loop.index0 + 2) }}u;
+				{% endfor %}
+
+				if (in_bounds) {
+					result = input_0.data[tmp_index] * input_1.data[base_kernel_index + k] + result;
+				}
+			}
+		}
+
+		{%- if i_lens | length == 3 -%}
+			result = result + input_2.data[m];
+		{%- endif -%}
+
+		{% set activation_input = "result" %}
+		{% set activation_output = "output_0.data[gidx]" %}
+		{% set activation_type = op_type | replace(from="Conv", to="") %}
+		{%- include "snippets/activation_scalar.wgsl" -%}
+	}
+}
@@ -0,0 +1,33 @@
+{%- include "structs.wgsl" -%}
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array;
+
+[[group(0), binding(1)]]
+var<storage, write> output_0: Array;
+
+[[stage(compute), workgroup_size(256, 1, 1)]]
+fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
+	let gidx = global_id.x;
+	if (gidx < {{ o_lens[0] }}u) {
+		// Each row of the output corresponds to a channel and position in the kernel, each column to an output pixel
+		let row = gidx / {{ o_shape[0][1] }}u;
+		let column = gidx % {{ o_shape[0][1] }}u;
+
+		let c = row / {{ kernel_len }}u;
+		let i = (row % {{ kernel_len }}u) / {{ kernel_shape[1] }}u;
+		let j = row % {{ kernel_shape[1] }}u;
+		let y = column / {{ width }}u;
+		let x = column % {{ width }}u;
+
+		// Positions in the padding area underflow, and are therefore also outside the bounds of the input
+		let tmp_y = y * {{ stride[0] }}u + i * {{ dilation[0] }}u - {{ pad[0] }}u;
+		let tmp_x = x * {{ stride[1] }}u + j * {{ dilation[1] }}u - {{ pad[1] }}u;
+
+		if (tmp_y < {{ original_height }}u && tmp_x < {{ original_width }}u) {
+			output_0.data[gidx] = input_0.data[c * {{ i_chunks[0][1] }}u + tmp_y * {{ original_width }}u + tmp_x];
+		} else {
+			output_0.data[gidx] = Scalar(0);
+		}
+	}
+}
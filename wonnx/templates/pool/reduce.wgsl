@@ -1,69 +1,106 @@
 {% include "structs.wgsl" %}
 
+{% if int64 %}
+// Int64 values are stored as two i32 values (low and high word). Only the low word is used, so values outside of the
+// i32 range are not supported.
+struct Int64Array {
+	data: [[stride(8)]] array<vec2<i32>>;
+};
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Int64Array;
+
+[[group(0), binding(1)]]
+var<storage, write> output_0: Int64Array;
+{% else %}
 [[group(0), binding(0)]]
 var<storage, read> input_0: Array;
 
 [[group(0), binding(1)]]
 var<storage, write> output_0: Array;
+{% endif %}
 
 [[stage(compute), workgroup_size({{ workgroup_size_x }}, 1, 1)]]
 fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 	let gidx = global_id.x;
 
 	{# We will be invoked once for each scalar in the output (output_0.data[gidx]) which represents one reduce operation.
-	Find out which output cell we are calculating (d_X refers to the index of dimension X in the input) #}
+	Find the input index of the first element that is reduced into it; each element of kept_axes is [output chunk,
+	dimension, input chunk] #}
 	if (gidx < {{ o_lens[0] }}u) {
-		var rest = gidx;
+		let base = 0u
+			{%- for axis in kept_axes %}
+				+ ((gidx / {{ axis[0] }}u) % {{ axis[1] }}u) * {{ axis[2] }}u
+			{%- endfor %};
 
-		{#- chunks_with_dims_preserved is the input dims, but the reduced axes have their size set to 1, e.g. an input 
-		tensor of dimension [3,3,2] with axes=[1] will have chunks_with_dims_preserved=[3,1,2] (This is equal to the 
-		shape of the output when keepdims = true) -#}
+		{#- Now iterate all values that are reduced into this output (each element of reduce_loops is [length, input
+		chunk]) and reduce them. Note, starting value may not always be zero. For ReduceMin/Max we should initialize as
+		NaN and keep a flag to check if we have seen at least one element -#}
 
-		{% for chunks in chunks_with_dims_preserved %}
-			{% if loop.last %}
-				{% if not loop.index0 in axes %}
-					let d_{{ loop.index0 }}: u32 = rest; 
-				{% endif %}
-			{% else %}
-				{% if not loop.index0 in axes %}
-					let d_{{ loop.index0 }}: u32 = rest / {{ chunks }}u; 
-				{% endif %}
-				{% if chunks != 1 %}
-				rest = gidx % {{ chunks }}u; 
-				{% endif %}
-			{% endif %}
-		{% endfor %}
+		{#- For numerical stability, ReduceLogSumExp and ReduceL2 first determine the largest (absolute) value, which is used
+		to scale the values before accumulating them (so that the exponent or square does not overflow) -#}
+		{% if op_type == "ReduceLogSumExp" or op_type == "ReduceL2" %}
+			var max_val = Scalar(0);
+			var is_first = true;
+
+			{% for reduce_loop in reduce_loops %}
+				for(var r_{{ loop.index0 }} = 0u; r_{{ loop.index0 }} < {{ reduce_loop[0] }}u; r_{{ loop.index0 }} = r_{{ loop.index0 }} + 1u) {
+			{% endfor %}
 
-		{#- At this point we have d_* variables set to the indexes on the fixed (non reduced) axes. In the above example,
-		the base indexes [d_0,d_1,d_2] will be [0,0,0], [0,0,1], [0,0,2], [1,0,0], [1,0,1], ..  
-		
-		Now for each reduced axis, iterate all values and reduce. Note, starting value may not always be zero. For 
-		ReduceMin/Max we should initialize as NaN and keep a flag to check if we have seen at least one element -#}
+					let input_val = input_0.data[base
+						{%- for reduce_loop in reduce_loops %} + r_{{ loop.index0 }} * {{ reduce_loop[1] }}u{% endfor %}];
+
+					{% if op_type == "ReduceL2" %}
+						let scale_val = abs(input_val);
+					{% else %}
+						let scale_val = input_val;
+					{% endif %}
+
+					if(is_first || scale_val > max_val) {
+						max_val = scale_val;
+						is_first = false;
+					}
+
+			{% for reduce_loop in reduce_loops %}
+				}
+			{% endfor %}
+
+			{% if op_type == "ReduceL2" %}
+				// Prevent division by zero when all values are zero
+				if(max_val == Scalar(0)) {
+					max_val = Scalar(1);
+				}
+			{% endif %}
+		{% endif %}
 
 		var accumulator = {% if op_type == "ReduceProd" %} Scalar(1) {% else %} Scalar(0) {% endif %}; 
 		var count = 0u;
+		{%- if compensated_summation %}
+			// Low-order bits of the terms that were lost when adding them to the accumulator (Kahan summation)
+			var compensation = Scalar(0);
+		{%- endif %}
 
-		{% for reducing_axis in axes %}
-			for(var d_{{reducing_axis}} = 0u; d_{{reducing_axis}} < {{i_shape[0][reducing_axis]}}u; d_{{reducing_axis}} = d_{{reducing_axis}} + 1u) {
+		{% for reduce_loop in reduce_loops %}
+			for(var r_{{ loop.index0 }} = 0u; r_{{ loop.index0 }} < {{ reduce_loop[0] }}u; r_{{ loop.index0 }} = r_{{ loop.index0 }} + 1u) {
 		{% endfor %}
 
-				let input_val = input_0.data[
-					{% for _axis in i_shape[0] %}
-						(d_{{loop.index0}} * {{i_chunks[0][loop.index0]}}u) {% if not loop.last %} + {% endif %}
-					{% endfor %}
-				];
+				let input_val = input_0.data[base
+					{%- for reduce_loop in reduce_loops %} + r_{{ loop.index0 }} * {{ reduce_loop[1] }}u{% endfor %}]{% if int64 %}.x{% endif %};
 
-				{% if op_type == "ReduceMean" or op_type == "ReduceSum" %}
-					accumulator = accumulator + input_val;
+				{% if op_type == "ReduceMean" or op_type == "ReduceSum" or op_type == "ReduceLogSum" %}
+					let term = input_val;
 				{% elif op_type == "ReduceL1" %}
-					accumulator = accumulator + abs(input_val);
-				{% elif op_type == "ReduceL2" or op_type == "ReduceSumSquare" %}
-					accumulator = accumulator + (input_val * input_val);
-				{% elif op_type == "ReduceLogSum" %}
-					accumulator = accumulator + input_val;
+					let term = abs(input_val);
+				{% elif op_type == "ReduceL2" %}
+					let scaled_val = input_val / max_val;
+					let term = scaled_val * scaled_val;
+				{% elif op_type == "ReduceSumSquare" %}
+					let term = input_val * input_val;
 				{% elif op_type == "ReduceLogSumExp" %}
-					accumulator = accumulator + exp(input_val);
-				{% elif op_type == "ReduceProd" %}
+					let term = exp(input_val - max_val);
+				{% endif %}
+
+				{% if op_type == "ReduceProd" %}
 					accumulator = accumulator * input_val;
 				{% elif op_type == "ReduceMin" %}
 					if(count == 0u) {
@@ -79,11 +116,18 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 					else if(accumulator < input_val) {
 						accumulator = input_val;
 					}
+				{% elif compensated_summation %}
+					let corrected_term = term - compensation;
+					let sum = accumulator + corrected_term;
+					compensation = (sum - accumulator) - corrected_term;
+					accumulator = sum;
+				{% else %}
+					accumulator = accumulator + term;
 				{% endif %}
 
 				count = count + 1u;
 
-		{% for reducing_axis in axes %}
+		{% for reduce_loop in reduce_loops %}
 			}
 		{% endfor %}
 
@@ -91,11 +135,17 @@ fn main([[builtin(global_invocation_id)]] global_id: vec3<u32>) {
 		{% if op_type == "ReduceMean" %}
 			accumulator = accumulator / Scalar(count);
 		{% elif op_type == "ReduceL2" %}
-			accumulator = sqrt(accumulator);
-		{% elif op_type == "ReduceLogSum" or op_type == "ReduceLogSumExp" %}
+			accumulator = max_val * sqrt(accumulator);
+		{% elif op_type == "ReduceLogSum" %}
 			accumulator = log(accumulator);
+		{% elif op_type == "ReduceLogSumExp" %}
+			accumulator = max_val + log(accumulator);
 		{% endif %}
 
-		output_0.data[gidx] = accumulator;
+		{% if int64 %}
+			output_0.data[gidx] = vec2<i32>(accumulator, select(0, -1, accumulator < 0));
+		{% else %}
+			output_0.data[gidx] = accumulator;
+		{% endif %}
 	}
 }
\ No newline at end of file
@@ -0,0 +1,87 @@
+{% include "structs.wgsl" %}
+
+[[group(0), binding(0)]]
+var<storage, read> input_0: Array;
+
+[[group(0), binding(1)]]
+var<storage, write> output_0: Array;
+
+// Partial results of the invocations in the workgroup
+var<workgroup> partial: array<Scalar, {{ workgroup_size_x }}>;
+{%- if compensated_summation %}
+
+// Compensation terms of the partial results (the exact partial result is partial - partial_compensation)
+var<workgroup> partial_compensation: array<Scalar, {{ workgroup_size_x }}>;
+{%- endif %}
+
+[[stage(compute), workgroup_size({{ workgroup_size_x }}, 1, 1)]]
+fn main([[builtin(workgroup_id)]] workgroup_id: vec3<u32>, [[builtin(local_invocation_id)]] local_id: vec3<u32>) {
+	{# Each workgroup calculates one scalar of the output. Find the input index of the first element that is reduced
+	into it; each element of kept_axes is [output chunk, dimension, input chunk] #}
+	let gidx = workgroup_id.x;
+	let base = 0u
+		{%- for axis in kept_axes %}
+			+ ((gidx / {{ axis[0] }}u) % {{ axis[1] }}u) * {{ axis[2] }}u
+		{%- endfor %};
+
+	{# Each invocation reduces every workgroup_size_x'th element; each element of reduced_axes is [chunk, dimension,
+	input chunk] #}
+	var accumulator = Scalar(0);
+	{%- if compensated_summation %}
+		// Low-order bits of the terms that were lost when adding them to the accumulator (Kahan summation)
+		var compensation = Scalar(0);
+	{%- endif %}
+	for(var index = local_id.x; index < {{ reduced_length }}u; index = index + {{ workgroup_size_x }}u) {
+		let input_index = base
+			{%- for axis in reduced_axes %}
+				+ ((index / {{ axis[0] }}u) % {{ axis[1] }}u) * {{ axis[2] }}u
+			{%- endfor %};
+		{%- if compensated_summation %}
+			let corrected_term = input_0.data[input_index] - compensation;
+			let sum = accumulator + corrected_term;
+			compensation = (sum - accumulator) - corrected_term;
+			accumulator = sum;
+		{%- else %}
+			accumulator = accumulator + input_0.data[input_index];
+		{%- endif %}
+	}
+	partial[local_id.x] = accumulator;
+	{%- if compensated_summation %}
+		partial_compensation[local_id.x] = compensation;
+	{%- endif %}
+	workgroupBarrier();
+
+	// Combine the partial results, halving the number of invocations that are involved in each step
+	for(var stride = {{ workgroup_size_x / 2 | int }}u; stride > 0u; stride = stride / 2u) {
+		if (local_id.x < stride) {
+			let a = partial[local_id.x];
+			let b = partial[local_id.x + stride];
+			{%- if compensated_summation %}
+				// The rounding error of a + b is determined exactly (TwoSum) and added to the compensation terms, so
+				// that the low-order bits are not lost when combining partial results of different magnitude
+				let sum = a + b;
+				let b_virtual = sum - a;
+				let error = (a - (sum - b_virtual)) + (b - b_virtual);
+				partial[local_id.x] = sum;
+				partial_compensation[local_id.x] = (partial_compensation[local_id.x]
+					+ partial_compensation[local_id.x + stride]) - error;
+			{%- else %}
+				partial[local_id.x] = a + b;
+			{%- endif %}
+		}
+		workgroupBarrier();
+	}
+
+	if (local_id.x == 0u) {
+		{%- if compensated_summation %}
+			let result = partial[0] - partial_compensation[0];
+		{%- else %}
+			let result = partial[0];
+		{%- endif %}
+		{% if op_type == "ReduceMean" %}
+			output_0.data[gidx] = result / Scalar({{ reduced_length }});
+		{% else %}
+			output_0.data[gidx] = result;
+		{% endif %}
+	}
+}
@@ -1,6 +1,16 @@
 {%- if activation_type == "Relu" -%}
 	{{ activation_output }} = max({{ activation_input }}, Scalar(0));
 
+{%- elif activation_type == "Sigmoid" and approximate_activations -%}
+	{
+		// sigmoid(x) = (1 + tanh(x / 2)) / 2, where tanh is approximated by a rational function (see map.wgsl)
+		let t = clamp({{ activation_input }} / Scalar(2), Scalar(-9), Scalar(9));
+		let t2 = t * t;
+		let p = t * (Scalar(135135) + t2 * (Scalar(17325) + t2 * (Scalar(378) + t2)));
+		let q = Scalar(135135) + t2 * (Scalar(62370) + t2 * (Scalar(3150) + t2 * Scalar(28)));
+		{{ activation_output }} = Scalar(0.5) + Scalar(0.5) * clamp(p / q, Scalar(-1), Scalar(1));
+	}
+
 {%- elif activation_type == "Sigmoid" -%}
 	{{ activation_output }} = Scalar(1) / (Scalar(1) + exp(-{{ activation_input }}));
 
@@ -9,37 +19,76 @@
 	{{ activation_output }} = input / (Scalar(1) + abs(input));
 
 {%- elif activation_type == "Softplus" -%}
-	{{ activation_output }} = log(Scalar(1) + exp({{ activation_input }}));
+	{
+		// log(1 + exp(x)) = max(x, 0) + log(1 + exp(-|x|)), which does not overflow for large x
+		let input_vec = {{ activation_input }};
+		{{ activation_output }} = max(input_vec, Scalar(0)) + log(Scalar(1) + exp(-abs(input_vec)));
+	}
 
 {%- elif activation_type == "Clip" -%}
-	let min_clip = input_1.data[0u];
-	let max_clip = input_2.data[0u];
+	{% set clamp_type = "Scalar" %}
+	{% set clamp_input = activation_input %}
+	{{ activation_output }} = {% include "snippets/clamp.wgsl" %};
 
-	{{ activation_output }} = clamp(
-		{{ activation_input }}, 
-		Vec4(min_clip, min_clip, min_clip, min_clip),
-		Vec4(max_clip, max_clip, max_clip, max_clip),
-	);
+{%- elif activation_type == "HardSigmoid" -%}
+	{
+		// hard_sigmoid(x) = max(0, min(1, alpha * x + beta))
+		let hard_sigmoid_input = Scalar({{ alpha }}) * {{ activation_input }} + Scalar({{ beta }});
+		{% set clamp_type = "Scalar" %}
+		{% set clamp_input = "hard_sigmoid_input" %}
+		{{ activation_output }} = {% include "snippets/clamp.wgsl" %};
+	}
 
-{%- elif activation_type == "Celu" -%}
-	let input_vec = {{ activation_input }};
+{%- elif activation_type == "HardSwish" -%}
+	{
+		// hard_swish(x) = x * hard_sigmoid(x), where alpha = 1/6 and beta = 0.5
+		let input_vec = {{ activation_input }};
+		let hard_swish_gate = Scalar({{ alpha }}) * input_vec + Scalar({{ beta }});
+		{% set clamp_type = "Scalar" %}
+		{% set clamp_input = "hard_swish_gate" %}
+		{{ activation_output }} = input_vec * {% include "snippets/clamp.wgsl" %};
+	}
 
-	{{ activation_output }} = max(Scalar(0), 
-			input_vec
-		) + min(
-			Scalar(0), 
-			{{ alpha }} * (exp(input_vec / {{ alpha }}) - Scalar(1))
-		);
+{%- elif activation_type == "Celu" -%}
+	{
+		// celu(x) = max(0, x) + min(0, alpha * (exp(x / alpha) - 1))
+		let input_vec = {{ activation_input }};
+		{{ activation_output }} = max(Scalar(0), input_vec)
+			+ min(Scalar(0), Scalar({{ alpha }}) * (exp(input_vec / Scalar({{ alpha }})) - Scalar(1)));
+	}
 
 {%- elif activation_type == "Elu" -%}
-		let input_vec = {{ activation_input }}; 
-		{{ activation_output }} = max(
-			Scalar(0), 
-			input_vec
-		) + min(
-			Scalar(0), 
-			{{ alpha }} * (exp(input_vec) - Scalar(1))
+	{
+		// elu(x) = x if x > 0, alpha * (exp(x) - 1) otherwise
+		let input_vec = {{ activation_input }};
+		{{ activation_output }} = select(
+			Scalar({{ alpha }}) * (exp(input_vec) - Scalar(1)),
+			input_vec,
+			input_vec > Scalar(0)
 		);
+	}
+
+{%- elif activation_type == "Mish" -%}
+	let input_vec = {{ activation_input }}; 
+	{{ activation_output }} = input_vec * tanh(log(Scalar(1) + exp(input_vec)));
+
+{%- elif activation_type == "Swish" -%}
+	{
+		// swish(x) = x * sigmoid(x) (also known as SiLU)
+		let input_vec = {{ activation_input }};
+		{{ activation_output }} = input_vec / (Scalar(1) + exp(-input_vec));
+	}
+
+{%- elif activation_type == "LeakyRelu" -%}
+	{{ activation_output }} = max({{ activation_input }}, Scalar(0))
+	                         + min(Scalar({{ alpha }}) * {{ activation_input }}, Scalar(0));
+
+{%- elif activation_type == "Affine" -%}
+	{{ activation_output }} = Scalar({{ alpha }}) * {{ activation_input }} + Scalar({{ beta }});
+
+{%- elif activation_type == "ThresholdedRelu" -%}
+	let input_vec = {{ activation_input }};
+	{{ activation_output }} = select(Scalar(0), input_vec, input_vec > Scalar({{ alpha }}));
 
 {%- elif activation_output != activation_input -%}
 	{{ activation_output }} = {{ activation_input }};
@@ -1,6 +1,16 @@
 {%- if activation_type == "Relu"-%}
 	{{ activation_output }} = max({{ activation_input }}, Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0)));
 
+{%- elif activation_type == "Sigmoid" and approximate_activations -%}
+	{
+		// sigmoid(x) = (1 + tanh(x / 2)) / 2, where tanh is approximated by a rational function (see map.wgsl)
+		let t = clamp({{ activation_input }} / Vec4(Scalar(2)), Vec4(Scalar(-9)), Vec4(Scalar(9)));
+		let t2 = t * t;
+		let p = t * (Vec4(Scalar(135135)) + t2 * (Vec4(Scalar(17325)) + t2 * (Vec4(Scalar(378)) + t2)));
+		let q = Vec4(Scalar(135135)) + t2 * (Vec4(Scalar(62370)) + t2 * (Vec4(Scalar(3150)) + t2 * Vec4(Scalar(28))));
+		{{ activation_output }} = Vec4(Scalar(0.5)) + Vec4(Scalar(0.5)) * clamp(p / q, Vec4(Scalar(-1)), Vec4(Scalar(1)));
+	}
+
 {%- elif activation_type == "Sigmoid" -%}
 	{{ activation_output }} = Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1)) / (Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1)) + exp(-{{ activation_input }}));
 
@@ -9,45 +19,82 @@
 	{{ activation_output }} = input / (Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1)) + abs(input));
 
 {%- elif activation_type == "Softplus" -%}
-	{{ activation_output }} = log(Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1)) + exp({{ activation_input }}));
+	{
+		// log(1 + exp(x)) = max(x, 0) + log(1 + exp(-|x|)), which does not overflow for large x
+		let input_vec = {{ activation_input }};
+		{{ activation_output }} = max(input_vec, Vec4(Scalar(0))) + log(Vec4(Scalar(1)) + exp(-abs(input_vec)));
+	}
 
 {%- elif activation_type == "Clip" -%}
-	let min_clip = input_1.data[0u];
-	let max_clip = input_2.data[0u];
-	{{ activation_output }} = clamp(
-		{{ activation_input }}, 
-		Vec4(min_clip, min_clip, min_clip, min_clip),
-		Vec4(max_clip, max_clip, max_clip, max_clip),
-	);
+	{% set clamp_type = "Vec4" %}
+	{% set clamp_input = activation_input %}
+	{{ activation_output }} = {% include "snippets/clamp.wgsl" %};
+
+{%- elif activation_type == "HardSigmoid" -%}
+	{
+		// hard_sigmoid(x) = max(0, min(1, alpha * x + beta))
+		let hard_sigmoid_input = Vec4(Scalar({{ alpha }})) * {{ activation_input }} + Vec4(Scalar({{ beta }}));
+		{% set clamp_type = "Vec4" %}
+		{% set clamp_input = "hard_sigmoid_input" %}
+		{{ activation_output }} = {% include "snippets/clamp.wgsl" %};
+	}
+
+{%- elif activation_type == "HardSwish" -%}
+	{
+		// hard_swish(x) = x * hard_sigmoid(x), where alpha = 1/6 and beta = 0.5
+		let input_vec = {{ activation_input }};
+		let hard_swish_gate = Vec4(Scalar({{ alpha }})) * input_vec + Vec4(Scalar({{ beta }}));
+		{% set clamp_type = "Vec4" %}
+		{% set clamp_input = "hard_swish_gate" %}
+		{{ activation_output }} = input_vec * {% include "snippets/clamp.wgsl" %};
+	}
 
 {%- elif activation_type == "Celu" -%}
-	let input_vec = {{ activation_input }}; 
-	{{ activation_output }} = max(
-			Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0)), 
-			input_vec
-		) + min(
-			Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0)), 
-			{{ alpha }} * (exp(input_vec / {{ alpha }}) - Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1)))
-		);
+	{
+		// celu(x) = max(0, x) + min(0, alpha * (exp(x / alpha) - 1))
+		let input_vec = {{ activation_input }};
+		let alpha_vec = Vec4(Scalar({{ alpha }}));
+		{{ activation_output }} = max(Vec4(Scalar(0)), input_vec)
+			+ min(Vec4(Scalar(0)), alpha_vec * (exp(input_vec / alpha_vec) - Vec4(Scalar(1))));
+	}
 
 {%- elif activation_type == "Elu" -%}
-		let input_vec = {{ activation_input }}; 
-		{{ activation_output }} = max(
-			Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0)), 
-			input_vec
-		) + min(
-			Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0)), 
-			{{ alpha }} * (exp(input_vec) - Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1)))
+	{
+		// elu(x) = x if x > 0, alpha * (exp(x) - 1) otherwise
+		let input_vec = {{ activation_input }};
+		{{ activation_output }} = select(
+			Vec4(Scalar({{ alpha }})) * (exp(input_vec) - Vec4(Scalar(1))),
+			input_vec,
+			input_vec > Vec4(Scalar(0))
 		);
+	}
 
 {%- elif activation_type == "Mish" -%}
 	let input_vec = {{ activation_input }}; 
 	{{ activation_output }} = input_vec * tanh(log(Vec4(Scalar(1), Scalar(1), Scalar(1), Scalar(1)) + exp(input_vec)));
 
+{%- elif activation_type == "Swish" -%}
+	{
+		// swish(x) = x * sigmoid(x) (also known as SiLU)
+		let input_vec = {{ activation_input }};
+		{{ activation_output }} = input_vec / (Vec4(Scalar(1)) + exp(-input_vec));
+	}
+
 {%- elif activation_type == "LeakyRelu" -%}
 	{{ activation_output }} = max({{ activation_input }}, Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0)))
 	                         + min(Scalar({{ alpha }}) * {{ activation_input }}, Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0)));
 
+{%- elif activation_type == "Affine" -%}
+	{{ activation_output }} = Vec4(Scalar({{ alpha }})) * {{ activation_input }} + Vec4(Scalar({{ beta }}));
+
+{%- elif activation_type == "ThresholdedRelu" -%}
+	let input_vec = {{ activation_input }};
+	{{ activation_output }} = select(
+		Vec4(Scalar(0), Scalar(0), Scalar(0), Scalar(0)),
+		input_vec,
+		input_vec > Vec4(Scalar({{ alpha }}), Scalar({{ alpha }}), Scalar({{ alpha }}), Scalar({{ alpha }}))
+	);
+
 {%- elif activation_output != activation_input -%}
 	{{ activation_output }} = {{ activation_input }};
 
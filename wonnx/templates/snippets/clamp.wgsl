@@ -0,0 +1,3 @@
+{#- Clamps `clamp_input` to the range [clamp_min, clamp_max] passed by the compiler. `clamp_type` is either Scalar or
+Vec4, depending on the type of the input. -#}
+clamp({{ clamp_input }}, {{ clamp_type }}(Scalar({{ clamp_min }})), {{ clamp_type }}(Scalar({{ clamp_max }})))
\ No newline at end of file
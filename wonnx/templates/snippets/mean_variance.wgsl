@@ -0,0 +1,14 @@
+{#- Computes the mean and (population) variance of `stats_count` values using Welford's algorithm, which remains
+accurate when the values are large compared to their spread. The values are found at positions 0, `stats_stride`,
+2 * `stats_stride`, etc.: `stats_value` is an expression for the value at position `stats_position`. Declares `mean` and
+`variance`, which hold the result. #}
+		var mean = Scalar(0);
+		var stats_squared_deviations = Scalar(0);
+		for(var stats_index: u32 = 0u; stats_index < {{ stats_count }}u; stats_index = stats_index + 1u) {
+			let stats_position = stats_index * {{ stats_stride }}u;
+			let stats_element = {{ stats_value }};
+			let stats_delta = stats_element - mean;
+			mean = mean + stats_delta / Scalar(stats_index + 1u);
+			stats_squared_deviations = stats_squared_deviations + stats_delta * (stats_element - mean);
+		}
+		let variance = stats_squared_deviations / Scalar({{ stats_count }});
@@ -0,0 +1,43 @@
+// Helpers shared by the recurrent operators (LSTM, GRU). These expect X, W, R and (optionally) B to be bound as input_0
+// to input_3 and the hidden state of the previous time step to be available in the workgroup variable 'hidden'.
+fn sigmoid(x: Scalar) -> Scalar {
+	return Scalar(1) / (Scalar(1) + exp(-x));
+}
+
+// Calculates Xt*(W^T) + Wb for a single row of the (stacked) gate weights
+fn input_projection(row: u32, step: u32, batch: u32) -> Scalar {
+	let x_base = (step * {{ batch_size }}u + batch) * {{ input_size }}u;
+	var sum = Scalar(0);
+
+	for(var k: u32 = 0u; k < {{ input_size }}u; k = k + 1u) {
+		sum = sum + input_0.data[x_base + k] * input_1.data[row * {{ input_size }}u + k];
+	}
+
+	{% if has_bias %}
+		sum = sum + input_3.data[row];
+	{% endif %}
+	return sum;
+}
+
+// Calculates Ht-1*(R^T) + Rb for a single row of the (stacked) gate weights
+fn recurrent_projection(row: u32) -> Scalar {
+	var sum = Scalar(0);
+
+	for(var k: u32 = 0u; k < {{ hidden_size }}u; k = k + 1u) {
+		sum = sum + hidden[k] * input_2.data[row * {{ hidden_size }}u + k];
+	}
+
+	{% if has_bias %}
+		sum = sum + input_3.data[{{ num_gates * hidden_size }}u + row];
+	{% endif %}
+	return sum;
+}
+
+// Clips the input of a gate activation function to [-clip, clip] when requested
+fn clip(x: Scalar) -> Scalar {
+	{% if clip %}
+		return clamp(x, Scalar(-{{ clip }}), Scalar({{ clip }}));
+	{% else %}
+		return x;
+	{% endif %}
+}
@@ -0,0 +1,464 @@
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, initializer, model, node, tensor};
+
+mod common;
+
+#[test]
+fn test_thresholded_relu() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let data: &[f32] = &[1.0, 2.0, 3.0];
+    let shape = vec![3];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> ThresholdedRelu -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "thresholded_relu",
+            "ThresholdedRelu",
+            vec![attribute("alpha", 2.0)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &[0.0, 0.0, 3.0]);
+}
+
+#[test]
+fn test_in_place_activation_chain() {
+    let n: usize = 16;
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..n).map(|x| (x as f32) - 8.0).collect();
+    let shape = vec![n as i64];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Relu -> A -> Sigmoid -> B -> Tanh -> Y. Sigmoid can write its output to the buffer of A (which is not
+    // used by any other node), so the four tensors only need three buffers.
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("A", &shape), tensor("B", &shape)],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "relu", "Relu", vec![]),
+            node(vec!["A"], vec!["B"], "sigmoid", "Sigmoid", vec![]),
+            node(vec!["B"], vec!["Y"], "tanh", "Tanh", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    assert_eq!(session.buffer_count(), 3);
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    let expected: Vec<f32> = data
+        .iter()
+        .map(|x| (1.0 / (1.0 + (-x.max(0.0)).exp())).tanh())
+        .collect();
+    for (actual, expected) in result["Y"].iter().zip(expected.iter()) {
+        assert!((actual - expected).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_relu_unaligned_length() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let x: &[f32] = &[-3.0, 1.0, -0.5, 2.0, 4.0, -7.0, 0.25];
+    let z: &[f32] = &[-1.0, f32::NAN, 5.0, -6.0, 8.0];
+    input_data.insert("X".to_string(), x.into());
+    input_data.insert("Z".to_string(), z.into());
+
+    // Model: X -> Relu -> A, (A, Z) -> Concat -> Y. The length of A is not a multiple of four, so the last vector that
+    // Relu works on is only partially filled (that the shader does not write past the end of A is checked in
+    // tests/compile.rs, as A has a buffer of its own here).
+    let model = model(graph(
+        vec![tensor("X", &[7]), tensor("Z", &[5])],
+        vec![tensor("Y", &[12])],
+        vec![tensor("A", &[7])],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "relu", "Relu", vec![]),
+            node(
+                vec!["A", "Z"],
+                vec!["Y"],
+                "concat",
+                "Concat",
+                vec![attribute("axis", 0)],
+            ),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    let y = &result["Y"];
+    assert_eq!(&y[..7], &[0.0, 1.0, 0.0, 2.0, 4.0, 0.0, 0.25]);
+    assert_eq!(y[7], -1.0);
+    assert!(y[8].is_nan());
+    assert_eq!(&y[9..], &[5.0, -6.0, 8.0]);
+}
+
+#[test]
+fn test_clip_legacy_attributes() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let data: &[f32] = &[-2.0, 0.5, 3.0, 6.0, 9.0, -0.5, 5.5, 12.0];
+    let shape = vec![8];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Clip(min=0, max=6) -> Y (ReLU6). Before opset 11, min and max are attributes.
+    let mut model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "clip",
+            "Clip",
+            vec![attribute("min", 0.0), attribute("max", 6.0)],
+        )],
+    ));
+    model.mut_opset_import()[0].set_version(6);
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(
+        result["Y"].as_slice(),
+        &[0.0, 0.5, 3.0, 6.0, 6.0, 0.0, 5.5, 6.0],
+    );
+}
+
+#[test]
+fn test_clip_inputs() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let data: &[f32] = &[-2.0, 0.5, 3.0, 6.0, 9.0, -0.5, 5.5, 12.0];
+    let shape = vec![8];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Clip(X, min, max) -> Y. Since opset 11, min and max are inputs.
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![initializer("min", vec![0.0]), initializer("max", vec![6.0])],
+        vec![node(
+            vec!["X", "min", "max"],
+            vec!["Y"],
+            "clip",
+            "Clip",
+            vec![],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(
+        result["Y"].as_slice(),
+        &[0.0, 0.5, 3.0, 6.0, 6.0, 0.0, 5.5, 6.0],
+    );
+}
+
+#[test]
+fn test_clip_relu6_constant_bounds() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let data: &[f32] = &[-1.0, 3.0, 8.0];
+    let shape = vec![3];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Clip(X, min, max) -> Y, where min and max are the outputs of Constant nodes (as exported for ReLU6)
+    let constant = |name: &str, value: f32| {
+        node(
+            vec![],
+            vec![name],
+            name,
+            "Constant",
+            vec![attribute("value", initializer(name, vec![value]))],
+        )
+    };
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![
+            constant("min", 0.0),
+            constant("max", 6.0),
+            node(vec!["X", "min", "max"], vec!["Y"], "clip", "Clip", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    // The bounds are baked into the shader, so the Clip only reads the buffer of X
+    let plan = session.execution_plan();
+    assert_eq!(plan.nodes.len(), 1);
+    assert_eq!(plan.nodes[0].op_type, "Clip");
+    assert_eq!(plan.nodes[0].input_buffers.len(), 1);
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &[0.0, 3.0, 6.0]);
+}
+
+#[test]
+fn test_affine() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let data: &[f32] = &[0.0, 1.0, 2.0];
+    let shape = vec![3];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Affine(alpha=2, beta=3) -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "affine",
+            "Affine",
+            vec![attribute("alpha", 2.0), attribute("beta", 3.0)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &[3.0, 5.0, 7.0]);
+}
+
+fn run_activation(op: &str, data: &[f32]) -> Vec<f32> {
+    let mut input_data = HashMap::new();
+    let shape = vec![data.len() as i64];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> op -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "activation", op, vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut result = pollster::block_on(session.run(&input_data)).unwrap();
+    result.remove("Y").unwrap()
+}
+
+#[test]
+fn test_softplus_large_input() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    // For 100, exp(x) does not fit in an f32, so log(1 + exp(x)) would be infinite
+    let result = run_activation("Softplus", &[-50.0, 0.0, 1.0, 50.0, 100.0]);
+    let expected = [0.0, std::f32::consts::LN_2, 1.313_261_7, 50.0, 100.0];
+    for (actual, expected) in result.iter().zip(expected.iter()) {
+        approx::assert_abs_diff_eq!(actual, expected, epsilon = 1e-5);
+    }
+}
+
+#[test]
+fn test_softsign() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let result = run_activation("Softsign", &[-3.0, 0.0, 1.0, 50.0]);
+    common::assert_eq_vector(&result, &[-0.75, 0.0, 0.5, 50.0 / 51.0]);
+}
+
+#[test]
+fn test_clamp_boundaries() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // HardSigmoid (alpha=0.2, beta=0.5) saturates at x=-2.5 and x=2.5, HardSwish at x=-3 and x=3
+    let data = [-4.0, -3.0, -2.5, 0.0, 2.5, 3.0, 4.0, 1.0];
+    let result = run_activation("HardSigmoid", &data);
+    common::assert_eq_vector(&result, &[0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, 0.7]);
+    let result = run_activation("HardSwish", &data);
+    let expected = [
+        0.0,
+        0.0,
+        -0.208_333_33,
+        0.0,
+        2.291_666_7,
+        3.0,
+        4.0,
+        0.666_666_7,
+    ];
+    for (actual, expected) in result.iter().zip(expected.iter()) {
+        approx::assert_abs_diff_eq!(actual, expected, epsilon = 1e-5);
+    }
+
+    // ReLU6 is Clip(min=0, max=6), which uses the same clamp
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[-0.0, 0.0, 6.0, 6.000_001, -1e-6, 1e30, -1e30, 5.999_999];
+    input_data.insert("X".to_string(), data.into());
+    let model = model(graph(
+        vec![tensor("X", &[8])],
+        vec![tensor("Y", &[8])],
+        vec![],
+        vec![initializer("min", vec![0.0]), initializer("max", vec![6.0])],
+        vec![node(
+            vec!["X", "min", "max"],
+            vec!["Y"],
+            "relu6",
+            "Clip",
+            vec![],
+        )],
+    ));
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(
+        result["Y"].as_slice(),
+        &[0.0, 0.0, 6.0, 6.0, 0.0, 6.0, 0.0, 5.999_999],
+    );
+}
+
+#[test]
+fn test_approximate_activations() {
+    // The approximations of Sigmoid and Tanh are accurate to within 1e-4 (see `SessionConfig::approximate_activations`)
+    const TOLERANCE: f32 = 1e-4;
+    let n: usize = 400;
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..n).map(|x| (x as f32) / 20.0 - 10.0).collect();
+    let shape = vec![n as i64];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    for op in ["Sigmoid", "Tanh"] {
+        let activation_model = model(graph(
+            vec![tensor("X", &shape)],
+            vec![tensor("Y", &shape)],
+            vec![],
+            vec![],
+            vec![node(vec!["X"], vec!["Y"], "activation", op, vec![])],
+        ));
+
+        let exact_session =
+            pollster::block_on(wonnx::Session::from_model(activation_model.clone()))
+                .expect("Session did not create");
+        let config = wonnx::SessionConfig {
+            approximate_activations: true,
+            ..wonnx::SessionConfig::default()
+        };
+        let approximate_session = pollster::block_on(wonnx::Session::from_model_with_config(
+            activation_model,
+            config,
+        ))
+        .expect("Session did not create");
+
+        let exact = pollster::block_on(exact_session.run(&input_data)).unwrap();
+        let approximate = pollster::block_on(approximate_session.run(&input_data)).unwrap();
+        for (x, (a, b)) in data
+            .iter()
+            .zip(exact["Y"].iter().zip(approximate["Y"].iter()))
+        {
+            assert!(
+                (a - b).abs() <= TOLERANCE,
+                "{}({}): exact {} approximate {}",
+                op,
+                x,
+                a,
+                b
+            );
+        }
+    }
+}
+
+#[test]
+fn test_elu_celu() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[-1.0, 0.0, 1.0, 2.0];
+    let shape = vec![4];
+    input_data.insert("X".to_string(), data.into());
+
+    // Elu(x) = alpha * (exp(x) - 1) and Celu(x) = alpha * (exp(x / alpha) - 1) for x <= 0, both are x otherwise
+    let e = std::f32::consts::E;
+    let expected_elu = [2.0 * (1.0 / e - 1.0), 0.0, 1.0, 2.0];
+    let expected_celu = [2.0 * ((-0.5f32).exp() - 1.0), 0.0, 1.0, 2.0];
+
+    for (op, expected) in [("Elu", expected_elu), ("Celu", expected_celu)] {
+        // Model: X -> op(alpha=2) -> Y
+        let model = model(graph(
+            vec![tensor("X", &shape)],
+            vec![tensor("Y", &shape)],
+            vec![],
+            vec![],
+            vec![node(
+                vec!["X"],
+                vec!["Y"],
+                "activation",
+                op,
+                vec![attribute("alpha", 2.0)],
+            )],
+        ));
+
+        let session =
+            pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+        let result = pollster::block_on(session.run(&input_data)).unwrap();
+        for (actual, expected) in result["Y"].iter().zip(expected.iter()) {
+            approx::assert_abs_diff_eq!(actual, expected, epsilon = 1e-5);
+        }
+    }
+}
+
+#[test]
+fn test_softmax_large_logits() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[1000.0, 1001.0];
+    let shape = vec![1, 2];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Softmax -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "softmax",
+            "Softmax",
+            vec![attribute("axis", 1)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // exp(1000) overflows, but the probabilities only depend on the difference between the logits
+    let expected = [1.0 / (1.0 + 1f32.exp()), 1.0 / (1.0 + (-1f32).exp())];
+    assert!(result["Y"].iter().all(|p| p.is_finite()));
+    for (actual, expected) in result["Y"].iter().zip(expected.iter()) {
+        approx::assert_abs_diff_eq!(actual, expected, epsilon = 1e-6);
+    }
+}
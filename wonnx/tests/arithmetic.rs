@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use wonnx::{
     onnx::TensorProto_DataType,
-    utils::{graph, model, node, tensor, tensor_of_type, InputTensor},
+    utils::{attribute, graph, initializer, model, node, tensor, tensor_of_type, InputTensor},
 };
 
 mod common;
@@ -82,3 +82,564 @@ fn test_integer() {
     let result = pollster::block_on(session.run(&input_data)).unwrap();
     assert_eq!(result["Y"], vec![42.0; n]);
 }
+
+fn test_bitshift(
+    data_type: TensorProto_DataType,
+    x: &[i32],
+    y: &[i32],
+    y_shape: &[i64],
+    direction: &str,
+    expected: &[f32],
+) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let shape = vec![x.len() as i64];
+    input_data.insert("X".to_string(), InputTensor::I32(x.into()));
+    input_data.insert("Y".to_string(), InputTensor::I32(y.into()));
+
+    // Model: (X, Y) -> BitShift -> Z
+    let model = model(graph(
+        vec![
+            tensor_of_type("X", &shape, data_type),
+            tensor_of_type("Y", y_shape, data_type),
+        ],
+        vec![tensor_of_type("Z", &shape, data_type)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X", "Y"],
+            vec!["Z"],
+            "bitshift",
+            "BitShift",
+            vec![attribute("direction", direction)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Z"], expected);
+}
+
+#[test]
+fn test_bitshift_left() {
+    test_bitshift(
+        TensorProto_DataType::INT32,
+        &[1, 2, 4],
+        &[1, 1, 1],
+        &[3],
+        "LEFT",
+        &[2.0, 4.0, 8.0],
+    );
+}
+
+#[test]
+fn test_bitshift_right_broadcast() {
+    test_bitshift(
+        TensorProto_DataType::INT32,
+        &[16, 8, 4],
+        &[2],
+        &[1],
+        "RIGHT",
+        &[4.0, 2.0, 1.0],
+    );
+}
+
+#[test]
+fn test_bitshift_right_high_bit() {
+    // The shift is logical, so the high bit is not copied into the vacated bits
+    test_bitshift(
+        TensorProto_DataType::INT32,
+        &[i32::MIN, -1, 8],
+        &[1, 28, 3],
+        &[3],
+        "RIGHT",
+        &[1073741824.0, 15.0, 1.0],
+    );
+}
+
+#[test]
+fn test_bitshift_uint8() {
+    // Bits shifted out of the byte to the left are dropped
+    test_bitshift(
+        TensorProto_DataType::UINT8,
+        &[200, 200, 3],
+        &[1, 0, 7],
+        &[3],
+        "LEFT",
+        &[144.0, 200.0, 128.0],
+    );
+    test_bitshift(
+        TensorProto_DataType::UINT8,
+        &[200, 255, 128],
+        &[1, 4, 7],
+        &[3],
+        "RIGHT",
+        &[100.0, 15.0, 1.0],
+    );
+}
+
+// ONNX test cases for Mod (https://github.com/onnx/onnx/blob/main/docs/Operators.md#Mod)
+#[test]
+fn test_mod_mixed_sign_int() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let x: &[i32] = &[-4, 7, 5, 4, -7, 8];
+    let y: &[i32] = &[2, -3, 8, -2, 3, 5];
+    let shape = vec![6];
+    input_data.insert("X".to_string(), InputTensor::I32(x.into()));
+    input_data.insert("Y".to_string(), InputTensor::I32(y.into()));
+
+    // Model: (X, Y) -> Mod -> Z
+    let model = model(graph(
+        vec![
+            tensor_of_type("X", &shape, TensorProto_DataType::INT32),
+            tensor_of_type("Y", &shape, TensorProto_DataType::INT32),
+        ],
+        vec![tensor_of_type("Z", &shape, TensorProto_DataType::INT32)],
+        vec![],
+        vec![],
+        vec![node(vec!["X", "Y"], vec!["Z"], "mod", "Mod", vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Z"], [0.0, -2.0, 5.0, 0.0, 2.0, 3.0]);
+}
+
+#[test]
+fn test_mod_mixed_sign_float_fmod() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let x: &[f32] = &[-4.3, 7.2, 5.0, 4.3, -7.2, 8.0];
+    let y: &[f32] = &[2.1, -3.4, 8.0, -2.1, 3.4, 5.0];
+    let shape = vec![6];
+    input_data.insert("X".to_string(), x.into());
+    input_data.insert("Y".to_string(), y.into());
+
+    // Model: (X, Y) -> Mod -> Z
+    let model = model(graph(
+        vec![tensor("X", &shape), tensor("Y", &shape)],
+        vec![tensor("Z", &shape)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X", "Y"],
+            vec!["Z"],
+            "fmod",
+            "Mod",
+            vec![attribute("fmod", 1)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    let expected = [-0.1, 0.4, 5.0, 0.1, -0.4, 3.0];
+    for (actual, expected) in result["Z"].iter().zip(expected.iter()) {
+        approx::assert_abs_diff_eq!(actual, expected, epsilon = 1e-5);
+    }
+}
+
+#[test]
+fn test_sum_broadcast_bias() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (n, c, h, w) = (2, 3, 2, 2);
+    let x: Vec<f32> = (0..n * c * h * w).map(|i| i as f32).collect();
+    let bias: Vec<f32> = vec![0.5, -1.0, 10.0];
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.as_slice().into());
+    input_data.insert("B".to_string(), bias.as_slice().into());
+
+    // Model: (X, B) -> Sum -> Y, where the bias B of shape [1, C, 1, 1] is broadcast to [N, C, H, W]
+    let model = model(graph(
+        vec![tensor("X", &[n, c, h, w]), tensor("B", &[1, c, 1, 1])],
+        vec![tensor("Y", &[n, c, h, w])],
+        vec![],
+        vec![],
+        vec![node(vec!["X", "B"], vec!["Y"], "sum", "Sum", vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    let expected: Vec<f32> = x
+        .iter()
+        .enumerate()
+        .map(|(i, x)| x + bias[(i / (h * w) as usize) % c as usize])
+        .collect();
+    assert_eq!(result["Y"], expected);
+}
+
+/// Runs X -> op -> Y and returns the output and the op types of the nodes in the execution plan
+fn run_with_plan(
+    inputs: Vec<&str>,
+    op_name: &str,
+    constant: Option<f32>,
+    x: &[f32],
+) -> (Vec<f32>, Vec<String>) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.into());
+
+    // The constant C (if any) is a scalar that is broadcast to the shape of X
+    let shape = [x.len() as i64];
+    let initializers = constant
+        .map(|value| vec![initializer("C", vec![value])])
+        .unwrap_or_default();
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        initializers,
+        vec![node(inputs, vec!["Y"], "op", op_name, vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let op_types = session
+        .execution_plan()
+        .nodes
+        .iter()
+        .map(|node| node.op_type.clone())
+        .collect();
+    let mut result = pollster::block_on(session.run(&input_data)).unwrap();
+    (result.remove("Y").unwrap(), op_types)
+}
+
+#[test]
+fn test_negation_patterns_become_neg() {
+    let x: &[f32] = &[1.5, -2.0, 0.0, 1e30, -7.25];
+    let (negated, op_types) = run_with_plan(vec!["X"], "Neg", None, x);
+    assert_eq!(op_types, vec!["Neg"]);
+
+    // Sub(0, X), Mul(-1, X) and Mul(X, -1) are replaced by Neg(X)
+    for (inputs, op_name, constant) in [
+        (vec!["C", "X"], "Sub", 0.0),
+        (vec!["C", "X"], "Mul", -1.0),
+        (vec!["X", "C"], "Mul", -1.0),
+    ] {
+        let (result, op_types) = run_with_plan(inputs, op_name, Some(constant), x);
+        assert_eq!(op_types, vec!["Neg"]);
+        assert_eq!(result, negated);
+    }
+
+    // Sub(X, 0) is not a negation
+    let (result, op_types) = run_with_plan(vec!["X", "C"], "Sub", Some(0.0), x);
+    assert_eq!(op_types, vec!["Sub"]);
+    assert_eq!(result, x);
+}
+
+fn test_unary(
+    op_name: &str,
+    data_type: TensorProto_DataType,
+    input: InputTensor,
+    expected: &[f32],
+) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), input);
+
+    // Model: X -> op -> Y
+    let shape = [expected.len() as i64];
+    let model = model(graph(
+        vec![tensor_of_type("X", &shape, data_type)],
+        vec![tensor_of_type("Y", &shape, data_type)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "unary", op_name, vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], expected);
+}
+
+#[test]
+fn test_sign_float() {
+    let data: &[f32] = &[-3.0, 0.0, 5.0];
+    test_unary(
+        "Sign",
+        TensorProto_DataType::FLOAT,
+        data.into(),
+        &[-1.0, 0.0, 1.0],
+    );
+}
+
+#[test]
+fn test_sign_int() {
+    let data: &[i32] = &[-3, 0, 5];
+    test_unary(
+        "Sign",
+        TensorProto_DataType::INT32,
+        InputTensor::I32(data.into()),
+        &[-1.0, 0.0, 1.0],
+    );
+}
+
+#[test]
+fn test_abs_int() {
+    let data: &[i32] = &[-1, -2, 3];
+    test_unary(
+        "Abs",
+        TensorProto_DataType::INT32,
+        InputTensor::I32(data.into()),
+        &[1.0, 2.0, 3.0],
+    );
+}
+
+#[test]
+fn test_floor_int() {
+    let data: &[i32] = &[-7, 0, 42];
+    test_unary(
+        "Floor",
+        TensorProto_DataType::INT32,
+        InputTensor::I32(data.into()),
+        &[-7.0, 0.0, 42.0],
+    );
+}
+
+#[test]
+fn test_round_half_to_even() {
+    // Halfway cases are rounded to the nearest even integer; 0.49999997 is the largest f32 below one half
+    let data: &[f32] = &[0.5, 1.5, 2.5, -0.5, 3.5, -2.5, 0.49999997, -1.6];
+    test_unary(
+        "Round",
+        TensorProto_DataType::FLOAT,
+        data.into(),
+        &[0.0, 2.0, 2.0, 0.0, 4.0, -2.0, 0.0, -2.0],
+    );
+}
+
+#[test]
+fn test_neg_int() {
+    let data: &[i32] = &[-1, 0, 3];
+    test_unary(
+        "Neg",
+        TensorProto_DataType::INT32,
+        InputTensor::I32(data.into()),
+        &[1.0, 0.0, -3.0],
+    );
+}
+
+#[test]
+fn test_abs_neg_int_boundary() {
+    // Negation wraps around, so that -i32::MIN == i32::MIN. The values are chosen to be exactly representable as f32.
+    let data: &[i32] = &[i32::MIN, i32::MIN + 128, i32::MAX - 127];
+    test_unary(
+        "Neg",
+        TensorProto_DataType::INT32,
+        InputTensor::I32(data.into()),
+        &[
+            i32::MIN as f32,
+            (i32::MAX - 127) as f32,
+            (i32::MIN + 128) as f32,
+        ],
+    );
+    test_unary(
+        "Abs",
+        TensorProto_DataType::INT32,
+        InputTensor::I32(data.into()),
+        &[
+            i32::MIN as f32,
+            (i32::MAX - 127) as f32,
+            (i32::MAX - 127) as f32,
+        ],
+    );
+}
+
+fn test_logical(op_name: &str, expected: &[f32]) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    // Booleans are passed as integers
+    let x: &[i32] = &[0, 1, 1, 0];
+    let y: &[i32] = &[0, 0, 1, 1];
+    let shape = vec![4];
+    input_data.insert("X".to_string(), InputTensor::I32(x.into()));
+    input_data.insert("Y".to_string(), InputTensor::I32(y.into()));
+
+    // Model: (X, Y) -> op -> Z
+    let model = model(graph(
+        vec![
+            tensor_of_type("X", &shape, TensorProto_DataType::BOOL),
+            tensor_of_type("Y", &shape, TensorProto_DataType::BOOL),
+        ],
+        vec![tensor_of_type("Z", &shape, TensorProto_DataType::BOOL)],
+        vec![],
+        vec![],
+        vec![node(vec!["X", "Y"], vec!["Z"], "logical", op_name, vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Z"], expected);
+}
+
+#[test]
+fn test_and() {
+    test_logical("And", &[0.0, 0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn test_or() {
+    test_logical("Or", &[0.0, 1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn test_xor() {
+    test_logical("Xor", &[0.0, 1.0, 0.0, 1.0]);
+}
+
+fn test_comparison(op_name: &str, expected: &[f32]) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let x: &[f32] = &[-1.5, 0.0, 2.0, 3.5];
+    let y: &[f32] = &[0.0, 0.0, 3.0, 1.0];
+    let shape = vec![4];
+    input_data.insert("X".to_string(), x.into());
+    input_data.insert("Y".to_string(), y.into());
+
+    // Model: (X, Y) -> op -> Z
+    let model = model(graph(
+        vec![tensor("X", &shape), tensor("Y", &shape)],
+        vec![tensor_of_type("Z", &shape, TensorProto_DataType::BOOL)],
+        vec![],
+        vec![],
+        vec![node(vec!["X", "Y"], vec!["Z"], "compare", op_name, vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Z"], expected);
+}
+
+#[test]
+fn test_greater() {
+    test_comparison("Greater", &[0.0, 0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_less() {
+    test_comparison("Less", &[1.0, 0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn test_equal() {
+    test_comparison("Equal", &[0.0, 1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn test_greater_where() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let x: &[f32] = &[-3.0, -0.5, 0.0, 0.25, 1.0, 7.5];
+    let threshold: &[f32] = &[0.0];
+    let fallback: &[f32] = &[-1.0];
+    input_data.insert("X".to_string(), x.into());
+    input_data.insert("T".to_string(), threshold.into());
+    input_data.insert("F".to_string(), fallback.into());
+
+    // Model: (X, T) -> Greater -> C, (C, X, F) -> Where -> Y. The threshold and fallback are broadcast to the shape of X.
+    let model = model(graph(
+        vec![tensor("X", &[2, 3]), tensor("T", &[1]), tensor("F", &[1])],
+        vec![tensor("Y", &[2, 3])],
+        vec![tensor_of_type("C", &[2, 3], TensorProto_DataType::BOOL)],
+        vec![],
+        vec![
+            node(vec!["X", "T"], vec!["C"], "greater", "Greater", vec![]),
+            node(vec!["C", "X", "F"], vec!["Y"], "where", "Where", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], vec![-1.0, -1.0, -1.0, 0.25, 1.0, 7.5]);
+}
+
+#[test]
+fn test_uneven_workgroup_split() {
+    // 786443 elements make for 196611 vec4's, which cannot be split evenly into workgroups of the preferred size
+    let n: usize = 786443;
+    let mut input_data = HashMap::new();
+
+    let data: Vec<f32> = (0..n).map(|i| ((i % 7) as f32) - 3.0).collect();
+    let expected: Vec<f32> = data.iter().map(|x| x.abs() + x).collect();
+    let shape = vec![n as i64];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Abs -> A, (A, X) -> Add -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("A", &shape)],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "abs", "Abs", vec![]),
+            node(vec!["A", "X"], vec!["Y"], "add", "Add", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &expected);
+}
+
+#[test]
+fn test_multiple_nodes_and_outputs() {
+    // All nodes are dispatched in a single submission, after which all outputs are read at once
+    let n: usize = 16;
+    let mut input_data = HashMap::new();
+
+    let data: Vec<f32> = (0..n).map(|i| (i as f32) - 8.0).collect();
+    let shape = vec![n as i64];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Abs -> A, (A, X) -> Sub -> B, (B, A) -> Mul -> C
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![
+            tensor("A", &shape),
+            tensor("B", &shape),
+            tensor("C", &shape),
+        ],
+        vec![],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "abs", "Abs", vec![]),
+            node(vec!["A", "X"], vec!["B"], "sub", "Sub", vec![]),
+            node(vec!["B", "A"], vec!["C"], "mul", "Mul", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    let a: Vec<f32> = data.iter().map(|x| x.abs()).collect();
+    let b: Vec<f32> = a.iter().zip(data.iter()).map(|(a, x)| a - x).collect();
+    let c: Vec<f32> = b.iter().zip(a.iter()).map(|(b, a)| b * a).collect();
+    common::assert_eq_vector(result["A"].as_slice(), &a);
+    common::assert_eq_vector(result["B"].as_slice(), &b);
+    common::assert_eq_vector(result["C"].as_slice(), &c);
+}
@@ -2,8 +2,8 @@ use std::collections::HashMap;
 
 use protobuf::ProtobufEnum;
 use wonnx::{
-    onnx::TensorProto_DataType,
-    utils::{attribute, graph, model, node, tensor, tensor_of_type},
+    onnx::{TensorProto, TensorProto_DataType},
+    utils::{attribute, graph, model, node, tensor, tensor_of_type, InputTensor},
 };
 
 #[test]
@@ -40,3 +40,150 @@ fn test_cast() {
         vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 3.0, 3.0, 3.0, 4.0, 4.0, 4.0, 5.0]
     );
 }
+
+fn cast_model(
+    input_type: TensorProto_DataType,
+    output_type: TensorProto_DataType,
+    dims: &[i64],
+) -> wonnx::onnx::ModelProto {
+    // Model: X -> Cast -> Y
+    model(graph(
+        vec![tensor_of_type("X", dims, input_type)],
+        vec![tensor_of_type("Y", dims, output_type)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "cast",
+            "Cast",
+            vec![attribute("to", output_type.value() as i64)],
+        )],
+    ))
+}
+
+#[test]
+fn test_cast_int_to_float() {
+    let mut input_data = HashMap::new();
+    let data: Vec<i32> = vec![-3, 0, 7, 1024];
+    input_data.insert("X".to_string(), InputTensor::I32(data.as_slice().into()));
+
+    let model = cast_model(
+        TensorProto_DataType::INT32,
+        TensorProto_DataType::FLOAT,
+        &[4],
+    );
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], vec![-3.0, 0.0, 7.0, 1024.0]);
+}
+
+#[test]
+fn test_cast_float_to_int64() {
+    let mut input_data = HashMap::new();
+
+    // The output elements are twice as large as the input elements; the cast truncates towards zero
+    let data: Vec<f32> = vec![-1.5, 2.7, 3.2, -4.0, 5.9];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let model = cast_model(
+        TensorProto_DataType::FLOAT,
+        TensorProto_DataType::INT64,
+        &[5],
+    );
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], vec![-1.0, 2.0, 3.0, -4.0, 5.0]);
+}
+
+#[test]
+fn test_bfloat16_initializer() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let x = vec![0.0f32; 4];
+    input_data.insert("X".to_string(), x.as_slice().into());
+
+    // bfloat16 values are the upper 16 bits of the corresponding f32 values: 1.0, -2.5, 0.15625 and 3.140625
+    let w: Vec<u16> = vec![0x3F80, 0xC020, 0x3E20, 0x4049];
+    let mut w_tensor = TensorProto::new();
+    w_tensor.set_name("W".to_string());
+    w_tensor.set_data_type(TensorProto_DataType::BFLOAT16.value());
+    w_tensor.set_dims(vec![4]);
+    w_tensor.set_raw_data(w.iter().flat_map(|v| v.to_le_bytes()).collect());
+
+    // Model: (X, W) -> Add -> Y, where W is upcast to f32 when it is uploaded
+    let model = model(graph(
+        vec![tensor("X", &[4])],
+        vec![tensor("Y", &[4])],
+        vec![],
+        vec![w_tensor],
+        vec![node(vec!["X", "W"], vec!["Y"], "add", "Add", vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], vec![1.0, -2.5, 0.15625, 3.140625]);
+}
+
+/// Model: (X, W) -> Mul -> Y, where X, W and Y have the specified data type
+fn scale_model(data_type: TensorProto_DataType, w: &[f64]) -> wonnx::onnx::ModelProto {
+    let mut w_tensor = TensorProto::new();
+    w_tensor.set_name("W".to_string());
+    w_tensor.set_data_type(data_type.value());
+    w_tensor.set_dims(vec![w.len() as i64]);
+    match data_type {
+        TensorProto_DataType::DOUBLE => w_tensor.set_double_data(w.to_vec()),
+        _ => w_tensor.set_float_data(w.iter().map(|v| *v as f32).collect()),
+    }
+
+    let shape = vec![w.len() as i64];
+    model(graph(
+        vec![tensor_of_type("X", &shape, data_type)],
+        vec![tensor_of_type("Y", &shape, data_type)],
+        vec![],
+        vec![w_tensor],
+        vec![node(vec!["X", "W"], vec!["Y"], "mul", "Mul", vec![])],
+    ))
+}
+
+#[test]
+fn test_double_model() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let x: Vec<f64> = vec![1.0, -2.5, 0.1, 1e6 + 0.125];
+    let w: Vec<f64> = vec![3.0, 0.2, -7.25, 1.0 / 3.0];
+
+    // The double model is computed with single precision, so it yields the same results as the float model
+    let session = pollster::block_on(wonnx::Session::from_model(scale_model(
+        TensorProto_DataType::DOUBLE,
+        &w,
+    )))
+    .expect("Session did not create");
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), InputTensor::from(x.as_slice()));
+    let result = pollster::block_on(session.run_f64(&input_data)).unwrap();
+
+    let float_session = pollster::block_on(wonnx::Session::from_model(scale_model(
+        TensorProto_DataType::FLOAT,
+        &w,
+    )))
+    .expect("Session did not create");
+    let x_float: Vec<f32> = x.iter().map(|v| *v as f32).collect();
+    let mut float_input_data = HashMap::new();
+    float_input_data.insert("X".to_string(), InputTensor::from(x_float.as_slice()));
+    let float_result = pollster::block_on(float_session.run(&float_input_data)).unwrap();
+
+    for ((double, float), (x, w)) in result["Y"]
+        .iter()
+        .zip(float_result["Y"].iter())
+        .zip(x.iter().zip(w.iter()))
+    {
+        assert_eq!(*double, *float as f64);
+        assert!((double - x * w).abs() <= 1e-6 * (x * w).abs().max(1.0));
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::HashMap, sync::Arc};
+use wonnx::utils::{attribute, graph, model, node, tensor, InputTensor};
+
+#[test]
+fn test_chain_sessions_on_gpu() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![4];
+
+    // Model A: X -> Relu -> Y; model B: Y -> Neg -> Z
+    let model_a = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "relu", "Relu", vec![])],
+    ));
+    let model_b = model(graph(
+        vec![tensor("Y", &shape)],
+        vec![tensor("Z", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["Y"], vec!["Z"], "neg", "Neg", vec![])],
+    ));
+
+    let session_a =
+        pollster::block_on(wonnx::Session::from_model(model_a)).expect("Session did not create");
+    let session_b = pollster::block_on(wonnx::Session::from_model_sharing_device(
+        model_b, &session_a,
+    ))
+    .expect("Session did not create");
+
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[-2.0, -1.0, 1.0, 2.0];
+    input_data.insert("X".to_string(), data.into());
+
+    // The output of session A remains in GPU memory and is passed to session B directly
+    let mut intermediate = pollster::block_on(session_a.run_to_buffers(&input_data)).unwrap();
+    let y = intermediate.remove("Y").unwrap();
+    assert_eq!(y.shape().dims, vec![4]);
+
+    let mut intermediate_data = HashMap::new();
+    intermediate_data.insert("Y".to_string(), InputTensor::Gpu(y));
+    let result = pollster::block_on(session_b.run(&intermediate_data)).unwrap();
+    assert_eq!(result["Z"], vec![0.0, 0.0, -1.0, -2.0]);
+}
+
+#[test]
+fn test_chain_sessions_on_gpu_after_flatten() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Model A: X -> Relu -> A -> Flatten -> Y; model B: Y -> Neg -> Z
+    let model_a = model(graph(
+        vec![tensor("X", &[2, 3])],
+        vec![tensor("Y", &[1, 6])],
+        vec![tensor("A", &[2, 3])],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "relu", "Relu", vec![]),
+            node(
+                vec!["A"],
+                vec!["Y"],
+                "flatten",
+                "Flatten",
+                vec![attribute("axis", 0)],
+            ),
+        ],
+    ));
+    let model_b = model(graph(
+        vec![tensor("Y", &[1, 6])],
+        vec![tensor("Z", &[1, 6])],
+        vec![],
+        vec![],
+        vec![node(vec!["Y"], vec!["Z"], "neg", "Neg", vec![])],
+    ));
+
+    let session_a =
+        pollster::block_on(wonnx::Session::from_model(model_a)).expect("Session did not create");
+    let session_b = pollster::block_on(wonnx::Session::from_model_sharing_device(
+        model_b, &session_a,
+    ))
+    .expect("Session did not create");
+
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[-2.0, -1.0, 0.0, 1.0, 2.0, 3.0];
+    input_data.insert("X".to_string(), data.into());
+
+    // The output of Flatten has the shape declared for it, rather than that of the output of Relu
+    let mut intermediate = pollster::block_on(session_a.run_to_buffers(&input_data)).unwrap();
+    let y = intermediate.remove("Y").unwrap();
+    assert_eq!(y.shape().dims, vec![1, 6]);
+
+    let mut intermediate_data = HashMap::new();
+    intermediate_data.insert("Y".to_string(), InputTensor::Gpu(y));
+    let result = pollster::block_on(session_b.run(&intermediate_data)).unwrap();
+    assert_eq!(result["Z"], vec![0.0, 0.0, 0.0, -1.0, -2.0, -3.0]);
+}
+
+#[test]
+fn test_run_to_buffers_input_as_output() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![2, 3];
+
+    // Model: X -> Relu -> Y, where X is an output of the model as well
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("X", &shape), tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "relu", "Relu", vec![])],
+    ));
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[-2.0, -1.0, 0.0, 1.0, 2.0, 3.0];
+    input_data.insert("X".to_string(), data.into());
+
+    // The input is returned with the shape that the model declares for it
+    let outputs = pollster::block_on(session.run_to_buffers(&input_data)).unwrap();
+    assert_eq!(outputs["X"].shape().dims, vec![2, 3]);
+    assert_eq!(outputs["Y"].shape().dims, vec![2, 3]);
+    assert_eq!(
+        pollster::block_on(session.read_tensor(&outputs["X"])),
+        data.to_vec()
+    );
+}
+
+#[test]
+fn test_session_on_external_device() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![4];
+
+    // Create the device and queue without the help of wonnx, as an application that also renders would
+    let (device, queue) = pollster::block_on(async {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let backends = wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all);
+        let adapter = wgpu::util::initialize_adapter_from_env_or_default(&instance, backends, None)
+            .await
+            .expect("No GPU found given preference");
+        adapter
+            .request_device(&wgpu::DeviceDescriptor::default(), None)
+            .await
+            .expect("Could not create device")
+    });
+    let (device, queue) = (Arc::new(device), Arc::new(queue));
+
+    // Model: X -> Identity -> A -> Relu -> Y. The Identity node is kept as the optimizer is disabled in the config.
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("A", &shape)],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "identity", "Identity", vec![]),
+            node(vec!["A"], vec!["Y"], "relu", "Relu", vec![]),
+        ],
+    ));
+    let config = wonnx::SessionConfig {
+        disable_optimizer: true,
+        ..wonnx::SessionConfig::default()
+    };
+    let session = pollster::block_on(wonnx::Session::from_model_with_device(
+        model, device, queue, config,
+    ))
+    .expect("Session did not create");
+    assert_eq!(session.execution_plan().nodes.len(), 2);
+
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[-2.0, -1.0, 1.0, 2.0];
+    input_data.insert("X".to_string(), data.into());
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], vec![0.0, 0.0, 1.0, 2.0]);
+}
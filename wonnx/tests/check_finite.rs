@@ -0,0 +1,123 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::{Mutex, Once};
+use wonnx::utils::{graph, model, node, tensor};
+
+/// Logger that records all warnings logged by wonnx, so that tests can check which warnings were logged
+struct WarningLogger {
+    warnings: Mutex<Vec<String>>,
+}
+
+impl log::Log for WarningLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Warn && metadata.target().starts_with("wonnx")
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            self.warnings
+                .lock()
+                .unwrap()
+                .push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+lazy_static! {
+    static ref LOGGER: WarningLogger = WarningLogger {
+        warnings: Mutex::new(Vec::new()),
+    };
+
+    /// Serializes the tests in this file, as they share the recorded warnings
+    static ref TEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+static INIT_LOGGER: Once = Once::new();
+
+fn init_logger() {
+    INIT_LOGGER.call_once(|| {
+        log::set_logger(&*LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+    });
+    LOGGER
+        .warnings
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+}
+
+#[test]
+fn test_check_finite_sqrt() {
+    let _guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    init_logger();
+
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[4.0, -1.0, 9.0, 16.0];
+    let shape = vec![4];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Sqrt -> A -> Neg -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("A", &shape)],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "sqrt", "Sqrt", vec![]),
+            node(vec!["A"], vec!["Y"], "neg", "Neg", vec![]),
+        ],
+    ));
+
+    let config = wonnx::SessionConfig {
+        check_finite: true,
+        ..wonnx::SessionConfig::default()
+    };
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(model, config))
+        .expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert!(result["Y"][1].is_nan());
+
+    // Only the node that introduced the NaN is reported
+    let warnings = LOGGER.warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("'sqrt'"), "{}", warnings[0]);
+}
+
+#[test]
+fn test_check_finite_element_wise_chain() {
+    let _guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    init_logger();
+
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[4.0, -1.0, 9.0, 16.0];
+    let shape = vec![4];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Sqrt -> A -> Relu -> B -> Neg -> Y. Relu and Neg would normally overwrite the output of the node
+    // before them in-place, which would leave only the output of Neg to be checked.
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("A", &shape), tensor("B", &shape)],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "sqrt", "Sqrt", vec![]),
+            node(vec!["A"], vec!["B"], "relu", "Relu", vec![]),
+            node(vec!["B"], vec!["Y"], "neg", "Neg", vec![]),
+        ],
+    ));
+
+    let config = wonnx::SessionConfig {
+        check_finite: true,
+        ..wonnx::SessionConfig::default()
+    };
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(model, config))
+        .expect("Session did not create");
+    pollster::block_on(session.run(&input_data)).unwrap();
+
+    let warnings = LOGGER.warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("'sqrt'"), "{}", warnings[0]);
+}
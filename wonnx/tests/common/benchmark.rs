@@ -0,0 +1,92 @@
+//! Helpers for the benchmarks among the tests. These are marked `#[ignore]` so that they only run when requested, e.g.
+//! `cargo test --release --test matrix benchmark_matmul_tiled -- --ignored --nocapture`.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wonnx::onnx::ModelProto;
+use wonnx::utils::{graph, model, tensor, InputTensor};
+use wonnx::{Session, SessionConfig};
+
+/// Number of timed runs of a session
+const RUNS: u32 = 10;
+
+/// The device (and its queue) used by the sessions of a benchmark, so that the benchmark can wait for the work submitted
+/// to it
+pub struct Benchmark {
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
+}
+
+impl Benchmark {
+    pub fn new() -> Benchmark {
+        let (device, queue) = pollster::block_on(wonnx::resource::request_device_queue());
+        Benchmark {
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+        }
+    }
+
+    /// Create a session for the model on the device of this benchmark
+    pub fn session(&self, model: ModelProto, config: SessionConfig) -> Session {
+        pollster::block_on(Session::from_model_with_device(
+            model,
+            self.device.clone(),
+            self.queue.clone(),
+            config,
+        ))
+        .expect("Session did not create")
+    }
+
+    /// Copy the values of the specified inputs (name, dimensions and values) to GPU memory, so that uploading them is not
+    /// part of the timed runs. This runs a model that returns its inputs as outputs.
+    pub fn upload(
+        &self,
+        inputs: &[(&str, &[i64], &[f32])],
+    ) -> HashMap<String, InputTensor<'static>> {
+        let values: Vec<_> = inputs
+            .iter()
+            .map(|(name, dims, _)| tensor(name, dims))
+            .collect();
+        let session = self.session(
+            model(graph(values.clone(), values, vec![], vec![], vec![])),
+            SessionConfig::default(),
+        );
+        let input_data: HashMap<String, InputTensor> = inputs
+            .iter()
+            .map(|(name, _, data)| (name.to_string(), (*data).into()))
+            .collect();
+        pollster::block_on(session.run_to_buffers(&input_data))
+            .unwrap()
+            .into_iter()
+            .map(|(name, tensor)| (name, InputTensor::Gpu(tensor)))
+            .collect()
+    }
+
+    /// Returns the average duration of running the session with inputs that reside in GPU memory (see `upload`), without
+    /// reading back its outputs. Only copying the inputs to the input buffers of the session (on the GPU) is timed along
+    /// with the shaders. The session is run once before timing, as some drivers only finish compiling a shader when it is
+    /// first used.
+    pub fn time(&self, session: &Session, input_data: &HashMap<String, InputTensor>) -> Duration {
+        pollster::block_on(session.run_to_buffers(input_data)).unwrap();
+        self.device.poll(wgpu::Maintain::Wait);
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            pollster::block_on(session.run_to_buffers(input_data)).unwrap();
+        }
+        self.device.poll(wgpu::Maintain::Wait);
+        start.elapsed() / RUNS
+    }
+}
+
+/// Prints the durations of two ways to compute the same result, and how much faster the first is than the second
+pub fn report(description: &str, optimized: (&str, Duration), baseline: (&str, Duration)) {
+    println!(
+        "{}: {} {:?}, {} {:?} (speedup {:.2}x)",
+        description,
+        optimized.0,
+        optimized.1,
+        baseline.0,
+        baseline.1,
+        baseline.1.as_secs_f64() / optimized.1.as_secs_f64()
+    );
+}
@@ -1,5 +1,10 @@
+// Each test includes this module, but not every test uses each of the helpers
+#![allow(dead_code)]
+
 use approx::assert_ulps_eq;
 
+pub mod benchmark;
+
 /// Assert two vectors are equal up to a specific number of units in last place (ULPS)
 pub fn assert_eq_vector(xs: &[f32], ys: &[f32]) {
     assert_eq!(xs.len(), ys.len());
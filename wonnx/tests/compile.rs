@@ -0,0 +1,172 @@
+use wonnx::compiler::CompileError;
+use wonnx::utils::{attribute, node, ScalarType, Shape};
+
+#[test]
+fn test_compile_node_relu() {
+    // 1000 elements are processed as 250 vec4's, each by a workgroup of a single thread
+    let shape = Shape::from(ScalarType::F32, &[10, 100]);
+    let relu = node(vec!["X"], vec!["Y"], "relu", "Relu", vec![]);
+    let compiled = wonnx::compile_node(&relu, &[&shape], &[&shape], 13).unwrap();
+    assert!(compiled.shader.contains("max("));
+    assert_eq!(compiled.threads, (250, 1, 1));
+}
+
+#[test]
+fn test_compile_node_relu_unaligned_length() {
+    // Only the first three lanes of the last vector belong to the tensor, so only these are read and written
+    let relu = node(vec!["X"], vec!["Y"], "relu", "Relu", vec![]);
+    let unaligned = Shape::from(ScalarType::F32, &[7]);
+    let compiled = wonnx::compile_node(&relu, &[&unaligned], &[&unaligned], 13).unwrap();
+    assert!(compiled.shader.contains("let is_tail = gidx == 1u;"));
+    assert!(compiled.shader.contains("output_0.data[gidx][2] = "));
+    assert!(!compiled.shader.contains("output_0.data[gidx][3] = "));
+
+    let aligned = Shape::from(ScalarType::F32, &[8]);
+    let compiled = wonnx::compile_node(&relu, &[&aligned], &[&aligned], 13).unwrap();
+    assert!(!compiled.shader.contains("is_tail"));
+}
+
+#[test]
+fn test_compile_node_transpose_invalid_perm() {
+    let input_shape = Shape::from(ScalarType::F32, &[2, 3, 4]);
+    let output_shape = Shape::from(ScalarType::F32, &[4, 3, 2]);
+    for perm in [vec![2, 1], vec![2, 1, 1], vec![2, 1, 3], vec![-1, 1, 0]] {
+        let transpose = node(
+            vec!["X"],
+            vec!["Y"],
+            "transpose",
+            "Transpose",
+            vec![attribute("perm", perm)],
+        );
+        let result = wonnx::compile_node(&transpose, &[&input_shape], &[&output_shape], 13);
+        assert!(matches!(
+            result,
+            Err(CompileError::InvalidAttributeValue { ref attribute, .. }) if attribute == "perm"
+        ));
+    }
+
+    // A valid permutation with an output shape that does not match it
+    let transpose = node(
+        vec!["X"],
+        vec!["Y"],
+        "transpose",
+        "Transpose",
+        vec![attribute("perm", vec![0, 2, 1])],
+    );
+    let result = wonnx::compile_node(&transpose, &[&input_shape], &[&output_shape], 13);
+    assert!(matches!(
+        result,
+        Err(CompileError::InvalidOutputShape { .. })
+    ));
+}
+
+#[test]
+fn test_compile_node_normalization_statistics() {
+    // Both normalizations compute the mean and variance with the shared (Welford) snippet
+    let shape = Shape::from(ScalarType::F32, &[2, 4, 3, 3]);
+    let params = Shape::from(ScalarType::F32, &[4]);
+    let group_norm = node(
+        vec!["X", "scale", "bias"],
+        vec!["Y"],
+        "group_norm",
+        "GroupNormalization",
+        vec![attribute("num_groups", 2)],
+    );
+    let group_norm =
+        wonnx::compile_node(&group_norm, &[&shape, &params, &params], &[&shape], 18).unwrap();
+
+    let row_shape = Shape::from(ScalarType::F32, &[2, 8]);
+    let gamma = Shape::from(ScalarType::F32, &[8]);
+    let mut layer_norm = node(
+        vec!["X", "skip", "gamma"],
+        vec!["Y"],
+        "layer_norm",
+        "SkipLayerNormalization",
+        vec![],
+    );
+    layer_norm.set_domain("com.microsoft".to_string());
+    let layer_norm = wonnx::compile_node(
+        &layer_norm,
+        &[&row_shape, &row_shape, &gamma],
+        &[&row_shape],
+        13,
+    )
+    .unwrap();
+
+    for compiled in [group_norm, layer_norm] {
+        assert!(compiled.shader.contains("stats_squared_deviations"));
+        assert!(compiled.shader.contains("sqrt(variance + "));
+    }
+}
+
+#[test]
+fn test_compile_node_conv_integer_group() {
+    let x = Shape::from(ScalarType::U8, &[1, 4, 4, 4]);
+    let w = Shape::from(ScalarType::I8, &[4, 2, 3, 3]);
+    let y = Shape::from(ScalarType::I32, &[1, 4, 2, 2]);
+    let conv = node(
+        vec!["X", "W"],
+        vec!["Y"],
+        "conv",
+        "ConvInteger",
+        vec![attribute("group", 2)],
+    );
+    let result = wonnx::compile_node(&conv, &[&x, &w], &[&y], 13);
+    assert!(matches!(
+        result,
+        Err(CompileError::UnimplementedVariant { ref variant, .. }) if variant == "group=2"
+    ));
+}
+
+#[test]
+fn test_compile_node_concat() {
+    // The inputs are concatenated as flat buffers, which only works when the dimensions before the axis are all 1
+    let a = Shape::from(ScalarType::F32, &[1, 2, 3]);
+    let b = Shape::from(ScalarType::F32, &[1, 4, 3]);
+    let y = Shape::from(ScalarType::F32, &[1, 6, 3]);
+    let concat = node(
+        vec!["A", "B"],
+        vec!["Y"],
+        "concat",
+        "Concat",
+        vec![attribute("axis", 1)],
+    );
+    assert!(wonnx::compile_node(&concat, &[&a, &b], &[&y], 13).is_ok());
+
+    let a = Shape::from(ScalarType::F32, &[2, 2, 3]);
+    let b = Shape::from(ScalarType::F32, &[2, 4, 3]);
+    let y = Shape::from(ScalarType::F32, &[2, 6, 3]);
+    let result = wonnx::compile_node(&concat, &[&a, &b], &[&y], 13);
+    assert!(matches!(
+        result,
+        Err(CompileError::UnimplementedVariant { ref op, .. }) if op == "Concat"
+    ));
+
+    // The axis attribute is required
+    let concat = node(vec!["A", "B"], vec!["Y"], "concat", "Concat", vec![]);
+    let result = wonnx::compile_node(&concat, &[&a, &b], &[&y], 13);
+    assert!(matches!(result, Err(CompileError::AttributeNotFound(_))));
+}
+
+#[test]
+fn test_compile_node_conv_invalid_pads() {
+    // A 2D convolution needs four pads (the begin and end padding of both spatial axes)
+    let x = Shape::from(ScalarType::F32, &[1, 1, 5, 5]);
+    let w = Shape::from(ScalarType::F32, &[1, 1, 3, 3]);
+    let y = Shape::from(ScalarType::F32, &[1, 1, 5, 5]);
+    let conv = node(
+        vec!["X", "W"],
+        vec!["Y"],
+        "conv",
+        "Conv",
+        vec![
+            attribute("kernel_shape", vec![3, 3]),
+            attribute("pads", vec![1, 1]),
+        ],
+    );
+    let result = wonnx::compile_node(&conv, &[&x, &w], &[&y], 13);
+    assert!(matches!(
+        result,
+        Err(CompileError::InvalidAttributeValue { ref attribute, .. }) if attribute == "pads"
+    ));
+}
@@ -2,6 +2,8 @@ use std::collections::HashMap;
 use wonnx::utils::{attribute, graph, initializer, model, node, tensor};
 use wonnx::*;
 
+mod common;
+
 #[test]
 fn conv_pad() {
     let n = 5;
@@ -247,3 +249,469 @@ fn _conv_kernel_3() {
     let mut model = crate::onnx::ModelProto::new();
     model.set_graph(graph);
 }
+
+/// Creates a session for a Conv with a square kernel and 'same' padding (and a bias) on a single image
+fn conv_same_padding_model(
+    w: &[f32],
+    b: &[f32],
+    channels: i64,
+    filters: i64,
+    kernel: i64,
+    size: i64,
+) -> wonnx::onnx::ModelProto {
+    let pad = kernel / 2;
+    model(graph(
+        vec![tensor("X", &[1, channels, size, size])],
+        vec![tensor("Y", &[1, filters, size, size])],
+        vec![],
+        vec![
+            initializer(
+                "W",
+                w[0..(filters * channels * kernel * kernel) as usize].to_vec(),
+            ),
+            initializer("B", b[0..filters as usize].to_vec()),
+        ],
+        vec![node(
+            vec!["X", "W", "B"],
+            vec!["Y"],
+            "conv",
+            "Conv",
+            vec![
+                attribute("kernel_shape", vec![kernel, kernel]),
+                attribute("pads", vec![pad, pad, pad, pad]),
+            ],
+        )],
+    ))
+}
+
+fn conv_same_padding_session(
+    w: &[f32],
+    b: &[f32],
+    channels: i64,
+    filters: i64,
+    kernel: i64,
+    size: i64,
+) -> wonnx::Session {
+    let conv_model = conv_same_padding_model(w, b, channels, filters, kernel, size);
+    pollster::block_on(wonnx::Session::from_model(conv_model)).expect("Session did not create")
+}
+
+#[test]
+fn conv_valid_padding() {
+    // With auto_pad=VALID the pads attribute is ignored and no padding is applied, so the 5x5 input shrinks to 3x3
+    let n = 5;
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..n * n).map(|x| x as f32).collect();
+    input_data.insert("X".to_string(), data.as_slice().into());
+    let data_w: Vec<f32> = vec![1.0; 9];
+
+    let conv_model = model(graph(
+        vec![tensor("X", &[1, 1, n, n])],
+        vec![tensor("Y", &[1, 1, n - 2, n - 2])],
+        vec![tensor("W", &[1, 1, 3, 3])],
+        vec![initializer("W", data_w)],
+        vec![node(
+            vec!["X", "W"],
+            vec!["Y"],
+            "conv",
+            "Conv",
+            vec![
+                attribute("kernel_shape", vec![3, 3]),
+                attribute("auto_pad", "VALID"),
+                attribute("pads", vec![1, 1, 1, 1]),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(conv_model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(
+        result["Y"],
+        [54.0, 63.0, 72.0, 99.0, 108.0, 117.0, 144.0, 153.0, 162.0]
+    );
+}
+
+#[test]
+fn conv_1d_same_upper() {
+    // The output has ceil(5 / 2) = 3 elements, which requires three elements of padding in total: one at the beginning
+    // and (as the padding is uneven) two at the end
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let conv_model = model(graph(
+        vec![tensor("X", &[1, 1, 5])],
+        vec![tensor("Y", &[1, 1, 3])],
+        vec![tensor("W", &[1, 1, 4])],
+        vec![initializer("W", vec![1.0, 10.0, 100.0, 1000.0])],
+        vec![node(
+            vec!["X", "W"],
+            vec!["Y"],
+            "conv",
+            "Conv",
+            vec![
+                attribute("kernel_shape", vec![4]),
+                attribute("strides", vec![2]),
+                attribute("auto_pad", "SAME_UPPER"),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(conv_model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // The windows are [0, 1, 2, 3], [2, 3, 4, 5] and [4, 5, 0, 0] (with zero padding)
+    assert_eq!(result["Y"], [3210.0, 5432.0, 54.0]);
+}
+
+#[test]
+fn conv_3d_pads() {
+    // A 3x3x3 kernel of ones over a 3x3x3 input padded by one on every side, so that each output is the sum of the
+    // input elements in its (clipped) 3x3x3 neighbourhood
+    let n = 3;
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..n * n * n).map(|x| x as f32).collect();
+    input_data.insert("X".to_string(), data.as_slice().into());
+    let data_w: Vec<f32> = vec![1.0; (n * n * n) as usize];
+
+    let conv_model = model(graph(
+        vec![tensor("X", &[1, 1, n, n, n])],
+        vec![tensor("Y", &[1, 1, n, n, n])],
+        vec![tensor("W", &[1, 1, 3, 3, 3])],
+        vec![initializer("W", data_w)],
+        vec![node(
+            vec!["X", "W"],
+            vec!["Y"],
+            "conv",
+            "Conv",
+            vec![
+                attribute("kernel_shape", vec![3, 3, 3]),
+                attribute("pads", vec![1, 1, 1, 1, 1, 1]),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(conv_model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    let mut expected = vec![];
+    for z in 0..n {
+        for y in 0..n {
+            for x in 0..n {
+                let mut sum = 0.0;
+                for in_z in (z - 1).max(0)..(z + 2).min(n) {
+                    for in_y in (y - 1).max(0)..(y + 2).min(n) {
+                        for in_x in (x - 1).max(0)..(x + 2).min(n) {
+                            sum += data[((in_z * n + in_y) * n + in_x) as usize];
+                        }
+                    }
+                }
+                expected.push(sum);
+            }
+        }
+    }
+    assert_eq!(result["Y"], expected);
+}
+
+/// Runs a 3x3 Conv with dilations [2, 2] (and padding such that the output has the size of the input) and compares it
+/// against a reference implementation
+fn conv_dilated(filters: i64) {
+    let (channels, size, dilation) = (2, 7, 2);
+    let pad = dilation;
+    let x: Vec<f32> = (0..channels * size * size)
+        .map(|i| ((i % 7) as f32) - 3.0)
+        .collect();
+    let w: Vec<f32> = (0..filters * channels * 3 * 3)
+        .map(|i| ((i % 5) as f32) - 2.0)
+        .collect();
+    let b: Vec<f32> = (0..filters).map(|i| i as f32).collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.as_slice().into());
+
+    let conv_model = model(graph(
+        vec![tensor("X", &[1, channels, size, size])],
+        vec![tensor("Y", &[1, filters, size, size])],
+        vec![],
+        vec![initializer("W", w.clone()), initializer("B", b.clone())],
+        vec![node(
+            vec!["X", "W", "B"],
+            vec!["Y"],
+            "conv",
+            "Conv",
+            vec![
+                attribute("kernel_shape", vec![3, 3]),
+                attribute("dilations", vec![dilation, dilation]),
+                attribute("pads", vec![pad, pad, pad, pad]),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(conv_model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    let mut expected = vec![];
+    for m in 0..filters {
+        for y in 0..size {
+            for x_pos in 0..size {
+                let mut sum = b[m as usize];
+                for c in 0..channels {
+                    for i in 0..3 {
+                        for j in 0..3 {
+                            let (in_y, in_x) = (y + i * dilation - pad, x_pos + j * dilation - pad);
+                            if in_y >= 0 && in_y < size && in_x >= 0 && in_x < size {
+                                sum += x[((c * size + in_y) * size + in_x) as usize]
+                                    * w[(((m * channels + c) * 3 + i) * 3 + j) as usize];
+                            }
+                        }
+                    }
+                }
+                expected.push(sum);
+            }
+        }
+    }
+    assert_eq!(result["Y"], expected);
+}
+
+#[test]
+fn conv_dilated_kernel_3() {
+    // Four filters are computed at a time by the shader specialized for 3x3 kernels
+    conv_dilated(4);
+}
+
+#[test]
+fn conv_dilated_generic() {
+    // The number of filters is not a multiple of four, so the generic Conv shader is used
+    conv_dilated(3);
+}
+
+#[test]
+fn conv_swish() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (channels, size) = (2, 5);
+
+    // Four filters are computed by the shader specialized for 3x3 kernels, three by the generic Conv shader
+    for filters in [3, 4] {
+        let x: Vec<f32> = (0..channels * size * size)
+            .map(|i| ((i % 9) as f32) * 0.25 - 1.0)
+            .collect();
+        let w: Vec<f32> = (0..filters * channels * 3 * 3)
+            .map(|i| ((i % 5) as f32) * 0.1 - 0.2)
+            .collect();
+        let b: Vec<f32> = (0..filters).map(|i| i as f32 * 0.5 - 1.0).collect();
+        let mut input_data = HashMap::new();
+        input_data.insert("X".to_string(), x.as_slice().into());
+
+        let conv_attributes = || {
+            vec![
+                attribute("kernel_shape", vec![3, 3]),
+                attribute("pads", vec![1, 1, 1, 1]),
+            ]
+        };
+        let initializers = || vec![initializer("W", w.clone()), initializer("B", b.clone())];
+        let output_shape = [1, filters, size, size];
+
+        // Model: (X, W, B) -> ConvSwish -> Y
+        let fused_model = model(graph(
+            vec![tensor("X", &[1, channels, size, size])],
+            vec![tensor("Y", &output_shape)],
+            vec![],
+            initializers(),
+            vec![node(
+                vec!["X", "W", "B"],
+                vec!["Y"],
+                "conv_swish",
+                "ConvSwish",
+                conv_attributes(),
+            )],
+        ));
+
+        // Model: (X, W, B) -> Conv -> C -> Sigmoid -> S, (C, S) -> Mul -> Y
+        let separate_model = model(graph(
+            vec![tensor("X", &[1, channels, size, size])],
+            vec![tensor("Y", &output_shape)],
+            vec![tensor("C", &output_shape), tensor("S", &output_shape)],
+            initializers(),
+            vec![
+                node(
+                    vec!["X", "W", "B"],
+                    vec!["C"],
+                    "conv",
+                    "Conv",
+                    conv_attributes(),
+                ),
+                node(vec!["C"], vec!["S"], "sigmoid", "Sigmoid", vec![]),
+                node(vec!["C", "S"], vec!["Y"], "mul", "Mul", vec![]),
+            ],
+        ));
+
+        let fused_session = pollster::block_on(wonnx::Session::from_model(fused_model))
+            .expect("Session did not create");
+        let fused = pollster::block_on(fused_session.run(&input_data)).unwrap();
+        let separate_session = pollster::block_on(wonnx::Session::from_model(separate_model))
+            .expect("Session did not create");
+        let separate = pollster::block_on(separate_session.run(&input_data)).unwrap();
+
+        assert_eq!(fused["Y"].len(), separate["Y"].len());
+        for (fused, separate) in fused["Y"].iter().zip(separate["Y"].iter()) {
+            approx::assert_abs_diff_eq!(fused, separate, epsilon = 1e-5);
+        }
+    }
+}
+
+#[test]
+fn conv_kernel_1_bias() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // The number of channels is a multiple of 16 and the number of filters a multiple of four, so the shader specialized
+    // for 1x1 kernels is used. Values are small integers so that the results are exact.
+    let (batches, channels, filters, size) = (2, 16, 8, 3);
+    let x: Vec<f32> = (0..batches * channels * size * size)
+        .map(|i| ((i % 5) as f32) - 2.0)
+        .collect();
+    let w: Vec<f32> = (0..filters * channels)
+        .map(|i| ((i % 3) as f32) - 1.0)
+        .collect();
+    let b: Vec<f32> = (0..filters).map(|i| i as f32 * 0.5 - 1.0).collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.as_slice().into());
+
+    // Model: (X, W, B) -> Conv -> Y
+    let conv_model = model(graph(
+        vec![tensor("X", &[batches, channels, size, size])],
+        vec![tensor("Y", &[batches, filters, size, size])],
+        vec![
+            tensor("W", &[filters, channels, 1, 1]),
+            tensor("B", &[filters]),
+        ],
+        vec![initializer("W", w.clone()), initializer("B", b.clone())],
+        vec![node(
+            vec!["X", "W", "B"],
+            vec!["Y"],
+            "conv",
+            "Conv",
+            vec![attribute("kernel_shape", vec![1, 1])],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(conv_model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    let spatial = size * size;
+    let mut expected = vec![];
+    for n in 0..batches {
+        for m in 0..filters {
+            for xy in 0..spatial {
+                let mut value = b[m as usize];
+                for c in 0..channels {
+                    value += x[((n * channels + c) * spatial + xy) as usize]
+                        * w[(m * channels + c) as usize];
+                }
+                expected.push(value);
+            }
+        }
+    }
+    assert_eq!(result["Y"], expected);
+}
+
+#[test]
+fn conv_kernel_5_im2col() {
+    // With more than GEMM_TILED_THRESHOLD filters the Conv is lowered to Im2Col+Gemm; with fewer filters the direct Conv
+    // shader is used. Values are small integers so that both paths give exact results.
+    let (channels, kernel, size) = (2, 5, 10);
+    let direct_filters = wonnx::compiler::GEMM_TILED_THRESHOLD as i64;
+    let filters = direct_filters + 8;
+    let x: Vec<f32> = (0..channels * size * size)
+        .map(|i| ((i % 5) as f32) - 2.0)
+        .collect();
+    let w: Vec<f32> = (0..filters * channels * kernel * kernel)
+        .map(|i| ((i % 3) as f32) - 1.0)
+        .collect();
+    let b: Vec<f32> = (0..filters).map(|i| (i % 4) as f32).collect();
+
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.as_slice().into());
+    let run = |filters: i64| {
+        let session = conv_same_padding_session(&w, &b, channels, filters, kernel, size);
+        pollster::block_on(session.run(&input_data))
+            .unwrap()
+            .remove("Y")
+            .unwrap()
+    };
+    let lowered = run(filters);
+    let direct = run(direct_filters);
+    assert_eq!(&lowered[0..direct.len()], direct.as_slice());
+
+    // Check the filters that were not computed by the direct path against a reference implementation
+    let pad = kernel / 2;
+    for m in direct_filters..filters {
+        for y in 0..size {
+            for x_pos in 0..size {
+                let mut expected = b[m as usize];
+                for c in 0..channels {
+                    for i in 0..kernel {
+                        for j in 0..kernel {
+                            let (in_y, in_x) = (y + i - pad, x_pos + j - pad);
+                            if in_y >= 0 && in_y < size && in_x >= 0 && in_x < size {
+                                expected += x[(c * size * size + in_y * size + in_x) as usize]
+                                    * w[(((m * channels + c) * kernel + i) * kernel + j) as usize];
+                            }
+                        }
+                    }
+                }
+                assert_eq!(lowered[((m * size + y) * size + x_pos) as usize], expected);
+            }
+        }
+    }
+}
+
+/// Compares the Im2Col+Gemm path against the direct Conv shader for the same 7x7 convolution. The Conv is only lowered
+/// to Im2Col+Gemm by the optimizer, so the direct shader is used when the optimizer is disabled. Run with
+/// `cargo test --release --test conv benchmark_conv_im2col -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn benchmark_conv_im2col() {
+    let (channels, kernel, size) = (16, 7, 64);
+    let filters = wonnx::compiler::GEMM_TILED_THRESHOLD as i64 * 4;
+    let x: Vec<f32> = (0..channels * size * size)
+        .map(|i| ((i % 5) as f32) - 2.0)
+        .collect();
+    let w: Vec<f32> = (0..filters * channels * kernel * kernel)
+        .map(|i| ((i % 3) as f32) - 1.0)
+        .collect();
+    let b: Vec<f32> = (0..filters).map(|i| (i % 4) as f32).collect();
+
+    let benchmark = common::benchmark::Benchmark::new();
+    let conv_model = conv_same_padding_model(&w, &b, channels, filters, kernel, size);
+    let lowered_session = benchmark.session(conv_model.clone(), wonnx::SessionConfig::default());
+    let direct_session = benchmark.session(
+        conv_model,
+        wonnx::SessionConfig {
+            disable_optimizer: true,
+            ..wonnx::SessionConfig::default()
+        },
+    );
+    assert!(lowered_session
+        .execution_plan()
+        .nodes
+        .iter()
+        .any(|node| node.op_type == "Im2Col"));
+    assert_eq!(direct_session.execution_plan().nodes.len(), 1);
+
+    let input_data = benchmark.upload(&[("X", &[1, channels, size, size], &x)]);
+    let lowered = benchmark.time(&lowered_session, &input_data);
+    let direct = benchmark.time(&direct_session, &input_data);
+
+    common::benchmark::report(
+        &format!(
+            "{}x{} conv of [1, {}, {}, {}] to {} filters",
+            kernel, kernel, channels, size, size, filters
+        ),
+        ("im2col", lowered),
+        ("direct", direct),
+    );
+}
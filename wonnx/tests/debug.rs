@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use wonnx::utils::{graph, model, node, tensor};
+
+mod common;
+
+#[test]
+fn test_run_with_debug() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+
+    let data: &[f32] = &[-2.0, -1.0, 0.0, 1.0, 2.0, 3.0, -4.0, 5.0];
+    let shape = vec![8];
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Relu -> H -> Add(H, H) -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("H", &shape)],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["H"], "relu", "Relu", vec![]),
+            node(vec!["H", "H"], vec!["Y"], "add", "Add", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run_with_debug(&input_data, &["H"])).unwrap();
+    common::assert_eq_vector(
+        result["H"].as_slice(),
+        &[0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 0.0, 5.0],
+    );
+    common::assert_eq_vector(
+        result["Y"].as_slice(),
+        &[0.0, 0.0, 0.0, 2.0, 4.0, 6.0, 0.0, 10.0],
+    );
+
+    // Reading back intermediates should not change the result
+    let plain_result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(plain_result["Y"], result["Y"]);
+    assert!(!plain_result.contains_key("H"));
+
+    assert!(pollster::block_on(session.run_with_debug(&input_data, &["Z"])).is_err());
+}
@@ -0,0 +1,46 @@
+use wonnx::utils::{graph, model, node, tensor};
+
+#[test]
+fn test_execution_plan() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Model: X -> Sin -> A, (A, X) -> Add -> Y. The nodes are listed in reverse to check that the plan follows the
+    // dependencies rather than the order of the nodes in the graph.
+    let shape = vec![2, 8];
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("A", &shape)],
+        vec![],
+        vec![
+            node(vec!["A", "X"], vec!["Y"], "add", "Add", vec![]),
+            node(vec!["X"], vec!["A"], "sin", "Sin", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let plan = session.execution_plan();
+
+    let names: Vec<&str> = plan.nodes.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["sin", "add"]);
+    let (sin, add) = (&plan.nodes[0], &plan.nodes[1]);
+    assert_eq!(sin.op_type, "Sin");
+    assert_eq!(add.op_type, "Add");
+
+    // Sin reads the buffer of X and writes A, which is read by Add (together with X)
+    assert_eq!(sin.input_buffers.len(), 1);
+    assert_eq!(plan.buffers[sin.input_buffers[0]].name, "X");
+    assert_eq!(sin.output_buffers.len(), 1);
+    assert_eq!(plan.buffers[sin.output_buffers[0]].name, "A");
+    assert_eq!(
+        add.input_buffers,
+        vec![sin.output_buffers[0], sin.input_buffers[0]]
+    );
+    assert_eq!(plan.buffers[add.output_buffers[0]].name, "Y");
+
+    // All buffers hold 16 f32's
+    assert_eq!(plan.buffers.len(), 3);
+    assert!(plan.buffers.iter().all(|b| b.size_bytes == 64));
+    assert!(sin.threads.is_some() && add.threads.is_some());
+}
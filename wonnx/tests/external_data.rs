@@ -0,0 +1,93 @@
+use protobuf::Message;
+use std::collections::HashMap;
+use wonnx::onnx::{StringStringEntryProto, TensorProto, TensorProto_DataLocation};
+use wonnx::utils::{graph, model, node, tensor};
+
+mod common;
+
+fn external_data_entry(key: &str, value: &str) -> StringStringEntryProto {
+    let mut entry = StringStringEntryProto::new();
+    entry.set_key(key.to_string());
+    entry.set_value(value.to_string());
+    entry
+}
+
+#[test]
+fn test_external_data() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let directory = std::env::temp_dir().join("wonnx_test_external_data");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    // Store the weights after some padding bytes, to check that the offset is honored
+    let weights: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0];
+    let mut weights_data = vec![0xffu8; 8];
+    weights_data.extend_from_slice(bytemuck::cast_slice(&weights));
+    std::fs::write(directory.join("weights.bin"), &weights_data).unwrap();
+
+    let mut w = TensorProto::new();
+    w.set_name("W".to_string());
+    w.set_data_type(1); // FLOAT
+    w.set_dims(vec![4]);
+    w.set_data_location(TensorProto_DataLocation::EXTERNAL);
+    w.mut_external_data()
+        .push(external_data_entry("location", "weights.bin"));
+    w.mut_external_data()
+        .push(external_data_entry("offset", "8"));
+    w.mut_external_data()
+        .push(external_data_entry("length", "16"));
+
+    // Model: (X, W) -> Add -> Y
+    let shape = vec![4];
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("W", &shape)],
+        vec![w],
+        vec![node(vec!["X", "W"], vec!["Y"], "add", "Add", vec![])],
+    ));
+    let model_path = directory.join("model.onnx");
+    std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+
+    let session =
+        pollster::block_on(wonnx::Session::from_path(&model_path)).expect("Session did not create");
+
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[10.0, 20.0, 30.0, 40.0];
+    input_data.insert("X".to_string(), data.into());
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &[11.0, 22.0, 33.0, 44.0]);
+}
+
+#[test]
+fn test_external_data_outside_model_directory() {
+    let directory = std::env::temp_dir().join("wonnx_test_external_data_outside");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    for location in ["../weights.bin", "data/../../weights.bin", "/etc/hosts"] {
+        let mut w = TensorProto::new();
+        w.set_name("W".to_string());
+        w.set_data_type(1); // FLOAT
+        w.set_dims(vec![4]);
+        w.set_data_location(TensorProto_DataLocation::EXTERNAL);
+        w.mut_external_data()
+            .push(external_data_entry("location", location));
+
+        let shape = vec![4];
+        let model = model(graph(
+            vec![tensor("X", &shape)],
+            vec![tensor("Y", &shape)],
+            vec![tensor("W", &shape)],
+            vec![w],
+            vec![node(vec!["X", "W"], vec!["Y"], "add", "Add", vec![])],
+        ));
+        let model_path = directory.join("model.onnx");
+        std::fs::write(&model_path, model.write_to_bytes().unwrap()).unwrap();
+
+        let result = pollster::block_on(wonnx::Session::from_path(&model_path));
+        assert!(
+            matches!(&result, Err(wonnx::SessionError::InvalidExternalData(name, _)) if name == "W"),
+            "location {} was not rejected",
+            location
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use protobuf::ProtobufEnum;
+use std::collections::HashMap;
+use wonnx::{
+    onnx::{TensorProto, TensorProto_DataType},
+    utils::{attribute, graph, model, node, tensor},
+    SessionConfig,
+};
+mod common;
+
+fn int64_initializer(name: &str, data: &[i64]) -> TensorProto {
+    let mut initializer = TensorProto::new();
+    initializer.set_name(name.to_string());
+    initializer.set_data_type(TensorProto_DataType::INT64.value());
+    initializer.set_dims(vec![data.len() as i64]);
+    initializer.set_int64_data(data.to_vec());
+    initializer
+}
+
+fn fallback_config() -> SessionConfig {
+    SessionConfig {
+        cpu_fallback: true,
+        ..SessionConfig::default()
+    }
+}
+
+#[test]
+fn test_cpu_fallback_slice() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..8).map(|x| x as f32 - 4.0).collect();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Neg -> A -> Slice -> B -> Relu -> Y, where Slice (which has no shader) is executed on the CPU
+    let model = model(graph(
+        vec![tensor("X", &[2, 4])],
+        vec![tensor("Y", &[2, 2])],
+        vec![tensor("A", &[2, 4]), tensor("B", &[2, 2])],
+        vec![
+            int64_initializer("starts", &[0, 3]),
+            int64_initializer("ends", &[2, 0]),
+            int64_initializer("axes", &[0, 1]),
+            int64_initializer("steps", &[1, -2]),
+        ],
+        vec![
+            node(vec!["X"], vec!["A"], "neg", "Neg", vec![]),
+            node(
+                vec!["A", "starts", "ends", "axes", "steps"],
+                vec!["B"],
+                "slice",
+                "Slice",
+                vec![],
+            ),
+            node(vec!["B"], vec!["Y"], "relu", "Relu", vec![]),
+        ],
+    ));
+
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(
+        model,
+        fallback_config(),
+    ))
+    .expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // A = [[4, 3, 2, 1], [0, -1, -2, -3]]; columns 3 and 1 (in that order) are selected
+    common::assert_eq_vector(result["Y"].as_slice(), &[1.0, 3.0, 0.0, 0.0]);
+}
+
+#[test]
+fn test_cpu_fallback_pad() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Pad -> A -> Neg -> Y
+    let model = model(graph(
+        vec![tensor("X", &[2, 2])],
+        vec![tensor("Y", &[2, 5])],
+        vec![tensor("A", &[2, 5])],
+        vec![int64_initializer("pads", &[0, 2, 0, 1])],
+        vec![
+            node(
+                vec!["X", "pads"],
+                vec!["A"],
+                "pad",
+                "Pad",
+                vec![attribute("mode", "reflect")],
+            ),
+            node(vec!["A"], vec!["Y"], "neg", "Neg", vec![]),
+        ],
+    ));
+
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(
+        model,
+        fallback_config(),
+    ))
+    .expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(
+        result["Y"].as_slice(),
+        &[-1.0, -2.0, -1.0, -2.0, -1.0, -3.0, -4.0, -3.0, -4.0, -3.0],
+    );
+}
+
+#[test]
+fn test_cpu_fallback_pad_crop() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..16).map(|x| x as f32).collect();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Pad -> Y, where the negative pads remove the outer rows and columns
+    let model = model(graph(
+        vec![tensor("X", &[4, 4])],
+        vec![tensor("Y", &[2, 2])],
+        vec![],
+        vec![int64_initializer("pads", &[-1, -1, -1, -1])],
+        vec![node(vec!["X", "pads"], vec!["Y"], "pad", "Pad", vec![])],
+    ));
+
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(
+        model,
+        fallback_config(),
+    ))
+    .expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &[5.0, 6.0, 9.0, 10.0]);
+}
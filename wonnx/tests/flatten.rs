@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+mod common;
+
+fn flatten_model(with_abs: bool) -> wonnx::onnx::ModelProto {
+    let flatten = node(
+        vec![if with_abs { "A" } else { "X" }],
+        vec!["Y"],
+        "flatten",
+        "Flatten",
+        vec![attribute("axis", 1)],
+    );
+    let nodes = if with_abs {
+        vec![node(vec!["X"], vec!["A"], "abs", "Abs", vec![]), flatten]
+    } else {
+        vec![flatten]
+    };
+
+    model(graph(
+        vec![tensor("X", &[2, 3, 4])],
+        vec![tensor("Y", &[2, 12])],
+        vec![tensor("A", &[2, 3, 4])],
+        vec![],
+        nodes,
+    ))
+}
+
+#[test]
+fn test_flatten() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let data: Vec<f32> = (0..24).map(|x| x as f32).collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Flatten -> Y and X -> Abs -> A -> Flatten -> Y
+    for with_abs in [false, true] {
+        let session = pollster::block_on(wonnx::Session::from_model(flatten_model(with_abs)))
+            .expect("Session did not create");
+        let result = pollster::block_on(session.run(&input_data)).unwrap();
+        common::assert_eq_vector(result["Y"].as_slice(), &data);
+    }
+}
@@ -1,5 +1,10 @@
+use protobuf::ProtobufEnum;
 use std::collections::HashMap;
-use wonnx::utils::{attribute, graph, model, node, tensor};
+use wonnx::{
+    onnx::{TensorProto, TensorProto_DataType},
+    utils::{attribute, graph, model, node, tensor, tensor_of_type},
+    GpuError, SessionError,
+};
 mod common;
 
 fn assert_gather(
@@ -88,3 +93,176 @@ fn gather() {
         0,
     );
 }
+
+#[test]
+fn gather_scalar_index() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // A scalar index selects a single row, so the output has one dimension less than data
+    assert_gather(
+        &[
+            0.0, 0.1, 0.2, 1.0, 1.1, 1.2, 2.0, 2.1, 2.2, 3.0, 3.1, 3.2, 4.0, 4.1, 4.2,
+        ],
+        &[5, 3],
+        &[2],
+        &[],
+        &[2.0, 2.1, 2.2],
+        &[3],
+        0,
+    );
+
+    // Selecting a single element results in a scalar
+    assert_gather(&[1.0, 1.2, 2.3, 3.4], &[4], &[-1], &[], &[3.4], &[], 0);
+}
+
+#[test]
+fn gather_negative_int64_index() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), [1.0, 2.0, 3.0, 4.0, 5.0].as_slice().into());
+
+    // Indices in ONNX models are usually 64-bit integers
+    let mut indices = TensorProto::new();
+    indices.set_name("I".to_string());
+    indices.set_data_type(TensorProto_DataType::INT64.value());
+    indices.set_dims(vec![1]);
+    indices.set_int64_data(vec![-1]);
+
+    // Model: X -> Gather(I) -> Y
+    let model = model(graph(
+        vec![tensor("X", &[5])],
+        vec![tensor("Y", &[1])],
+        vec![],
+        vec![indices],
+        vec![node(
+            vec!["X", "I"],
+            vec!["Y"],
+            "myGather",
+            "Gather",
+            vec![attribute("axis", 0)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &[5.0]);
+}
+
+/// Runs a Gather on [1, 2, 3, 4, 5] with the specified int64 indices supplied as inference input
+fn gather_int64_input(indices: &[i64]) -> Result<HashMap<String, Vec<f32>>, SessionError> {
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), [1.0, 2.0, 3.0, 4.0, 5.0].as_slice().into());
+    input_data.insert("I".to_string(), indices.into());
+
+    // Model: (X, I) -> Gather -> Y
+    let model = model(graph(
+        vec![
+            tensor("X", &[5]),
+            tensor_of_type("I", &[indices.len() as i64], TensorProto_DataType::INT64),
+        ],
+        vec![tensor("Y", &[indices.len() as i64])],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X", "I"],
+            vec!["Y"],
+            "myGather",
+            "Gather",
+            vec![attribute("axis", 0)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    pollster::block_on(session.run(&input_data))
+}
+
+#[test]
+fn gather_int64_input_indices() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let result = gather_int64_input(&[4, 0, -2, 1]).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &[5.0, 1.0, 4.0, 2.0]);
+}
+
+#[test]
+fn gather_int64_input_indices_out_of_range() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Indices that do not fit in 32 bits cannot be used by the GPU
+    let result = gather_int64_input(&[1, 1 << 40]);
+    assert!(matches!(
+        result,
+        Err(SessionError::GpuError(GpuError::InputOutOfRange(ref name, value)))
+            if name == "I" && value == 1 << 40
+    ));
+}
+
+fn assert_gather_nd(
+    data: &[f32],
+    data_shape: &[i64],
+    indices: &[i64],
+    indices_shape: &[i64],
+    output: &[f32],
+    output_shape: &[i64],
+    batch_dims: i64,
+) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.into());
+
+    let mut indices_tensor = TensorProto::new();
+    indices_tensor.set_name("I".to_string());
+    indices_tensor.set_data_type(TensorProto_DataType::INT64.value());
+    indices_tensor.set_dims(indices_shape.to_vec());
+    indices_tensor.set_int64_data(indices.to_vec());
+
+    // Model: (X, I) -> GatherND -> Y
+    let model = model(graph(
+        vec![tensor("X", data_shape)],
+        vec![tensor("Y", output_shape)],
+        vec![],
+        vec![indices_tensor],
+        vec![node(
+            vec!["X", "I"],
+            vec!["Y"],
+            "myGatherND",
+            "GatherND",
+            vec![attribute("batch_dims", batch_dims)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), output);
+}
+
+#[test]
+fn gather_nd_diagonal() {
+    assert_gather_nd(
+        &[0.0, 1.0, 2.0, 3.0],
+        &[2, 2],
+        &[0, 0, 1, 1],
+        &[2, 2],
+        &[0.0, 3.0],
+        &[2],
+        0,
+    );
+}
+
+#[test]
+fn gather_nd_batch_dims() {
+    // In each of the two batches, select one row (with a negative index in the second batch)
+    assert_gather_nd(
+        &[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0],
+        &[2, 2, 2],
+        &[1, -2],
+        &[2, 1],
+        &[2.0, 3.0, 4.0, 5.0],
+        &[2, 2],
+        1,
+    );
+}
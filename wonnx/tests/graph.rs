@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use wonnx::{
+    ir::IrError,
+    utils::{graph, initializer_of, model, node, tensor},
+    SessionError,
+};
+
+#[test]
+fn test_cyclic_graph() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![2, 2];
+
+    // Model: X, B -> Add -> A -> Relu -> B (B is used before it is produced)
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("A", &shape), tensor("B", &shape)],
+        vec![],
+        vec![
+            node(vec!["X", "B"], vec!["A"], "add", "Add", vec![]),
+            node(vec!["A"], vec!["B"], "relu", "Relu", vec![]),
+            node(vec!["B"], vec!["Y"], "neg", "Neg", vec![]),
+        ],
+    ));
+
+    let result = pollster::block_on(wonnx::Session::from_model(model));
+    assert!(matches!(
+        result,
+        Err(SessionError::IrError(IrError::CyclicGraph(_)))
+    ));
+}
+
+#[test]
+fn test_unreachable_output() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![2, 2];
+
+    // Model: X -> Relu -> Y, but the graph also lists Z as an output
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape), tensor("Z", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "relu", "Relu", vec![])],
+    ));
+
+    let result = pollster::block_on(wonnx::Session::from_model(model));
+    assert!(matches!(
+        result,
+        Err(SessionError::IrError(IrError::UnreachableOutput(output))) if output == "Z"
+    ));
+}
+
+#[test]
+fn test_input_as_output() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![2, 2];
+
+    // Model: X -> Relu -> Y, where the graph also lists its input X as an output
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape), tensor("X", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "relu", "Relu", vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[-1.0, 2.0, -3.0, 4.0];
+    input_data.insert("X".to_string(), data.into());
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], vec![0.0, 2.0, 0.0, 4.0]);
+    assert_eq!(result["X"], data.to_vec());
+}
+
+#[test]
+fn test_initializer_as_output() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![2, 2];
+
+    // Model: X -> Relu -> Y, where the graph also lists the initializer C as an output
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape), tensor("C", &shape)],
+        vec![],
+        vec![initializer_of("C", &shape, vec![1.0f32, 2.0, 3.0, 4.0])],
+        vec![node(vec!["X"], vec!["Y"], "relu", "Relu", vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut input_data = HashMap::new();
+    let data: &[f32] = &[-1.0, 2.0, -3.0, 4.0];
+    input_data.insert("X".to_string(), data.into());
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], vec![0.0, 2.0, 0.0, 4.0]);
+    assert_eq!(result["C"], vec![1.0, 2.0, 3.0, 4.0]);
+}
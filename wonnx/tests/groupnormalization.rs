@@ -0,0 +1,77 @@
+use approx::assert_abs_diff_eq;
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+/// Reference implementation of GroupNormalization (opset 18) for an input of shape [N, C, ...] holding `batches`
+/// batches, where the scale and bias hold a value for each group.
+fn group_normalization_reference(
+    input: &[f32],
+    batches: usize,
+    scale: &[f32],
+    bias: &[f32],
+    epsilon: f32,
+) -> Vec<f32> {
+    let num_groups = scale.len();
+    let group_size = input.len() / (batches * num_groups);
+    let mut output = vec![];
+    for (index, group) in input.chunks(group_size).enumerate() {
+        let mean = group.iter().sum::<f32>() / group_size as f32;
+        let variance = group.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / group_size as f32;
+        let inv_std_dev = 1.0 / (variance + epsilon).sqrt();
+        let group_index = index % num_groups;
+        output.extend(
+            group
+                .iter()
+                .map(|x| (x - mean) * inv_std_dev * scale[group_index] + bias[group_index]),
+        );
+    }
+    output
+}
+
+#[test]
+fn test_group_normalization() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let input: Vec<f32> = vec![
+        0.5, -1.0, 2.0, 3.5, // channel 0
+        1.0, 1.5, -2.0, 0.0, // channel 1
+        0.1, 0.2, 0.3, 0.4, // channel 2
+        -0.5, -0.6, 0.7, 0.8, // channel 3
+    ];
+    let scale: Vec<f32> = vec![1.5, -0.5];
+    let bias: Vec<f32> = vec![0.25, 1.0];
+    let epsilon = 1e-5;
+    let expected = group_normalization_reference(&input, 1, &scale, &bias, epsilon);
+
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), input.as_slice().into());
+    input_data.insert("scale".to_string(), scale.as_slice().into());
+    input_data.insert("bias".to_string(), bias.as_slice().into());
+
+    // Model: (X, scale, bias) -> GroupNormalization -> Y
+    let mut model = model(graph(
+        vec![
+            tensor("X", &[1, 4, 2, 2]),
+            tensor("scale", &[2]),
+            tensor("bias", &[2]),
+        ],
+        vec![tensor("Y", &[1, 4, 2, 2])],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X", "scale", "bias"],
+            vec!["Y"],
+            "group_normalization",
+            "GroupNormalization",
+            vec![attribute("num_groups", 2), attribute("epsilon", epsilon)],
+        )],
+    ));
+    model.mut_opset_import()[0].set_version(18);
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"].len(), expected.len());
+    for (actual, expected) in result["Y"].iter().zip(expected.iter()) {
+        assert_abs_diff_eq!(actual, expected, epsilon = 1e-4);
+    }
+}
@@ -0,0 +1,149 @@
+use approx::assert_abs_diff_eq;
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Reference implementation of a single-layer, forward GRU (see https://github.com/onnx/onnx/blob/main/docs/Operators.md#GRU)
+/// for a batch size of one. Returns (Y, Y_h).
+fn gru_reference(
+    x: &[f32],
+    w: &[f32],
+    r: &[f32],
+    b: &[f32],
+    initial_h: &[f32],
+    hidden_size: usize,
+    linear_before_reset: bool,
+) -> (Vec<f32>, Vec<f32>) {
+    let input_size = w.len() / (3 * hidden_size);
+    let mut h = initial_h.to_vec();
+    let mut y = vec![];
+
+    let dot = |a: &[f32], b: &[f32]| -> f32 { a.iter().zip(b.iter()).map(|(a, b)| a * b).sum() };
+
+    for xt in x.chunks(input_size) {
+        let input = |row: usize| dot(xt, &w[row * input_size..(row + 1) * input_size]) + b[row];
+        let recurrent = |state: &[f32], row: usize| {
+            dot(state, &r[row * hidden_size..(row + 1) * hidden_size]) + b[3 * hidden_size + row]
+        };
+
+        let z: Vec<f32> = (0..hidden_size)
+            .map(|unit| sigmoid(input(unit) + recurrent(&h, unit)))
+            .collect();
+        let reset: Vec<f32> = (0..hidden_size)
+            .map(|unit| sigmoid(input(hidden_size + unit) + recurrent(&h, hidden_size + unit)))
+            .collect();
+        let reset_h: Vec<f32> = reset.iter().zip(h.iter()).map(|(r, h)| r * h).collect();
+
+        let new_h: Vec<f32> = (0..hidden_size)
+            .map(|unit| {
+                let row = 2 * hidden_size + unit;
+                let candidate = if linear_before_reset {
+                    (input(row) + reset[unit] * recurrent(&h, row)).tanh()
+                } else {
+                    (input(row) + recurrent(&reset_h, row)).tanh()
+                };
+                (1.0 - z[unit]) * candidate + z[unit] * h[unit]
+            })
+            .collect();
+        h = new_h;
+        y.extend(h.iter());
+    }
+
+    (y, h)
+}
+
+/// Runs a GRU with the specified outputs (an empty name omits the output) and compares the outputs that are present
+/// against the reference implementation
+fn test_gru(linear_before_reset: bool, output_names: &[&str]) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (seq_length, batch_size, input_size, hidden_size) = (3, 1, 2, 3);
+
+    let x: Vec<f32> = vec![0.1, 0.2, 0.3, -0.4, 0.5, 0.6];
+    let w: Vec<f32> = (0..3 * hidden_size * input_size)
+        .map(|i| ((i as f32) * 0.37).sin() * 0.5)
+        .collect();
+    let r: Vec<f32> = (0..3 * hidden_size * hidden_size)
+        .map(|i| ((i as f32) * 0.73).cos() * 0.5)
+        .collect();
+    let b: Vec<f32> = (0..6 * hidden_size).map(|i| (i as f32) * 0.01).collect();
+    let initial_h = vec![0.1, -0.1, 0.2];
+
+    let (y, y_h) = gru_reference(&x, &w, &r, &b, &initial_h, hidden_size, linear_before_reset);
+
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.as_slice().into());
+    input_data.insert("W".to_string(), w.as_slice().into());
+    input_data.insert("R".to_string(), r.as_slice().into());
+    input_data.insert("B".to_string(), b.as_slice().into());
+    input_data.insert("initial_h".to_string(), initial_h.as_slice().into());
+
+    let (s, n, i, h) = (
+        seq_length as i64,
+        batch_size as i64,
+        input_size as i64,
+        hidden_size as i64,
+    );
+
+    // Model: (X, W, R, B, initial_h) -> GRU -> (Y, Y_h)
+    let output_shapes = [("Y", vec![s, 1, n, h]), ("Y_h", vec![1, n, h])];
+    let model = model(graph(
+        vec![
+            tensor("X", &[s, n, i]),
+            tensor("W", &[1, 3 * h, i]),
+            tensor("R", &[1, 3 * h, h]),
+            tensor("B", &[1, 6 * h]),
+            tensor("initial_h", &[1, n, h]),
+        ],
+        output_shapes
+            .iter()
+            .filter(|(name, _)| output_names.contains(name))
+            .map(|(name, dims)| tensor(name, dims))
+            .collect(),
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X", "W", "R", "B", "", "initial_h"],
+            output_names.to_vec(),
+            "gru",
+            "GRU",
+            vec![
+                attribute("hidden_size", h),
+                attribute("linear_before_reset", linear_before_reset as i64),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    for (name, expected) in [("Y", &y), ("Y_h", &y_h)] {
+        if !output_names.contains(&name) {
+            assert!(!result.contains_key(name));
+            continue;
+        }
+        assert_eq!(result[name].len(), expected.len());
+        for (actual, expected) in result[name].iter().zip(expected.iter()) {
+            assert_abs_diff_eq!(actual, expected, epsilon = 1e-5);
+        }
+    }
+}
+
+#[test]
+fn test_gru_default() {
+    test_gru(false, &["Y", "Y_h"]);
+}
+
+#[test]
+fn test_gru_linear_before_reset() {
+    test_gru(true, &["Y", "Y_h"]);
+}
+
+#[test]
+fn test_gru_without_y() {
+    // Y is omitted by leaving its name empty
+    test_gru(false, &["", "Y_h"]);
+}
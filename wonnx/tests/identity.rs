@@ -1,5 +1,9 @@
 use std::collections::HashMap;
-use wonnx::utils::{graph, model, node, tensor};
+use wonnx::{
+    onnx::TensorProto_DataType,
+    utils::{attribute, graph, initializer, model, node, tensor, tensor_of_type},
+    SessionConfig,
+};
 
 #[test]
 fn test_identity() {
@@ -53,3 +57,213 @@ fn test_double_identity() {
     let result = pollster::block_on(session.run(&input_data)).unwrap();
     assert_eq!(result["Z"], data);
 }
+
+#[test]
+fn test_dropout_mask() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let n: usize = 10;
+    let mut input_data = HashMap::new();
+
+    let data: Vec<f32> = (0..n).map(|x| x as f32).collect();
+    let dims = vec![n as i64];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Dropout -> (Y, M). At inference Dropout does not drop anything, so Y==X and the mask is all ones.
+    let model = model(graph(
+        vec![tensor("X", &dims)],
+        vec![
+            tensor("Y", &dims),
+            tensor_of_type("M", &dims, TensorProto_DataType::BOOL),
+        ],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y", "M"],
+            "dropout",
+            "Dropout",
+            vec![],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], data);
+    assert_eq!(result["M"], vec![1.0; n]);
+}
+
+#[test]
+fn test_aliased_outputs() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let n: usize = 16;
+    let mut input_data = HashMap::new();
+
+    let data: Vec<f32> = (0..n).map(|x| x as f32 - 8.0).collect();
+    let dims = vec![n as i64];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Relu -> R; R -> Identity -> Y1; R -> Identity -> Y2; R -> Neg -> Z. The outputs R, Y1 and Y2 all refer
+    // to the result of Relu, which is also used as input by Neg.
+    let model = model(graph(
+        vec![tensor("X", &dims)],
+        vec![
+            tensor("R", &dims),
+            tensor("Y1", &dims),
+            tensor("Y2", &dims),
+            tensor("Z", &dims),
+        ],
+        vec![],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["R"], "relu", "Relu", vec![]),
+            node(vec!["R"], vec!["Y1"], "a", "Identity", vec![]),
+            node(vec!["R"], vec!["Y2"], "b", "Identity", vec![]),
+            node(vec!["R"], vec!["Z"], "neg", "Neg", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let relu: Vec<f32> = data.iter().map(|x| x.max(0.0)).collect();
+    let negated: Vec<f32> = relu.iter().map(|x| -x).collect();
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["R"], relu);
+    assert_eq!(result["Y1"], relu);
+    assert_eq!(result["Y2"], relu);
+    assert_eq!(result["Z"], negated);
+
+    // The aliased outputs can also be read into separate buffers
+    let (mut y1, mut y2) = (vec![0.0; n], vec![0.0; n]);
+    {
+        let mut outputs = HashMap::new();
+        outputs.insert("Y1".to_string(), y1.as_mut_slice());
+        outputs.insert("Y2".to_string(), y2.as_mut_slice());
+        pollster::block_on(session.run_into(&input_data, &mut outputs)).unwrap();
+    }
+    assert_eq!(y1, relu);
+    assert_eq!(y2, relu);
+}
+
+#[test]
+fn test_disable_optimizer() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let dims = vec![8];
+    let data: Vec<f32> = (0..8).map(|x| x as f32 - 4.0).collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Identity -> A -> Relu -> B -> Dropout -> C, (C, X) -> Add -> Y. The optimizer removes the Identity and
+    // Dropout nodes; without it, their input buffer is forwarded.
+    let model = model(graph(
+        vec![tensor("X", &dims)],
+        vec![tensor("Y", &dims)],
+        vec![tensor("A", &dims), tensor("B", &dims), tensor("C", &dims)],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "identity", "Identity", vec![]),
+            node(vec!["A"], vec!["B"], "relu", "Relu", vec![]),
+            node(vec!["B"], vec!["C"], "dropout", "Dropout", vec![]),
+            node(vec!["C", "X"], vec!["Y"], "add", "Add", vec![]),
+        ],
+    ));
+
+    let mut results = vec![];
+    for disable_optimizer in [false, true] {
+        let config = SessionConfig {
+            disable_optimizer,
+            ..SessionConfig::default()
+        };
+        let session = pollster::block_on(wonnx::Session::from_model_with_config(
+            model.clone(),
+            config,
+        ))
+        .expect("Session did not create");
+        let op_types: Vec<String> = session
+            .execution_plan()
+            .nodes
+            .iter()
+            .map(|node| node.op_type.clone())
+            .collect();
+        let result = pollster::block_on(session.run(&input_data)).unwrap();
+        results.push((op_types, result["Y"].clone()));
+    }
+
+    // Without the optimizer, the Identity and Dropout nodes are scheduled too (they forward their input)
+    let expected: Vec<f32> = data.iter().map(|x| x.max(0.0) + x).collect();
+    assert_eq!(results[0].1, expected);
+    assert_eq!(results[1].1, expected);
+    assert!(!results[0].0.contains(&"Identity".to_string()));
+    assert_eq!(results[1].0, vec!["Identity", "Relu", "Dropout", "Add"]);
+}
+
+#[test]
+fn test_disable_optimizer_constant_input() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let data: &[f32] = &[-1.0, 3.0, 8.0];
+    let shape = vec![3];
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> Clip(X, min, max) -> Y, where min and max are the outputs of Constant nodes. The compiler needs the
+    // bounds as attributes, so these are moved there even when the optimizer is disabled.
+    let constant = |name: &str, value: f32| {
+        node(
+            vec![],
+            vec![name],
+            name,
+            "Constant",
+            vec![attribute("value", initializer(name, vec![value]))],
+        )
+    };
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![
+            constant("min", 0.0),
+            constant("max", 6.0),
+            node(vec!["X", "min", "max"], vec!["Y"], "clip", "Clip", vec![]),
+        ],
+    ));
+
+    let config = SessionConfig {
+        disable_optimizer: true,
+        ..SessionConfig::default()
+    };
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(model, config))
+        .expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"].as_slice(), &[0.0, 3.0, 6.0]);
+}
+
+#[test]
+fn test_disable_optimizer_dropout_training_mode() {
+    let shape = vec![4];
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "dropout",
+            "Dropout",
+            vec![attribute("training_mode", 1)],
+        )],
+    ));
+
+    // Dropout with training_mode=1 is rejected whether the optimizer is disabled or not
+    let config = SessionConfig {
+        disable_optimizer: true,
+        ..SessionConfig::default()
+    };
+    let result = pollster::block_on(wonnx::Session::from_model_with_config(model, config));
+    assert!(matches!(
+        result,
+        Err(wonnx::SessionError::OptimizerError(_))
+    ));
+}
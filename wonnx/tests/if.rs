@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use wonnx::onnx::{TensorProto, TensorProto_DataType};
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+mod common;
+
+/// Model: X -> If(condition) -> Y, where the then branch computes Relu(X) and the else branch computes Abs(X)
+fn if_model(condition_is_initializer: bool, condition: bool) -> wonnx::onnx::ModelProto {
+    let shape = vec![4];
+    let then_branch = graph(
+        vec![],
+        vec![tensor("Y_then", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y_then"], "relu", "Relu", vec![])],
+    );
+    let else_branch = graph(
+        vec![],
+        vec![tensor("Y_else", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y_else"], "abs", "Abs", vec![])],
+    );
+
+    let mut condition_tensor = TensorProto::new();
+    condition_tensor.set_name("C".to_string());
+    condition_tensor.set_data_type(TensorProto_DataType::BOOL as i32);
+    condition_tensor.set_int32_data(vec![condition as i32]);
+
+    let if_node = node(
+        vec!["C"],
+        vec!["Y"],
+        "if",
+        "If",
+        vec![
+            attribute("then_branch", then_branch),
+            attribute("else_branch", else_branch),
+        ],
+    );
+
+    let (initializers, nodes) = if condition_is_initializer {
+        (vec![condition_tensor], vec![if_node])
+    } else {
+        let constant = node(
+            vec![],
+            vec!["C"],
+            "constant",
+            "Constant",
+            vec![attribute("value", condition_tensor)],
+        );
+        (vec![], vec![constant, if_node])
+    };
+
+    model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        initializers,
+        nodes,
+    ))
+}
+
+#[test]
+fn test_if_constant_condition() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let data: Vec<f32> = vec![-2.0, -1.0, 1.0, 2.0];
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    for (condition_is_initializer, condition, expected) in [
+        (true, true, [0.0, 0.0, 1.0, 2.0]),
+        (true, false, [2.0, 1.0, 1.0, 2.0]),
+        (false, true, [0.0, 0.0, 1.0, 2.0]),
+        (false, false, [2.0, 1.0, 1.0, 2.0]),
+    ] {
+        let session = pollster::block_on(wonnx::Session::from_model(if_model(
+            condition_is_initializer,
+            condition,
+        )))
+        .expect("Session did not create");
+        let result = pollster::block_on(session.run(&input_data)).unwrap();
+        common::assert_eq_vector(result["Y"].as_slice(), &expected);
+    }
+}
@@ -0,0 +1,164 @@
+use approx::assert_abs_diff_eq;
+use std::collections::HashMap;
+use wonnx::onnx::OperatorSetIdProto;
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+/// Reference implementation of com.microsoft.SkipLayerNormalization, normalizing over the last dimension. Returns
+/// (output, mean, inv_std_var, input_skip_bias_sum).
+fn skip_layer_normalization_reference(
+    input: &[f32],
+    skip: &[f32],
+    gamma: &[f32],
+    beta: &[f32],
+    bias: &[f32],
+    epsilon: f32,
+) -> (Vec<f32>, Vec<f32>, Vec<f32>, Vec<f32>) {
+    let hidden_size = gamma.len();
+    let sum: Vec<f32> = input
+        .iter()
+        .zip(skip)
+        .enumerate()
+        .map(|(i, (x, s))| x + s + bias[i % hidden_size])
+        .collect();
+
+    let (mut output, mut means, mut inv_std_vars) = (vec![], vec![], vec![]);
+    for row in sum.chunks(hidden_size) {
+        let mean = row.iter().sum::<f32>() / hidden_size as f32;
+        let variance = row.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / hidden_size as f32;
+        let inv_std_var = 1.0 / (variance + epsilon).sqrt();
+        output.extend(
+            row.iter()
+                .enumerate()
+                .map(|(i, x)| (x - mean) * inv_std_var * gamma[i] + beta[i]),
+        );
+        means.push(mean);
+        inv_std_vars.push(inv_std_var);
+    }
+    (output, means, inv_std_vars, sum)
+}
+
+#[test]
+fn test_skip_layer_normalization() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let input: Vec<f32> = vec![0.5, -1.0, 2.0, 3.5, 1.0, 1.5, -2.0, 0.0];
+    let skip: Vec<f32> = vec![0.1, 0.2, 0.3, 0.4, -0.5, -0.6, 0.7, 0.8];
+    let gamma: Vec<f32> = vec![1.0, 0.5, 2.0, -1.0];
+    let beta: Vec<f32> = vec![0.0, 0.1, -0.1, 0.2];
+    let bias: Vec<f32> = vec![0.25, -0.25, 0.5, 0.0];
+    let epsilon = 1e-5;
+
+    let (output, mean, inv_std_var, sum) =
+        skip_layer_normalization_reference(&input, &skip, &gamma, &beta, &bias, epsilon);
+
+    let mut input_data = HashMap::new();
+    input_data.insert("input".to_string(), input.as_slice().into());
+    input_data.insert("skip".to_string(), skip.as_slice().into());
+    input_data.insert("gamma".to_string(), gamma.as_slice().into());
+    input_data.insert("beta".to_string(), beta.as_slice().into());
+    input_data.insert("bias".to_string(), bias.as_slice().into());
+
+    // Model: (input, skip, gamma, beta, bias) -> SkipLayerNormalization -> (Y, mean, inv_std_var, sum)
+    let mut skip_layer_normalization = node(
+        vec!["input", "skip", "gamma", "beta", "bias"],
+        vec!["Y", "mean", "inv_std_var", "sum"],
+        "skip_layer_normalization",
+        "SkipLayerNormalization",
+        vec![attribute("epsilon", epsilon)],
+    );
+    skip_layer_normalization.set_domain("com.microsoft".to_string());
+
+    let mut model = model(graph(
+        vec![
+            tensor("input", &[1, 2, 4]),
+            tensor("skip", &[1, 2, 4]),
+            tensor("gamma", &[4]),
+            tensor("beta", &[4]),
+            tensor("bias", &[4]),
+        ],
+        vec![
+            tensor("Y", &[1, 2, 4]),
+            tensor("mean", &[1, 2, 1]),
+            tensor("inv_std_var", &[1, 2, 1]),
+            tensor("sum", &[1, 2, 4]),
+        ],
+        vec![],
+        vec![],
+        vec![skip_layer_normalization],
+    ));
+    let mut microsoft_opset = OperatorSetIdProto::new();
+    microsoft_opset.set_domain("com.microsoft".to_string());
+    microsoft_opset.set_version(1);
+    model.mut_opset_import().push(microsoft_opset);
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    for (name, expected) in [
+        ("Y", &output),
+        ("mean", &mean),
+        ("inv_std_var", &inv_std_var),
+        ("sum", &sum),
+    ] {
+        assert_eq!(result[name].len(), expected.len());
+        for (actual, expected) in result[name].iter().zip(expected.iter()) {
+            assert_abs_diff_eq!(actual, expected, epsilon = 1e-4);
+        }
+    }
+}
+
+#[test]
+fn test_layer_normalization() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let input: Vec<f32> = vec![0.5, -1.0, 2.0, 3.5, 1.0, 1.5, -2.0, 0.0];
+    let gamma: Vec<f32> = vec![1.0, 0.5, 2.0, -1.0];
+    let beta: Vec<f32> = vec![0.0, 0.1, -0.1, 0.2];
+    let epsilon = 1e-5;
+
+    // Without skip connection and bias, SkipLayerNormalization is the same as LayerNormalization
+    let (output, mean, inv_std_var, _) =
+        skip_layer_normalization_reference(&input, &[0.0; 8], &gamma, &beta, &[0.0; 4], epsilon);
+
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), input.as_slice().into());
+    input_data.insert("scale".to_string(), gamma.as_slice().into());
+    input_data.insert("B".to_string(), beta.as_slice().into());
+
+    // Model: (X, scale, B) -> LayerNormalization -> (Y, mean, inv_std_dev), normalizing over the last two dimensions
+    let model = model(graph(
+        vec![
+            tensor("X", &[2, 2, 2]),
+            tensor("scale", &[2, 2]),
+            tensor("B", &[2, 2]),
+        ],
+        vec![
+            tensor("Y", &[2, 2, 2]),
+            tensor("mean", &[2, 1, 1]),
+            tensor("inv_std_dev", &[2, 1, 1]),
+        ],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X", "scale", "B"],
+            vec!["Y", "mean", "inv_std_dev"],
+            "layer_normalization",
+            "LayerNormalization",
+            vec![attribute("axis", 1), attribute("epsilon", epsilon)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    for (name, expected) in [
+        ("Y", &output),
+        ("mean", &mean),
+        ("inv_std_dev", &inv_std_var),
+    ] {
+        assert_eq!(result[name].len(), expected.len());
+        for (actual, expected) in result[name].iter().zip(expected.iter()) {
+            assert_abs_diff_eq!(actual, expected, epsilon = 1e-4);
+        }
+    }
+}
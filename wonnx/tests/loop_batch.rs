@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use wonnx::{
+    utils::{attribute, graph, initializer, model, node, tensor},
+    SessionConfig,
+};
+
+#[test]
+fn test_loop_batch_softmax() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (batch_size, n) = (4, 3);
+    let data: Vec<f32> = (0..batch_size * n).map(|i| (i % 5) as f32 * 0.5).collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Softmax -> Y. The Softmax shader normalizes over the whole tensor, so it only supports a batch size
+    // of one.
+    let model = model(graph(
+        vec![tensor("X", &[batch_size, n])],
+        vec![tensor("Y", &[batch_size, n])],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "softmax",
+            "Softmax",
+            vec![attribute("axis", 1)],
+        )],
+    ));
+
+    let config = SessionConfig {
+        loop_batch: true,
+        ..SessionConfig::default()
+    };
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(model, config))
+        .expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // Each row is normalized separately
+    let expected: Vec<f32> = data
+        .chunks(n as usize)
+        .flat_map(|row| {
+            let max = row.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            let sum: f32 = row.iter().map(|x| (x - max).exp()).sum();
+            row.iter()
+                .map(|x| (x - max).exp() / sum)
+                .collect::<Vec<f32>>()
+        })
+        .collect();
+    assert_eq!(result["Y"].len(), expected.len());
+    for (actual, expected) in result["Y"].iter().zip(expected.iter()) {
+        approx::assert_abs_diff_eq!(actual, expected, epsilon = 1e-5);
+    }
+}
+
+#[test]
+fn test_loop_batch_unbatched_output() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (batch_size, n) = (4, 3);
+    let data: Vec<f32> = (0..batch_size * n).map(|i| i as f32).collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Neg -> Y, W -> Neg -> Z. Z does not depend on the batched input, so it is the same for each batch
+    // element and only returned once.
+    let mut weights = initializer("W", vec![1.0, 2.0]);
+    weights.set_dims(vec![2]);
+    let model = model(graph(
+        vec![tensor("X", &[batch_size, n])],
+        vec![tensor("Y", &[batch_size, n]), tensor("Z", &[2])],
+        vec![],
+        vec![weights],
+        vec![
+            node(vec!["X"], vec!["Y"], "negX", "Neg", vec![]),
+            node(vec!["W"], vec!["Z"], "negW", "Neg", vec![]),
+        ],
+    ));
+
+    let config = SessionConfig {
+        loop_batch: true,
+        ..SessionConfig::default()
+    };
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(model, config))
+        .expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], data.iter().map(|x| -x).collect::<Vec<f32>>());
+    assert_eq!(result["Z"], vec![-1.0, -2.0]);
+
+    // The length of each input needs to be a multiple of the batch size
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data[0..10].into());
+    assert!(matches!(
+        pollster::block_on(session.run(&input_data)),
+        Err(wonnx::SessionError::InvalidBatchLength(_, 10, 4))
+    ));
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+fn run_pool(
+    op_type: &str,
+    data: &[f32],
+    shape: &[i64],
+    output_shape: &[i64],
+    attributes: Vec<wonnx::onnx::AttributeProto>,
+) -> Vec<f32> {
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> (Global)LpPool -> Y
+    let model = model(graph(
+        vec![tensor("X", shape)],
+        vec![tensor("Y", output_shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "pool", op_type, attributes)],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut result = pollster::block_on(session.run(&input_data)).unwrap();
+    result.remove("Y").unwrap()
+}
+
+#[test]
+fn test_global_lp_pool() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let data: Vec<f32> = (0..8).map(|x| x as f32).collect();
+
+    // The L2 norm of each channel: sqrt(0 + 1 + 4 + 9) and sqrt(16 + 25 + 36 + 49)
+    let result = run_pool("GlobalLpPool", &data, &[1, 2, 2, 2], &[1, 2, 1, 1], vec![]);
+    let expected = [14.0f32.sqrt(), 126.0f32.sqrt()];
+    assert_eq!(result.len(), expected.len());
+    for (actual, expected) in result.iter().zip(expected.iter()) {
+        approx::assert_relative_eq!(actual, expected, max_relative = 1e-6);
+    }
+}
+
+#[test]
+fn test_lp_pool() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let data: Vec<f32> = (0..16).map(|x| (x as f32) - 8.0).collect();
+
+    // The L1 norm of each 2x2 window (which here covers the whole channel)
+    let result = run_pool(
+        "LpPool",
+        &data,
+        &[1, 4, 2, 2],
+        &[1, 4, 1, 1],
+        vec![attribute("kernel_shape", vec![2, 2]), attribute("p", 1)],
+    );
+    assert_eq!(result, vec![26.0, 10.0, 6.0, 22.0]);
+}
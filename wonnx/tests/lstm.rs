@@ -0,0 +1,153 @@
+use approx::assert_abs_diff_eq;
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+/// Reference implementation of a single-layer, forward LSTM (see https://github.com/onnx/onnx/blob/main/docs/Operators.md#LSTM)
+/// for a batch size of one. Returns (Y, Y_h, Y_c).
+#[allow(clippy::too_many_arguments)]
+fn lstm_reference(
+    x: &[f32],
+    w: &[f32],
+    r: &[f32],
+    b: &[f32],
+    initial_h: &[f32],
+    initial_c: &[f32],
+    input_size: usize,
+    hidden_size: usize,
+) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+    let mut h = initial_h.to_vec();
+    let mut c = initial_c.to_vec();
+    let mut y = vec![];
+
+    for xt in x.chunks(input_size) {
+        let gate = |gate_index: usize, unit: usize| -> f32 {
+            let row = gate_index * hidden_size + unit;
+            let input: f32 = (0..input_size)
+                .map(|k| xt[k] * w[row * input_size + k])
+                .sum();
+            let recurrent: f32 = (0..hidden_size)
+                .map(|k| h[k] * r[row * hidden_size + k])
+                .sum();
+            input + recurrent + b[row] + b[4 * hidden_size + row]
+        };
+
+        let mut new_h = vec![0.0; hidden_size];
+        for unit in 0..hidden_size {
+            let i = sigmoid(gate(0, unit));
+            let o = sigmoid(gate(1, unit));
+            let f = sigmoid(gate(2, unit));
+            let cell = gate(3, unit).tanh();
+            c[unit] = f * c[unit] + i * cell;
+            new_h[unit] = o * c[unit].tanh();
+        }
+        h = new_h;
+        y.extend(h.iter());
+    }
+
+    (y, h, c)
+}
+
+/// Runs an LSTM with the specified outputs (an empty name omits the output) and compares the outputs that are present
+/// against the reference implementation
+fn test_lstm_outputs(output_names: &[&str]) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (seq_length, batch_size, input_size, hidden_size) = (3, 1, 2, 3);
+
+    let x: Vec<f32> = vec![0.1, 0.2, 0.3, -0.4, 0.5, 0.6];
+    let w: Vec<f32> = (0..4 * hidden_size * input_size)
+        .map(|i| ((i as f32) * 0.37).sin() * 0.5)
+        .collect();
+    let r: Vec<f32> = (0..4 * hidden_size * hidden_size)
+        .map(|i| ((i as f32) * 0.73).cos() * 0.5)
+        .collect();
+    let b: Vec<f32> = (0..8 * hidden_size).map(|i| (i as f32) * 0.01).collect();
+    let initial_h = vec![0.1, -0.1, 0.2];
+    let initial_c = vec![0.3, 0.0, -0.3];
+
+    let (y, y_h, y_c) = lstm_reference(
+        &x,
+        &w,
+        &r,
+        &b,
+        &initial_h,
+        &initial_c,
+        input_size,
+        hidden_size,
+    );
+
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.as_slice().into());
+    input_data.insert("W".to_string(), w.as_slice().into());
+    input_data.insert("R".to_string(), r.as_slice().into());
+    input_data.insert("B".to_string(), b.as_slice().into());
+    input_data.insert("initial_h".to_string(), initial_h.as_slice().into());
+    input_data.insert("initial_c".to_string(), initial_c.as_slice().into());
+
+    let (s, n, i, h) = (
+        seq_length as i64,
+        batch_size as i64,
+        input_size as i64,
+        hidden_size as i64,
+    );
+
+    // Model: (X, W, R, B, initial_h, initial_c) -> LSTM -> (Y, Y_h, Y_c)
+    let output_shapes = [
+        ("Y", vec![s, 1, n, h]),
+        ("Y_h", vec![1, n, h]),
+        ("Y_c", vec![1, n, h]),
+    ];
+    let model = model(graph(
+        vec![
+            tensor("X", &[s, n, i]),
+            tensor("W", &[1, 4 * h, i]),
+            tensor("R", &[1, 4 * h, h]),
+            tensor("B", &[1, 8 * h]),
+            tensor("initial_h", &[1, n, h]),
+            tensor("initial_c", &[1, n, h]),
+        ],
+        output_shapes
+            .iter()
+            .filter(|(name, _)| output_names.contains(name))
+            .map(|(name, dims)| tensor(name, dims))
+            .collect(),
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X", "W", "R", "B", "", "initial_h", "initial_c"],
+            output_names.to_vec(),
+            "lstm",
+            "LSTM",
+            vec![attribute("hidden_size", h)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    for (name, expected) in [("Y", &y), ("Y_h", &y_h), ("Y_c", &y_c)] {
+        if !output_names.contains(&name) {
+            assert!(!result.contains_key(name));
+            continue;
+        }
+        assert_eq!(result[name].len(), expected.len());
+        for (actual, expected) in result[name].iter().zip(expected.iter()) {
+            assert_abs_diff_eq!(actual, expected, epsilon = 1e-5);
+        }
+    }
+}
+
+#[test]
+fn test_lstm() {
+    test_lstm_outputs(&["Y", "Y_h", "Y_c"]);
+}
+
+#[test]
+fn test_lstm_without_y() {
+    // Y is omitted by leaving its name empty, Y_c by leaving it out of the outputs
+    test_lstm_outputs(&["", "Y_h"]);
+}
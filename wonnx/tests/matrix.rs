@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use wonnx::utils::{attribute, graph, initializer, model, node, tensor};
 
+mod common;
+
 #[test]
 fn test_matmul_square_matrix() {
     let n = 16;
@@ -40,6 +42,161 @@ fn test_matmul_square_matrix() {
     assert_eq!(result["C"].as_slice(), sum.as_slice().unwrap());
 }
 
+#[test]
+fn test_gemm_without_bias() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Both the single-row and the general Gemm shader are tested
+    for m in [1, 16] {
+        let (k, n) = (16, 16);
+        let mut input_data = HashMap::new();
+        let data_a = ndarray::Array2::from_shape_fn((m, k), |(i, j)| ((i + j) % 5) as f32);
+        let data_b = ndarray::Array2::from_shape_fn((k, n), |(i, j)| ((i * j) % 3) as f32 - 1.0);
+        let expected = data_a.dot(&data_b) * 2.0;
+
+        input_data.insert("A".to_string(), data_a.as_slice().unwrap().into());
+        input_data.insert("B".to_string(), data_b.as_slice().unwrap().into());
+
+        let (m, k, n) = (m as i64, k as i64, n as i64);
+        let model = model(graph(
+            vec![tensor("A", &[m, k]), tensor("B", &[k, n])],
+            vec![tensor("Y", &[m, n])],
+            vec![],
+            vec![],
+            vec![node(
+                vec!["A", "B"],
+                vec!["Y"],
+                "Gemm",
+                "Gemm",
+                vec![attribute("alpha", 2.0), attribute("beta", 3.0)],
+            )],
+        ));
+
+        let session =
+            pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+        let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+        assert_eq!(result["Y"].as_slice(), expected.as_slice().unwrap());
+    }
+}
+
+fn matmul_model(m: i64, k: i64, n: i64) -> wonnx::onnx::ModelProto {
+    model(graph(
+        vec![tensor("A", &[m, k]), tensor("B", &[k, n])],
+        vec![tensor("C", &[m, n])],
+        vec![],
+        vec![],
+        vec![node(vec!["A", "B"], vec!["C"], "MatMul", "MatMul", vec![])],
+    ))
+}
+
+fn matmul_session(m: i64, k: i64, n: i64) -> wonnx::Session {
+    pollster::block_on(wonnx::Session::from_model(matmul_model(m, k, n)))
+        .expect("Session did not create")
+}
+
+#[test]
+fn test_matmul_tiled() {
+    // Both output dimensions exceed the threshold, so this is computed by the tiled shader. When the number of rows is
+    // at or below the threshold, the (non-tiled) matrix shader is used, so the first rows are computed with that shader
+    // for comparison. Values are small integers so that both paths (and the reference) give exact results.
+    let n = 64;
+    let rows = wonnx::compiler::GEMM_TILED_THRESHOLD as usize;
+    let data_a: Vec<f32> = (0..n * n).map(|i| ((i % 5) as f32) - 2.0).collect();
+    let data_b: Vec<f32> = (0..n * n).map(|i| ((i % 3) as f32) - 1.0).collect();
+
+    let expected = ndarray::Array2::from_shape_vec((n, n), data_a.clone())
+        .unwrap()
+        .dot(&ndarray::Array2::from_shape_vec((n, n), data_b.clone()).unwrap());
+
+    let mut input_data = HashMap::new();
+    input_data.insert("A".to_string(), data_a.as_slice().into());
+    input_data.insert("B".to_string(), data_b.as_slice().into());
+    let tiled =
+        pollster::block_on(matmul_session(n as i64, n as i64, n as i64).run(&input_data)).unwrap();
+    assert_eq!(tiled["C"].as_slice(), expected.as_slice().unwrap());
+
+    let mut input_data = HashMap::new();
+    input_data.insert("A".to_string(), data_a[0..rows * n].into());
+    input_data.insert("B".to_string(), data_b.as_slice().into());
+    let untiled =
+        pollster::block_on(matmul_session(rows as i64, n as i64, n as i64).run(&input_data))
+            .unwrap();
+    assert_eq!(untiled["C"].as_slice(), &tiled["C"][0..rows * n]);
+}
+
+/// Times the tiled shader for a [512, 512] x [512, 512] matrix multiplication. The non-tiled shader is not used for
+/// matrices of this size, so it cannot be compared on the same problem. Run with
+/// `cargo test --release --test matrix benchmark_matmul_tiled -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn benchmark_matmul_tiled() {
+    let n = 512;
+    let data_a: Vec<f32> = (0..n * n).map(|i| ((i % 5) as f32) - 2.0).collect();
+    let data_b: Vec<f32> = (0..n * n).map(|i| ((i % 3) as f32) - 1.0).collect();
+
+    let benchmark = common::benchmark::Benchmark::new();
+    let session = benchmark.session(matmul_model(n, n, n), wonnx::SessionConfig::default());
+    let input_data = benchmark.upload(&[("A", &[n, n], &data_a), ("B", &[n, n], &data_b)]);
+    let duration = benchmark.time(&session, &input_data);
+    println!(
+        "[{n}, {n}] x [{n}, {n}] tiled: {:?} ({:.1} GFLOP/s)",
+        duration,
+        (2 * n * n * n) as f64 / duration.as_secs_f64() / 1e9,
+        n = n
+    );
+}
+
+/// Multiplies A and B (either of which may be one-dimensional) and checks the shape of the result
+fn matmul_of_shapes(
+    a: &[f32],
+    a_shape: &[i64],
+    b: &[f32],
+    b_shape: &[i64],
+    c_shape: &[i64],
+) -> Vec<f32> {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    input_data.insert("A".to_string(), a.into());
+    input_data.insert("B".to_string(), b.into());
+
+    let model = model(graph(
+        vec![tensor("A", a_shape), tensor("B", b_shape)],
+        vec![tensor("C", c_shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["A", "B"], vec!["C"], "MatMul", "MatMul", vec![])],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut result = pollster::block_on(session.run(&input_data)).unwrap();
+    result.remove("C").unwrap()
+}
+
+#[test]
+fn test_matmul_vector_matrix() {
+    // [3] x [3, 2] -> [2]
+    let b = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let result = matmul_of_shapes(&[1.0, -1.0, 2.0], &[3], &b, &[3, 2], &[2]);
+    assert_eq!(result, vec![8.0, 10.0]);
+}
+
+#[test]
+fn test_matmul_matrix_vector() {
+    // [2, 3] x [3] -> [2]
+    let a = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    let result = matmul_of_shapes(&a, &[2, 3], &[1.0, -1.0, 2.0], &[3], &[2]);
+    assert_eq!(result, vec![5.0, 11.0]);
+}
+
+#[test]
+fn test_matmul_vector_vector() {
+    // [3] x [3] -> [] (a scalar)
+    let result = matmul_of_shapes(&[1.0, 2.0, 3.0], &[3], &[4.0, 5.0, 6.0], &[3], &[]);
+    assert_eq!(result, vec![32.0]);
+}
+
 #[test]
 fn test_two_transposes() {
     let mut input_data = HashMap::new();
@@ -77,8 +234,72 @@ fn test_two_transposes() {
     assert_eq!(result["Z"], data);
 }
 
+/// Transpose a tensor of the specified shape (holding 0, 1, 2, ...) and compare to a reference implementation
+fn check_transpose(dims: &[i64], perm: &[i64]) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let element_count = dims.iter().product::<i64>();
+    let data = (0..element_count).map(|x| x as f32).collect::<Vec<f32>>();
+    input_data.insert("X".to_string(), data.as_slice().into());
+    let output_dims: Vec<i64> = perm.iter().map(|p| dims[*p as usize]).collect();
+
+    // Model: X -> Transpose -> Y
+    let model = model(graph(
+        vec![tensor("X", dims)],
+        vec![tensor("Y", &output_dims)],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "Transpose",
+            "Transpose",
+            vec![attribute("perm", perm.to_vec())],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // Element i of the output is at the index of the input that is obtained by permuting its output coordinates back
+    let input_chunks: Vec<i64> = (0..dims.len())
+        .map(|axis| dims[axis + 1..].iter().product())
+        .collect();
+    let expected: Vec<f32> = (0..element_count)
+        .map(|mut index| {
+            let mut input_index = 0;
+            for (axis, p) in perm.iter().enumerate().rev() {
+                input_index += (index % output_dims[axis]) * input_chunks[*p as usize];
+                index /= output_dims[axis];
+            }
+            input_index as f32
+        })
+        .collect();
+    assert_eq!(result["Y"], expected);
+}
+
+#[test]
+fn test_transpose_3d() {
+    check_transpose(&[2, 3, 4], &[2, 0, 1]);
+}
+
+#[test]
+fn test_transpose_4d() {
+    check_transpose(&[2, 3, 4, 5], &[1, 3, 0, 2]);
+}
+
 #[test]
 fn test_split() {
+    check_split(1);
+}
+
+#[test]
+fn test_split_negative_axis() {
+    check_split(-1);
+}
+
+fn check_split(axis: i64) {
     let mut input_data = HashMap::new();
     let data = (1..=2 * 6).map(|x| x as f32).collect::<Vec<f32>>();
     input_data.insert("X".to_string(), data.as_slice().into());
@@ -93,7 +314,7 @@ fn test_split() {
             vec!["Y", "W"],
             "Split",
             "Split",
-            vec![attribute("axis", 1)],
+            vec![attribute("axis", axis)],
         )],
     ));
 
@@ -163,3 +384,208 @@ fn test_resize() {
     //];
     //assert_eq!(result["Y"], test_y);
 }
+
+#[test]
+fn test_resize_fractional_scales() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data = (1..=8).map(|x| x as f32).collect::<Vec<f32>>();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let model = model(graph(
+        vec![tensor("X", &[1, 2, 2, 2])],
+        vec![tensor("Y", &[1, 2, 3, 3])],
+        vec![],
+        vec![initializer("scales", vec![1., 1., 1.5, 1.5])],
+        vec![node(
+            vec!["X", "" /* roi */, "scales"],
+            vec!["Y"],
+            "Resize",
+            "Resize",
+            vec![attribute("nearest_mode", "floor")],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // With half_pixel coordinates, output positions 0, 1, 2 map to input positions 0 (clamped), 0 and 1
+    let test_y = vec![
+        1., 1., 2., 1., 1., 2., 3., 3., 4., //
+        5., 5., 6., 5., 5., 6., 7., 7., 8.,
+    ];
+    assert_eq!(result["Y"], test_y);
+}
+
+/// Resize a 3x3 input to the specified (square) size using the `sizes` attribute, and check the result against a reference
+/// that maps output position d to input position floor((d + 0.5) * 3 / size - 0.5) using the exact sizes
+fn test_resize_sizes(size: usize) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data = (1..=9).map(|x| x as f32).collect::<Vec<f32>>();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let n = size as i64;
+    let model = model(graph(
+        vec![tensor("X", &[1, 1, 3, 3])],
+        vec![tensor("Y", &[1, 1, n, n])],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "Resize",
+            "Resize",
+            vec![
+                attribute("sizes", vec![1, 1, n, n]),
+                attribute("nearest_mode", "floor"),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    let source_positions: Vec<usize> = (0..size)
+        .map(|d| {
+            ((d as f64 + 0.5) * 3.0 / size as f64 - 0.5)
+                .floor()
+                .clamp(0.0, 2.0) as usize
+        })
+        .collect();
+    let mut test_y = vec![];
+    for y in &source_positions {
+        for x in &source_positions {
+            test_y.push(data[y * 3 + x]);
+        }
+    }
+    assert_eq!(result["Y"], test_y);
+}
+
+#[test]
+fn test_resize_sizes_3_to_7() {
+    // Output position 3 maps to input position 1 exactly
+    test_resize_sizes(7);
+}
+
+#[test]
+fn test_resize_sizes_3_to_5() {
+    // Output position 2 maps to input position 1 exactly. A scale rounded to two decimals (1.67) would map it to
+    // 2.5 / 1.67 - 0.5 = 0.997, and so select input position 0 instead.
+    test_resize_sizes(5);
+}
+
+#[test]
+fn test_resize_pytorch_half_pixel() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data = (1..=16).map(|x| x as f32).collect::<Vec<f32>>();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let model = model(graph(
+        vec![tensor("X", &[1, 1, 4, 4])],
+        vec![tensor("Y", &[1, 1, 1, 1])],
+        vec![],
+        vec![initializer("scales", vec![1., 1., 0.25, 0.25])],
+        vec![node(
+            vec!["X", "" /* roi */, "scales"],
+            vec!["Y"],
+            "Resize",
+            "Resize",
+            vec![
+                attribute("coordinate_transformation_mode", "pytorch_half_pixel"),
+                attribute("nearest_mode", "floor"),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // Axes resized to a single element take the first input element (half_pixel would map to input position 1)
+    assert_eq!(result["Y"], vec![1.]);
+}
+
+#[test]
+fn test_resize_channel_axis() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data = (1..=8).map(|x| x as f32).collect::<Vec<f32>>();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let model = model(graph(
+        vec![tensor("X", &[1, 2, 2, 2])],
+        vec![tensor("Y", &[1, 4, 2, 2])],
+        vec![],
+        vec![initializer("scales", vec![1., 2., 1., 1.])],
+        vec![node(
+            vec!["X", "" /* roi */, "scales"],
+            vec!["Y"],
+            "Resize",
+            "Resize",
+            vec![attribute("nearest_mode", "floor")],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // Output channels 0, 1, 2, 3 map to input channels 0 (clamped), 0, 0 and 1
+    let test_y = vec![
+        1., 2., 3., 4., 1., 2., 3., 4., 1., 2., 3., 4., 5., 6., 7., 8.,
+    ];
+    assert_eq!(result["Y"], test_y);
+}
+
+fn check_resize_crop(roi: Vec<f32>, scales: Vec<f32>, expected: &[f32]) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data = (0..16).map(|x| x as f32).collect::<Vec<f32>>();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let model = model(graph(
+        vec![tensor("X", &[1, 1, 4, 4])],
+        vec![tensor("Y", &[1, 1, 3, 3])],
+        vec![],
+        vec![initializer("roi", roi), initializer("scales", scales)],
+        vec![node(
+            vec!["X", "roi", "scales"],
+            vec!["Y"],
+            "Resize",
+            "Resize",
+            vec![
+                attribute("nearest_mode", "floor"),
+                attribute("coordinate_transformation_mode", "tf_crop_and_resize"),
+                attribute("extrapolation_value", -1.0),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], expected);
+}
+
+#[test]
+fn test_resize_tf_crop_and_resize() {
+    // The region of interest is the bottom right quarter of the image, which is resized to 3x3.
+    // The output coordinates map to input coordinates 1.5, 2.25 and 3.0 along both spatial axes.
+    check_resize_crop(
+        vec![0., 0., 0.5, 0.5, 1., 1., 1., 1.],
+        vec![1., 1., 1.5, 1.5],
+        &[5., 6., 7., 9., 10., 11., 13., 14., 15.],
+    );
+
+    // The region of interest extends beyond the input, so the last output coordinate (4.5) is set to the extrapolation
+    // value
+    check_resize_crop(
+        vec![0., 0., 0.5, 0.5, 1., 1., 1.5, 1.5],
+        vec![1., 1., 0.75, 0.75],
+        &[5., 7., -1., 13., 15., -1., -1., -1., -1.],
+    );
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use wonnx::{
+    onnx::TensorProto_DataType,
+    utils::{attribute, graph, model, node, tensor, tensor_of_type},
+};
+
+fn max_pool_with_indices(storage_order: i64) -> (Vec<f32>, Vec<f32>) {
+    #[rustfmt::skip]
+    let data: &[f32] = &[
+        -1.0, -5.0, 3.0, 2.0,
+        -2.0, -3.0, 1.0, 4.0,
+        0.0, 9.0, -7.0, -8.0,
+        5.0, 1.0, -9.0, -6.0,
+    ];
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: X -> MaxPool -> (Y, I)
+    let model = model(graph(
+        vec![tensor("X", &[1, 1, 4, 4])],
+        vec![
+            tensor("Y", &[1, 1, 2, 2]),
+            tensor_of_type("I", &[1, 1, 2, 2], TensorProto_DataType::INT64),
+        ],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y", "I"],
+            "pool",
+            "MaxPool",
+            vec![
+                attribute("kernel_shape", vec![2, 2]),
+                attribute("strides", vec![2, 2]),
+                attribute("storage_order", storage_order),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut result = pollster::block_on(session.run(&input_data)).unwrap();
+    (result.remove("Y").unwrap(), result.remove("I").unwrap())
+}
+
+#[test]
+fn test_max_pool_indices() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // The maximum of each 2x2 window is at (0, 0), (1, 3), (2, 1) and (3, 3) respectively
+    let (values, indices) = max_pool_with_indices(0);
+    assert_eq!(values, vec![-1.0, 4.0, 9.0, -6.0]);
+    assert_eq!(indices, vec![0.0, 7.0, 9.0, 15.0]);
+}
+
+#[test]
+fn test_max_pool_indices_column_major() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let (values, indices) = max_pool_with_indices(1);
+    assert_eq!(values, vec![-1.0, 4.0, 9.0, -6.0]);
+    assert_eq!(indices, vec![0.0, 13.0, 6.0, 15.0]);
+}
@@ -0,0 +1,126 @@
+use protobuf::ProtobufEnum;
+use std::collections::HashMap;
+use wonnx::{
+    onnx::{TensorProto, TensorProto_DataType},
+    utils::{attribute, graph, initializer, model, node, tensor, tensor_of_type},
+};
+mod common;
+
+fn initializer_int(name: &str, data: Vec<i64>) -> TensorProto {
+    let mut initializer = TensorProto::new();
+    initializer.set_name(name.to_string());
+    initializer.set_data_type(TensorProto_DataType::INT64.value());
+    initializer.set_int64_data(data);
+    initializer
+}
+
+fn test_nonmaxsuppression(
+    boxes: &[f32],
+    scores: &[f32],
+    center_point_box: i64,
+    score_threshold: f32,
+    max_selected: i64,
+    expected: &[f32],
+) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    input_data.insert("boxes".to_string(), boxes.into());
+    input_data.insert("scores".to_string(), scores.into());
+
+    let spatial_dimension = (boxes.len() / 4) as i64;
+
+    // Model: (boxes, scores, max_output_boxes_per_class, iou_threshold, score_threshold) -> NonMaxSuppression -> Y
+    let model = model(graph(
+        vec![
+            tensor("boxes", &[1, spatial_dimension, 4]),
+            tensor("scores", &[1, 1, spatial_dimension]),
+        ],
+        vec![tensor_of_type(
+            "Y",
+            &[max_selected, 3],
+            TensorProto_DataType::INT64,
+        )],
+        vec![],
+        vec![
+            initializer_int("max_output_boxes_per_class", vec![3]),
+            initializer("iou_threshold", vec![0.5]),
+            initializer("score_threshold", vec![score_threshold]),
+        ],
+        vec![node(
+            vec![
+                "boxes",
+                "scores",
+                "max_output_boxes_per_class",
+                "iou_threshold",
+                "score_threshold",
+            ],
+            vec!["Y"],
+            "nms",
+            "NonMaxSuppression",
+            vec![attribute("center_point_box", center_point_box)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), expected);
+}
+
+// ONNX test case for NonMaxSuppression (https://github.com/onnx/onnx/blob/main/docs/Operators.md#NonMaxSuppression)
+#[test]
+fn test_nonmaxsuppression_suppress_by_iou() {
+    #[rustfmt::skip]
+    let boxes = [
+        0.0, 0.0, 1.0, 1.0,
+        0.0, 0.1, 1.0, 1.1,
+        0.0, -0.1, 1.0, 0.9,
+        0.0, 10.0, 1.0, 11.0,
+        0.0, 10.1, 1.0, 11.1,
+        0.0, 100.0, 1.0, 101.0,
+    ];
+    let scores = [0.9, 0.75, 0.6, 0.95, 0.5, 0.3];
+
+    test_nonmaxsuppression(
+        &boxes,
+        &scores,
+        0,
+        0.0,
+        3,
+        &[0., 0., 3., 0., 0., 0., 0., 0., 5.],
+    );
+
+    // With a score threshold, fewer boxes are selected; rows that remain are filled with -1
+    test_nonmaxsuppression(
+        &boxes,
+        &scores,
+        0,
+        0.4,
+        3,
+        &[0., 0., 3., 0., 0., 0., -1., -1., -1.],
+    );
+}
+
+#[test]
+fn test_nonmaxsuppression_center_point_box() {
+    #[rustfmt::skip]
+    let boxes = [
+        0.5, 0.5, 1.0, 1.0,
+        0.5, 0.6, 1.0, 1.0,
+        0.5, 0.4, 1.0, 1.0,
+        0.5, 10.5, 1.0, 1.0,
+        0.5, 10.6, 1.0, 1.0,
+        0.5, 100.5, 1.0, 1.0,
+    ];
+    let scores = [0.9, 0.75, 0.6, 0.95, 0.5, 0.3];
+
+    test_nonmaxsuppression(
+        &boxes,
+        &scores,
+        1,
+        0.0,
+        3,
+        &[0., 0., 3., 0., 0., 0., 0., 0., 5.],
+    );
+}
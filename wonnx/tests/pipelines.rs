@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use wonnx::utils::{graph, model, node, tensor};
+
+mod common;
+
+#[test]
+fn test_identical_nodes_share_pipeline() {
+    let n = 8;
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..16).map(|x| (x as f32) - 8.0).collect();
+    let shape = vec![16];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Relu -> Y0, X -> Relu -> Y1, ... (every Relu reads the graph input, so none of them runs in place)
+    let output_names: Vec<String> = (0..n).map(|i| format!("Y{}", i)).collect();
+    let nodes = output_names
+        .iter()
+        .enumerate()
+        .map(|(i, output_name)| {
+            node(
+                vec!["X"],
+                vec![output_name],
+                &format!("relu{}", i),
+                "Relu",
+                vec![],
+            )
+        })
+        .collect();
+    let outputs = output_names.iter().map(|x| tensor(x, &shape)).collect();
+
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        outputs,
+        vec![],
+        vec![],
+        nodes,
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    assert_eq!(session.pipeline_count(), 1);
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    let expected: Vec<f32> = data.iter().map(|x| x.max(0.0)).collect();
+    for output_name in &output_names {
+        common::assert_eq_vector(result[output_name].as_slice(), &expected);
+    }
+}
+
+#[test]
+fn test_in_place_nodes_share_pipeline() {
+    let n = 8;
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..16).map(|x| (x as f32) - 8.0).collect();
+    let shape = vec![16];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Relu -> A1 -> Relu -> A2 ... -> Relu -> Y
+    let names: Vec<String> = (0..=n)
+        .map(|i| match i {
+            0 => "X".to_string(),
+            i if i == n => "Y".to_string(),
+            i => format!("A{}", i),
+        })
+        .collect();
+    let nodes = (0..n)
+        .map(|i| {
+            node(
+                vec![&names[i]],
+                vec![&names[i + 1]],
+                &format!("relu{}", i),
+                "Relu",
+                vec![],
+            )
+        })
+        .collect();
+    let intermediates = names[1..n].iter().map(|x| tensor(x, &shape)).collect();
+
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        intermediates,
+        vec![],
+        nodes,
+    ));
+
+    // The first Relu reads the graph input and the last one writes the (readable) graph output, so these compute into a
+    // separate buffer. All others run in place on the output of the previous Relu and share the in-place pipeline.
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    assert_eq!(session.pipeline_count(), 2);
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    let expected: Vec<f32> = data.iter().map(|x| x.max(0.0)).collect();
+    common::assert_eq_vector(result["Y"].as_slice(), &expected);
+}
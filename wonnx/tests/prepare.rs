@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use wonnx::utils::{graph, initializer, model, node, tensor};
+mod common;
+
+#[test]
+fn test_prepare() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let n = 64;
+    let shape = vec![n, n];
+
+    let mut weights = initializer("W", vec![0.5; (n * n) as usize]);
+    weights.set_dims(shape.clone());
+
+    // Model: X -> MatMul(W) -> A -> Sigmoid -> B -> MatMul(W) -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![
+            tensor("A", &shape),
+            tensor("B", &shape),
+            tensor("W", &shape),
+        ],
+        vec![weights],
+        vec![
+            node(vec!["X", "W"], vec!["A"], "matmul_a", "MatMul", vec![]),
+            node(vec!["A"], vec!["B"], "sigmoid", "Sigmoid", vec![]),
+            node(vec!["B", "W"], vec!["Y"], "matmul_y", "MatMul", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let (pipelines, buffers) = (session.pipeline_count(), session.buffer_count());
+    pollster::block_on(session.prepare()).unwrap();
+
+    // Preparing runs the pipelines that were created with the session, without creating any resources of its own
+    assert_eq!(session.pipeline_count(), pipelines);
+    assert_eq!(session.buffer_count(), buffers);
+
+    let mut input_data = HashMap::new();
+    let data = vec![0.0f32; (n * n) as usize];
+    input_data.insert("X".to_string(), data.as_slice().into());
+    let first_result = pollster::block_on(session.run(&input_data)).unwrap();
+    let second_result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // sigmoid(0) = 0.5, and each output is the sum of n products 0.5 * 0.5
+    common::assert_eq_vector(
+        &first_result["Y"],
+        &vec![(n as f32) * 0.25; (n * n) as usize],
+    );
+    assert_eq!(first_result["Y"], second_result["Y"]);
+    assert_eq!(session.pipeline_count(), pipelines);
+    assert_eq!(session.buffer_count(), buffers);
+}
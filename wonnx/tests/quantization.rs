@@ -0,0 +1,249 @@
+use protobuf::ProtobufEnum;
+use std::collections::HashMap;
+use wonnx::{
+    onnx::{TensorProto, TensorProto_DataType},
+    utils::{attribute, graph, initializer, model, node, tensor, tensor_of_type},
+};
+mod common;
+
+#[test]
+fn dequantize_linear_per_axis() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let x: Vec<i32> = vec![1, 2, 3, 4, 5, 6];
+    input_data.insert("X".to_string(), x.as_slice().into());
+
+    // The scale and zero point are specified for each row
+    let scale = vec![0.1, 0.2];
+    let mut scale_tensor = initializer("S", scale.clone());
+    scale_tensor.set_dims(vec![2]);
+    let zero_point = vec![1, -1];
+    let mut zero_point_tensor = TensorProto::new();
+    zero_point_tensor.set_name("Z".to_string());
+    zero_point_tensor.set_data_type(TensorProto_DataType::INT8.value());
+    zero_point_tensor.set_dims(vec![2]);
+    zero_point_tensor.set_int32_data(zero_point.clone());
+
+    // Model: (X, S, Z) -> DequantizeLinear -> Y
+    let model = model(graph(
+        vec![tensor_of_type("X", &[2, 3], TensorProto_DataType::INT8)],
+        vec![tensor("Y", &[2, 3])],
+        vec![],
+        vec![scale_tensor, zero_point_tensor],
+        vec![node(
+            vec!["X", "S", "Z"],
+            vec!["Y"],
+            "dequantize",
+            "DequantizeLinear",
+            vec![attribute("axis", 0)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    let expected: Vec<f32> = x
+        .iter()
+        .enumerate()
+        .map(|(i, x)| (x - zero_point[i / 3]) as f32 * scale[i / 3])
+        .collect();
+    common::assert_eq_vector(result["Y"].as_slice(), &expected);
+}
+
+/// Creates an 8-bit integer tensor (the elements are stored as 32-bit integers)
+fn int8_tensor(name: &str, dims: &[i64], data: &[i32]) -> TensorProto {
+    let mut tensor = TensorProto::new();
+    tensor.set_name(name.to_string());
+    tensor.set_data_type(TensorProto_DataType::INT8.value());
+    tensor.set_dims(dims.to_vec());
+    tensor.set_int32_data(data.to_vec());
+    tensor
+}
+
+#[test]
+fn conv_integer_int8() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (channels, kernels, size, kernel_size) = (2, 3, 5, 3);
+
+    let x: Vec<i32> = (0..channels * size * size)
+        .map(|i| ((i * 37) % 255) as i32 - 128)
+        .collect();
+    let w: Vec<i32> = (0..kernels * channels * kernel_size * kernel_size)
+        .map(|i| ((i * 53) % 255) as i32 - 128)
+        .collect();
+    let x_zero_point = 3;
+    let w_zero_point = vec![-2, 0, 5];
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.as_slice().into());
+
+    // Model: (X, W, X_zero_point, W_zero_point) -> ConvInteger -> Y
+    let model = model(graph(
+        vec![tensor_of_type(
+            "X",
+            &[1, channels, size, size],
+            TensorProto_DataType::INT8,
+        )],
+        vec![tensor_of_type(
+            "Y",
+            &[1, kernels, size, size],
+            TensorProto_DataType::INT32,
+        )],
+        vec![],
+        vec![
+            int8_tensor("W", &[kernels, channels, kernel_size, kernel_size], &w),
+            int8_tensor("X_zero_point", &[], &[x_zero_point]),
+            int8_tensor("W_zero_point", &[kernels], &w_zero_point),
+        ],
+        vec![node(
+            vec!["X", "W", "X_zero_point", "W_zero_point"],
+            vec!["Y"],
+            "conv",
+            "ConvInteger",
+            vec![
+                attribute("kernel_shape", vec![kernel_size, kernel_size]),
+                attribute("pads", vec![1, 1, 1, 1]),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    // Reference: dequantize the inputs, perform a floating point convolution and quantize the result again
+    let (x_scale, w_scale) = (0.5f32, 0.25f32);
+    let x_float: Vec<f32> = x
+        .iter()
+        .map(|x| (x - x_zero_point) as f32 * x_scale)
+        .collect();
+    let w_float: Vec<f32> = w
+        .iter()
+        .enumerate()
+        .map(|(i, w)| {
+            let m = i / (channels * kernel_size * kernel_size) as usize;
+            (w - w_zero_point[m]) as f32 * w_scale
+        })
+        .collect();
+    let mut expected = vec![];
+    for m in 0..kernels {
+        for oy in 0..size {
+            for ox in 0..size {
+                let mut sum = 0.0;
+                for c in 0..channels {
+                    for ky in 0..kernel_size {
+                        for kx in 0..kernel_size {
+                            let (iy, ix) = (oy + ky - 1, ox + kx - 1);
+                            if iy < 0 || iy >= size || ix < 0 || ix >= size {
+                                continue;
+                            }
+                            let x_index = (c * size + iy) * size + ix;
+                            let w_index =
+                                ((m * channels + c) * kernel_size + ky) * kernel_size + kx;
+                            sum += x_float[x_index as usize] * w_float[w_index as usize];
+                        }
+                    }
+                }
+                expected.push((sum / (x_scale * w_scale)).round());
+            }
+        }
+    }
+    assert_eq!(result["Y"], expected);
+}
+
+/// Creates an unsigned 8-bit integer tensor (the elements are stored as 32-bit integers)
+fn uint8_tensor(name: &str, dims: &[i64], data: &[i32]) -> TensorProto {
+    let mut tensor = int8_tensor(name, dims, data);
+    tensor.set_data_type(TensorProto_DataType::UINT8.value());
+    tensor
+}
+
+#[test]
+fn dequantize_activation_quantize_fused() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let x: Vec<i32> = vec![100, 120, 127, 128, 129, 140, 200, 255];
+    let (x_scale, x_zero_point, y_scale, y_zero_point) = (0.5, 128, 0.25, 10);
+
+    // Model: (X, X_scale, X_zero_point) -> DequantizeLinear -> A -> LeakyRelu -> B -> (B, Y_scale, Y_zero_point) ->
+    // QuantizeLinear -> Y. When the scales and zero points are initializers, the three nodes are fused; when they are
+    // inputs, the nodes are executed one by one.
+    let quantized_model = |constant: bool| {
+        let mut inputs = vec![tensor_of_type("X", &[2, 4], TensorProto_DataType::UINT8)];
+        let mut initializers = vec![];
+        if constant {
+            let mut x_scale_tensor = initializer("X_scale", vec![x_scale]);
+            x_scale_tensor.set_dims(vec![1]);
+            let mut y_scale_tensor = initializer("Y_scale", vec![y_scale]);
+            y_scale_tensor.set_dims(vec![1]);
+            initializers.extend([
+                x_scale_tensor,
+                uint8_tensor("X_zero_point", &[1], &[x_zero_point]),
+                y_scale_tensor,
+                uint8_tensor("Y_zero_point", &[1], &[y_zero_point]),
+            ]);
+        } else {
+            inputs.extend([
+                tensor("X_scale", &[1]),
+                tensor_of_type("X_zero_point", &[1], TensorProto_DataType::UINT8),
+                tensor("Y_scale", &[1]),
+                tensor_of_type("Y_zero_point", &[1], TensorProto_DataType::UINT8),
+            ]);
+        }
+
+        model(graph(
+            inputs,
+            vec![tensor_of_type("Y", &[2, 4], TensorProto_DataType::UINT8)],
+            vec![tensor("A", &[2, 4]), tensor("B", &[2, 4])],
+            initializers,
+            vec![
+                node(
+                    vec!["X", "X_scale", "X_zero_point"],
+                    vec!["A"],
+                    "dequantize",
+                    "DequantizeLinear",
+                    vec![],
+                ),
+                node(
+                    vec!["A"],
+                    vec!["B"],
+                    "leaky_relu",
+                    "LeakyRelu",
+                    vec![attribute("alpha", 0.1)],
+                ),
+                node(
+                    vec!["B", "Y_scale", "Y_zero_point"],
+                    vec!["Y"],
+                    "quantize",
+                    "QuantizeLinear",
+                    vec![],
+                ),
+            ],
+        ))
+    };
+
+    let scales = [vec![x_scale], vec![y_scale]];
+    let zero_points = [vec![x_zero_point], vec![y_zero_point]];
+
+    let fused_session = pollster::block_on(wonnx::Session::from_model(quantized_model(true)))
+        .expect("Session did not create");
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), x.as_slice().into());
+    let fused_result = pollster::block_on(fused_session.run(&input_data)).unwrap();
+
+    let literal_session = pollster::block_on(wonnx::Session::from_model(quantized_model(false)))
+        .expect("Session did not create");
+    input_data.insert("X_scale".to_string(), scales[0].as_slice().into());
+    input_data.insert("X_zero_point".to_string(), zero_points[0].as_slice().into());
+    input_data.insert("Y_scale".to_string(), scales[1].as_slice().into());
+    input_data.insert("Y_zero_point".to_string(), zero_points[1].as_slice().into());
+    let literal_result = pollster::block_on(literal_session.run(&input_data)).unwrap();
+
+    // The fused node does not need buffers for the intermediate (floating point) values
+    assert!(fused_session.buffer_count() < literal_session.buffer_count());
+
+    // Dequantized: -14, -4, -0.5, 0, 0.5, 6, 36, 63.5. After LeakyRelu: -1.4, -0.4, -0.05, 0, 0.5, 6, 36, 63.5. Divided
+    // by the output scale and rounded: -6, -2, 0, 0, 2, 24, 144, 254, which is then saturated after adding the zero point.
+    let expected = vec![4., 8., 10., 10., 12., 34., 154., 255.];
+    assert_eq!(fused_result["Y"], expected);
+    assert_eq!(literal_result["Y"], expected);
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+/// Model: RandomNormal/RandomUniform -> Y
+fn random_model(op_type: &str, seed: Option<f32>) -> wonnx::onnx::ModelProto {
+    let mut attributes = vec![
+        attribute("shape", vec![16, 16]),
+        attribute("low", -2.0),
+        attribute("high", 2.0),
+        attribute("mean", 1.0),
+        attribute("scale", 0.5),
+    ];
+    if let Some(seed) = seed {
+        attributes.push(attribute("seed", seed));
+    }
+
+    model(graph(
+        vec![],
+        vec![tensor("Y", &[16, 16])],
+        vec![],
+        vec![],
+        vec![node(vec![], vec!["Y"], "random", op_type, attributes)],
+    ))
+}
+
+fn random_session(op_type: &str, seed: f32) -> wonnx::Session {
+    pollster::block_on(wonnx::Session::from_model(random_model(
+        op_type,
+        Some(seed),
+    )))
+    .expect("Session did not create")
+}
+
+#[test]
+fn test_random_uniform_fixed_seed() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let input_data = HashMap::new();
+
+    let session = random_session("RandomUniform", 42.0);
+    let first = pollster::block_on(session.run(&input_data)).unwrap();
+    let second = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(first["Y"], second["Y"]);
+    // Values are drawn from [low, high), so high itself is never generated
+    assert!(first["Y"].iter().all(|x| (-2.0..2.0).contains(x)));
+
+    // Another session with the same seed produces the same values, another seed produces different values
+    let same_seed =
+        pollster::block_on(random_session("RandomUniform", 42.0).run(&input_data)).unwrap();
+    assert_eq!(first["Y"], same_seed["Y"]);
+    let other_seed =
+        pollster::block_on(random_session("RandomUniform", 7.0).run(&input_data)).unwrap();
+    assert_ne!(first["Y"], other_seed["Y"]);
+}
+
+#[test]
+fn test_random_normal_fixed_seed() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let input_data = HashMap::new();
+
+    let session = random_session("RandomNormal", 42.0);
+    let first = pollster::block_on(session.run(&input_data)).unwrap();
+    let second = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(first["Y"], second["Y"]);
+
+    // The sample mean of 256 values with a standard deviation of 0.5 should be close to the requested mean of 1
+    let values = &first["Y"];
+    assert!(values.iter().all(|x| x.is_finite()));
+    let mean = values.iter().sum::<f32>() / (values.len() as f32);
+    assert!((mean - 1.0).abs() < 0.2);
+}
+
+#[test]
+fn test_random_deterministic() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let input_data = HashMap::new();
+
+    // Without a seed, the values only depend on the model when deterministic execution is requested
+    let run = |deterministic: bool| {
+        let config = wonnx::SessionConfig {
+            deterministic,
+            ..wonnx::SessionConfig::default()
+        };
+        let session = pollster::block_on(wonnx::Session::from_model_with_config(
+            random_model("RandomUniform", None),
+            config,
+        ))
+        .expect("Session did not create");
+        pollster::block_on(session.run(&input_data)).unwrap()
+    };
+    let first = run(true);
+    let second = run(true);
+    assert_eq!(first["Y"], second["Y"]);
+
+    // Otherwise each session generates its own seed
+    let first = run(false);
+    let second = run(false);
+    assert_ne!(first["Y"], second["Y"]);
+}
+
+#[test]
+fn test_deterministic_dropout_training_mode() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let model = model(graph(
+        vec![tensor("X", &[4])],
+        vec![tensor("Y", &[4]), tensor("mask", &[4])],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y", "mask"],
+            "dropout",
+            "Dropout",
+            vec![attribute("training_mode", 1)],
+        )],
+    ));
+
+    // A random dropout mask cannot be reproduced, so it is rejected, also when the optimizer is disabled
+    let config = wonnx::SessionConfig {
+        deterministic: true,
+        disable_optimizer: true,
+        ..wonnx::SessionConfig::default()
+    };
+    let result = pollster::block_on(wonnx::Session::from_model_with_config(model, config));
+    assert!(result.is_err());
+}
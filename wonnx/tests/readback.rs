@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use wonnx::utils::{graph, model, node, tensor};
+
+mod common;
+
+#[test]
+fn test_chunked_readback() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // The output is one element larger than the readback limit, so it has to be read back in two chunks
+    let n: usize = 257;
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..n).map(|x| x as f32).collect();
+    let shape = vec![n as i64];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Neg -> A -> Neg -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![tensor("A", &shape)],
+        vec![],
+        vec![
+            node(vec!["X"], vec!["A"], "neg_a", "Neg", vec![]),
+            node(vec!["A"], vec!["Y"], "neg_y", "Neg", vec![]),
+        ],
+    ));
+
+    let config = wonnx::SessionConfig {
+        max_readback_bytes: Some(256 * 4),
+        ..wonnx::SessionConfig::default()
+    };
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(model, config))
+        .expect("Session did not create");
+
+    let result = pollster::block_on(session.run_with_debug(&input_data, &["A"])).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &data);
+    let negated: Vec<f32> = data.iter().map(|x| -x).collect();
+    common::assert_eq_vector(result["A"].as_slice(), &negated);
+}
+
+#[test]
+fn test_run_into() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![2, 3];
+
+    // Model: X -> Neg -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape)],
+        vec![tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "neg", "Neg", vec![])],
+    ));
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    // Both runs write to the same buffer, which is overwritten by the second run
+    let mut y = vec![0.0f32; 6];
+    for run in 0..2 {
+        let data: Vec<f32> = (0..6).map(|x| (x * (run + 1)) as f32).collect();
+        let mut input_data = HashMap::new();
+        input_data.insert("X".to_string(), data.as_slice().into());
+        {
+            let mut outputs = HashMap::new();
+            outputs.insert("Y".to_string(), y.as_mut_slice());
+            pollster::block_on(session.run_into(&input_data, &mut outputs)).unwrap();
+        }
+
+        let negated: Vec<f32> = data.iter().map(|x| -x).collect();
+        common::assert_eq_vector(&y, &negated);
+    }
+
+    // Buffers of the wrong size are rejected
+    let data: Vec<f32> = vec![0.0; 6];
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+    let mut too_small = vec![0.0f32; 5];
+    let mut outputs = HashMap::new();
+    outputs.insert("Y".to_string(), too_small.as_mut_slice());
+    assert!(pollster::block_on(session.run_into(&input_data, &mut outputs)).is_err());
+}
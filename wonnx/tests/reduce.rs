@@ -2,7 +2,7 @@ use protobuf::ProtobufEnum;
 use std::collections::HashMap;
 use wonnx::{
     onnx::{AttributeProto, TensorProto, TensorProto_DataType},
-    utils::{attribute, graph, model, node, tensor},
+    utils::{attribute, graph, initializer_of, model, node, tensor, tensor_of, ScalarType},
 };
 mod common;
 
@@ -235,6 +235,117 @@ fn reduce() {
     );
 }
 
+// Reducing all axes without keeping the reduced dimensions yields a scalar (rank 0) tensor
+#[test]
+fn test_reduce_to_scalar() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let data = [1.0, -2.0, 3.0, 4.0, -5.0, 6.0];
+
+    test_reduce(&data, &[2, 3], None, "ReduceSum", false, &[7.], &[]);
+    test_reduce(&data, &[2, 3], None, "ReduceMax", false, &[6.], &[]);
+    test_reduce(
+        &data,
+        &[2, 3],
+        Some(vec![0, 1]),
+        "ReduceMean",
+        false,
+        &[7. / 6.],
+        &[],
+    );
+}
+
+// Each row consists of a one followed by many values that are too small to change a one when added to it. A naive sum
+// therefore stays at exactly one, whereas compensated summation keeps track of the small values.
+#[test]
+fn test_reduce_sum_compensated() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (rows, length) = (2, 1000);
+    let small = 3e-8f32;
+    let data: Vec<f32> = (0..rows * length)
+        .map(|i| if i % length == 0 { 1.0 } else { small })
+        .collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let run = |compensated_summation: bool| {
+        // Model: X -> ReduceSum -> Y
+        let model = model(graph(
+            vec![tensor("X", &[rows as i64, length as i64])],
+            vec![tensor("Y", &[rows as i64])],
+            vec![],
+            vec![],
+            vec![node(
+                vec!["X"],
+                vec!["Y"],
+                "reduce",
+                "ReduceSum",
+                vec![attribute("axes", vec![1]), attribute("keepdims", 0)],
+            )],
+        ));
+        let config = wonnx::SessionConfig {
+            compensated_summation,
+            ..wonnx::SessionConfig::default()
+        };
+        let session = pollster::block_on(wonnx::Session::from_model_with_config(model, config))
+            .expect("Session did not create");
+        pollster::block_on(session.run(&input_data))
+            .unwrap()
+            .remove("Y")
+            .unwrap()
+    };
+
+    let expected = (1.0 + (length - 1) as f64 * small as f64) as f32;
+    assert_eq!(run(false), vec![1.0; rows]);
+    for sum in run(true) {
+        approx::assert_abs_diff_eq!(sum, expected, epsilon = 2e-7);
+    }
+}
+
+// Reductions of at least REDUCE_WORKGROUP_THRESHOLD elements are split over the invocations of a workgroup, of which the
+// partial results are combined afterwards. Here the first element of each invocation is +1 or -1 and the remaining
+// elements are too small to change it, so each partial result carries most of its value in its compensation term. The
+// large partial results cancel out, and only carrying the compensation terms through the combination keeps the sum of
+// the small values.
+#[test]
+fn test_reduce_workgroup_compensated() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (length, large, small) = (4096, 256, 3e-9f32);
+    let data: Vec<f32> = (0..length)
+        .map(|i| match i {
+            i if i >= large => small,
+            i if i % 2 == 0 => 1.0,
+            _ => -1.0,
+        })
+        .collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> ReduceSum -> Y
+    let model = model(graph(
+        vec![tensor("X", &[1, length as i64])],
+        vec![tensor("Y", &[1])],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["X"],
+            vec!["Y"],
+            "reduce",
+            "ReduceSum",
+            vec![attribute("axes", vec![1]), attribute("keepdims", 0)],
+        )],
+    ));
+    let config = wonnx::SessionConfig {
+        compensated_summation: true,
+        ..wonnx::SessionConfig::default()
+    };
+    let session = pollster::block_on(wonnx::Session::from_model_with_config(model, config))
+        .expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    let expected = ((length - large) as f64 * small as f64) as f32;
+    approx::assert_relative_eq!(result["Y"][0], expected, max_relative = 1e-3);
+}
+
 pub fn initializer_int(name: &str, data: Vec<i64>) -> TensorProto {
     let mut initializer = TensorProto::new();
     initializer.set_name(name.to_string());
@@ -287,3 +398,293 @@ fn test_reduce_sum_with_axes_as_input() {
     log::info!("OUT: {:?}", result["Y"]);
     common::assert_eq_vector(result["Y"].as_slice(), &[4., 6., 12., 14., 20., 22.]);
 }
+
+// ReduceLogSumExp and ReduceL2 on values for which a naive implementation would overflow (exp(1000) and 1e20 * 1e20
+// are both larger than f32::MAX)
+#[test]
+fn test_reduce_numerical_stability() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    for (op_name, data, expected) in [
+        (
+            "ReduceLogSumExp",
+            [1000.0, 1001.0],
+            1001.0 + (1.0 + (-1.0f32).exp()).ln(),
+        ),
+        ("ReduceL2", [1e20, 1e20], 2.0f32.sqrt() * 1e20),
+    ] {
+        let mut input_data = HashMap::new();
+        input_data.insert("X".to_string(), data.as_slice().into());
+
+        // Model: X -> Reduce -> Y
+        let model = model(graph(
+            vec![tensor("X", &[2])],
+            vec![tensor("Y", &[1])],
+            vec![],
+            vec![],
+            vec![node(
+                vec!["X"],
+                vec!["Y"],
+                "myReduce",
+                op_name,
+                vec![attribute("keepdims", 1)],
+            )],
+        ));
+
+        let session =
+            pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+        let result = pollster::block_on(session.run(&input_data)).unwrap();
+        assert!(result["Y"][0].is_finite());
+        approx::assert_relative_eq!(result["Y"][0], expected, max_relative = 1e-5);
+    }
+}
+
+#[test]
+fn reduce_size_one_axis() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let data = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+
+    // Reducing an axis of size one copies the input
+    test_reduce(
+        &data,
+        &[3, 1, 2],
+        Some(vec![1]),
+        "ReduceMean",
+        true,
+        &data,
+        &[3, 1, 2],
+    );
+
+    // The same axis listed twice (after normalizing negative axes) is reduced once
+    test_reduce(
+        &data,
+        &[3, 1, 2],
+        Some(vec![1, -2]),
+        "ReduceMean",
+        false,
+        &data,
+        &[3, 2],
+    );
+}
+
+/// Creates a session for a model that reduces X (of the given shape) over `axes` at once into Y as well as one axis at
+/// a time into Z. As each single-axis reduction reduces fewer than `REDUCE_WORKGROUP_THRESHOLD` elements per output,
+/// Z is calculated by the shader that reduces serially.
+fn reduce_workgroup_session(op_name: &str, shape: &[i64], axes: &[i64]) -> wonnx::Session {
+    let mut output_shape = shape.to_vec();
+    for axis in axes {
+        output_shape[*axis as usize] = 1;
+    }
+
+    let mut nodes = vec![node(
+        vec!["X"],
+        vec!["Y"],
+        "reduceAll",
+        op_name,
+        vec![attribute("axes", axes.to_vec())],
+    )];
+    let mut value_info = vec![];
+    let mut intermediate_shape = shape.to_vec();
+    for (index, axis) in axes.iter().rev().enumerate() {
+        let input = if index == 0 {
+            "X".to_string()
+        } else {
+            format!("Z{}", index - 1)
+        };
+        let output = if index == axes.len() - 1 {
+            "Z".to_string()
+        } else {
+            format!("Z{}", index)
+        };
+        intermediate_shape[*axis as usize] = 1;
+        if index != axes.len() - 1 {
+            value_info.push(tensor(&output, &intermediate_shape));
+        }
+        nodes.push(node(
+            vec![&input],
+            vec![&output],
+            &format!("reduce{}", axis),
+            op_name,
+            vec![attribute("axes", vec![*axis])],
+        ));
+    }
+
+    let model = model(graph(
+        vec![tensor("X", shape)],
+        vec![tensor("Y", &output_shape), tensor("Z", &output_shape)],
+        value_info,
+        vec![],
+        nodes,
+    ));
+    pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create")
+}
+
+#[test]
+fn test_reduce_workgroup() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    for (op_name, shape, axes) in [
+        ("ReduceSum", vec![1, 2, 64, 64], vec![2, 3]),
+        ("ReduceMean", vec![1, 2, 64, 64], vec![2, 3]),
+        ("ReduceSum", vec![4, 2, 16, 32], vec![0, 2, 3]),
+        ("ReduceMean", vec![4, 2, 16, 32], vec![0, 2, 3]),
+    ] {
+        let length = shape.iter().product::<i64>() as usize;
+        let data: Vec<f32> = (0..length).map(|i| (i % 7) as f32).collect();
+        let mut input_data = HashMap::new();
+        input_data.insert("X".to_string(), data.as_slice().into());
+
+        let session = reduce_workgroup_session(op_name, &shape, &axes);
+        let result = pollster::block_on(session.run(&input_data)).unwrap();
+        assert_eq!(result["Y"].len(), result["Z"].len());
+        for (y, z) in result["Y"].iter().zip(result["Z"].iter()) {
+            approx::assert_relative_eq!(y, z, max_relative = 1e-5);
+        }
+    }
+}
+
+/// Compares the workgroup reduction against reducing one axis at a time for a global ReduceMean over a [1, 1, 512, 512]
+/// tensor. Run with `cargo test --release --test reduce benchmark_reduce_workgroup -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn benchmark_reduce_workgroup() {
+    let n = 512;
+    let data: Vec<f32> = (0..n * n).map(|i| (i % 7) as f32).collect();
+
+    let workgroup_model = model(graph(
+        vec![tensor("X", &[1, 1, n as i64, n as i64])],
+        vec![tensor("Y", &[1, 1, 1, 1])],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "reduce", "ReduceMean", vec![])],
+    ));
+    let benchmark = common::benchmark::Benchmark::new();
+    let workgroup_session = benchmark.session(workgroup_model, wonnx::SessionConfig::default());
+    let serial_model = model(graph(
+        vec![tensor("X", &[1, 1, n as i64, n as i64])],
+        vec![tensor("Z", &[1, 1, 1, 1])],
+        vec![tensor("R", &[1, 1, n as i64, 1])],
+        vec![],
+        vec![
+            node(
+                vec!["X"],
+                vec!["R"],
+                "reduceRows",
+                "ReduceMean",
+                vec![attribute("axes", vec![3])],
+            ),
+            node(
+                vec!["R"],
+                vec!["Z"],
+                "reduceColumns",
+                "ReduceMean",
+                vec![attribute("axes", vec![2])],
+            ),
+        ],
+    ));
+    let serial_session = benchmark.session(serial_model, wonnx::SessionConfig::default());
+
+    let input_data = benchmark.upload(&[("X", &[1, 1, n as i64, n as i64], &data)]);
+    let workgroup = benchmark.time(&workgroup_session, &input_data);
+    let serial = benchmark.time(&serial_session, &input_data);
+    common::benchmark::report(
+        &format!("[1, 1, {n}, {n}] ReduceMean", n = n),
+        ("workgroup", workgroup),
+        ("serial", serial),
+    );
+}
+
+/// Reference implementation of ReduceSum/ReduceMean (with keepdims=0) that visits every input element once
+fn reduce_reference(data: &[f32], shape: &[i64], axes: &[i64], mean: bool) -> (Vec<f32>, Vec<i64>) {
+    let output_shape: Vec<i64> = (0..shape.len())
+        .filter(|axis| !axes.contains(&(*axis as i64)))
+        .map(|axis| shape[axis])
+        .collect();
+    let mut output = vec![0.0; output_shape.iter().product::<i64>() as usize];
+    let mut counts = vec![0; output.len()];
+    for (flat_index, value) in data.iter().enumerate() {
+        let mut rest = flat_index as i64;
+        let mut output_index = 0;
+        let mut output_chunk = 1;
+        for axis in (0..shape.len()).rev() {
+            let index = rest % shape[axis];
+            rest /= shape[axis];
+            if !axes.contains(&(axis as i64)) {
+                output_index += index * output_chunk;
+                output_chunk *= shape[axis];
+            }
+        }
+        output[output_index as usize] += value;
+        counts[output_index as usize] += 1;
+    }
+    if mean {
+        for (value, count) in output.iter_mut().zip(counts.iter()) {
+            *value /= *count as f32;
+        }
+    }
+    (output, output_shape)
+}
+
+#[test]
+fn reduce_non_adjacent_axes() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    for (shape, axes) in [
+        (vec![3, 4, 5], vec![0, 2]),
+        (vec![2, 3, 4, 5], vec![0, 2]),
+        (vec![2, 3, 4, 5], vec![1, 3]),
+        (vec![3, 1, 5], vec![0, 2]),
+        // A kept axis of size one at the end should not affect the index of the input elements
+        (vec![2, 3, 1], vec![0]),
+    ] {
+        let length = shape.iter().product::<i64>() as usize;
+        let data: Vec<f32> = (0..length).map(|i| (i % 11) as f32).collect();
+        for (op_name, mean) in [("ReduceSum", false), ("ReduceMean", true)] {
+            let (expected, output_shape) = reduce_reference(&data, &shape, &axes, mean);
+            test_reduce(
+                &data,
+                &shape,
+                Some(axes.clone()),
+                op_name,
+                false,
+                &expected,
+                &output_shape,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_reduce_int64() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    // Model: S -> ReduceProd -> N; S -> ReduceSum -> T; S -> ReduceMin -> U; S -> ReduceMax -> V, where S is an int64
+    // shape
+    let shape = initializer_of("S", &[3], vec![2i64, 3, 4]);
+    let ops = [
+        ("N", "ReduceProd"),
+        ("T", "ReduceSum"),
+        ("U", "ReduceMin"),
+        ("V", "ReduceMax"),
+    ];
+    let model = model(graph(
+        vec![],
+        ops.iter()
+            .map(|&(output, _)| tensor_of(output, &[1], ScalarType::I64))
+            .collect(),
+        vec![],
+        vec![shape],
+        ops.iter()
+            .map(|&(output, op)| node(vec!["S"], vec![output], op, op, vec![]))
+            .collect(),
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&HashMap::new())).unwrap();
+    assert_eq!(result["N"], &[24.0]);
+    assert_eq!(result["T"], &[9.0]);
+    assert_eq!(result["U"], &[2.0]);
+    assert_eq!(result["V"], &[4.0]);
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, initializer_of, model, node, tensor};
+mod common;
+
+fn reverse_sequence(
+    data: &[f32],
+    shape: &[i64],
+    lengths: Vec<i64>,
+    batch_axis: i64,
+    time_axis: i64,
+) -> Vec<f32> {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.into());
+
+    // Model: (X, L) -> ReverseSequence -> Y
+    let model = model(graph(
+        vec![tensor("X", shape)],
+        vec![tensor("Y", shape)],
+        vec![],
+        vec![initializer_of("L", &[lengths.len() as i64], lengths)],
+        vec![node(
+            vec!["X", "L"],
+            vec!["Y"],
+            "reverse",
+            "ReverseSequence",
+            vec![
+                attribute("batch_axis", batch_axis),
+                attribute("time_axis", time_axis),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut result = pollster::block_on(session.run(&input_data)).unwrap();
+    result.remove("Y").unwrap()
+}
+
+#[test]
+fn test_reverse_sequence_batch_major() {
+    // Each row is a sequence; the first one has length 2, so its last element is padding
+    #[rustfmt::skip]
+    let data = [
+        1.0, 2.0, 3.0,
+        4.0, 5.0, 6.0,
+    ];
+    let result = reverse_sequence(&data, &[2, 3], vec![2, 3], 0, 1);
+    common::assert_eq_vector(&result, &[2.0, 1.0, 3.0, 6.0, 5.0, 4.0]);
+}
+
+#[test]
+fn test_reverse_sequence_time_major() {
+    // Each column is a sequence (the default axes), of lengths 1, 3 and 2 respectively
+    #[rustfmt::skip]
+    let data = [
+        0.0, 1.0, 2.0,
+        3.0, 4.0, 5.0,
+        6.0, 7.0, 8.0,
+    ];
+    let result = reverse_sequence(&data, &[3, 3], vec![1, 3, 2], 1, 0);
+    #[rustfmt::skip]
+    common::assert_eq_vector(&result, &[
+        0.0, 7.0, 5.0,
+        3.0, 4.0, 2.0,
+        6.0, 1.0, 8.0,
+    ]);
+}
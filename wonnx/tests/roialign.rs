@@ -0,0 +1,59 @@
+use protobuf::ProtobufEnum;
+use std::collections::HashMap;
+use wonnx::{
+    onnx::{TensorProto, TensorProto_DataType},
+    utils::{attribute, graph, model, node, tensor},
+};
+mod common;
+
+fn test_roialign(rois: &[f32], sampling_ratio: i64, expected: &[f32]) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..16).map(|x| x as f32).collect();
+    input_data.insert("X".to_string(), data.as_slice().into());
+    input_data.insert("rois".to_string(), rois.into());
+
+    // Batch indices are int64, stored as raw data (like most exported models do)
+    let mut batch_indices = TensorProto::new();
+    batch_indices.set_name("batch_indices".to_string());
+    batch_indices.set_data_type(TensorProto_DataType::INT64.value());
+    batch_indices.set_dims(vec![1]);
+    batch_indices.set_raw_data(0i64.to_le_bytes().to_vec());
+
+    // Model: (X, rois, batch_indices) -> RoiAlign -> Y
+    let model = model(graph(
+        vec![tensor("X", &[1, 1, 4, 4]), tensor("rois", &[1, 4])],
+        vec![tensor("Y", &[1, 1, 2, 2])],
+        vec![],
+        vec![batch_indices],
+        vec![node(
+            vec!["X", "rois", "batch_indices"],
+            vec!["Y"],
+            "roialign",
+            "RoiAlign",
+            vec![
+                attribute("output_height", 2),
+                attribute("output_width", 2),
+                attribute("sampling_ratio", sampling_ratio),
+                attribute("spatial_scale", 1.0),
+            ],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), expected);
+}
+
+#[test]
+fn test_roialign_fixed_sampling_ratio() {
+    test_roialign(&[0.0, 0.0, 3.0, 3.0], 2, &[3.75, 5.25, 9.75, 11.25]);
+}
+
+#[test]
+fn test_roialign_adaptive_sampling_ratio() {
+    // The sampling grid is derived from the bin size; samples beyond the last row/column are clamped to the edge
+    test_roialign(&[0.5, 1.0, 3.5, 3.0], 0, &[7.25, 8.6875, 11.25, 12.6875]);
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, model, node, tensor};
+
+mod common;
+
+#[test]
+fn test_scan_running_sum() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let (sequence_length, n) = (4, 2);
+
+    // Body: (sum_in, x) -> Add -> sum_out, which is both the new state and the scan output
+    let body = graph(
+        vec![tensor("sum_in", &[n]), tensor("x", &[n])],
+        vec![tensor("sum_out", &[n]), tensor("scan_out", &[n])],
+        vec![],
+        vec![],
+        vec![
+            node(vec!["sum_in", "x"], vec!["sum_out"], "add", "Add", vec![]),
+            node(vec!["sum_out"], vec!["scan_out"], "id", "Identity", vec![]),
+        ],
+    );
+
+    // Model: (S, X) -> Scan -> (T, Y), where T is the sum over the sequence and Y holds the running sums
+    let model = model(graph(
+        vec![tensor("S", &[n]), tensor("X", &[sequence_length, n])],
+        vec![tensor("T", &[n]), tensor("Y", &[sequence_length, n])],
+        vec![],
+        vec![],
+        vec![node(
+            vec!["S", "X"],
+            vec!["T", "Y"],
+            "scan",
+            "Scan",
+            vec![attribute("num_scan_inputs", 1), attribute("body", body)],
+        )],
+    ));
+
+    let initial: Vec<f32> = vec![10.0, 20.0];
+    let data: Vec<f32> = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+    let mut input_data = HashMap::new();
+    input_data.insert("S".to_string(), initial.as_slice().into());
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+
+    common::assert_eq_vector(result["T"].as_slice(), &[26.0, 40.0]);
+    common::assert_eq_vector(
+        result["Y"].as_slice(),
+        &[11.0, 22.0, 14.0, 26.0, 19.0, 32.0, 26.0, 40.0],
+    );
+}
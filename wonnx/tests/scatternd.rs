@@ -0,0 +1,89 @@
+use protobuf::ProtobufEnum;
+use std::collections::HashMap;
+use wonnx::{
+    onnx::{TensorProto, TensorProto_DataType},
+    utils::{attribute, graph, model, node, tensor},
+};
+mod common;
+
+fn scatter_nd(
+    data: &[f32],
+    data_shape: &[i64],
+    indices: &[i64],
+    indices_shape: &[i64],
+    updates: &[f32],
+    updates_shape: &[i64],
+    reduction: &str,
+) -> Vec<f32> {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.into());
+    input_data.insert("U".to_string(), updates.into());
+
+    let mut indices_tensor = TensorProto::new();
+    indices_tensor.set_name("I".to_string());
+    indices_tensor.set_data_type(TensorProto_DataType::INT64.value());
+    indices_tensor.set_dims(indices_shape.to_vec());
+    indices_tensor.set_int64_data(indices.to_vec());
+
+    // Model: (X, I, U) -> ScatterND -> Y
+    let model = model(graph(
+        vec![tensor("X", data_shape), tensor("U", updates_shape)],
+        vec![tensor("Y", data_shape)],
+        vec![],
+        vec![indices_tensor],
+        vec![node(
+            vec!["X", "I", "U"],
+            vec!["Y"],
+            "scatter",
+            "ScatterND",
+            vec![attribute("reduction", reduction)],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut result = pollster::block_on(session.run(&input_data)).unwrap();
+    result.remove("Y").unwrap()
+}
+
+#[test]
+fn scatter_nd_elements() {
+    // Two updates of a single element each, the first at index [1, 1]
+    let data: Vec<f32> = (0..16).map(|x| x as f32).collect();
+    let result = scatter_nd(
+        &data,
+        &[4, 4],
+        &[1, 1, 3, -2],
+        &[2, 2],
+        &[100.0, 200.0],
+        &[2],
+        "none",
+    );
+
+    let mut expected = data.clone();
+    expected[5] = 100.0;
+    expected[14] = 200.0;
+    common::assert_eq_vector(&result, &expected);
+}
+
+#[test]
+fn scatter_nd_slices_add() {
+    // Both updates target row 1, and are added to it
+    let data: Vec<f32> = (0..16).map(|x| x as f32).collect();
+    let result = scatter_nd(
+        &data,
+        &[4, 4],
+        &[1, 1],
+        &[2, 1],
+        &[1.0, 2.0, 3.0, 4.0, 10.0, 20.0, 30.0, 40.0],
+        &[2, 4],
+        "add",
+    );
+
+    let mut expected = data.clone();
+    for (i, update) in [11.0, 22.0, 33.0, 44.0].iter().enumerate() {
+        expected[4 + i] += update;
+    }
+    common::assert_eq_vector(&result, &expected);
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use wonnx::{
+    onnx::{AttributeProto, TensorProto_DataType},
+    utils::{attribute, graph, model, node, tensor, tensor_of_type},
+};
+
+fn shape_of_slice(attributes: Vec<AttributeProto>, expected: &[f32]) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data = vec![0.0f32; 2 * 3 * 4 * 5];
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Model: X -> Shape -> Y
+    let mut model = model(graph(
+        vec![tensor("X", &[2, 3, 4, 5])],
+        vec![tensor_of_type(
+            "Y",
+            &[expected.len() as i64],
+            TensorProto_DataType::INT64,
+        )],
+        vec![],
+        vec![],
+        vec![node(vec!["X"], vec!["Y"], "shape", "Shape", attributes)],
+    ));
+    model.mut_opset_import()[0].set_version(15);
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], expected);
+}
+
+#[test]
+fn test_shape() {
+    shape_of_slice(vec![], &[2.0, 3.0, 4.0, 5.0]);
+}
+
+#[test]
+fn test_shape_negative_start() {
+    shape_of_slice(vec![attribute("start", -2)], &[4.0, 5.0]);
+}
+
+#[test]
+fn test_shape_start_end() {
+    // Out of range values are clamped
+    shape_of_slice(
+        vec![attribute("start", 1), attribute("end", -1)],
+        &[3.0, 4.0],
+    );
+    shape_of_slice(vec![attribute("start", -10), attribute("end", 1)], &[2.0]);
+}
+
+#[test]
+fn test_shape_as_reshape_input() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let x = vec![0.0f32; 2 * 3 * 4 * 5];
+    let z: Vec<f32> = (0..20).map(|i| i as f32).collect();
+    input_data.insert("X".to_string(), x.as_slice().into());
+    input_data.insert("Z".to_string(), z.as_slice().into());
+
+    // Model: X -> Shape(start=2) -> S, (Z, S) -> Reshape -> Y
+    let mut model = model(graph(
+        vec![tensor("X", &[2, 3, 4, 5]), tensor("Z", &[20])],
+        vec![tensor("Y", &[4, 5])],
+        vec![],
+        vec![],
+        vec![
+            node(
+                vec!["X"],
+                vec!["S"],
+                "shape",
+                "Shape",
+                vec![attribute("start", 2)],
+            ),
+            node(vec!["Z", "S"], vec!["Y"], "reshape", "Reshape", vec![]),
+        ],
+    ));
+    model.mut_opset_import()[0].set_version(15);
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    assert_eq!(result["Y"], z);
+}
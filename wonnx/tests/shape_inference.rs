@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+use wonnx::utils::{attribute, graph, initializer, model, node, tensor};
+
+mod common;
+
+#[test]
+fn test_without_value_info() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let mut input_data = HashMap::new();
+    let data: Vec<f32> = (0..16).map(|x| x as f32).collect();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    let mut w = initializer("W", vec![1.0; 4 * 9]);
+    w.set_dims(vec![4, 1, 3, 3]);
+
+    // Model: X -> Conv -> A -> Relu -> B -> GlobalAveragePool -> Y, without shapes for A and B
+    let model = model(graph(
+        vec![tensor("X", &[1, 1, 4, 4])],
+        vec![tensor("Y", &[1, 4, 1, 1])],
+        vec![],
+        vec![w],
+        vec![
+            node(
+                vec!["X", "W"],
+                vec!["A"],
+                "conv",
+                "Conv",
+                vec![attribute("kernel_shape", vec![3, 3])],
+            ),
+            node(vec!["A"], vec!["B"], "relu", "Relu", vec![]),
+            node(vec!["B"], vec!["Y"], "pool", "GlobalAveragePool", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+
+    // The valid 3x3 convolution of the 4x4 input yields [45, 54, 81, 90] for each output channel
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &[67.5; 4]);
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use wonnx::{
+    utils::{graph, model, node, tensor, InputTensor},
+    StatefulSession,
+};
+
+#[test]
+fn test_stateful_session_accumulates_state() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let shape = vec![4];
+
+    // Model: (X, past) -> Add -> present -> Neg -> Y
+    let model = model(graph(
+        vec![tensor("X", &shape), tensor("past", &shape)],
+        vec![tensor("present", &shape), tensor("Y", &shape)],
+        vec![],
+        vec![],
+        vec![
+            node(vec!["X", "past"], vec!["present"], "add", "Add", vec![]),
+            node(vec!["present"], vec!["Y"], "neg", "Neg", vec![]),
+        ],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let mut decoder = StatefulSession::new(session, &[("past", "present")]);
+
+    // The initial state is supplied in the first step only; after that it is kept in GPU memory
+    let initial_state: &[f32] = &[0.5, 0.0, -0.5, 1.0];
+    let steps: [&[f32]; 3] = [
+        &[1.0, 2.0, 3.0, 4.0],
+        &[10.0, 20.0, 30.0, 40.0],
+        &[100.0, 200.0, 300.0, 400.0],
+    ];
+    let expected_outputs = [
+        vec![-1.5, -2.0, -2.5, -5.0],
+        vec![-11.5, -22.0, -32.5, -45.0],
+        vec![-111.5, -222.0, -332.5, -445.0],
+    ];
+    for (step, (x, expected)) in steps.iter().zip(expected_outputs.iter()).enumerate() {
+        let mut input_data = HashMap::new();
+        input_data.insert("X".to_string(), InputTensor::from(*x));
+        if step == 0 {
+            input_data.insert("past".to_string(), initial_state.into());
+        }
+
+        let result = pollster::block_on(decoder.step(input_data)).unwrap();
+        assert!(!result.contains_key("present"));
+        assert_eq!(&result["Y"], expected);
+    }
+
+    let state = decoder.state("past").unwrap();
+    assert_eq!(state.shape().dims, vec![4]);
+    assert_eq!(
+        pollster::block_on(decoder.session().read_tensor(state)),
+        vec![111.5, 222.0, 332.5, 445.0]
+    );
+
+    // After a reset the initial state must be supplied again
+    decoder.reset();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), InputTensor::from(steps[0]));
+    assert!(pollster::block_on(decoder.step(input_data)).is_err());
+}
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use wonnx::onnx::{TensorProto, TensorProto_DataType};
+use wonnx::utils::{graph, model, node, tensor};
+
+mod common;
+
+#[test]
+fn test_unsqueeze_axes_input() {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let data: Vec<f32> = (0..6).map(|x| x as f32).collect();
+    let mut input_data = HashMap::new();
+    input_data.insert("X".to_string(), data.as_slice().into());
+
+    // Since opset 13, the axes are provided as an input
+    let mut axes = TensorProto::new();
+    axes.set_name("axes".to_string());
+    axes.set_data_type(TensorProto_DataType::INT64 as i32);
+    axes.set_dims(vec![1]);
+    axes.set_int64_data(vec![1]);
+
+    // Model: (X, axes) -> Unsqueeze -> Y
+    let model = model(graph(
+        vec![tensor("X", &[2, 3])],
+        vec![tensor("Y", &[2, 1, 3])],
+        vec![],
+        vec![axes],
+        vec![node(
+            vec!["X", "axes"],
+            vec!["Y"],
+            "unsqueeze",
+            "Unsqueeze",
+            vec![],
+        )],
+    ));
+
+    let session =
+        pollster::block_on(wonnx::Session::from_model(model)).expect("Session did not create");
+    let result = pollster::block_on(session.run(&input_data)).unwrap();
+    common::assert_eq_vector(result["Y"].as_slice(), &data);
+}